@@ -0,0 +1,388 @@
+// Scene for fit testing: copies of shapes placed, turned and snapped port to port
+// the way the in-game builder joins blocks, to check that a set of shapes tiles.
+// Placements refer to shapes by ID, so the scene follows edits to the shapes.
+use crate::data_structures::{Shape, SharedShape, Vertex};
+use crate::geometry::{is_convex_poly, triangulate, Vec2};
+
+// Ports closer than this, in shape units, count as joined
+pub const JOIN_DISTANCE: f32 = 0.05;
+// Overlaps shallower than this are rounding, not blocks pushed into each other
+const OVERLAP_TOLERANCE: f32 = 0.01;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub shape_id: usize,
+    pub position: Vertex,
+    // Degrees, from the x axis towards the y axis
+    pub angle: f32,
+}
+
+impl Placement {
+    pub fn new(shape_id: usize, position: Vertex) -> Self {
+        Placement { shape_id, position, angle: 0.0 }
+    }
+
+    // Point of the shape moved into the scene
+    pub fn transform(&self, v: &Vertex) -> Vertex {
+        let p = Vec2::from_angle(self.angle.to_radians()).rotate(Vec2::new(v.x, v.y));
+        Vertex { x: p.x + self.position.x, y: p.y + self.position.y }
+    }
+
+    pub fn outline(&self, shape: &Shape) -> Vec<Vertex> {
+        shape.vertices.iter().map(|v| self.transform(v)).collect()
+    }
+
+    // Scene positions of the shape's ports that lie on an edge
+    pub fn ports(&self, shape: &Shape) -> Vec<(usize, Vertex)> {
+        (0..shape.ports.len())
+            .filter_map(|i| shape.port_position(i).map(|p| (i, self.transform(&p))))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub placements: Vec<Placement>,
+    pub selected: Option<usize>,
+    // Ports picked for mating, as (placement, port): the first stays put and the
+    // second's placement is moved onto it
+    pub picked_ports: Vec<(usize, usize)>,
+}
+
+/// Where to put a shape so one of its ports mates a port of a placed shape, and how
+/// well the two then fit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mate {
+    pub placement: Placement,
+    // Distance left between the two ports, from rounding
+    pub port_gap: f32,
+    // How far the port normals are from pointing exactly at each other, in degrees
+    pub angle_error: f32,
+    // Difference in length between the two ports' edges
+    pub edge_length_difference: f32,
+    // Furthest any other port on either edge is from a port on the other edge, when
+    // the edges have other ports
+    pub port_misalignment: Option<f32>,
+}
+
+/// A port of one placement sitting on a port of another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Joint {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+}
+
+pub fn find_shape(shapes: &[SharedShape], id: usize) -> Option<&Shape> {
+    shapes.iter().find(|shape| shape.id == id).map(|shape| &**shape)
+}
+
+impl Scene {
+    // Add a placement and select it
+    pub fn add(&mut self, placement: Placement) {
+        self.placements.push(placement);
+        self.selected = Some(self.placements.len() - 1);
+    }
+
+    pub fn remove(&mut self, idx: usize) {
+        if idx >= self.placements.len() {
+            return;
+        }
+        self.placements.remove(idx);
+        self.picked_ports.retain(|&(placement, _)| placement != idx);
+        for (placement, _) in &mut self.picked_ports {
+            if *placement > idx {
+                *placement -= 1;
+            }
+        }
+        self.selected = match self.selected {
+            Some(selected) if selected == idx => None,
+            Some(selected) if selected > idx => Some(selected - 1),
+            other => other,
+        };
+    }
+
+    // Topmost placement whose outline contains the point
+    pub fn placement_at(&self, shapes: &[SharedShape], point: &Vertex) -> Option<usize> {
+        let point = Vec2::new(point.x, point.y);
+        self.placements.iter().enumerate().rev()
+            .find(|(_, placement)| {
+                find_shape(shapes, placement.shape_id).is_some_and(|shape| {
+                    let outline: Vec<Vec2> = placement.outline(shape).iter().map(|v| Vec2::new(v.x, v.y)).collect();
+                    outline.len() > 2 && crate::geometry::intersect_poly_point(&outline, point)
+                })
+            })
+            .map(|(i, _)| i)
+    }
+
+    // Move a placement so that the port of it nearest to a port of another placement
+    // lands exactly on that port, if they are within `max_distance`
+    pub fn snap(&mut self, shapes: &[SharedShape], idx: usize, max_distance: f32) -> bool {
+        let Some(moving) = self.placements.get(idx) else { return false };
+        let Some(shape) = find_shape(shapes, moving.shape_id) else { return false };
+        let own_ports = moving.ports(shape);
+
+        let mut best: Option<(f32, Vertex)> = None;
+        for (_, other) in self.placements.iter().enumerate().filter(|(j, _)| *j != idx) {
+            let Some(other_shape) = find_shape(shapes, other.shape_id) else { continue };
+            for (_, target) in other.ports(other_shape) {
+                for (_, port) in &own_ports {
+                    let distance = distance(port, &target);
+                    if distance <= max_distance && best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                        best = Some((distance, Vertex { x: target.x - port.x, y: target.y - port.y }));
+                    }
+                }
+            }
+        }
+
+        match best {
+            Some((_, delta)) => {
+                let position = &mut self.placements[idx].position;
+                position.x += delta.x;
+                position.y += delta.y;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Pick a port for mating, or unpick it; picking a third starts over
+    pub fn pick_port(&mut self, placement: usize, port: usize) {
+        if let Some(pos) = self.picked_ports.iter().position(|&picked| picked == (placement, port)) {
+            self.picked_ports.remove(pos);
+            return;
+        }
+        if self.picked_ports.len() == 2 {
+            self.picked_ports.clear();
+        }
+        self.picked_ports.push((placement, port));
+    }
+    
+    // Mate for the two picked ports, if they are on different placements
+    pub fn picked_mate(&self, shapes: &[SharedShape]) -> Option<(usize, Mate)> {
+        let [(a, port_a), (b, port_b)] = self.picked_ports[..] else { return None };
+        if a == b {
+            return None;
+        }
+        let (placement_a, placement_b) = (self.placements.get(a)?, self.placements.get(b)?);
+        let shape_a = find_shape(shapes, placement_a.shape_id)?;
+        let shape_b = find_shape(shapes, placement_b.shape_id)?;
+        mate(shape_a, placement_a, port_a, shape_b, port_b).map(|mate| (b, mate))
+    }
+
+    // Pairs of ports of different placements that sit on each other
+    pub fn joints(&self, shapes: &[SharedShape]) -> Vec<Joint> {
+        let ports: Vec<Vec<(usize, Vertex)>> = self.placements.iter()
+            .map(|placement| find_shape(shapes, placement.shape_id).map(|shape| placement.ports(shape)).unwrap_or_default())
+            .collect();
+
+        let mut joints = Vec::new();
+        for a in 0..ports.len() {
+            for b in a + 1..ports.len() {
+                for (port_a, pos_a) in &ports[a] {
+                    for (port_b, pos_b) in &ports[b] {
+                        if distance(pos_a, pos_b) <= JOIN_DISTANCE {
+                            joints.push(Joint { a: (a, *port_a), b: (b, *port_b) });
+                        }
+                    }
+                }
+            }
+        }
+        joints
+    }
+
+    // Pairs of placements whose outlines overlap. A separating axis among the edge
+    // normals only decides it for convex outlines, so concave ones are tested a
+    // triangle at a time.
+    pub fn overlaps(&self, shapes: &[SharedShape]) -> Vec<(usize, usize)> {
+        let pieces: Vec<Vec<Vec<Vec2>>> = self.placements.iter()
+            .map(|placement| {
+                find_shape(shapes, placement.shape_id)
+                    .map(|shape| convex_pieces(placement.outline(shape).iter().map(|v| Vec2::new(v.x, v.y)).collect()))
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut pairs = Vec::new();
+        for a in 0..pieces.len() {
+            for b in a + 1..pieces.len() {
+                let overlap = pieces[a].iter().any(|piece_a| pieces[b].iter().any(|piece_b| !separated(piece_a, piece_b)));
+                if overlap {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+fn distance(a: &Vertex, b: &Vertex) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+// The outline itself when it is convex, else its triangles; nothing for outlines
+// without area
+fn convex_pieces(outline: Vec<Vec2>) -> Vec<Vec<Vec2>> {
+    if outline.len() < 3 {
+        Vec::new()
+    } else if is_convex_poly(&outline) {
+        vec![outline]
+    } else {
+        triangulate(&outline).into_iter().map(|triangle| triangle.to_vec()).collect()
+    }
+}
+
+// Whether some edge normal of either convex outline has their projections apart, or
+// overlapping by no more than the tolerance
+fn separated(a: &[Vec2], b: &[Vec2]) -> bool {
+    let axes = |outline: &[Vec2]| -> Vec<Vec2> {
+        (0..outline.len())
+            .filter_map(|i| (outline[(i + 1) % outline.len()] - outline[i]).perp().try_normalize())
+            .collect()
+    };
+    let project = |outline: &[Vec2], axis: Vec2| {
+        outline.iter()
+            .map(|p| p.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| (min.min(d), max.max(d)))
+    };
+
+    axes(a).into_iter().chain(axes(b)).any(|axis| {
+        let (min_a, max_a) = project(a, axis);
+        let (min_b, max_b) = project(b, axis);
+        max_a.min(max_b) - min_a.max(min_b) <= OVERLAP_TOLERANCE
+    })
+}
+
+// Position and outward normal of a port in the shape's own coordinates
+fn port_frame(shape: &Shape, port: usize) -> Option<(Vec2, Vec2)> {
+    let position = shape.port_position(port)?;
+    let (nx, ny) = shape.winding_normal(shape.ports[port].edge)?;
+    Some((Vec2::new(position.x, position.y), Vec2::new(nx, ny)))
+}
+
+/// Placement of shape `b` that puts its port `port_b` on port `port_a` of shape `a`,
+/// placed at `placement_a`, with the two ports facing each other as the game joins
+/// them. None if either port isn't on an edge of its shape.
+pub fn mate(a: &Shape, placement_a: &Placement, port_a: usize, b: &Shape, port_b: usize) -> Option<Mate> {
+    let (position_a, normal_a) = port_frame(a, port_a)?;
+    let (position_b, normal_b) = port_frame(b, port_b)?;
+    
+    // Turn b so its port faces back along a's, then move the port onto a's
+    let rotation_a = Vec2::from_angle(placement_a.angle.to_radians());
+    let target = rotation_a.rotate(position_a) + Vec2::new(placement_a.position.x, placement_a.position.y);
+    let facing = -rotation_a.rotate(normal_a);
+    let angle = normal_b.angle_between(facing).to_degrees();
+    let offset = target - Vec2::from_angle(angle.to_radians()).rotate(position_b);
+    let placement = Placement { shape_id: b.id, position: Vertex { x: offset.x, y: offset.y }, angle };
+    
+    // Measure the result the way it will be drawn, rather than trusting the algebra
+    let world = |shape: &Shape, placement: &Placement, port: usize| {
+        let rotation = Vec2::from_angle(placement.angle.to_radians());
+        let (position, normal) = port_frame(shape, port)?;
+        Some((rotation.rotate(position) + Vec2::new(placement.position.x, placement.position.y), rotation.rotate(normal)))
+    };
+    let (world_a, world_normal_a) = world(a, placement_a, port_a)?;
+    let (world_b, world_normal_b) = world(b, &placement, port_b)?;
+    let port_gap = world_a.distance(world_b);
+    let angle_error = world_normal_a.angle_between(-world_normal_b).to_degrees().abs();
+    
+    let (edge_a, edge_b) = (a.ports[port_a].edge, b.ports[port_b].edge);
+    let edge_length_difference = (a.edge_length(edge_a) - b.edge_length(edge_b)).abs();
+    
+    // Other ports on the two edges should pair up as well
+    let edge_ports = |shape: &Shape, placement: &Placement, edge: usize| -> Vec<Vec2> {
+        placement.ports(shape).into_iter()
+            .filter(|(i, _)| shape.ports[*i].edge == edge)
+            .map(|(_, v)| Vec2::new(v.x, v.y))
+            .collect()
+    };
+    let ports_a = edge_ports(a, placement_a, edge_a);
+    let ports_b = edge_ports(b, &placement, edge_b);
+    let nearest = |p: &Vec2, others: &[Vec2]| others.iter().map(|o| p.distance(*o)).fold(f32::INFINITY, f32::min);
+    let port_misalignment = (ports_a.len() + ports_b.len() > 2).then(|| {
+        ports_a.iter().map(|p| nearest(p, &ports_b))
+            .chain(ports_b.iter().map(|p| nearest(p, &ports_a)))
+            .fold(0.0, f32::max)
+    });
+    
+    Some(Mate { placement, port_gap, angle_error, edge_length_difference, port_misalignment })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{Port, PortType};
+
+    fn shape(id: usize, points: &[(f32, f32)], ports: &[(usize, f32)]) -> Shape {
+        let mut shape = Shape::new(id);
+        shape.vertices = points.iter().map(|&(x, y)| Vertex { x, y }).collect();
+        shape.ports = ports.iter().map(|&(edge, position)| Port { edge, position, port_type: PortType::Default }).collect();
+        shape
+    }
+
+    fn square(id: usize) -> Shape {
+        // Ports in the middle of the right (edge 1) and left (edge 3) sides
+        shape(id, &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)], &[(1, 0.5), (3, 0.5)])
+    }
+
+    fn origin(shape_id: usize) -> Placement {
+        Placement::new(shape_id, Vertex { x: 0.0, y: 0.0 })
+    }
+
+    #[test]
+    fn mate_unit_squares() {
+        let (a, b) = (square(1), square(2));
+        let mate = mate(&a, &origin(1), 0, &b, 1).unwrap();
+        assert!(mate.port_gap < 1e-5);
+        assert!(mate.angle_error < 1e-3);
+        assert!(mate.edge_length_difference < 1e-6);
+        assert_eq!(mate.port_misalignment, None);
+        assert!((mate.placement.position.x - 1.0).abs() < 1e-5);
+        assert!(mate.placement.position.y.abs() < 1e-5);
+        assert!(mate.placement.angle.abs() < 1e-3);
+    }
+
+    #[test]
+    fn mate_turns_the_shape_to_face_the_port() {
+        // Right side onto right side: b has to turn half way round
+        let (a, b) = (square(1), square(2));
+        let mate = mate(&a, &origin(1), 0, &b, 0).unwrap();
+        assert!(mate.port_gap < 1e-5);
+        assert!(mate.angle_error < 1e-3);
+        assert!((mate.placement.angle.abs() - 180.0).abs() < 1e-3);
+        assert!((mate.placement.position.x - 2.0).abs() < 1e-5);
+        assert!((mate.placement.position.y - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn mate_reports_mismatched_edges() {
+        let a = square(1);
+        let tall = shape(2, &[(0.0, 0.0), (1.0, 0.0), (1.0, 2.0), (0.0, 2.0)], &[(3, 0.5)]);
+        let mate = mate(&a, &origin(1), 0, &tall, 0).unwrap();
+        assert!(mate.port_gap < 1e-5);
+        assert!(mate.angle_error < 1e-3);
+        assert!((mate.edge_length_difference - 1.0).abs() < 1e-5);
+        assert!((mate.placement.position.x - 1.0).abs() < 1e-5);
+        assert!((mate.placement.position.y + 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn mate_needs_ports_on_edges() {
+        let (a, b) = (square(1), square(2));
+        assert!(mate(&a, &origin(1), 5, &b, 0).is_none());
+    }
+
+    #[test]
+    fn concave_outlines_overlap_only_where_they_do() {
+        // An L and a square sitting in its notch, touching but not overlapping
+        let l = shape(1, &[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)], &[]);
+        let shapes: Vec<SharedShape> = vec![l.into(), square(2).into()];
+        let mut scene = Scene::default();
+        scene.add(origin(1));
+        scene.add(Placement::new(2, Vertex { x: 1.0, y: 1.0 }));
+        assert!(scene.overlaps(&shapes).is_empty());
+
+        // Pushed into the L's arm
+        scene.placements[1].position = Vertex { x: 0.5, y: 1.0 };
+        assert_eq!(scene.overlaps(&shapes), vec![(0, 1)]);
+    }
+}
@@ -1,5 +1,5 @@
 // Abstract Syntax Tree for parsing Lua shape definitions
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Represents a complete shapes definition file
 /// 
@@ -29,11 +29,18 @@ use serde::Serialize;
 ///   }                  -- End shape
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShapesFile {
     pub shapes: Vec<Shape>,
 }
 
+impl ShapesFile {
+    /// The first shape with the given ID
+    pub fn shape_by_id(&self, id: usize) -> Option<&Shape> {
+        self.shapes.iter().find(|shape| shape.id == id)
+    }
+}
+
 /// Represents a single shape definition
 /// 
 /// Each shape must have:
@@ -59,7 +66,7 @@ pub struct ShapesFile {
 ///   }
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Shape {
     pub id: usize,
     pub name: Option<String>,
@@ -106,7 +113,7 @@ pub struct Shape {
 ///   }
 /// }
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Scale {
     pub verts: Vec<Vertex>,
     pub ports: Vec<Port>,
@@ -125,7 +132,7 @@ pub struct Scale {
 /// {20, 10}   -- x=20, y=10
 /// {-5, 3.5}  -- Fractional coordinates allowed
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vertex {
     pub x: f32,
     pub y: f32,
@@ -162,7 +169,7 @@ pub struct Vertex {
 /// {2, 0.5, ROOT}          -- Environment attachment
 /// {3, 0.5, NONE}          -- No connection
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Port {
     pub edge: usize,
     pub position: f32,
@@ -171,17 +178,10 @@ pub struct Port {
 
 /// Port types supported in Reassembly
 /// 
-/// Port Type Rules:
-/// - THRUSTER_OUT: Where thrust is generated (only one per thruster)
-/// - THRUSTER_IN: Where thrusters can connect (multiple allowed)
-/// - WEAPON_OUT: Where projectiles are generated (weapon source)
-/// - WEAPON_IN: Where weapons can connect (weapon target)
-/// - LAUNCHER: Where launched objects are generated
-/// - MISSILE: Where missiles attach and generate thrust
-/// - ROOT: For attaching to environment blocks
-/// - NONE: No special behavior
-/// - Default: Standard connection point
-#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The rules for each type are given by [`PortType::description`], which the
+/// editor also shows as a tooltip wherever a port type is picked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PortType {
     Default,
     ThrusterIn,
@@ -195,7 +195,7 @@ pub enum PortType {
 }
 
 /// Represents a shroud decoration component
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShroudComponent {
     pub size: (f32, f32),
     pub offset: (f32, f32, f32),
@@ -209,7 +209,7 @@ pub struct ShroudComponent {
 }
 
 /// Properties for cannon weapons
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CannonProperties {
     pub damage: f32,
     pub power: f32,
@@ -225,7 +225,7 @@ pub struct CannonProperties {
 }
 
 /// Properties for thruster components
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ThrusterProperties {
     pub force: f32,
     pub power: f32,
@@ -233,7 +233,7 @@ pub struct ThrusterProperties {
 }
 
 /// Properties for explosive fragments
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FragmentProperties {
     pub rounds_per_burst: usize,
     pub muzzle_vel: f32,
@@ -259,6 +259,45 @@ impl PortType {
         }
     }
     
+    /// A port type as files write it: the name in any case, optionally quoted, or the
+    /// number the game gives it (0 for DEFAULT up to 8 for NONE). None if it's neither.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_matches(|c| c == '"' || c == '\'');
+        if let Ok(number) = text.parse::<u32>() {
+            return match number {
+                0 => Some(PortType::Default),
+                1 => Some(PortType::ThrusterIn),
+                2 => Some(PortType::ThrusterOut),
+                3 => Some(PortType::Missile),
+                4 => Some(PortType::Launcher),
+                5 => Some(PortType::WeaponIn),
+                6 => Some(PortType::WeaponOut),
+                7 => Some(PortType::Root),
+                8 => Some(PortType::None),
+                _ => None,
+            };
+        }
+        match text.to_uppercase().as_str() {
+            "DEFAULT" => Some(PortType::Default),
+            name => Some(PortType::from_str(name)).filter(|port_type| *port_type != PortType::Default),
+        }
+    }
+    
+    /// What the port type does in the game
+    pub fn description(&self) -> &'static str {
+        match self {
+            PortType::Default => "Standard connection point: any block can attach here",
+            PortType::ThrusterIn => "Where thrusters can connect (multiple allowed)",
+            PortType::ThrusterOut => "Where thrust is generated and the exhaust comes out (only one per thruster)",
+            PortType::WeaponIn => "Where weapons can connect (weapon target)",
+            PortType::WeaponOut => "Where projectiles are generated (weapon source)",
+            PortType::Missile => "Where missiles attach and generate thrust",
+            PortType::Launcher => "Where launched objects are generated",
+            PortType::Root => "For attaching to environment blocks, such as asteroids and plants",
+            PortType::None => "No special behavior; nothing attaches here",
+        }
+    }
+    
     pub fn to_str(&self) -> &'static str {
         match self {
             PortType::Default => "DEFAULT",
@@ -0,0 +1,57 @@
+// Backups of files the editor overwrites: `<name>.bak` is the copy from before
+// the last write, `<name>.bak.1` the one before that, and so on
+#![cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Backups kept per file, the newest included
+const MAX_BACKUPS: usize = 3;
+
+/// Path of the newest backup, or an older one for `age` > 0
+pub fn backup_path(path: &str, age: usize) -> PathBuf {
+    if age == 0 {
+        PathBuf::from(format!("{}.bak", path))
+    } else {
+        PathBuf::from(format!("{}.bak.{}", path, age))
+    }
+}
+
+pub fn has_backup(path: &str) -> bool {
+    backup_path(path, 0).exists()
+}
+
+/// Copy the file to its newest backup, shifting the older ones. Nothing to do if
+/// the file doesn't exist yet.
+pub fn backup_before_write(path: &str) -> io::Result<()> {
+    if !Path::new(path).exists() {
+        return Ok(());
+    }
+
+    for age in (1..MAX_BACKUPS).rev() {
+        let newer = backup_path(path, age - 1);
+        if newer.exists() {
+            fs::rename(&newer, backup_path(path, age))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 0))?;
+    Ok(())
+}
+
+/// Swap the file with its newest backup, so restoring twice undoes the restore
+pub fn restore(path: &str) -> io::Result<()> {
+    let backup = backup_path(path, 0);
+    if !backup.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("No backup of {}", path)));
+    }
+
+    if Path::new(path).exists() {
+        let swap = PathBuf::from(format!("{}.restoring", path));
+        fs::rename(path, &swap)?;
+        fs::rename(&backup, path)?;
+        fs::rename(&swap, &backup)?;
+    } else {
+        fs::copy(&backup, path)?;
+    }
+    Ok(())
+}
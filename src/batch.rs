@@ -0,0 +1,85 @@
+// Operations applied to many shapes at once, with a count of what they changed so
+// the result can be summed up before and after applying
+use crate::data_structures::{Shape, Vertex};
+use crate::geometry::round_to;
+use crate::translations::t;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchOp {
+    // Resize every scale about the origin
+    Scale(f32),
+    // Move vertices onto the nearest point of a grid with this spacing
+    SnapToGrid(f32),
+    // Round coordinates to this many decimal places
+    Round(u32),
+}
+
+/// What an operation changed, or would change, in a set of shapes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub shapes: usize,
+    pub shapes_changed: usize,
+    pub vertices_moved: usize,
+}
+
+/// Copy of `shape` with the operation applied to all its scales
+pub fn apply(shape: &Shape, op: BatchOp) -> Shape {
+    let mut result = shape.clone();
+    let scale_vertices = result.scales.iter_mut().flat_map(|scale| scale.vertices.iter_mut());
+    for v in result.vertices.iter_mut().chain(scale_vertices) {
+        *v = apply_vertex(v, op);
+    }
+    result
+}
+
+fn apply_vertex(v: &Vertex, op: BatchOp) -> Vertex {
+    let f = |a: f32| match op {
+        BatchOp::Scale(factor) => a * factor,
+        BatchOp::SnapToGrid(step) => round_to(a, step),
+        BatchOp::Round(decimals) => round_to(a, 10f32.powi(-(decimals as i32))),
+    };
+    // Adding 0.0 turns a rounded -0 into 0, which would be written out as "-0"
+    Vertex { x: f(v.x) + 0.0, y: f(v.y) + 0.0 }
+}
+
+/// Number of vertices, over all scales, that differ between two versions of a shape
+pub fn vertices_moved(before: &Shape, after: &Shape) -> usize {
+    let vertices = |shape: &Shape| {
+        let scale_vertices = shape.scales.iter().flat_map(|scale| scale.vertices.iter());
+        shape.vertices.iter().chain(scale_vertices).cloned().collect::<Vec<_>>()
+    };
+    vertices(before).iter()
+        .zip(&vertices(after))
+        .filter(|(a, b)| a != b)
+        .count()
+}
+
+/// Apply the operation to each shape, returning the results and what changed
+pub fn apply_all<'a>(shapes: impl IntoIterator<Item = &'a Shape>, op: BatchOp) -> (Vec<Shape>, BatchSummary) {
+    let mut summary = BatchSummary::default();
+    let results = shapes.into_iter()
+        .map(|shape| {
+            let result = apply(shape, op);
+            let moved = vertices_moved(shape, &result);
+            summary.shapes += 1;
+            summary.vertices_moved += moved;
+            if moved > 0 {
+                summary.shapes_changed += 1;
+            }
+            result
+        })
+        .collect();
+    (results, summary)
+}
+
+/// One-line summary for the dialog and the toast after applying
+pub fn describe(summary: &BatchSummary) -> String {
+    format!(
+        "{}: {} / {}, {}: {}",
+        t("shapes_changed"),
+        summary.shapes_changed,
+        summary.shapes,
+        t("vertices_moved"),
+        summary.vertices_moved
+    )
+}
@@ -0,0 +1,379 @@
+// Minimal reader for a mod's blocks.lua. It only picks out what the editor needs
+// to know which blocks use which shapes, and keeps the source positions of the
+// `shape=` values so references can be rewritten without touching anything else.
+use std::ops::Range;
+
+use full_moon::tokenizer::{Symbol, TokenType};
+
+/// A `shape=` value in the source
+#[derive(Clone, Debug)]
+pub struct ShapeRef {
+    pub value: String,
+    pub span: Range<usize>,
+}
+
+/// Firing properties from a block's `cannon={...}` table
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cannon {
+    // Half-angle of the spread, in radians
+    pub spread: Option<f32>,
+    pub range: Option<f32>,
+}
+
+/// What a launcher spawns, from its `replicateBlock`: either the ident of another
+/// block, or an inline block table with its own `shape=` or an `extends=`
+#[derive(Clone, Debug, Default)]
+pub struct Replicate {
+    pub shape: Option<String>,
+    pub extends: Option<String>,
+}
+
+/// One top-level block definition
+#[derive(Clone, Debug, Default)]
+pub struct Block {
+    // The leading number of the block table, e.g. `{17001, ...}`
+    pub ident: Option<String>,
+    pub name: Option<String>,
+    pub shape: Option<ShapeRef>,
+    // Blocks inherit the shape of the block they extend unless they set their own
+    pub extends: Option<String>,
+    pub cannon: Option<Cannon>,
+    // Which of the shape's scales the block uses, 1 for the first
+    pub scale: Option<usize>,
+    pub group: Option<usize>,
+    pub density: Option<f32>,
+    pub durability: Option<f32>,
+    // Cost in P, when the block sets it instead of leaving it to the game
+    pub points: Option<f32>,
+    pub replicate: Option<Replicate>,
+}
+
+impl Block {
+    /// Name for lists and warnings, e.g. `Custom Block (#17001)`
+    pub fn label(&self) -> String {
+        match (&self.name, &self.ident) {
+            (Some(name), Some(ident)) => format!("{} (#{})", name, ident),
+            (Some(name), None) => name.clone(),
+            (None, Some(ident)) => format!("#{}", ident),
+            (None, None) => "?".to_string(),
+        }
+    }
+}
+
+pub struct BlocksFile {
+    pub path: String,
+    pub source: String,
+    pub blocks: Vec<Block>,
+}
+
+impl BlocksFile {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        Ok(Self::parse(source, path.to_string()))
+    }
+
+    pub fn parse(source: String, path: String) -> Self {
+        let blocks = scan_blocks(&source);
+        Self { path, source, blocks }
+    }
+
+    /// Blocks whose shape, set directly or inherited through `extends`, is the given shape ID
+    pub fn blocks_using(&self, shape_id: usize) -> Vec<&Block> {
+        self.blocks.iter()
+            .filter(|block| self.effective_shape(block).and_then(|shape| parse_id(&shape.value)) == Some(shape_id))
+            .collect()
+    }
+
+    /// IDs of the mod shapes the blocks refer to
+    pub fn shape_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks.iter()
+            .filter_map(|block| block.shape.as_ref())
+            .filter_map(|shape| parse_id(&shape.value))
+    }
+
+    /// Point every `shape=` field set to `old_id` at `new_id`, returning how many were
+    /// changed. Blocks that inherit the shape follow along without being touched.
+    pub fn renumber_shape(&mut self, old_id: usize, new_id: usize) -> usize {
+        let mut spans: Vec<(Range<usize>, String)> = self.blocks.iter()
+            .filter_map(|block| block.shape.as_ref())
+            .filter(|shape| parse_id(&shape.value) == Some(old_id))
+            .map(|shape| {
+                // Keep the notation the mod author used
+                let value = if shape.value.starts_with("0x") || shape.value.starts_with("0X") {
+                    format!("0x{:x}", new_id)
+                } else {
+                    new_id.to_string()
+                };
+                (shape.span.clone(), value)
+            })
+            .collect();
+
+        // Back to front, so earlier spans stay valid
+        spans.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+        for (span, value) in &spans {
+            self.source.replace_range(span.clone(), value);
+        }
+
+        if !spans.is_empty() {
+            self.blocks = scan_blocks(&self.source);
+        }
+        spans.len()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        crate::backup::backup_before_write(&self.path).map_err(|e| format!("Failed to back up {}: {}", self.path, e))?;
+        std::fs::write(&self.path, &self.source).map_err(|e| format!("Failed to write {}: {}", self.path, e))
+    }
+
+    /// Cannon of the first block using the shape that has one
+    pub fn cannon_for_shape(&self, shape_id: usize) -> Option<Cannon> {
+        self.blocks_using(shape_id).into_iter()
+            .find_map(|block| self.inherited(block, |block| block.cannon.as_ref()))
+            .copied()
+    }
+
+    /// The mod shape a block is built from and the index of the scale it uses (0 for
+    /// scale 1); None for blocks with a built-in shape or no shape at all
+    pub fn shape_of(&self, block: &Block) -> Option<(usize, usize)> {
+        let shape_id = self.effective_shape(block).and_then(|shape| parse_id(&shape.value))?;
+        let scale = self.inherited(block, |block| block.scale.as_ref()).copied().unwrap_or(1);
+        Some((shape_id, scale.saturating_sub(1)))
+    }
+
+    /// A block's group, density, durability and points, each taken from the nearest
+    /// block up the `extends` chain that sets it
+    pub fn stats(&self, block: &Block) -> Block {
+        Block {
+            group: self.inherited(block, |block| block.group.as_ref()).copied(),
+            density: self.inherited(block, |block| block.density.as_ref()).copied(),
+            durability: self.inherited(block, |block| block.durability.as_ref()).copied(),
+            points: self.inherited(block, |block| block.points.as_ref()).copied(),
+            ..block.clone()
+        }
+    }
+
+    /// Shape ID of what the first launcher block using the shape spawns, if that is one
+    /// of the mod's shapes
+    pub fn payload_for_shape(&self, shape_id: usize) -> Option<usize> {
+        self.blocks_using(shape_id).into_iter()
+            .find_map(|block| self.inherited(block, |block| block.replicate.as_ref()))
+            .and_then(|replicate| {
+                let shape = match (&replicate.shape, &replicate.extends) {
+                    (Some(shape), _) => shape,
+                    (None, Some(ident)) => {
+                        let block = self.blocks.iter().find(|block| block.ident.as_ref() == Some(ident))?;
+                        &self.effective_shape(block)?.value
+                    }
+                    (None, None) => return None,
+                };
+                parse_id(shape)
+            })
+    }
+
+    fn effective_shape<'a>(&'a self, block: &'a Block) -> Option<&'a ShapeRef> {
+        self.inherited(block, |block| block.shape.as_ref())
+    }
+
+    // A field of the block, or of the nearest block up the `extends` chain that sets it
+    fn inherited<'a, T>(&'a self, block: &'a Block, field: impl Fn(&'a Block) -> Option<&'a T>) -> Option<&'a T> {
+        let mut current = block;
+        // Bounded, so a cycle in `extends` can't hang the editor
+        for _ in 0..16 {
+            if let Some(value) = field(current) {
+                return Some(value);
+            }
+            let parent = current.extends.as_ref()?;
+            current = self.blocks.iter().find(|block| block.ident.as_ref() == Some(parent))?;
+        }
+        None
+    }
+}
+
+// Shape IDs are plain or hex numbers; anything else (SQUARE, ...) is a built-in shape
+fn parse_id(value: &str) -> Option<usize> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Equals,
+    Name,
+    Number,
+    Str,
+    Other,
+}
+
+fn scan_blocks(source: &str) -> Vec<Block> {
+    let tokens = tokenize(source);
+    let text = |span: &Range<usize>| source[span.clone()].to_string();
+
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut depth = 0;
+    // Set once the first value of the block table (the ident, if it is a number) is seen
+    let mut seen_first_value = false;
+    // Inside the block's `cannon={...}` table
+    let mut in_cannon = false;
+    // Inside the block's `replicateBlock={...}` table
+    let mut in_replicate = false;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let (token, span) = &tokens[i];
+        match token {
+            Token::Open => {
+                depth += 1;
+                if depth == 2 {
+                    current = Some(Block::default());
+                    seen_first_value = false;
+                } else if depth > 2 {
+                    // A nested table is a value of the block too
+                    seen_first_value = true;
+                }
+            }
+            Token::Close => {
+                if depth == 2 {
+                    blocks.extend(current.take());
+                } else if depth == 3 {
+                    in_cannon = false;
+                    in_replicate = false;
+                }
+                depth -= 1;
+            }
+            Token::Name if depth == 3 && in_cannon && tokens.get(i + 1).map(|(token, _)| *token) == Some(Token::Equals) => {
+                if let (Some(block), Some((Token::Number, value_span))) = (&mut current, tokens.get(i + 2)) {
+                    let value = text(value_span).parse::<f32>().ok();
+                    let cannon = block.cannon.get_or_insert_with(Cannon::default);
+                    match text(span).as_str() {
+                        "spread" => cannon.spread = value,
+                        "range" => cannon.range = value,
+                        _ => {}
+                    }
+                }
+                i += 1;
+            }
+            Token::Name if depth == 3 && in_replicate && tokens.get(i + 1).map(|(token, _)| *token) == Some(Token::Equals) => {
+                if let (Some(block), Some((Token::Name | Token::Number, value_span))) = (&mut current, tokens.get(i + 2)) {
+                    let replicate = block.replicate.get_or_insert_with(Replicate::default);
+                    match text(span).as_str() {
+                        "shape" => replicate.shape = Some(text(value_span)),
+                        "extends" => replicate.extends = Some(text(value_span)),
+                        _ => {}
+                    }
+                }
+                i += 1;
+            }
+            _ if depth != 2 => {}
+            Token::Number if !seen_first_value => {
+                if let Some(block) = &mut current {
+                    block.ident = Some(text(span));
+                }
+                seen_first_value = true;
+            }
+            Token::Name if tokens.get(i + 1).map(|(token, _)| *token) == Some(Token::Equals) => {
+                seen_first_value = true;
+                let key = text(span);
+                if let (Some(block), Some((value_token, value_span))) = (&mut current, tokens.get(i + 2)) {
+                    match (key.as_str(), value_token) {
+                        ("shape", Token::Name | Token::Number) => {
+                            block.shape = Some(ShapeRef { value: text(value_span), span: value_span.clone() });
+                        }
+                        ("name", Token::Str) => {
+                            block.name = Some(unquote(&text(value_span)));
+                        }
+                        ("extends", Token::Number) => {
+                            block.extends = Some(text(value_span));
+                        }
+                        ("scale", Token::Number) => block.scale = text(value_span).parse().ok(),
+                        ("group", Token::Number) => block.group = parse_id(&text(value_span)),
+                        ("density", Token::Number) => block.density = text(value_span).parse().ok(),
+                        ("durability", Token::Number) => block.durability = text(value_span).parse().ok(),
+                        ("points", Token::Number) => block.points = text(value_span).parse().ok(),
+                        ("replicateBlock", Token::Number) => {
+                            block.replicate = Some(Replicate { shape: None, extends: Some(text(value_span)) });
+                        }
+                        ("replicateBlock", Token::Open) => {
+                            block.replicate = Some(Replicate::default());
+                            in_replicate = true;
+                        }
+                        ("cannon", Token::Open) => {
+                            block.cannon = Some(Cannon::default());
+                            in_cannon = true;
+                        }
+                        _ => {}
+                    }
+                }
+                i += 1;
+            }
+            _ => seen_first_value = true,
+        }
+        i += 1;
+    }
+
+    blocks
+}
+
+/// Where to add entries to the outermost table of a Lua file (blocks.lua or
+/// shapes.lua): the offset of its closing brace, and the offset after the last
+/// entry if a comma has to go there first
+pub fn table_end(source: &str) -> Option<(usize, Option<usize>)> {
+    let tokens = tokenize(source);
+    let mut depth = 0;
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        match token {
+            Token::Open => depth += 1,
+            Token::Close if depth == 1 => {
+                let comma_at = match i.checked_sub(1).map(|prev| &tokens[prev]) {
+                    Some((Token::Open, _)) | None => None,
+                    Some((_, prev_span)) if matches!(&source[prev_span.clone()], "," | ";") => None,
+                    Some((_, prev_span)) => Some(prev_span.end),
+                };
+                return Some((span.start, comma_at));
+            }
+            Token::Close => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote(literal: &str) -> String {
+    literal.trim_start_matches('[').trim_end_matches(']')
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string()
+}
+
+// The tokens that show table structure, keys and values, with comments and
+// whitespace left out. A minus sign right before a number is part of it.
+fn tokenize(source: &str) -> Vec<(Token, Range<usize>)> {
+    let mut tokens: Vec<(Token, Range<usize>)> = Vec::new();
+    for lua_token in crate::lua_lexer::tokens(source).filter(|token| !token.is_trivia()) {
+        let token = match &lua_token.kind {
+            Some(TokenType::Symbol { symbol: Symbol::LeftBrace }) => Token::Open,
+            Some(TokenType::Symbol { symbol: Symbol::RightBrace }) => Token::Close,
+            Some(TokenType::Symbol { symbol: Symbol::Equal }) => Token::Equals,
+            Some(TokenType::Identifier { .. }) => Token::Name,
+            // Keywords such as `true` read as names here
+            Some(TokenType::Symbol { symbol }) if symbol.to_string().starts_with(char::is_alphabetic) => Token::Name,
+            Some(TokenType::Number { .. }) => Token::Number,
+            Some(TokenType::StringLiteral { .. }) => Token::Str,
+            _ => Token::Other,
+        };
+        let span = lua_token.span;
+        match tokens.last_mut() {
+            Some((last @ Token::Other, minus)) if token == Token::Number && minus.end == span.start && &source[minus.clone()] == "-" => {
+                *last = Token::Number;
+                minus.end = span.end;
+            }
+            _ => tokens.push((token, span)),
+        }
+    }
+    tokens
+}
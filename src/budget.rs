@@ -0,0 +1,128 @@
+// Derived stats across the whole file, for balancing a faction's parts against each
+// other: area, mass and health of every block, and an estimate of what it costs. The
+// game works the cost out itself, so the estimate only follows its general trend.
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::blocks::{Block, BlocksFile};
+use crate::data_structures::Shape;
+use crate::geometry::{area_for_poly, Vec2};
+
+/// Values assumed for blocks that don't set their own, and how much mass and health
+/// add to the estimated cost
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetHeuristics {
+    pub density: f32,
+    pub durability: f32,
+    pub points_per_mass: f32,
+    pub points_per_health: f32,
+}
+
+impl Default for BudgetHeuristics {
+    fn default() -> Self {
+        Self {
+            density: 0.1,
+            durability: 1.0,
+            points_per_mass: 0.02,
+            points_per_health: 0.02,
+        }
+    }
+}
+
+pub struct BlockBudget {
+    pub label: String,
+    pub shape_id: usize,
+    pub group: Option<usize>,
+    pub area: f32,
+    // Area times density
+    pub mass: f32,
+    // Area times durability
+    pub health: f32,
+    pub points: f32,
+    // The block sets its cost, so `points` is not an estimate
+    pub points_set: bool,
+}
+
+#[derive(Default)]
+pub struct GroupBudget {
+    pub blocks: usize,
+    pub area: f32,
+    pub mass: f32,
+    pub points: f32,
+}
+
+pub struct Budget {
+    pub blocks: Vec<BlockBudget>,
+    // Totals by group; blocks without a group are under None
+    pub groups: BTreeMap<Option<usize>, GroupBudget>,
+    // Blocks built from a shape, or a scale of one, that isn't in the file
+    pub missing_shapes: Vec<String>,
+}
+
+/// The budget of every block in blocks.lua that is built from one of `shapes`. Without
+/// a blocks file each shape counts as a block of its own with the assumed values.
+pub fn budget<'a>(shapes: impl IntoIterator<Item = &'a Shape>, blocks_file: Option<&BlocksFile>, heuristics: &BudgetHeuristics) -> Budget {
+    let shapes: Vec<&Shape> = shapes.into_iter().collect();
+    let by_id: HashMap<usize, &Shape> = shapes.iter().map(|&shape| (shape.id, shape)).collect();
+    let mut budget = Budget { blocks: Vec::new(), groups: BTreeMap::new(), missing_shapes: Vec::new() };
+
+    match blocks_file {
+        Some(blocks_file) => {
+            for block in &blocks_file.blocks {
+                let Some((shape_id, scale)) = blocks_file.shape_of(block) else {
+                    continue;
+                };
+                let Some(area) = by_id.get(&shape_id).and_then(|shape| scale_area(shape, scale)) else {
+                    budget.missing_shapes.push(block.label());
+                    continue;
+                };
+                let stats = blocks_file.stats(block);
+                budget.blocks.push(block_budget(block.label(), shape_id, area, &stats, heuristics));
+            }
+        }
+        None => {
+            let stats = Block::default();
+            for shape in shapes {
+                let label = format!("{} (ID: {})", shape.name, shape.id);
+                budget.blocks.push(block_budget(label, shape.id, shape.signed_area().abs(), &stats, heuristics));
+            }
+        }
+    }
+
+    for block in &budget.blocks {
+        let group = budget.groups.entry(block.group).or_default();
+        group.blocks += 1;
+        group.area += block.area;
+        group.mass += block.mass;
+        group.points += block.points;
+    }
+    budget
+}
+
+fn block_budget(label: String, shape_id: usize, area: f32, stats: &Block, heuristics: &BudgetHeuristics) -> BlockBudget {
+    let mass = area * stats.density.unwrap_or(heuristics.density);
+    let health = area * stats.durability.unwrap_or(heuristics.durability);
+    let estimate = mass * heuristics.points_per_mass + health * heuristics.points_per_health;
+    BlockBudget {
+        label,
+        shape_id,
+        group: stats.group,
+        area,
+        mass,
+        health,
+        points: stats.points.unwrap_or(estimate),
+        points_set: stats.points.is_some(),
+    }
+}
+
+// Area of one of the shape's scales, 0 for scale 1; None if the shape has no such scale
+fn scale_area(shape: &Shape, scale: usize) -> Option<f32> {
+    let vertices = match scale {
+        0 => &shape.vertices,
+        _ => &shape.scales.get(scale - 1)?.vertices,
+    };
+    let points: Vec<Vec2> = vertices.iter().map(|v| Vec2::new(v.x, v.y)).collect();
+    Some(area_for_poly(&points).abs())
+}
@@ -0,0 +1,125 @@
+// Canonical form of a shapes file: one spelling for everything the Lua format can
+// say in more than one way, and nothing it can't carry, so that writing a file out
+// and reading it back gives exactly the same value
+use crate::ast::{PortType, Shape, ShapesFile};
+
+impl ShapesFile {
+    /// Copy of the file in canonical form, for tools that diff, hash or cache shapes
+    ///
+    /// - shapes are ordered by ID, keeping the file order of equal IDs
+    /// - ports are ordered by edge and position, and an explicit `DEFAULT` type is dropped
+    /// - features are split on `|`, trimmed, sorted and deduplicated; none at all is `None`
+    /// - names are trimmed to a single line and an empty name is `None`
+    /// - quotes, backslashes and control characters are removed from strings
+    /// - an empty shroud is `None` and `-0` is `0`
+    ///
+    /// For any file with finite numbers, within the default
+    /// [`ParseLimits`](crate::ParseLimits), the canonical form survives a round trip:
+    /// ```
+    /// use reassembly_shape_editor::{parse_shapes_content, serialize_shapes_file};
+    ///
+    /// let file = parse_shapes_content(
+    ///     "{{301, {{verts={{0,0},{10,0},{0,10}}, ports={{2,0.5},{0,0.5,DEFAULT}}}}}, {300, {}}}",
+    /// ).unwrap();
+    /// let canonical = file.normalize();
+    /// assert_eq!(canonical.shapes[0].id, 300);
+    /// assert_eq!(parse_shapes_content(&serialize_shapes_file(&canonical)).unwrap(), canonical);
+    /// ```
+    pub fn normalize(&self) -> ShapesFile {
+        let mut shapes: Vec<Shape> = self.shapes.iter().map(normalize_shape).collect();
+        shapes.sort_by_key(|shape| shape.id);
+        ShapesFile { shapes }
+    }
+}
+
+fn normalize_shape(shape: &Shape) -> Shape {
+    let mut shape = shape.clone();
+
+    // The name is written as a line comment after the ID, so it can't span lines or
+    // open a long comment
+    shape.name = shape.name
+        .map(|name| {
+            let name: String = name.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+            name.trim().trim_start_matches('[').trim().to_string()
+        })
+        .filter(|name| !name.is_empty());
+
+    for scale in &mut shape.scales {
+        for vert in &mut scale.verts {
+            vert.x = zero(vert.x);
+            vert.y = zero(vert.y);
+        }
+        for port in &mut scale.ports {
+            port.position = zero(port.position);
+            if port.port_type == Some(PortType::Default) {
+                port.port_type = None;
+            }
+        }
+        scale.ports.sort_by(|a, b| a.edge.cmp(&b.edge).then(a.position.total_cmp(&b.position)));
+    }
+
+    shape.features = shape.features
+        .map(|features| {
+            let mut features: Vec<String> = features.iter()
+                .flat_map(|feature| feature.split('|'))
+                .map(|feature| clean(feature).trim().to_string())
+                .filter(|feature| !feature.is_empty())
+                .collect();
+            features.sort();
+            features.dedup();
+            features
+        })
+        .filter(|features| !features.is_empty());
+
+    shape.durability = shape.durability.map(zero);
+    shape.density = shape.density.map(zero);
+    shape.grow_rate = shape.grow_rate.map(zero);
+
+    shape.shroud = shape.shroud.filter(|shroud| !shroud.is_empty());
+    for component in shape.shroud.iter_mut().flatten() {
+        component.size = (zero(component.size.0), zero(component.size.1));
+        component.offset = (zero(component.offset.0), zero(component.offset.1), zero(component.offset.2));
+        component.taper = zero(component.taper);
+        component.angle = zero(component.angle);
+        component.shape = clean(&component.shape);
+    }
+
+    if let Some(cannon) = &mut shape.cannon {
+        for value in [
+            &mut cannon.damage,
+            &mut cannon.power,
+            &mut cannon.rounds_per_sec,
+            &mut cannon.muzzle_vel,
+            &mut cannon.range,
+            &mut cannon.spread,
+        ] {
+            *value = zero(*value);
+        }
+        cannon.burstyness = cannon.burstyness.map(zero);
+        cannon.explosive = cannon.explosive.as_deref().map(clean);
+        if let Some(fragment) = &mut cannon.fragment {
+            fragment.muzzle_vel = zero(fragment.muzzle_vel);
+            fragment.spread = zero(fragment.spread);
+            fragment.damage = zero(fragment.damage);
+            fragment.range = zero(fragment.range);
+            fragment.pattern = fragment.pattern.as_deref().map(clean);
+        }
+    }
+
+    if let Some(thruster) = &mut shape.thruster {
+        thruster.force = zero(thruster.force);
+        thruster.power = zero(thruster.power);
+    }
+
+    shape
+}
+
+// -0 is written out as "-0"; adding 0.0 turns it into 0
+fn zero(value: f32) -> f32 {
+    value + 0.0
+}
+
+// Strings are written in double quotes without escapes
+fn clean(text: &str) -> String {
+    text.chars().filter(|&c| c != '"' && c != '\\' && !c.is_control()).collect()
+}
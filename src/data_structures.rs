@@ -1,4 +1,9 @@
 // Data structures module
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use crate::geometry::{area_for_poly, spanned_area, Vec2};
+use crate::validation::MIN_SHAPE_AREA;
 
 // Структура точки (вершины)
 #[derive(Clone, Debug, PartialEq)]
@@ -15,6 +20,51 @@ pub struct Port {
     pub port_type: PortType,
 }
 
+impl Port {
+    // Canonical positions for n equally spaced ports on one edge:
+    // position = (1/n)/2 + k*(1/n) where k=0..n-1
+    pub fn distributed_positions(count: usize) -> Vec<f32> {
+        let step = 1.0 / count as f32;
+        (0..count).map(|k| step / 2.0 + k as f32 * step).collect()
+    }
+    
+    // Snap a position along an edge to the nearest common fraction (eighths or an
+    // n-port formula value) if it is close enough, otherwise leave it unchanged
+    pub fn snap_position(position: f32) -> f32 {
+        const SNAP_TOLERANCE: f32 = 0.02;
+        
+        Port::standard_positions()
+            .map(|candidate| (candidate, (candidate - position).abs()))
+            .filter(|&(_, distance)| distance < SNAP_TOLERANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(candidate, _)| candidate)
+            .unwrap_or(position)
+    }
+    
+    // Nearest common fraction to a position, however far away it is
+    pub fn round_position(position: f32) -> f32 {
+        Port::standard_positions()
+            .min_by(|a, b| (a - position).abs().total_cmp(&(b - position).abs()))
+            .unwrap_or(position)
+    }
+    
+    // Eighths of an edge, then the n-port formula values
+    fn standard_positions() -> impl Iterator<Item = f32> {
+        let eighths = (0..=8).map(|k| k as f32 / 8.0);
+        let formula_values = (1..=8).flat_map(Port::distributed_positions);
+        eighths.chain(formula_values)
+    }
+}
+
+// Скопированная раскладка портов (Copy port layout)
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortLayout {
+    // Ports of a single edge as (position, type), pasted onto another edge
+    Edge(Vec<(f32, PortType)>),
+    // All ports of a shape, pasted onto another shape by edge index
+    Shape(Vec<Port>),
+}
+
 // Перечисление типов портов
 #[derive(Clone, Debug, PartialEq)]
 pub enum PortType {
@@ -31,6 +81,24 @@ pub enum PortType {
 
 // Получение строкового представления типа порта
 impl PortType {
+    // All port types, in the order they are offered in the UI
+    pub const ALL: [PortType; 9] = [
+        PortType::Default,
+        PortType::ThrusterIn,
+        PortType::ThrusterOut,
+        PortType::Missile,
+        PortType::Launcher,
+        PortType::WeaponIn,
+        PortType::WeaponOut,
+        PortType::Root,
+        PortType::None,
+    ];
+    
+    // Game semantics of the type, from the AST documentation
+    pub fn description(&self) -> &'static str {
+        crate::ast::PortType::from_str(&self.to_string()).description()
+    }
+    
     pub fn to_string(&self) -> String {
         match self {
             PortType::Default => "DEFAULT".to_string(),
@@ -62,28 +130,120 @@ impl PortType {
     }
 }
 
+// Outline and ports of one additional scale of a shape
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShapeScale {
+    pub vertices: Vec<Vertex>,
+    pub ports: Vec<Port>,
+}
+
+// Number of ports on each of `edges` edges; ports on edges past the end are ignored
+fn ports_per_edge(ports: &[Port], edges: usize) -> Vec<usize> {
+    let mut counts = vec![0; edges];
+    for port in ports {
+        if let Some(count) = counts.get_mut(port.edge) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+// Rotate an outline so `first` becomes vertex 0. Edge e starts at vertex e, so each
+// port moves with its edge.
+fn rotate_outline(vertices: &mut [Vertex], ports: &mut [Port], first: usize) {
+    let n = vertices.len();
+    vertices.rotate_left(first);
+    for port in ports.iter_mut().filter(|port| port.edge < n) {
+        port.edge = (port.edge + n - first) % n;
+    }
+}
+
+// Swap vertex `idx` with the next one. The edge between them now runs the other way,
+// so its ports are mirrored along it; the neighbouring edges keep theirs.
+fn swap_outline_vertices(vertices: &mut [Vertex], ports: &mut [Port], idx: usize) {
+    vertices.swap(idx, idx + 1);
+    for port in ports.iter_mut().filter(|port| port.edge == idx) {
+        port.position = 1.0 - port.position;
+    }
+}
+
 // Структура формы
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Shape {
     pub id: usize,
     pub name: String,
     pub vertices: Vec<Vertex>,
     pub ports: Vec<Port>,
-    pub selected_vertex: Option<usize>,
-    pub selected_port: Option<usize>,
     pub launcher_radial: bool,
+    // Scales 2, 3, ... from shapes.lua; `vertices` and `ports` are scale 1
+    pub scales: Vec<ShapeScale>,
 }
 
-// Implement PartialEq to compare shapes for undo/redo functionality
-impl PartialEq for Shape {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id &&
-        self.name == other.name &&
-        self.vertices == other.vertices &&
-        self.ports == other.ports &&
-        self.launcher_radial == other.launcher_radial
-        // Note: We deliberately exclude selected_vertex and selected_port from comparison
-        // since those are UI state rather than actual data we want to track for undo/redo
+// What is selected in the shape being edited. The editor keeps it apart from the
+// shapes so that selecting never copies a shape shared with the undo history.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    pub vertex: Option<usize>,
+    pub port: Option<usize>,
+    // Extra ports selected together with `port` (Shift+click)
+    pub ports: Vec<usize>,
+}
+
+impl Selection {
+    // All selected ports of a shape with `port_count` ports (primary selection plus
+    // multi-selection), sorted
+    pub fn port_indices(&self, port_count: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.ports.iter()
+            .copied()
+            .chain(self.port)
+            .filter(|&i| i < port_count)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+    
+    pub fn is_port_selected(&self, idx: usize) -> bool {
+        self.port == Some(idx) || self.ports.contains(&idx)
+    }
+    
+    // Select a single vertex, dropping any port selection
+    pub fn select_vertex(&mut self, idx: Option<usize>) {
+        self.select_port(None);
+        self.vertex = idx;
+    }
+    
+    // Select a single port, dropping any multi-selection
+    pub fn select_port(&mut self, idx: Option<usize>) {
+        self.port = idx;
+        self.ports.clear();
+        self.vertex = None;
+    }
+    
+    // Add or remove a port from the multi-selection
+    pub fn toggle_port(&mut self, idx: usize) {
+        // Keep the current primary selection as part of the set
+        if let Some(primary) = self.port {
+            if !self.ports.contains(&primary) {
+                self.ports.push(primary);
+            }
+        }
+        
+        if let Some(pos) = self.ports.iter().position(|&i| i == idx) {
+            self.ports.remove(pos);
+            self.port = self.ports.last().copied();
+        } else {
+            self.ports.push(idx);
+            self.port = Some(idx);
+        }
+        self.vertex = None;
+    }
+    
+    // Drop whatever no longer exists in `shape`
+    pub fn clamp_to(&mut self, shape: &Shape) {
+        self.vertex = self.vertex.filter(|&i| i < shape.vertices.len());
+        self.port = self.port.filter(|&i| i < shape.ports.len());
+        self.ports.retain(|&i| i < shape.ports.len());
     }
 }
 
@@ -94,12 +254,286 @@ impl Shape {
             name: format!("Shape_{}", id),
             vertices: vec![],
             ports: vec![],
-            selected_vertex: None,
-            selected_port: None,
             launcher_radial: false,
+            scales: vec![],
         }
     }
-
+    
+    // Length of the edge starting at vertex `edge`
+    pub fn edge_length(&self, edge: usize) -> f32 {
+        let n = self.vertices.len();
+        if edge >= n {
+            return 0.0;
+        }
+        let a = &self.vertices[edge];
+        let b = &self.vertices[(edge + 1) % n];
+        ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+    }
+    
+    // Angle of the edge starting at vertex `edge`, in degrees
+    pub fn edge_angle(&self, edge: usize) -> f32 {
+        let n = self.vertices.len();
+        if edge >= n {
+            return 0.0;
+        }
+        let a = &self.vertices[edge];
+        let b = &self.vertices[(edge + 1) % n];
+        (b.y - a.y).atan2(b.x - a.x).to_degrees()
+    }
+    
+    // Signed polygon area, positive for counter-clockwise winding
+    pub fn signed_area(&self) -> f32 {
+        // geometry counts clockwise as positive
+        -area_for_poly(&self.points())
+    }
+    
+    fn points(&self) -> Vec<Vec2> {
+        self.vertices.iter().map(|v| Vec2::new(v.x, v.y)).collect()
+    }
+    
+    // Too few vertices or no area to speak of: the game can't build a block from it
+    pub fn is_degenerate(&self) -> bool {
+        self.vertices.len() < 3 || spanned_area(&self.points()) < MIN_SHAPE_AREA
+    }
+    
+    // Area-weighted center of the polygon; the vertex average for degenerate shapes
+    pub fn centroid(&self) -> Option<Vertex> {
+        let n = self.vertices.len();
+        if n == 0 {
+            return None;
+        }
+        
+        let area = self.signed_area();
+        if area.abs() < 1e-6 {
+            let (sx, sy) = self.vertices.iter().fold((0.0, 0.0), |(sx, sy), v| (sx + v.x, sy + v.y));
+            return Some(Vertex { x: sx / n as f32, y: sy / n as f32 });
+        }
+        
+        let (mut cx, mut cy) = (0.0, 0.0);
+        for i in 0..n {
+            let a = &self.vertices[i];
+            let b = &self.vertices[(i + 1) % n];
+            let cross = a.x * b.y - b.x * a.y;
+            cx += (a.x + b.x) * cross;
+            cy += (a.y + b.y) * cross;
+        }
+        Some(Vertex { x: cx / (6.0 * area), y: cy / (6.0 * area) })
+    }
+    
+    // Interior angle at a vertex, in degrees; reflex corners are above 180
+    pub fn interior_angle(&self, vertex: usize) -> f32 {
+        let n = self.vertices.len();
+        if vertex >= n || n < 3 {
+            return 0.0;
+        }
+        let prev = &self.vertices[(vertex + n - 1) % n];
+        let cur = &self.vertices[vertex];
+        let next = &self.vertices[(vertex + 1) % n];
+        let (ax, ay) = (cur.x - prev.x, cur.y - prev.y);
+        let (bx, by) = (next.x - cur.x, next.y - cur.y);
+        let turn = (ax * by - ay * bx).atan2(ax * bx + ay * by).to_degrees();
+        if self.signed_area() >= 0.0 {
+            180.0 - turn
+        } else {
+            180.0 + turn
+        }
+    }
+    
+    // Unit normal of an edge pointing out of the shape, taking winding into account
+    pub fn edge_normal(&self, edge: usize) -> Option<(f32, f32)> {
+        if self.vertices.len() < 3 {
+            return None;
+        }
+        // The winding normal, turned round where the winding is reversed
+        let (x, y) = self.winding_normal(edge)?;
+        let sign = if self.signed_area() >= 0.0 { 1.0 } else { -1.0 };
+        Some((x * sign, y * sign))
+    }
+    
+    // Whether the vertices run clockwise. The game takes the right of each edge, in
+    // vertex order, as the outside, which is only the real outside for the
+    // counter-clockwise winding it expects.
+    pub fn is_winding_reversed(&self) -> bool {
+        self.vertices.len() >= 3 && self.signed_area() < 0.0
+    }
+    
+    // Unit normal on the side of an edge the game treats as outside; it points into
+    // the shape when the winding is reversed
+    pub fn winding_normal(&self, edge: usize) -> Option<(f32, f32)> {
+        let n = self.vertices.len();
+        if edge >= n || n < 2 {
+            return None;
+        }
+        let a = &self.vertices[edge];
+        let b = &self.vertices[(edge + 1) % n];
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= 0.0 {
+            return None;
+        }
+        Some((dy / length, -dx / length))
+    }
+    
+    // Absolute coordinates of a port, interpolated along its edge
+    pub fn port_position(&self, port_idx: usize) -> Option<Vertex> {
+        let port = self.ports.get(port_idx)?;
+        let n = self.vertices.len();
+        if port.edge >= n {
+            return None;
+        }
+        let a = &self.vertices[port.edge];
+        let b = &self.vertices[(port.edge + 1) % n];
+        Some(Vertex {
+            x: a.x + (b.x - a.x) * port.position,
+            y: a.y + (b.y - a.y) * port.position,
+        })
+    }
+    
+    // Edges whose port count in the given additional scale (0 = scale 2) differs from
+    // scale 1, including edges only one of the two has
+    pub fn scale_port_mismatches(&self, scale: usize) -> Vec<usize> {
+        let target = match self.scales.get(scale) {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+        let source_counts = ports_per_edge(&self.ports, self.vertices.len());
+        let target_counts = ports_per_edge(&target.ports, target.vertices.len());
+        (0..source_counts.len().max(target_counts.len()))
+            .filter(|&edge| source_counts.get(edge) != target_counts.get(edge))
+            .collect()
+    }
+    
+    // Copy the ports of scale 1 onto an additional scale, edge by edge at the same
+    // normalized position. Returns the edges of scale 1 the target scale doesn't have,
+    // whose ports were left out.
+    pub fn project_ports_to_scale(&mut self, scale: usize) -> Vec<usize> {
+        let target = match self.scales.get_mut(scale) {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+        let target_edges = target.vertices.len();
+        
+        // Ports on edges scale 1 doesn't have stay where they are
+        target.ports.retain(|port| port.edge >= self.vertices.len());
+        let mut skipped = Vec::new();
+        for port in &self.ports {
+            if port.edge < target_edges {
+                target.ports.push(port.clone());
+            } else if !skipped.contains(&port.edge) {
+                skipped.push(port.edge);
+            }
+        }
+        target.ports.sort_by(|a, b| a.edge.cmp(&b.edge).then(a.position.total_cmp(&b.position)));
+        skipped
+    }
+    
+    // Make `first` vertex 0, in scale 1 and in every scale with as many vertices
+    pub fn rotate_vertices(&mut self, first: usize) {
+        let n = self.vertices.len();
+        if first == 0 || first >= n {
+            return;
+        }
+        rotate_outline(&mut self.vertices, &mut self.ports, first);
+        for scale in self.scales.iter_mut().filter(|scale| scale.vertices.len() == n) {
+            rotate_outline(&mut scale.vertices, &mut scale.ports, first);
+        }
+    }
+    
+    // Swap vertex `idx` with the next one, in scale 1 and in every scale with as many
+    // vertices
+    pub fn swap_with_next_vertex(&mut self, idx: usize) {
+        let n = self.vertices.len();
+        if idx + 1 >= n {
+            return;
+        }
+        swap_outline_vertices(&mut self.vertices, &mut self.ports, idx);
+        for scale in self.scales.iter_mut().filter(|scale| scale.vertices.len() == n) {
+            swap_outline_vertices(&mut scale.vertices, &mut scale.ports, idx);
+        }
+    }
+    
+    // Every outline of the shape: scale 1 and the extra scales
+    pub(crate) fn outlines_mut(&mut self) -> impl Iterator<Item = (&mut Vec<Vertex>, &mut Vec<Port>)> {
+        std::iter::once((&mut self.vertices, &mut self.ports))
+            .chain(self.scales.iter_mut().map(|scale| (&mut scale.vertices, &mut scale.ports)))
+    }
+    
+    // Turn every scale about the origin, counter-clockwise in degrees
+    pub fn rotate_about_origin(&mut self, degrees: f32) {
+        self.rotate_about(&Vertex { x: 0.0, y: 0.0 }, degrees);
+    }
+    
+    // Turn every scale about `pivot`, counter-clockwise in degrees
+    pub fn rotate_about(&mut self, pivot: &Vertex, degrees: f32) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        for (vertices, _) in self.outlines_mut() {
+            for v in vertices.iter_mut() {
+                let (x, y) = (v.x - pivot.x, v.y - pivot.y);
+                *v = Vertex { x: pivot.x + x * cos - y * sin, y: pivot.y + x * sin + y * cos };
+            }
+        }
+    }
+    
+    // Stretch every scale away from the origin; the factors must be positive
+    pub fn scale_about_origin(&mut self, sx: f32, sy: f32) {
+        self.scale_about(&Vertex { x: 0.0, y: 0.0 }, sx, sy);
+    }
+    
+    // Stretch every scale away from `pivot`; the factors must be positive
+    pub fn scale_about(&mut self, pivot: &Vertex, sx: f32, sy: f32) {
+        for (vertices, _) in self.outlines_mut() {
+            for v in vertices.iter_mut() {
+                v.x = pivot.x + (v.x - pivot.x) * sx;
+                v.y = pivot.y + (v.y - pivot.y) * sy;
+            }
+        }
+    }
+    
+    // Flip every scale across the y axis (`flip_x`) or the x axis
+    pub fn mirror(&mut self, flip_x: bool) {
+        for (vertices, ports) in self.outlines_mut() {
+            for v in vertices.iter_mut() {
+                if flip_x { v.x = -v.x } else { v.y = -v.y }
+            }
+            // Flipping reverses the winding; reversing the vertices restores it, and
+            // each edge then runs the other way under a new number
+            vertices.reverse();
+            let n = vertices.len();
+            for port in ports.iter_mut().filter(|port| port.edge < n) {
+                port.edge = (2 * n - 2 - port.edge) % n;
+                port.position = 1.0 - port.position;
+            }
+        }
+    }
+    
+    // Sort ports into canonical (edge, position) order
+    pub fn sort_ports(&mut self) {
+        self.ports.sort_by(|a, b| a.edge.cmp(&b.edge).then(a.position.total_cmp(&b.position)));
+    }
+    
+    // Space the ports already on an edge by the n-port formula, keeping their order
+    // and types
+    pub fn respace_ports(&mut self, edge: usize) {
+        let mut on_edge: Vec<&mut Port> = self.ports.iter_mut().filter(|p| p.edge == edge).collect();
+        on_edge.sort_by(|a, b| a.position.total_cmp(&b.position));
+        let positions = Port::distributed_positions(on_edge.len());
+        for (port, position) in on_edge.into_iter().zip(positions) {
+            port.position = position;
+        }
+    }
+    
+    // Replace all ports on an edge with `count` equally spaced default ports
+    pub fn distribute_ports(&mut self, edge: usize, count: usize) {
+        self.ports.retain(|p| p.edge != edge);
+        for position in Port::distributed_positions(count) {
+            self.ports.push(Port {
+                edge,
+                position,
+                port_type: PortType::Default,
+            });
+        }
+    }
+    
     // Генерация Lua кода для формы
     pub fn to_lua(&self) -> String {
         let mut lua = format!("    {{{}  --{}\n        {{\n            {{\n", self.id, self.name);
@@ -126,4 +560,43 @@ impl Shape {
         
         lua
     }
-} 
\ No newline at end of file
+} 
+
+// Copy-on-write handle to a shape. Cloning is just a reference count bump, so the
+// editor and every undo snapshot can hold the same shape; the first mutable access
+// through a shared handle copies the shape.
+#[derive(Clone, Debug)]
+pub struct SharedShape(Arc<Shape>);
+
+impl SharedShape {
+    // True if both handles point to the same copy (cheap check before comparing contents)
+    pub fn ptr_eq(&self, other: &SharedShape) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl From<Shape> for SharedShape {
+    fn from(shape: Shape) -> Self {
+        SharedShape(Arc::new(shape))
+    }
+}
+
+impl Deref for SharedShape {
+    type Target = Shape;
+    
+    fn deref(&self) -> &Shape {
+        &self.0
+    }
+}
+
+impl DerefMut for SharedShape {
+    fn deref_mut(&mut self) -> &mut Shape {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl PartialEq for SharedShape {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || *self.0 == *other.0
+    }
+}
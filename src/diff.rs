@@ -0,0 +1,231 @@
+// Line diff between a file on disk and what an export would write, shown as a
+// unified diff before writing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    // "@@ -a,b +c,d @@" header of a group of changes
+    Hunk,
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+// Most differing lines lined up one by one. The search keeps a row of 2d+1 entries
+// for each of the d rounds, so past this the diff falls back to replacing the whole
+// changed middle, which is what it comes to anyway for a file that was rewritten.
+const MAX_EDIT_DISTANCE: isize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff of `old` against `new` with `context` unchanged lines around each
+/// change. Empty if the two are the same line for line.
+pub fn unified_diff(old: &str, new: &str, context: usize) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // Leave the common start and end out of the search, it's usually most of the file
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+
+    let mut edits = vec![Edit::Equal; prefix];
+    edits.extend(edit_script(&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]));
+    edits.extend(std::iter::repeat_n(Edit::Equal, suffix));
+
+    // Line in each file at the start of every edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for edit in &edits {
+        positions.push((i, j));
+        match edit {
+            Edit::Equal => { i += 1; j += 1; }
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    // Ranges of edits to show, changes closer than twice the context merged into one
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, edit) in edits.iter().enumerate() {
+        if *edit == Edit::Equal {
+            continue;
+        }
+        let start = index.saturating_sub(context);
+        let end = (index + 1 + context).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (start, end) in ranges {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        lines.push(DiffLine {
+            kind: LineKind::Hunk,
+            text: format!(
+                "@@ -{} +{} @@",
+                hunk_range(old_start, old_end - old_start),
+                hunk_range(new_start, new_end - new_start)
+            ),
+        });
+        for (edit, &(i, j)) in edits[start..end].iter().zip(&positions[start..end]) {
+            lines.push(match edit {
+                Edit::Equal => DiffLine { kind: LineKind::Context, text: format!(" {}", a[i]) },
+                Edit::Delete => DiffLine { kind: LineKind::Removed, text: format!("-{}", a[i]) },
+                Edit::Insert => DiffLine { kind: LineKind::Added, text: format!("+{}", b[j]) },
+            });
+        }
+    }
+    lines
+}
+
+// "start,count" with 1-based lines; an empty range names the line before it
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+// Shortest edit script turning `a` into `b` (Myers' algorithm)
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let offset = max as usize;
+    // Furthest x reached on each diagonal k = x - y, indexed by k + max
+    let mut v = vec![0isize; 2 * offset + 2];
+    // Diagonals -d..=d of `v` at the start of each round d, all the walk back needs
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut found = false;
+
+    'search: for d in 0..=max.min(MAX_EDIT_DISTANCE) {
+        trace.push(v[(max - d) as usize..=(max + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + max) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+
+    if !found {
+        let mut edits = vec![Edit::Delete; a.len()];
+        edits.extend(std::iter::repeat_n(Edit::Insert, b.len()));
+        return edits;
+    }
+
+    // Walk back from the end through the saved rounds
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, row) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| row[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = if d == 0 { 0 } else { prev_x - prev_k };
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x { Edit::Insert } else { Edit::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[DiffLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn identical_input_has_no_hunks() {
+        assert!(unified_diff("a\nb\nc\n", "a\nb\nc\n", 3).is_empty());
+        assert!(unified_diff("", "", 3).is_empty());
+    }
+
+    #[test]
+    fn pure_insert() {
+        let diff = unified_diff("a\nb\n", "a\nx\nb\n", 1);
+        assert_eq!(texts(&diff), ["@@ -1,2 +1,3 @@", " a", "+x", " b"]);
+    }
+
+    #[test]
+    fn pure_delete() {
+        let diff = unified_diff("a\nx\nb\n", "a\nb\n", 1);
+        assert_eq!(texts(&diff), ["@@ -1,3 +1,2 @@", " a", "-x", " b"]);
+        assert_eq!(diff[2].kind, LineKind::Removed);
+    }
+
+    #[test]
+    fn empty_ranges_name_the_line_before() {
+        let diff = unified_diff("", "x\ny\n", 3);
+        assert_eq!(texts(&diff), ["@@ -0,0 +1,2 @@", "+x", "+y"]);
+
+        let diff = unified_diff("a\nb\n", "", 3);
+        assert_eq!(texts(&diff), ["@@ -1,2 +0,0 @@", "-a", "-b"]);
+
+        // Appended after line 2 with no context
+        let diff = unified_diff("a\nb\n", "a\nb\nc\n", 0);
+        assert_eq!(texts(&diff), ["@@ -2,0 +3 @@", "+c"]);
+    }
+
+    #[test]
+    fn close_changes_share_a_hunk() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\nTWO\n3\n4\n5\nSIX\n7\n8\n9\n10\n";
+        let diff = unified_diff(old, new, 2);
+        assert_eq!(diff.iter().filter(|line| line.kind == LineKind::Hunk).count(), 1);
+        assert_eq!(diff[0].text, "@@ -1,8 +1,8 @@");
+
+        // With no context they are apart
+        let diff = unified_diff(old, new, 0);
+        let hunks: Vec<&str> = diff.iter().filter(|line| line.kind == LineKind::Hunk).map(|line| line.text.as_str()).collect();
+        assert_eq!(hunks, ["@@ -2 +2 @@", "@@ -6 +6 @@"]);
+    }
+
+    #[test]
+    fn rewritten_file_falls_back_to_one_hunk() {
+        let old: String = (0..3000).map(|i| format!("old {}\n", i)).collect();
+        let new: String = (0..3000).map(|i| format!("new {}\n", i)).collect();
+        let diff = unified_diff(&old, &new, 3);
+        assert_eq!(diff[0].text, "@@ -1,3000 +1,3000 @@");
+        assert_eq!(diff.iter().filter(|line| line.kind == LineKind::Removed).count(), 3000);
+        assert_eq!(diff.iter().filter(|line| line.kind == LineKind::Added).count(), 3000);
+    }
+}
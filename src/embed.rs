@@ -0,0 +1,172 @@
+// Handle through which a host page drives an editor embedded in it: loading shapes,
+// reading them back and hearing about edits, for sites with their own save flow.
+// Each handle shares a state with the one editor it started; the editor picks up
+// requests and reports changes there once per frame.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::data_structures::SharedShape;
+use crate::parser::parse_shapes_content;
+use crate::shape_editor::{ImportDialog, ShapeEditor};
+
+#[derive(Default)]
+pub struct EmbedState {
+    canvas_id: String,
+    // Shapes from loadShapes, not yet picked up by the editor
+    pending: Option<Vec<crate::data_structures::Shape>>,
+    // Shapes as last reported, and the same as Lua for getShapes
+    reported: Option<Vec<SharedShape>>,
+    lua: String,
+    on_change: Option<js_sys::Function>,
+    ctx: Option<egui::Context>,
+    destroyed: bool,
+}
+
+pub type SharedEmbedState = Rc<RefCell<EmbedState>>;
+
+/// Returned by `start`; every method applies to the editor started on that canvas
+#[wasm_bindgen]
+pub struct EditorHandle {
+    state: SharedEmbedState,
+}
+
+pub fn handle(canvas_id: &str) -> EditorHandle {
+    let state = EmbedState { canvas_id: canvas_id.to_string(), ..EmbedState::default() };
+    EditorHandle { state: Rc::new(RefCell::new(state)) }
+}
+
+impl EditorHandle {
+    /// The state to give the editor this handle drives
+    pub fn state(&self) -> SharedEmbedState {
+        Rc::clone(&self.state)
+    }
+
+    fn check_alive(&self) -> Result<(), JsValue> {
+        if self.state.borrow().destroyed {
+            return Err(JsValue::from_str("The editor has been destroyed"));
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl EditorHandle {
+    /// Replace the shapes in the editor with the ones in `lua`. Throws the parse
+    /// error; otherwise onChange fires once the editor has the new shapes.
+    #[wasm_bindgen(js_name = loadShapes)]
+    pub fn load_shapes(&self, lua: &str) -> Result<(), JsValue> {
+        self.check_alive()?;
+        let shapes_file = parse_shapes_content(lua).map_err(|e| JsValue::from_str(&e))?;
+        let shapes = shapes_file.shapes.iter().map(ShapeEditor::convert_from_ast_shape).collect();
+        let mut state = self.state.borrow_mut();
+        state.pending = Some(shapes);
+        if let Some(ctx) = &state.ctx {
+            ctx.request_repaint();
+        }
+        Ok(())
+    }
+
+    /// The shapes in the editor as shapes.lua text, as of the last frame
+    #[wasm_bindgen(js_name = getShapes)]
+    pub fn get_shapes(&self) -> Result<String, JsValue> {
+        self.check_alive()?;
+        Ok(self.state.borrow().lua.clone())
+    }
+
+    /// Call `callback` with the shapes.lua text whenever the shapes change; null
+    /// removes the callback
+    #[wasm_bindgen(js_name = onChange)]
+    pub fn on_change(&self, callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+        self.check_alive()?;
+        self.state.borrow_mut().on_change = callback;
+        Ok(())
+    }
+
+    /// Hide the editor and let go of its shapes and callback. The handle can't be
+    /// used afterwards. eframe offers no way to stop its frame loop, so that keeps
+    /// running, idle, on the hidden canvas.
+    pub fn destroy(&self) {
+        let canvas_id = {
+            let mut state = self.state.borrow_mut();
+            state.destroyed = true;
+            state.pending = None;
+            state.on_change = None;
+            state.reported = None;
+            state.lua.clear();
+            if let Some(ctx) = &state.ctx {
+                ctx.request_repaint();
+            }
+            state.canvas_id.clone()
+        };
+
+        let document = web_sys::window().and_then(|window| window.document());
+        let canvas = document.as_ref().and_then(|document| document.get_element_by_id(&canvas_id));
+        if let Some(canvas) = canvas {
+            let _ = canvas.set_attribute("style", "display: none");
+        }
+        let input = document.as_ref().and_then(|document| document.get_element_by_id("file-input"));
+        if let Some(input) = input.and_then(|input| input.dyn_into::<web_sys::HtmlInputElement>().ok()) {
+            input.set_onchange(None);
+        }
+    }
+}
+
+/// Load shapes the page asked for through the editor's handle and report changes to
+/// it; called once per frame
+pub fn sync(editor: &mut ShapeEditor, state: &SharedEmbedState, ctx: &egui::Context) {
+    let (pending, destroyed) = {
+        let mut state = state.borrow_mut();
+        state.ctx.get_or_insert_with(|| ctx.clone());
+        (state.pending.take(), state.destroyed)
+    };
+
+    if destroyed {
+        if !editor.shapes.is_empty() || editor.import_job.is_some() {
+            editor.cancel_import();
+            editor.shapes.clear();
+        }
+        return;
+    }
+
+    if let Some(shapes) = pending {
+        if shapes.is_empty() {
+            editor.save_state();
+            editor.shapes.clear();
+        } else {
+            // The import dialog's replace path, without showing the dialog
+            editor.import_dialog = Some(ImportDialog {
+                include: (0..shapes.len()).collect(),
+                conflicts: BTreeMap::new(),
+                shapes,
+                path: String::new(),
+                replace_session: true,
+            });
+            editor.apply_import_dialog();
+        }
+    }
+
+    // Half-imported files aren't worth reporting
+    if editor.import_job.is_some() {
+        return;
+    }
+    let (changed, callback) = {
+        let mut state = state.borrow_mut();
+        if state.reported.as_deref() == Some(editor.shapes.as_slice()) {
+            return;
+        }
+        // The shapes the editor starts with aren't a change
+        let first = state.reported.is_none();
+        state.reported = Some(editor.shapes.clone());
+        state.lua = crate::recovery::shapes_to_lua(&editor.shapes);
+        (!first, state.on_change.clone().map(|callback| (callback, state.lua.clone())))
+    };
+    if let (true, Some((callback, lua))) = (changed, callback) {
+        if let Err(e) = callback.call1(&JsValue::NULL, &JsValue::from_str(&lua)) {
+            log::warn!("onChange callback failed: {:?}", e);
+        }
+    }
+}
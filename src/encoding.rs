@@ -0,0 +1,42 @@
+// Text of shapes files as they turn up: with or without a byte order mark, in UTF-8
+// or in Windows-1251 (common for Russian comments), with any kind of line ending
+const UTF8_BOM: char = '\u{FEFF}';
+
+// Windows-1251 bytes 0x80-0xBF; 0xC0-0xFF are А-я in order
+const WINDOWS_1251_HIGH: [char; 64] = [
+    '\u{0402}', '\u{0403}', '\u{201A}', '\u{0453}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{20AC}', '\u{2030}', '\u{0409}', '\u{2039}', '\u{040A}', '\u{040C}', '\u{040B}', '\u{040F}',
+    '\u{0452}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{FFFD}', '\u{2122}', '\u{0459}', '\u{203A}', '\u{045A}', '\u{045C}', '\u{045B}', '\u{045F}',
+    '\u{00A0}', '\u{040E}', '\u{045E}', '\u{0408}', '\u{00A4}', '\u{0490}', '\u{00A6}', '\u{00A7}',
+    '\u{0401}', '\u{00A9}', '\u{0404}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{0407}',
+    '\u{00B0}', '\u{00B1}', '\u{0406}', '\u{0456}', '\u{0491}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{0451}', '\u{2116}', '\u{0454}', '\u{00BB}', '\u{0458}', '\u{0405}', '\u{0455}', '\u{0457}',
+];
+
+/// Text of a file read as bytes: UTF-8 if it is valid UTF-8, otherwise Windows-1251,
+/// then normalized as by [`normalize`]
+pub fn decode(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => normalize(text),
+        Err(_) => normalize(&decode_windows_1251(bytes)),
+    }
+}
+
+/// `text` without a byte order mark and with "\n" for every line ending, "\r\n" and a
+/// lone "\r" included
+pub fn normalize(text: &str) -> String {
+    let text = text.strip_prefix(UTF8_BOM).unwrap_or(text);
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn decode_windows_1251(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&byte| match byte {
+            0x00..=0x7F => byte as char,
+            0x80..=0xBF => WINDOWS_1251_HIGH[(byte - 0x80) as usize],
+            // А (U+0410) onwards
+            _ => char::from_u32(0x0410 + (byte - 0xC0) as u32).unwrap_or('\u{FFFD}'),
+        })
+        .collect()
+}
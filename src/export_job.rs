@@ -0,0 +1,61 @@
+// Exports written on a worker thread, so a big file or a slow drive doesn't freeze the
+// editor; the result is picked up the next time the editor polls
+use std::io;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use crate::shape_editor::{ExportDialog, ExportPreview};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shape_editor::ModExportDialog;
+
+// What the editor has to do once the export is done
+pub enum ExportDone {
+    // The file is written; the message to show
+    Written(String),
+    // The mod folder is written
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    WrittenToMod(String),
+    // The diff is ready for the preview window, nothing is written yet
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    Preview(ExportPreview),
+}
+
+// The dialog the export was started from, opened again if it fails so the path can
+// be fixed
+pub enum ExportRetry {
+    Selection(ExportDialog),
+    #[cfg(not(target_arch = "wasm32"))]
+    Mod(ModExportDialog),
+}
+
+pub struct ExportJob {
+    receiver: Receiver<io::Result<ExportDone>>,
+    // File or mod folder being written
+    pub path: String,
+    pub retry: Option<ExportRetry>,
+}
+
+impl ExportJob {
+    // Run `work` on a worker thread. Browsers have no threads, but there the export is
+    // a download that doesn't hold anything up, so it runs right away.
+    pub fn spawn(path: String, retry: Option<ExportRetry>, work: impl FnOnce() -> io::Result<ExportDone> + Send + 'static) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            sender.send(work()).ok();
+        });
+        #[cfg(target_arch = "wasm32")]
+        sender.send(work()).ok();
+
+        Self { receiver, path, retry }
+    }
+
+    // The result, once the work is done
+    pub fn poll(&self) -> Option<io::Result<ExportDone>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("The export thread exited without reporting a result"))),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Procedural shapes for the Generate dialog. Every generator is deterministic in its
+// parameters, so a seed always gives back the same shape.
+use crate::data_structures::{Port, PortType, Shape, Vertex};
+
+// Coordinates are rounded to this many decimals, like hand-written shapes.lua files
+const DECIMALS: f32 = 100.0;
+
+/// Irregular convex blob: points at random radii around a circle, then the convex
+/// hull of those
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsteroidParams {
+    pub vertex_count: usize,
+    pub radius: f32,
+    // How far a point's radius may stray from `radius`, as a fraction of it
+    pub irregularity: f32,
+    // How far a point may stray from even spacing, as a fraction of the spacing
+    pub angle_jitter: f32,
+    pub seed: u64,
+}
+
+impl Default for AsteroidParams {
+    fn default() -> Self {
+        Self {
+            vertex_count: 9,
+            radius: 10.0,
+            irregularity: 0.35,
+            angle_jitter: 0.5,
+            seed: 1,
+        }
+    }
+}
+
+pub fn asteroid(id: usize, params: &AsteroidParams) -> Shape {
+    let mut rng = SplitMix64(params.seed);
+    let n = params.vertex_count.max(3);
+    let step = std::f32::consts::TAU / n as f32;
+
+    let points: Vec<(f32, f32)> = (0..n)
+        .map(|i| {
+            let angle = (i as f32 + params.angle_jitter * rng.range(-0.5, 0.5)) * step;
+            let radius = params.radius * (1.0 + params.irregularity * rng.range(-1.0, 1.0)).max(0.1);
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect();
+
+    let mut shape = Shape::new(id);
+    shape.name = format!("Asteroid_{}", params.seed);
+    shape.vertices = convex_hull(points).into_iter()
+        .map(|(x, y)| Vertex { x: round(x), y: round(y) })
+        .collect();
+    shape.ports = (0..shape.vertices.len())
+        .map(|edge| Port { edge, position: 0.5, port_type: PortType::Default })
+        .collect();
+    shape
+}
+
+/// Mirror-symmetric hull along the x axis, nose towards +x. Notches are cut into
+/// the straight middle of both sides, which makes the outline concave.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HullParams {
+    pub length: f32,
+    pub max_width: f32,
+    // Part of the length over which the nose narrows to a point; 0 is a flat nose
+    pub nose_taper: f32,
+    // Part of the length over which the tail narrows to half the width; 0 is a flat tail
+    pub tail_taper: f32,
+    pub notch_count: usize,
+    // Distance between ports on the straight edges
+    pub port_spacing: f32,
+}
+
+impl Default for HullParams {
+    fn default() -> Self {
+        Self {
+            length: 60.0,
+            max_width: 20.0,
+            nose_taper: 0.3,
+            tail_taper: 0.1,
+            notch_count: 0,
+            port_spacing: 10.0,
+        }
+    }
+}
+
+pub fn hull(id: usize, params: &HullParams) -> Shape {
+    let half_width = params.max_width / 2.0;
+    let nose_taper = params.nose_taper.clamp(0.0, 0.9);
+    let tail_taper = params.tail_taper.clamp(0.0, 0.9 - nose_taper);
+    let (tail_x, nose_x) = (-params.length / 2.0, params.length / 2.0);
+    let body_start = tail_x + params.length * tail_taper;
+    let body_end = nose_x - params.length * nose_taper;
+
+    // Lower side from the tail to the nose; the upper side mirrors it
+    let mut side = vec![(tail_x, if tail_taper > 0.0 { half_width / 2.0 } else { half_width })];
+    side.push((body_start, half_width));
+    if params.notch_count > 0 {
+        let slot = (body_end - body_start) / (2 * params.notch_count + 1) as f32;
+        let floor = half_width * 0.8;
+        for notch in 0..params.notch_count {
+            let start = body_start + slot * (2 * notch + 1) as f32;
+            side.extend([(start, half_width), (start, floor), (start + slot, floor), (start + slot, half_width)]);
+        }
+    }
+    side.push((body_end, half_width));
+    if nose_taper == 0.0 {
+        side.push((nose_x, half_width));
+    }
+
+    let mut points: Vec<(f32, f32)> = side.iter().map(|&(x, y)| (x, -y)).collect();
+    if nose_taper > 0.0 {
+        points.push((nose_x, 0.0));
+    }
+    points.extend(side.iter().rev().copied());
+    points.dedup();
+
+    let mut shape = Shape::new(id);
+    shape.name = format!("Hull_{}", id);
+    shape.vertices = points.into_iter().map(|(x, y)| Vertex { x: round(x), y: round(y) }).collect();
+    shape.ports = straight_edge_ports(&shape.vertices, params.port_spacing);
+    shape
+}
+
+// Ports spread evenly along the edges parallel to either axis, one per `spacing`
+fn straight_edge_ports(vertices: &[Vertex], spacing: f32) -> Vec<Port> {
+    let n = vertices.len();
+    let mut ports = Vec::new();
+    for edge in 0..n {
+        let (a, b) = (&vertices[edge], &vertices[(edge + 1) % n]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = dx.hypot(dy);
+        let straight = dx.abs() < 1e-3 || dy.abs() < 1e-3;
+        if !straight || length < spacing / 2.0 {
+            continue;
+        }
+        let count = (length / spacing).round().max(1.0) as usize;
+        ports.extend((0..count).map(|k| Port {
+            edge,
+            position: (k as f32 + 0.5) / count as f32,
+            port_type: PortType::Default,
+        }));
+    }
+    ports
+}
+
+// Counter-clockwise hull (monotone chain), without collinear points
+fn convex_hull(mut points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<(f32, f32)> = Vec::with_capacity(points.len() * 2);
+    // Lower half left to right, then upper half right to left
+    let reversed: Vec<(f32, f32)> = points.iter().rev().copied().collect();
+    for ordered in [&points, &reversed] {
+        let start = hull.len();
+        for &point in ordered {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point of each half is the first of the other
+        hull.pop();
+    }
+    hull
+}
+
+fn round(value: f32) -> f32 {
+    (value * DECIMALS).round() / DECIMALS
+}
+
+// Small seeded generator, so shapes don't depend on a platform RNG
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [low, high)
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        low + (high - low) * unit
+    }
+}
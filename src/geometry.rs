@@ -1,40 +1,57 @@
-// geometry.rs - general purpose geometry and math
-// - polygon intersections and computational geometry
-// - vector and numerical operations
+//! Polygon geometry for Reassembly shapes: area, moment of inertia, convexity,
+//! intersection tests and bounding boxes.
+//!
+//! Points are [`Vec2`] (glam's `Vec2`) in shape coordinates, and a polygon is a slice
+//! of its vertices in order, with the last connected back to the first. The public
+//! items of this module are kept stable for other Reassembly tools; the rest of the
+//! math helpers here are internal to the editor.
+//!
+//! ```
+//! use reassembly_shape_editor::geometry::{area_for_poly, is_convex_poly, intersect_poly_point, AABBox, Vec2};
+//!
+//! let square = [Vec2::new(0.0, 0.0), Vec2::new(0.0, 10.0), Vec2::new(10.0, 10.0), Vec2::new(10.0, 0.0)];
+//! assert_eq!(area_for_poly(&square), 100.0);
+//! assert!(is_convex_poly(&square));
+//! assert!(intersect_poly_point(&square, Vec2::new(5.0, 5.0)));
+//!
+//! let mut bounds = AABBox::new(square[0], square[0]);
+//! bounds.insert_poly(&square);
+//! assert_eq!(bounds.get_center(), Vec2::new(5.0, 5.0));
+//! ```
 
 use std::f32::consts::PI;
 use std::ops::{Add, Sub, Mul, Neg};
 
 // Constants
 pub const EPSILON: f32 = 0.0001;
-pub const TAU: f32 = PI * 2.0;
-pub const GOLDEN_RATIO: f32 = 1.61803398875;
+pub(crate) const TAU: f32 = PI * 2.0;
+pub(crate) const GOLDEN_RATIO: f32 = 1.61803398875;
 
 // Type aliases for common vector types
 pub type Vec2 = glam::Vec2;
-pub type Vec3 = glam::Vec3;
-pub type Vec4 = glam::Vec4;
-pub type DVec2 = glam::DVec2;
-pub type DVec3 = glam::DVec3;
-pub type DVec4 = glam::DVec4;
-pub type IVec2 = glam::IVec2;
-pub type IVec3 = glam::IVec3;
-pub type IVec4 = glam::IVec4;
+pub(crate) type Vec3 = glam::Vec3;
+pub(crate) type Vec4 = glam::Vec4;
+pub(crate) type DVec2 = glam::DVec2;
+pub(crate) type DVec3 = glam::DVec3;
+pub(crate) type DVec4 = glam::DVec4;
+pub(crate) type IVec2 = glam::IVec2;
+pub(crate) type IVec3 = glam::IVec3;
+pub(crate) type IVec4 = glam::IVec4;
 
 // Short aliases
-pub type F2 = Vec2;
-pub type F3 = Vec3;
-pub type F4 = Vec4;
-pub type D2 = DVec2;
-pub type D3 = DVec3;
-pub type D4 = DVec4;
-pub type I2 = IVec2;
-pub type I3 = IVec3;
-pub type I4 = IVec4;
+pub(crate) type F2 = Vec2;
+pub(crate) type F3 = Vec3;
+pub(crate) type F4 = Vec4;
+pub(crate) type D2 = DVec2;
+pub(crate) type D3 = DVec3;
+pub(crate) type D4 = DVec4;
+pub(crate) type I2 = IVec2;
+pub(crate) type I3 = IVec3;
+pub(crate) type I4 = IVec4;
 
 // Ternary digit: -1, 0, or 1, (false, unknown, true)
 #[derive(Debug, Clone, Copy)]
-pub struct Trit {
+pub(crate) struct Trit {
     val: i8,
 }
 
@@ -98,28 +115,28 @@ impl PartialEq<bool> for Trit {
 
 // Error checking functions
 #[inline]
-pub fn fpu_error(x: f32) -> bool {
+pub(crate) fn fpu_error(x: f32) -> bool {
     x.is_nan() || x.is_infinite()
 }
 
 #[inline]
-pub fn fpu_error_vec2(v: Vec2) -> bool {
+pub(crate) fn fpu_error_vec2(v: Vec2) -> bool {
     fpu_error(v.x) || fpu_error(v.y)
 }
 
 #[inline]
-pub fn fpu_error_vec3(v: Vec3) -> bool {
+pub(crate) fn fpu_error_vec3(v: Vec3) -> bool {
     fpu_error(v.x) || fpu_error(v.y) || fpu_error(v.z)
 }
 
 // Custom min and max functions for Vec2 since it doesn't implement Ord
 #[inline]
-pub fn vec2_min(a: Vec2, b: Vec2) -> Vec2 {
+pub(crate) fn vec2_min(a: Vec2, b: Vec2) -> Vec2 {
     Vec2::new(a.x.min(b.x), a.y.min(b.y))
 }
 
 #[inline]
-pub fn vec2_max(a: Vec2, b: Vec2) -> Vec2 {
+pub(crate) fn vec2_max(a: Vec2, b: Vec2) -> Vec2 {
     Vec2::new(a.x.max(b.x), a.y.max(b.y))
 }
 
@@ -127,7 +144,7 @@ pub fn vec2_max(a: Vec2, b: Vec2) -> Vec2 {
 
 /// Round float to nearest multiple of v
 #[inline]
-pub fn round_to(a: f32, v: f32) -> f32 {
+pub(crate) fn round_to(a: f32, v: f32) -> f32 {
     if v.abs() < EPSILON {
         return a;
     }
@@ -136,13 +153,13 @@ pub fn round_to(a: f32, v: f32) -> f32 {
 
 /// Round Vec2 to nearest multiple of v
 #[inline]
-pub fn round_vec2(a: Vec2, v: f32) -> Vec2 {
+pub(crate) fn round_vec2(a: Vec2, v: f32) -> Vec2 {
     Vec2::new(round_to(a.x, v), round_to(a.y, v))
 }
 
 /// Round double to nearest multiple of v
 #[inline]
-pub fn round_to_f64(a: f64, v: f64) -> f64 {
+pub(crate) fn round_to_f64(a: f64, v: f64) -> f64 {
     if v.abs() < EPSILON as f64 {
         return a;
     }
@@ -151,25 +168,25 @@ pub fn round_to_f64(a: f64, v: f64) -> f64 {
 
 /// Round DVec2 to nearest multiple of v
 #[inline]
-pub fn round_dvec2(a: DVec2, v: f64) -> DVec2 {
+pub(crate) fn round_dvec2(a: DVec2, v: f64) -> DVec2 {
     DVec2::new(round_to_f64(a.x, v), round_to_f64(a.y, v))
 }
 
 /// Round up integer to nearest multiple
 #[inline]
-pub fn round_up(num: i32, mult: i32) -> i32 {
+pub(crate) fn round_up(num: i32, mult: i32) -> i32 {
     ((num + mult - 1) / mult) * mult
 }
 
 /// Round down integer to nearest multiple
 #[inline]
-pub fn round_down(num: i32, mult: i32) -> i32 {
+pub(crate) fn round_down(num: i32, mult: i32) -> i32 {
     (num / mult) * mult
 }
 
 /// Round up to the next power of 2
 #[inline]
-pub fn round_up_power2(v: u32) -> u32 {
+pub(crate) fn round_up_power2(v: u32) -> u32 {
     let mut i = 1;
     while i < v {
         i *= 2;
@@ -179,7 +196,7 @@ pub fn round_up_power2(v: u32) -> u32 {
 
 /// Ceil float to nearest multiple of v
 #[inline]
-pub fn ceil_to(a: f32, v: f32) -> f32 {
+pub(crate) fn ceil_to(a: f32, v: f32) -> f32 {
     if v.abs() < EPSILON {
         return a;
     }
@@ -188,7 +205,7 @@ pub fn ceil_to(a: f32, v: f32) -> f32 {
 
 /// Floor float to nearest multiple of v
 #[inline]
-pub fn floor_to(a: f32, v: f32) -> f32 {
+pub(crate) fn floor_to(a: f32, v: f32) -> f32 {
     if v.abs() < EPSILON {
         return a;
     }
@@ -197,19 +214,19 @@ pub fn floor_to(a: f32, v: f32) -> f32 {
 
 /// Ceil Vec2 to nearest multiple of v
 #[inline]
-pub fn ceil_vec2(a: Vec2, v: f32) -> Vec2 {
+pub(crate) fn ceil_vec2(a: Vec2, v: f32) -> Vec2 {
     Vec2::new(ceil_to(a.x, v), ceil_to(a.y, v))
 }
 
 /// Floor Vec2 to nearest multiple of v
 #[inline]
-pub fn floor_vec2(a: Vec2, v: f32) -> Vec2 {
+pub(crate) fn floor_vec2(a: Vec2, v: f32) -> Vec2 {
     Vec2::new(floor_to(a.x, v), floor_to(a.y, v))
 }
 
 /// Convert float to int using floor
 #[inline]
-pub fn floor_int(f: f32) -> i32 {
+pub(crate) fn floor_int(f: f32) -> i32 {
     debug_assert!(f.abs() < (2 << 23) as f32);
     let i = f as i32;
     if f < 0.0 && f != i as f32 { i - 1 } else { i }
@@ -217,7 +234,7 @@ pub fn floor_int(f: f32) -> i32 {
 
 /// Convert float to int using ceil
 #[inline]
-pub fn ceil_int(f: f32) -> i32 {
+pub(crate) fn ceil_int(f: f32) -> i32 {
     debug_assert!(f.abs() < (2 << 23) as f32);
     let i = f as i32;
     if f >= 0.0 && f != i as f32 { i + 1 } else { i }
@@ -225,7 +242,7 @@ pub fn ceil_int(f: f32) -> i32 {
 
 /// Convert float to int using round
 #[inline]
-pub fn round_int(f: f32) -> i32 {
+pub(crate) fn round_int(f: f32) -> i32 {
     debug_assert!(f.abs() < (2 << 23) as f32);
     if f >= 0.0 {
         (f + 0.49999997).floor() as i32
@@ -236,19 +253,19 @@ pub fn round_int(f: f32) -> i32 {
 
 /// Convert Vec2 to IVec2 using floor
 #[inline]
-pub fn floor_ivec2(f: Vec2) -> IVec2 {
+pub(crate) fn floor_ivec2(f: Vec2) -> IVec2 {
     IVec2::new(floor_int(f.x), floor_int(f.y))
 }
 
 /// Convert Vec2 to IVec2 using ceil
 #[inline]
-pub fn ceil_ivec2(f: Vec2) -> IVec2 {
+pub(crate) fn ceil_ivec2(f: Vec2) -> IVec2 {
     IVec2::new(ceil_int(f.x), ceil_int(f.y))
 }
 
 /// Convert Vec2 to IVec2 using round
 #[inline]
-pub fn round_ivec2(f: Vec2) -> IVec2 {
+pub(crate) fn round_ivec2(f: Vec2) -> IVec2 {
     IVec2::new(round_int(f.x), round_int(f.y))
 }
 
@@ -256,61 +273,61 @@ pub fn round_ivec2(f: Vec2) -> IVec2 {
 
 /// Convert angle to unit vector
 #[inline]
-pub fn angle_to_vector(angle: f32) -> Vec2 {
+pub(crate) fn angle_to_vector(angle: f32) -> Vec2 {
     Vec2::new(angle.cos(), angle.sin())
 }
 
 /// Convert vector to angle
 #[inline]
-pub fn vector_to_angle(vec: Vec2) -> f32 {
+pub(crate) fn vector_to_angle(vec: Vec2) -> f32 {
     vec.y.atan2(vec.x)
 }
 
 /// Convert angle to unit vector (f64 version)
 #[inline]
-pub fn angle_to_vector_f64(angle: f64) -> DVec2 {
+pub(crate) fn angle_to_vector_f64(angle: f64) -> DVec2 {
     DVec2::new(angle.cos(), angle.sin())
 }
 
 /// Convert vector to angle (f64 version)
 #[inline]
-pub fn vector_to_angle_f64(vec: DVec2) -> f64 {
+pub(crate) fn vector_to_angle_f64(vec: DVec2) -> f64 {
     vec.y.atan2(vec.x)
 }
 
 /// Short alias for angle_to_vector
 #[inline]
-pub fn a2v(angle: f32) -> Vec2 {
+pub(crate) fn a2v(angle: f32) -> Vec2 {
     angle_to_vector(angle)
 }
 
 /// Short alias for vector_to_angle
 #[inline]
-pub fn v2a(vec: Vec2) -> f32 {
+pub(crate) fn v2a(vec: Vec2) -> f32 {
     vector_to_angle(vec)
 }
 
 /// Short alias for angle_to_vector (f64 version)
 #[inline]
-pub fn a2v_f64(angle: f64) -> DVec2 {
+pub(crate) fn a2v_f64(angle: f64) -> DVec2 {
     angle_to_vector_f64(angle)
 }
 
 /// Short alias for vector_to_angle (f64 version)
 #[inline]
-pub fn v2a_f64(vec: DVec2) -> f64 {
+pub(crate) fn v2a_f64(vec: DVec2) -> f64 {
     vector_to_angle_f64(vec)
 }
 
 /// Return [-1, 1] indicating how closely the angles are aligned
 #[inline]
-pub fn dot_angles(a: f32, b: f32) -> f32 {
+pub(crate) fn dot_angles(a: f32, b: f32) -> f32 {
     (a - b).cos()
 }
 
 /// Return squared value
 #[inline]
-pub fn squared<T>(val: T) -> T 
+pub(crate) fn squared<T>(val: T) -> T 
 where T: Mul<Output = T> + Copy
 {
     val * val
@@ -318,7 +335,7 @@ where T: Mul<Output = T> + Copy
 
 /// Return sign of value as -1, 0, or 1
 #[inline]
-pub fn sign<T>(val: T) -> T 
+pub(crate) fn sign<T>(val: T) -> T 
 where T: PartialOrd + Sub<Output = T> + From<i8> + Copy
 {
     let zero = T::from(0);
@@ -333,25 +350,25 @@ where T: PartialOrd + Sub<Output = T> + From<i8> + Copy
 
 /// Return sign of Vec2 components
 #[inline]
-pub fn sign_vec2(v: Vec2) -> Vec2 {
+pub(crate) fn sign_vec2(v: Vec2) -> Vec2 {
     Vec2::new(sign(v.x), sign(v.y))
 }
 
 /// Return sign of Vec3 components
 #[inline]
-pub fn sign_vec3(v: Vec3) -> Vec3 {
+pub(crate) fn sign_vec3(v: Vec3) -> Vec3 {
     Vec3::new(sign(v.x), sign(v.y), sign(v.z))
 }
 
 /// Return sign of Vec4 components
 #[inline]
-pub fn sign_vec4(v: Vec4) -> Vec4 {
+pub(crate) fn sign_vec4(v: Vec4) -> Vec4 {
     Vec4::new(sign(v.x), sign(v.y), sign(v.z), sign(v.w))
 }
 
 /// Return sign of value with threshold
 #[inline]
-pub fn sign_int<T>(val: T, threshold: T) -> i8
+pub(crate) fn sign_int<T>(val: T, threshold: T) -> i8
 where T: PartialOrd + Neg<Output = T> + Copy
 {
     if val > threshold {
@@ -365,31 +382,31 @@ where T: PartialOrd + Neg<Output = T> + Copy
 
 /// Rotate vector 90 degrees counter-clockwise
 #[inline]
-pub fn rotate90(v: Vec2) -> Vec2 {
+pub(crate) fn rotate90(v: Vec2) -> Vec2 {
     Vec2::new(-v.y, v.x)
 }
 
 /// Rotate vector 90 degrees clockwise
 #[inline]
-pub fn rotate_n90(v: Vec2) -> Vec2 {
+pub(crate) fn rotate_n90(v: Vec2) -> Vec2 {
     Vec2::new(v.y, -v.x)
 }
 
 /// Calculate distance between two integers
 #[inline]
-pub fn distance_int(a: i32, b: i32) -> i32 {
+pub(crate) fn distance_int(a: i32, b: i32) -> i32 {
     (a - b).abs()
 }
 
 /// Create Vec2 with golden ratio (y to x)
 #[inline]
-pub fn to_golden_ratio_y(y: f32) -> Vec2 {
+pub(crate) fn to_golden_ratio_y(y: f32) -> Vec2 {
     Vec2::new(y * GOLDEN_RATIO, y)
 }
 
 /// Create Vec2 with golden ratio (x to y)
 #[inline]
-pub fn to_golden_ratio_x(x: f32) -> Vec2 {
+pub(crate) fn to_golden_ratio_x(x: f32) -> Vec2 {
     Vec2::new(x, x / GOLDEN_RATIO)
 }
 
@@ -401,7 +418,7 @@ pub fn cross_2d(a: Vec2, b: Vec2) -> f32 {
 
 /// Clamp a value between min and max
 #[inline]
-pub fn clamp<T>(v: T, mn: T, mx: T) -> T
+pub(crate) fn clamp<T>(v: T, mn: T, mx: T) -> T
 where T: PartialOrd
 {
     if v < mn { mn } else if v > mx { mx } else { v }
@@ -409,7 +426,7 @@ where T: PartialOrd
 
 /// Clamp a Vec2 between min and max vectors
 #[inline]
-pub fn clamp_vec2(v: Vec2, mn: Vec2, mx: Vec2) -> Vec2 {
+pub(crate) fn clamp_vec2(v: Vec2, mn: Vec2, mx: Vec2) -> Vec2 {
     Vec2::new(
         clamp(v.x, mn.x, mx.x),
         clamp(v.y, mn.y, mx.y)
@@ -418,7 +435,7 @@ pub fn clamp_vec2(v: Vec2, mn: Vec2, mx: Vec2) -> Vec2 {
 
 /// Clamp the length of a vector between min and max
 #[inline]
-pub fn clamp_length(v: Vec2, mn: f32, mx: f32) -> Vec2 {
+pub(crate) fn clamp_length(v: Vec2, mn: f32, mx: f32) -> Vec2 {
     let len = v.length();
     if len < mn {
         v * (mn / len)
@@ -431,7 +448,7 @@ pub fn clamp_length(v: Vec2, mn: f32, mx: f32) -> Vec2 {
 
 /// Clamp the magnitude of a floating point value between min and max
 #[inline]
-pub fn clamp_mag(v: f32, mn: f32, mx: f32) -> f32 {
+pub(crate) fn clamp_mag(v: f32, mn: f32, mx: f32) -> f32 {
     let vm = v.abs();
     if vm < mn {
         if v > 0.0 { mn } else { -mn }
@@ -444,37 +461,37 @@ pub fn clamp_mag(v: f32, mn: f32, mx: f32) -> f32 {
 
 /// Get maximum dimension of Vec2
 #[inline]
-pub fn max_dim(v: Vec2) -> f32 {
+pub(crate) fn max_dim(v: Vec2) -> f32 {
     v.x.max(v.y)
 }
 
 /// Get minimum dimension of Vec2
 #[inline]
-pub fn min_dim(v: Vec2) -> f32 {
+pub(crate) fn min_dim(v: Vec2) -> f32 {
     v.x.min(v.y)
 }
 
 /// Check if Vec2 is near zero
 #[inline]
-pub fn near_zero(v: Vec2) -> bool {
+pub(crate) fn near_zero(v: Vec2) -> bool {
     v.x.abs() <= EPSILON && v.y.abs() <= EPSILON
 }
 
 /// Check if Vec3 is near zero
 #[inline]
-pub fn near_zero_vec3(v: Vec3) -> bool {
+pub(crate) fn near_zero_vec3(v: Vec3) -> bool {
     v.x.abs() <= EPSILON && v.y.abs() <= EPSILON && v.z.abs() <= EPSILON
 }
 
 /// Check if float is near zero
 #[inline]
-pub fn near_zero_f32(v: f32) -> bool {
+pub(crate) fn near_zero_f32(v: f32) -> bool {
     v.abs() <= EPSILON
 }
 
 /// Modulo for integers that works with negative numbers
 #[inline]
-pub fn modulo(x: i32, y: i32) -> i32 {
+pub(crate) fn modulo(x: i32, y: i32) -> i32 {
     debug_assert!(y > 0);
     
     if x >= 0 {
@@ -493,7 +510,7 @@ pub fn modulo(x: i32, y: i32) -> i32 {
 
 /// Modulo for floats that works with negative numbers
 #[inline]
-pub fn modulo_f32(x: f32, y: f32) -> f32 {
+pub(crate) fn modulo_f32(x: f32, y: f32) -> f32 {
     let m = x - y * (x / y).floor();
     
     if y > 0.0 {
@@ -517,49 +534,49 @@ pub fn modulo_f32(x: f32, y: f32) -> f32 {
 
 /// Modulo for Vec2
 #[inline]
-pub fn modulo_vec2(val: Vec2, div: Vec2) -> Vec2 {
+pub(crate) fn modulo_vec2(val: Vec2, div: Vec2) -> Vec2 {
     Vec2::new(modulo_f32(val.x, div.x), modulo_f32(val.y, div.y))
 }
 
 /// Modulo for Vec2 with scalar divisor
 #[inline]
-pub fn modulo_vec2_scalar(val: Vec2, div: f32) -> Vec2 {
+pub(crate) fn modulo_vec2_scalar(val: Vec2, div: f32) -> Vec2 {
     Vec2::new(modulo_f32(val.x, div), modulo_f32(val.y, div))
 }
 
 /// Return value with smallest absolute value
 #[inline]
-pub fn min_abs(a: f32, b: f32) -> f32 {
+pub(crate) fn min_abs(a: f32, b: f32) -> f32 {
     if a.abs() <= b.abs() { a } else { b }
 }
 
 /// Return value with largest absolute value
 #[inline]
-pub fn max_abs(a: f32, b: f32) -> f32 {
+pub(crate) fn max_abs(a: f32, b: f32) -> f32 {
     if a.abs() >= b.abs() { a } else { b }
 }
 
 /// Return Vec2 with min absolute values
 #[inline]
-pub fn min_abs_vec2(a: Vec2, b: Vec2) -> Vec2 {
+pub(crate) fn min_abs_vec2(a: Vec2, b: Vec2) -> Vec2 {
     Vec2::new(min_abs(a.x, b.x), min_abs(a.y, b.y))
 }
 
 /// Return Vec2 with max absolute values
 #[inline]
-pub fn max_abs_vec2(a: Vec2, b: Vec2) -> Vec2 {
+pub(crate) fn max_abs_vec2(a: Vec2, b: Vec2) -> Vec2 {
     Vec2::new(max_abs(a.x, b.x), max_abs(a.y, b.y))
 }
 
 /// Return shortest signed difference between angles [0, pi]
 #[inline]
-pub fn distance_angles(a: f32, b: f32) -> f32 {
+pub(crate) fn distance_angles(a: f32, b: f32) -> f32 {
     modulo_f32(b - a + 1.5 * TAU, TAU) - PI
 }
 
 /// Normalize vector safely (prevents NaN)
 #[inline]
-pub fn normalize_safe(a: Vec2) -> Vec2 {
+pub(crate) fn normalize_safe(a: Vec2) -> Vec2 {
     if near_zero(a) {
         debug_assert!(false, "length < epsilon: [{}, {}]", a.x, a.y);
         a
@@ -570,7 +587,7 @@ pub fn normalize_safe(a: Vec2) -> Vec2 {
 
 /// Normalize vector safely (returns zero if near-zero input)
 #[inline]
-pub fn normalize_or_zero(a: Vec2) -> Vec2 {
+pub(crate) fn normalize_or_zero(a: Vec2) -> Vec2 {
     if near_zero(a) {
         Vec2::ZERO
     } else {
@@ -580,19 +597,19 @@ pub fn normalize_or_zero(a: Vec2) -> Vec2 {
 
 /// Raises each component of a Vec2 to power e
 #[inline]
-pub fn pow_vec2(v: Vec2, e: f32) -> Vec2 {
+pub(crate) fn pow_vec2(v: Vec2, e: f32) -> Vec2 {
     Vec2::new(v.x.powf(e), v.y.powf(e))
 }
 
 /// Raises each component of a Vec3 to power e
 #[inline]
-pub fn pow_vec3(v: Vec3, e: f32) -> Vec3 {
+pub(crate) fn pow_vec3(v: Vec3, e: f32) -> Vec3 {
     Vec3::new(v.x.powf(e), v.y.powf(e), v.z.powf(e))
 }
 
 /// Limit vector length to maximum
 #[inline]
-pub fn max_length(v: Vec2, max: f32) -> Vec2 {
+pub(crate) fn max_length(v: Vec2, max: f32) -> Vec2 {
     let l = v.length();
     if l > max {
         v * (max / l)
@@ -603,7 +620,7 @@ pub fn max_length(v: Vec2, max: f32) -> Vec2 {
 
 /// Enforce minimum vector length
 #[inline]
-pub fn min_length(v: Vec2, min: f32) -> Vec2 {
+pub(crate) fn min_length(v: Vec2, min: f32) -> Vec2 {
     let l = v.length();
     if l < min {
         v * (min / l)
@@ -614,43 +631,43 @@ pub fn min_length(v: Vec2, min: f32) -> Vec2 {
 
 /// Calculate squared length of Vec2
 #[inline]
-pub fn length_sqr(a: Vec2) -> f32 {
+pub(crate) fn length_sqr(a: Vec2) -> f32 {
     a.x * a.x + a.y * a.y
 }
 
 /// Calculate squared distance between two Vec2
 #[inline]
-pub fn distance_sqr(a: Vec2, b: Vec2) -> f32 {
+pub(crate) fn distance_sqr(a: Vec2, b: Vec2) -> f32 {
     length_sqr(a - b)
 }
 
 /// Calculate squared length of Vec3
 #[inline]
-pub fn length_sqr_vec3(a: Vec3) -> f32 {
+pub(crate) fn length_sqr_vec3(a: Vec3) -> f32 {
     a.x * a.x + a.y * a.y + a.z * a.z
 }
 
 /// Calculate squared distance between two Vec3
 #[inline]
-pub fn distance_sqr_vec3(a: Vec3, b: Vec3) -> f32 {
+pub(crate) fn distance_sqr_vec3(a: Vec3, b: Vec3) -> f32 {
     length_sqr_vec3(a - b)
 }
 
 /// Convert radians to degrees
 #[inline]
-pub fn to_degrees(radians: f32) -> f32 {
+pub(crate) fn to_degrees(radians: f32) -> f32 {
     radians * 180.0 / PI
 }
 
 /// Convert degrees to radians
 #[inline]
-pub fn to_radians(degrees: f32) -> f32 {
+pub(crate) fn to_radians(degrees: f32) -> f32 {
     degrees * PI / 180.0
 }
 
 /// Rotate vector v by angle a
 #[inline]
-pub fn rotate(v: Vec2, a: f32) -> Vec2 {
+pub(crate) fn rotate(v: Vec2, a: f32) -> Vec2 {
     let cosa = a.cos();
     let sina = a.sin();
     Vec2::new(
@@ -661,7 +678,7 @@ pub fn rotate(v: Vec2, a: f32) -> Vec2 {
 
 /// Rotate vector v counter-clockwise by vector a
 #[inline]
-pub fn rotate_vec(v: Vec2, a: Vec2) -> Vec2 {
+pub(crate) fn rotate_vec(v: Vec2, a: Vec2) -> Vec2 {
     Vec2::new(
         a.x * v.x - a.y * v.y,
         a.y * v.x + a.x * v.y
@@ -670,7 +687,7 @@ pub fn rotate_vec(v: Vec2, a: Vec2) -> Vec2 {
 
 /// Rotate vector v clockwise by vector a
 #[inline]
-pub fn rotate_vec_clockwise(v: Vec2, a: Vec2) -> Vec2 {
+pub(crate) fn rotate_vec_clockwise(v: Vec2, a: Vec2) -> Vec2 {
     Vec2::new(
         a.x * v.x + a.y * v.y,
         -a.y * v.x + a.x * v.y
@@ -679,55 +696,55 @@ pub fn rotate_vec_clockwise(v: Vec2, a: Vec2) -> Vec2 {
 
 /// Swap X and Y components
 #[inline]
-pub fn swap_xy(v: Vec2) -> Vec2 {
+pub(crate) fn swap_xy(v: Vec2) -> Vec2 {
     Vec2::new(v.y, v.x)
 }
 
 /// Flip Y component
 #[inline]
-pub fn flip_y(v: f32) -> Vec2 {
+pub(crate) fn flip_y(v: f32) -> Vec2 {
     Vec2::new(v, -v)
 }
 
 /// Flip X component
 #[inline]
-pub fn flip_x(v: f32) -> Vec2 {
+pub(crate) fn flip_x(v: f32) -> Vec2 {
     Vec2::new(-v, v)
 }
 
 /// Flip Y component of Vec2
 #[inline]
-pub fn flip_y_vec2(v: Vec2) -> Vec2 {
+pub(crate) fn flip_y_vec2(v: Vec2) -> Vec2 {
     Vec2::new(v.x, -v.y)
 }
 
 /// Flip X component of Vec2
 #[inline]
-pub fn flip_x_vec2(v: Vec2) -> Vec2 {
+pub(crate) fn flip_x_vec2(v: Vec2) -> Vec2 {
     Vec2::new(-v.x, v.y)
 }
 
 /// Create Vec2 with only Y component
 #[inline]
-pub fn just_y(v: impl Into<f32>) -> Vec2 {
+pub(crate) fn just_y(v: impl Into<f32>) -> Vec2 {
     Vec2::new(0.0, v.into())
 }
 
 /// Create Vec2 with only X component
 #[inline]
-pub fn just_x(v: impl Into<f32>) -> Vec2 {
+pub(crate) fn just_x(v: impl Into<f32>) -> Vec2 {
     Vec2::new(v.into(), 0.0)
 }
 
 /// Create Vec3 with only Z component
 #[inline]
-pub fn just_z(v: impl Into<f32>) -> Vec3 {
+pub(crate) fn just_z(v: impl Into<f32>) -> Vec3 {
     Vec3::new(0.0, 0.0, v.into())
 }
 
 /// Linear interpolation between from and to values
 #[inline]
-pub fn lerp<T>(from: T, to: T, v: f32) -> T
+pub(crate) fn lerp<T>(from: T, to: T, v: f32) -> T
 where T: Add<Output = T> + Mul<f32, Output = T> + Copy
 {
     from * (1.0 - v) + to * v
@@ -735,7 +752,7 @@ where T: Add<Output = T> + Mul<f32, Output = T> + Copy
 
 /// Clamped linear interpolation (ensures v is between 0 and 1)
 #[inline]
-pub fn clamp_lerp<T>(from: T, to: T, v: f32) -> T
+pub(crate) fn clamp_lerp<T>(from: T, to: T, v: f32) -> T
 where T: Add<Output = T> + Mul<f32, Output = T> + Copy
 {
     lerp(from, to, clamp(v, 0.0, 1.0))
@@ -743,13 +760,13 @@ where T: Add<Output = T> + Mul<f32, Output = T> + Copy
 
 /// Linear interpolation between angles
 #[inline]
-pub fn lerp_angles(a: f32, b: f32, v: f32) -> f32 {
+pub(crate) fn lerp_angles(a: f32, b: f32, v: f32) -> f32 {
     vector_to_angle(lerp(angle_to_vector(a), angle_to_vector(b), v))
 }
 
 /// Inverse linear interpolation - returns ratio between a and b where val falls
 #[inline]
-pub fn inv_lerp<T>(zero: T, one: T, val: T) -> f32
+pub(crate) fn inv_lerp<T>(zero: T, one: T, val: T) -> f32
 where T: Sub<Output = T> + Copy, f32: From<T>
 {
     let denom = f32::from(one - zero);
@@ -761,7 +778,7 @@ where T: Sub<Output = T> + Copy, f32: From<T>
 
 /// Normalized sigmoid (s-shape) function
 #[inline]
-pub fn signorm(x: f32, k: f32) -> f32 {
+pub(crate) fn signorm(x: f32, k: f32) -> f32 {
     let mut y = 0.0;
     let mut x = x;
     let mut k = k;
@@ -777,27 +794,27 @@ pub fn signorm(x: f32, k: f32) -> f32 {
 
 /// Smooth step implementation
 #[inline]
-pub fn smootherstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+pub(crate) fn smootherstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     let x = clamp((x - edge0) / (edge1 - edge0), 0.0, 1.0);
     x * x * x * (x * (x * 6.0 - 15.0) + 10.0)
 }
 
 /// Map unorm to bell curve (0->0, 0.5->1, 1->0)
 #[inline]
-pub fn bellcurve(x: f32) -> f32 {
+pub(crate) fn bellcurve(x: f32) -> f32 {
     0.5 * (-f32::cos(TAU * x) + 1.0)
 }
 
 /// Gaussian distribution
 #[inline]
-pub fn gaussian(x: f32, stdev: f32) -> f32 {
+pub(crate) fn gaussian(x: f32, stdev: f32) -> f32 {
     let sqrt_2pi = 2.5066282746310002;
     f32::exp(-(x * x) / (2.0 * stdev * stdev)) / (stdev * sqrt_2pi)
 }
 
 /// Check if point is within range
 #[inline]
-pub fn is_in_range<T>(p: T, mn: T, mx: T) -> bool
+pub(crate) fn is_in_range<T>(p: T, mn: T, mx: T) -> bool
 where T: PartialOrd
 {
     mn <= p && p < mx
@@ -805,7 +822,7 @@ where T: PartialOrd
 
 /// Check if Vec2 is within range
 #[inline]
-pub fn is_in_range_vec2(p: Vec2, mn: Vec2, mx: Vec2) -> bool {
+pub(crate) fn is_in_range_vec2(p: Vec2, mn: Vec2, mx: Vec2) -> bool {
     is_in_range(p.x, mn.x, mx.x) && is_in_range(p.y, mn.y, mx.y)
 }
 
@@ -818,7 +835,7 @@ pub fn intersect_point_circle(p: Vec2, c: Vec2, r: f32) -> bool {
 
 /// Check if point is inside ring
 #[inline]
-pub fn intersect_point_ring(p: Vec2, c: Vec2, min_r: f32, max_r: f32) -> bool {
+pub(crate) fn intersect_point_ring(p: Vec2, c: Vec2, min_r: f32, max_r: f32) -> bool {
     let x = p - c;
     let v = x.x * x.x + x.y * x.y;
     (min_r * min_r) <= v && v <= (max_r * max_r)
@@ -826,12 +843,12 @@ pub fn intersect_point_ring(p: Vec2, c: Vec2, min_r: f32, max_r: f32) -> bool {
 
 /// Check if two circles intersect
 #[inline]
-pub fn intersect_circle_circle(p: Vec2, pr: f32, c: Vec2, cr: f32) -> bool {
+pub(crate) fn intersect_circle_circle(p: Vec2, pr: f32, c: Vec2, cr: f32) -> bool {
     intersect_point_circle(p, c, pr + cr)
 }
 
 /// Intersect two circles
-pub fn intersect_circle_circle_points(p: Vec2, pr: f32, c: Vec2, cr: f32) -> Vec<Vec2> {
+pub(crate) fn intersect_circle_circle_points(p: Vec2, pr: f32, c: Vec2, cr: f32) -> Vec<Vec2> {
     let mut results = Vec::new();
     
     let d = p.distance(c);
@@ -1016,7 +1033,7 @@ pub fn closest_point_on_segment(a: Vec2, b: Vec2, p: Vec2) -> Vec2 {
 }
 
 /// Return the point on ray starting at point a in direction dir closest to point p
-pub fn closest_point_on_ray(a: Vec2, dir: Vec2, p: Vec2) -> Vec2 {
+pub(crate) fn closest_point_on_ray(a: Vec2, dir: Vec2, p: Vec2) -> Vec2 {
     if near_zero(dir) {
         return a;
     }
@@ -1032,13 +1049,13 @@ pub fn closest_point_on_ray(a: Vec2, dir: Vec2, p: Vec2) -> Vec2 {
 }
 
 /// Return true if ray starting at point E in direction d intersects circle at point C with radius r
-pub fn intersect_ray_circle(e: Vec2, d: Vec2, c: Vec2, r: f32) -> bool {
+pub(crate) fn intersect_ray_circle(e: Vec2, d: Vec2, c: Vec2, r: f32) -> bool {
     let closest = closest_point_on_ray(e, d, c);
     intersect_point_circle(closest, c, r)
 }
 
 /// Intersect ray with circle, returning intersection points
-pub fn intersect_ray_circle_points(e: Vec2, d: Vec2, c: Vec2, r: f32) -> Vec<Vec2> {
+pub(crate) fn intersect_ray_circle_points(e: Vec2, d: Vec2, c: Vec2, r: f32) -> Vec<Vec2> {
     let mut results = Vec::new();
     
     // Normalize direction
@@ -1098,14 +1115,14 @@ pub fn intersect_ray_segment(ray_pt: Vec2, ray_dir: Vec2, sa: Vec2, sb: Vec2) ->
 
 /// Check if two rectangles intersect
 #[inline]
-pub fn intersect_rectangle_rectangle(a: Vec2, ar: Vec2, b: Vec2, br: Vec2) -> bool {
+pub(crate) fn intersect_rectangle_rectangle(a: Vec2, ar: Vec2, b: Vec2, br: Vec2) -> bool {
     let delta = (a - b).abs();
     delta.x <= (ar.x + br.x) && delta.y <= (ar.y + br.y)
 }
 
 /// Check if circle intersects rectangle
 #[inline]
-pub fn intersect_circle_rectangle(circle: Vec2, circle_r: f32, rect_pos: Vec2, rect_half_size: Vec2) -> bool {
+pub(crate) fn intersect_circle_rectangle(circle: Vec2, circle_r: f32, rect_pos: Vec2, rect_half_size: Vec2) -> bool {
     let circle_distance = (circle - rect_pos).abs();
     
     // Circle is too far away from rectangle
@@ -1127,14 +1144,14 @@ pub fn intersect_circle_rectangle(circle: Vec2, circle_r: f32, rect_pos: Vec2, r
 
 /// Check if point is inside rectangle
 #[inline]
-pub fn intersect_point_rectangle(p: Vec2, b: Vec2, br: Vec2) -> bool {
+pub(crate) fn intersect_point_rectangle(p: Vec2, b: Vec2, br: Vec2) -> bool {
     let v = b - p;
     v.x > -br.x && v.y > -br.y && v.x <= br.x && v.y <= br.y
 }
 
 /// Check if point is inside rectangle defined by corners
 #[inline]
-pub fn intersect_point_rectangle_corners(p: Vec2, a: Vec2, b: Vec2) -> bool {
+pub(crate) fn intersect_point_rectangle_corners(p: Vec2, a: Vec2, b: Vec2) -> bool {
     let mn = vec2_min(a, b);
     let mx = vec2_max(a, b);
     p.x >= mn.x && p.y >= mn.y && p.x <= mx.x && p.y <= mx.y
@@ -1142,7 +1159,7 @@ pub fn intersect_point_rectangle_corners(p: Vec2, a: Vec2, b: Vec2) -> bool {
 
 /// Check if circle is fully contained inside rectangle
 #[inline]
-pub fn contained_circle_in_rectangle(circle: Vec2, circle_r: f32, rect_pos: Vec2, rect_half_size: Vec2) -> bool {
+pub(crate) fn contained_circle_in_rectangle(circle: Vec2, circle_r: f32, rect_pos: Vec2, rect_half_size: Vec2) -> bool {
     intersect_point_rectangle(circle, rect_pos, rect_half_size - Vec2::splat(circle_r))
 }
 
@@ -1281,11 +1298,12 @@ pub fn orient(p1: Vec2, p2: Vec2, p3: Vec2) -> f32 {
 
 /// Return orientation with p1 at origin
 #[inline]
-pub fn orient2(p2: Vec2, p3: Vec2) -> f32 {
+pub(crate) fn orient2(p2: Vec2, p3: Vec2) -> f32 {
     p2.x * p3.y - p2.y * p3.x
 }
 
-/// Compute the area of a polygon
+/// Signed area of a polygon: positive for clockwise winding (in a y-up frame),
+/// negative for counter-clockwise, 0 for fewer than 3 vertices
 #[inline]
 pub fn area_for_poly(verts: &[Vec2]) -> f32 {
     if verts.len() < 3 {
@@ -1301,7 +1319,25 @@ pub fn area_for_poly(verts: &[Vec2]) -> f32 {
     -area / 2.0
 }
 
-/// Compute the moment of inertia of a polygon
+/// Area covered by the triangles fanned out from the first vertex, each counted as
+/// positive. Unlike [`area_for_poly`] it only comes out 0 when all the vertices are on
+/// one line, not for outlines whose crossing halves cancel out.
+pub fn spanned_area(verts: &[Vec2]) -> f32 {
+    if verts.len() < 3 {
+        return 0.0;
+    }
+    
+    let origin = verts[0];
+    let mut area = 0.0;
+    for pair in verts[1..].windows(2) {
+        area += cross_2d(pair[0] - origin, pair[1] - origin).abs();
+    }
+    
+    area / 2.0
+}
+
+/// Moment of inertia of a polygon of uniform density with total `mass`, about the
+/// point `offset`
 pub fn moment_for_poly(mass: f32, verts: &[Vec2], offset: Vec2) -> f32 {
     if verts.len() < 3 {
         return 0.0;
@@ -1324,33 +1360,97 @@ pub fn moment_for_poly(mass: f32, verts: &[Vec2], offset: Vec2) -> f32 {
     return mass * sum1 / (6.0 * sum2);
 }
 
+/// Whether a polygon is convex, in either winding. Collinear vertices are allowed;
+/// polygons with fewer than 3 vertices or whose outline winds around more than once
+/// are not convex.
+pub fn is_convex_poly(verts: &[Vec2]) -> bool {
+    let n = verts.len();
+    if n < 3 {
+        return false;
+    }
+    
+    let mut winding = 0.0;
+    let mut turn = 0.0;
+    for i in 0..n {
+        let (a, b, c) = (verts[i], verts[(i + 1) % n], verts[(i + 2) % n]);
+        let cross = orient(a, b, c);
+        if near_zero_f32(cross) {
+            continue;
+        }
+        // Every corner has to turn the same way as the first real one
+        if winding == 0.0 {
+            winding = cross.signum();
+        } else if cross.signum() != winding {
+            return false;
+        }
+        turn += cross_2d(b - a, c - b).atan2((b - a).dot(c - b));
+    }
+    
+    // A star turns one way at every corner too, but twice around
+    winding != 0.0 && turn.abs() < TAU + 0.1
+}
+
+/// Split a polygon into triangles by clipping ears, in either winding. Whatever is
+/// left once no ear can be found, as with self-crossing outlines, is fanned out from
+/// its first vertex.
+pub fn triangulate(verts: &[Vec2]) -> Vec<[Vec2; 3]> {
+    let mut remaining = verts.to_vec();
+    let mut triangles = Vec::new();
+    if remaining.len() < 3 {
+        return triangles;
+    }
+    
+    // Convex corners turn this way; area_for_poly is negative for counter-clockwise
+    let winding = if area_for_poly(verts) <= 0.0 { 1.0 } else { -1.0 };
+    let inside = |p: Vec2, [a, b, c]: [Vec2; 3]| {
+        orient(a, b, p) * winding >= 0.0 && orient(b, c, p) * winding >= 0.0 && orient(c, a, p) * winding >= 0.0
+    };
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| [remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]];
+        let ear = (0..n).find(|&i| {
+            let triangle = corner(i);
+            orient(triangle[0], triangle[1], triangle[2]) * winding > 0.0
+                && !remaining.iter().any(|&p| !triangle.contains(&p) && inside(p, triangle))
+        });
+        let Some(i) = ear else { break };
+        triangles.push(corner(i));
+        remaining.remove(i);
+    }
+    
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
 /// Regular polygon apothem (inradius) given circumradius
 #[inline]
-pub fn regpoly_apothem(n: i32, r: f32) -> f32 {
+pub(crate) fn regpoly_apothem(n: i32, r: f32) -> f32 {
     r * f32::cos(PI / n as f32)
 }
 
 /// Regular polygon circumradius given apothem (inradius)
 #[inline]
-pub fn regpoly_circumradius(n: i32, r: f32) -> f32 {
+pub(crate) fn regpoly_circumradius(n: i32, r: f32) -> f32 {
     r / f32::cos(PI / n as f32)
 }
 
 /// Regular polygon radius from side length
 #[inline]
-pub fn regpoly_radius_from_side(n: i32, s: f32) -> f32 {
+pub(crate) fn regpoly_radius_from_side(n: i32, s: f32) -> f32 {
     s / (2.0 * f32::sin(PI / n as f32))
 }
 
 /// Regular polygon area
 #[inline]
-pub fn regpoly_area(n: i32, r: f32, r1: f32) -> f32 {
+pub(crate) fn regpoly_area(n: i32, r: f32, r1: f32) -> f32 {
     let r1 = if r1 == 0.0 { r } else { r1 };
     0.5 * n as f32 * r * r1 * f32::sin(TAU / n as f32)
 }
 
 /// Regular polygon perimeter
 #[inline]
-pub fn regpoly_perimeter(n: i32, r: f32) -> f32 {
+pub(crate) fn regpoly_perimeter(n: i32, r: f32) -> f32 {
     n as f32 * 2.0 * r * f32::sin(PI / n as f32)
 } 
\ No newline at end of file
@@ -0,0 +1,202 @@
+// Background import of shapes files with progress reporting
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use crate::ast;
+use crate::data_structures::Shape;
+use crate::parser::{parse_shapes_content, parse_shapes_content_recovering, ParseLimits, RecoveredShapes, SkippedShape};
+use crate::shape_editor::ShapeEditor;
+
+// Shapes converted and handed to the editor at a time
+const BATCH_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImportStage {
+    Reading,
+    Parsing,
+    Converting,
+}
+
+impl ImportStage {
+    // Translation key of the stage name
+    pub fn key(&self) -> &'static str {
+        match self {
+            ImportStage::Reading => "import_stage_reading",
+            ImportStage::Parsing => "import_stage_parsing",
+            ImportStage::Converting => "import_stage_converting",
+        }
+    }
+}
+
+// What the editor has to do after polling the job
+pub enum ImportEvent {
+    // All shapes are converted and waiting in `shapes`
+    Finished,
+    // Short message and the full error for the details section
+    Failed(String, String),
+}
+
+// Messages from the import thread
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+enum Message {
+    Stage(ImportStage),
+    Progress(f32),
+    Skipped(Vec<SkippedShape>),
+    Shapes(Vec<Shape>),
+    Finished,
+    Failed(String, String),
+}
+
+// Shapes of a file, leaving out the shape tables that don't parse instead of failing
+// the whole file. If none of them parse, the more lenient line-by-line parser
+// gets a go at it.
+fn parse(content: &str) -> Result<RecoveredShapes, String> {
+    let recovered = parse_shapes_content_recovering(content, &ParseLimits::default())?;
+    if recovered.shapes_file.shapes.is_empty() && !recovered.skipped.is_empty() {
+        return Ok(parse_shapes_content(content)
+            .map(|shapes_file| RecoveredShapes { shapes_file, skipped: Vec::new() })
+            .unwrap_or(recovered));
+    }
+    Ok(recovered)
+}
+
+enum Source {
+    // Read, parse and convert on a worker thread
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    Thread(Receiver<Message>),
+    // Already parsed; converted a few batches per frame (WASM has no threads)
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    Chunked { shapes: Vec<ast::Shape>, next: usize },
+}
+
+pub struct ImportJob {
+    source: Source,
+    pub path: String,
+    pub stage: ImportStage,
+    pub progress: f32,
+    // Shapes converted so far
+    pub shapes: Vec<Shape>,
+    // Shape tables in the file that didn't parse
+    pub skipped: Vec<SkippedShape>,
+}
+
+impl ImportJob {
+    // Import a file on a background thread
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn(path: String) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let thread_path = path.clone();
+
+        std::thread::spawn(move || {
+            let send = |message| sender.send(message).is_ok();
+
+            send(Message::Stage(ImportStage::Reading));
+            // A file too large to parse isn't worth reading into memory
+            let size = std::fs::metadata(&thread_path).map(|m| m.len()).unwrap_or(0);
+            if let Err(e) = ParseLimits::default().check_size(size) {
+                send(Message::Failed("File is too large".to_string(), e));
+                return;
+            }
+            let content = match std::fs::read(&thread_path) {
+                Ok(bytes) => crate::encoding::decode(&bytes),
+                Err(e) => {
+                    send(Message::Failed("Failed to read file".to_string(), e.to_string()));
+                    return;
+                }
+            };
+
+            send(Message::Stage(ImportStage::Parsing));
+            let shapes_file = match parse(&content) {
+                Ok(recovered) => {
+                    send(Message::Skipped(recovered.skipped));
+                    recovered.shapes_file
+                }
+                Err(e) => {
+                    send(Message::Failed("Failed to parse shapes".to_string(), e));
+                    return;
+                }
+            };
+
+            send(Message::Stage(ImportStage::Converting));
+            let total = shapes_file.shapes.len();
+            for (i, chunk) in shapes_file.shapes.chunks(BATCH_SIZE).enumerate() {
+                let batch = chunk.iter().map(ShapeEditor::convert_from_ast_shape).collect();
+                // A closed channel means the import was cancelled
+                if !send(Message::Shapes(batch)) {
+                    return;
+                }
+                send(Message::Progress(((i + 1) * BATCH_SIZE).min(total) as f32 / total as f32));
+            }
+            send(Message::Finished);
+        });
+
+        Self::new(Source::Thread(receiver), path)
+    }
+
+    // Import content that is already in memory. Parsing happens right away, the
+    // conversion is spread over the following frames.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    // On failure returns the short message and the full error.
+    pub fn from_content(content: &str, path: String) -> Result<Self, (String, String)> {
+        let recovered = parse(content)
+            .map_err(|e| ("Failed to parse shapes".to_string(), e))?;
+        let mut job = Self::new(Source::Chunked { shapes: recovered.shapes_file.shapes, next: 0 }, path);
+        job.stage = ImportStage::Converting;
+        job.skipped = recovered.skipped;
+        Ok(job)
+    }
+
+    fn new(source: Source, path: String) -> Self {
+        Self {
+            source,
+            path,
+            stage: ImportStage::Reading,
+            progress: 0.0,
+            shapes: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    // Collect everything that happened since the last poll
+    pub fn poll(&mut self) -> Vec<ImportEvent> {
+        let mut events = Vec::new();
+
+        match &mut self.source {
+            Source::Thread(receiver) => loop {
+                match receiver.try_recv() {
+                    Ok(Message::Stage(stage)) => self.stage = stage,
+                    Ok(Message::Progress(progress)) => self.progress = progress,
+                    Ok(Message::Skipped(skipped)) => self.skipped = skipped,
+                    Ok(Message::Shapes(batch)) => self.shapes.extend(batch),
+                    Ok(Message::Finished) => {
+                        events.push(ImportEvent::Finished);
+                        break;
+                    }
+                    Ok(Message::Failed(message, details)) => {
+                        events.push(ImportEvent::Failed(message, details));
+                        break;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        events.push(ImportEvent::Failed(
+                            "Import stopped unexpectedly".to_string(),
+                            "The import thread exited without reporting a result".to_string(),
+                        ));
+                        break;
+                    }
+                }
+            },
+            Source::Chunked { shapes, next } => {
+                // A few batches per frame keeps the UI responsive
+                let end = (*next + BATCH_SIZE * 4).min(shapes.len());
+                self.shapes.extend(shapes[*next..end].iter().map(ShapeEditor::convert_from_ast_shape));
+                *next = end;
+                self.progress = if shapes.is_empty() { 1.0 } else { end as f32 / shapes.len() as f32 };
+                if end == shapes.len() {
+                    events.push(ImportEvent::Finished);
+                }
+            }
+        }
+
+        events
+    }
+}
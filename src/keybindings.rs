@@ -0,0 +1,214 @@
+// Remappable keyboard shortcuts
+use eframe::egui;
+use egui::{InputState, Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::data_structures::PortType;
+use crate::translations::t;
+
+/// Editor command that can be bound to a key
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Undo,
+    Redo,
+    DeleteSelection,
+    ClearSelection,
+    ToggleMeasureTool,
+    ZoomToFit,
+    ZoomToSelection,
+    ResetView,
+    ToggleShortcuts,
+    // Index into PortType::ALL
+    SetPortType(usize),
+    // View bookmark slot, from 0
+    RecallBookmark(usize),
+    SaveBookmark(usize),
+}
+
+impl Action {
+    pub fn label(&self) -> String {
+        match self {
+            Action::Undo => t("undo"),
+            Action::Redo => t("redo"),
+            Action::DeleteSelection => t("delete_selected"),
+            Action::ClearSelection => t("clear_selection"),
+            Action::ToggleMeasureTool => t("measure_tool"),
+            Action::ZoomToFit => t("zoom_fit"),
+            Action::ZoomToSelection => t("zoom_selection"),
+            Action::ResetView => t("reset_view"),
+            Action::ToggleShortcuts => t("shortcuts"),
+            Action::SetPortType(idx) => format!(
+                "{}: {}",
+                t("port_type"),
+                PortType::ALL.get(*idx).map(|port_type| port_type.to_string()).unwrap_or_default()
+            ),
+            Action::RecallBookmark(slot) => format!("{} {}", t("shortcut_recall_bookmark"), slot + 1),
+            Action::SaveBookmark(slot) => format!("{} {}", t("shortcut_save_bookmark"), slot + 1),
+        }
+    }
+
+    /// Translation key of the cheat sheet group the action is listed under
+    pub fn category(&self) -> &'static str {
+        match self {
+            Action::Undo | Action::Redo | Action::DeleteSelection | Action::ClearSelection => "shortcuts_edit",
+            Action::ZoomToFit | Action::ZoomToSelection | Action::ResetView
+            | Action::RecallBookmark(_) | Action::SaveBookmark(_) => "shortcuts_view",
+            Action::SetPortType(_) => "shortcuts_ports",
+            Action::ToggleMeasureTool | Action::ToggleShortcuts => "shortcuts_tools",
+        }
+    }
+}
+
+/// Cheat sheet groups in display order
+pub const SHORTCUT_CATEGORIES: [&str; 6] = [
+    "shortcuts_edit",
+    "shortcuts_mouse",
+    "shortcuts_selection",
+    "shortcuts_view",
+    "shortcuts_ports",
+    "shortcuts_tools",
+];
+
+/// Shortcuts that can't be remapped, as (category, keys, description) translation keys
+pub const FIXED_SHORTCUTS: [(&str, &str, &str); 9] = [
+    ("shortcuts_mouse", "keys_click", "shortcut_add_vertex"),
+    ("shortcuts_mouse", "keys_alt_click", "shortcut_add_port"),
+    ("shortcuts_mouse", "keys_ctrl_click", "shortcut_split_edge"),
+    ("shortcuts_mouse", "keys_shift_click", "shortcut_select_ports"),
+    ("shortcuts_mouse", "keys_ctrl_alt_drag", "shortcut_precision_drag"),
+    ("shortcuts_mouse", "keys_right_click", "shortcut_context_menu"),
+    ("shortcuts_selection", "keys_tab", "shortcut_cycle_vertices"),
+    ("shortcuts_selection", "keys_ctrl_tab", "shortcut_cycle_ports"),
+    ("shortcuts_view", "keys_pan", "shortcut_pan"),
+];
+
+/// A key together with the modifiers that must be held with it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub const fn new(key: Key) -> Self {
+        Self { key, ctrl: false, shift: false, alt: false }
+    }
+
+    pub const fn ctrl(key: Key) -> Self {
+        Self { key, ctrl: true, shift: false, alt: false }
+    }
+
+    pub const fn shift(key: Key) -> Self {
+        Self { key, ctrl: false, shift: true, alt: false }
+    }
+
+    pub const fn ctrl_shift(key: Key) -> Self {
+        Self { key, ctrl: true, shift: true, alt: false }
+    }
+
+    pub fn from_key_event(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, ctrl: modifiers.command, shift: modifiers.shift, alt: modifiers.alt }
+    }
+
+    /// True if the key was pressed this frame with exactly these modifiers
+    pub fn pressed(&self, input: &InputState) -> bool {
+        input.key_pressed(self.key)
+            && input.modifiers.command == self.ctrl
+            && input.modifiers.shift == self.shift
+            && input.modifiers.alt == self.alt
+    }
+
+    pub fn display(&self) -> String {
+        let mut text = String::new();
+        if self.ctrl {
+            text.push_str("Ctrl+");
+        }
+        if self.shift {
+            text.push_str("Shift+");
+        }
+        if self.alt {
+            text.push_str("Alt+");
+        }
+        let key = format!("{:?}", self.key);
+        // Num1 reads better as 1
+        text.push_str(key.strip_prefix("Num").unwrap_or(&key));
+        text
+    }
+}
+
+/// All shortcut assignments; an action may have more than one binding
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keybindings {
+    pub bindings: Vec<(Action, KeyBinding)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        const TYPE_KEYS: [Key; 9] = [
+            Key::Num1, Key::Num2, Key::Num3,
+            Key::Num4, Key::Num5, Key::Num6,
+            Key::Num7, Key::Num8, Key::Num9,
+        ];
+
+        let mut bindings = vec![
+            (Action::Undo, KeyBinding::ctrl(Key::Z)),
+            (Action::Redo, KeyBinding::ctrl(Key::Y)),
+            (Action::Redo, KeyBinding::ctrl_shift(Key::Z)),
+            (Action::DeleteSelection, KeyBinding::new(Key::Delete)),
+            (Action::DeleteSelection, KeyBinding::new(Key::Backspace)),
+            (Action::ClearSelection, KeyBinding::new(Key::Escape)),
+            (Action::ToggleMeasureTool, KeyBinding::new(Key::M)),
+            (Action::ZoomToFit, KeyBinding::new(Key::F)),
+            (Action::ZoomToSelection, KeyBinding::shift(Key::F)),
+            (Action::ResetView, KeyBinding::new(Key::Home)),
+            // egui has no function keys, so no F1
+            (Action::ToggleShortcuts, KeyBinding::new(Key::H)),
+        ];
+        for (idx, key) in TYPE_KEYS.into_iter().enumerate() {
+            bindings.push((Action::SetPortType(idx), KeyBinding::new(key)));
+        }
+        // One per view bookmark slot
+        for (slot, key) in TYPE_KEYS.into_iter().take(3).enumerate() {
+            bindings.push((Action::RecallBookmark(slot), KeyBinding::ctrl(key)));
+            bindings.push((Action::SaveBookmark(slot), KeyBinding::ctrl_shift(key)));
+        }
+
+        Self { bindings }
+    }
+}
+
+impl Keybindings {
+    /// True if any binding of the action was pressed this frame
+    pub fn pressed(&self, action: Action, input: &InputState) -> bool {
+        self.bindings.iter().any(|(a, binding)| *a == action && binding.pressed(input))
+    }
+
+    /// The first action whose binding was pressed this frame, among those matching the filter
+    pub fn pressed_action(&self, input: &InputState, filter: impl Fn(Action) -> bool) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(action, binding)| filter(*action) && binding.pressed(input))
+            .map(|(action, _)| *action)
+    }
+
+    /// Add the default bindings of actions that have none, e.g. actions added after
+    /// the bindings were saved
+    pub fn with_missing_defaults(mut self) -> Self {
+        for (action, binding) in Keybindings::default().bindings {
+            if !self.bindings.iter().any(|(a, _)| *a == action) {
+                self.bindings.push((action, binding));
+            }
+        }
+        self
+    }
+
+    /// Display text of all bindings of an action, e.g. "Ctrl+Y / Ctrl+Shift+Z"
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings.iter()
+            .filter(|(a, _)| *a == action)
+            .map(|(_, binding)| binding.display())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
@@ -14,17 +14,53 @@ mod visual;
 mod data_structures;
 mod ui;
 mod shape_editor;
-mod geometry;
-mod ast;
+pub mod geometry;
+pub mod ast;
 mod project_generator;
 mod translations;
+mod keybindings;
+mod import_job;
+mod export_job;
+mod settings;
+mod toasts;
+mod log_console;
+mod recovery;
+mod blocks;
+mod validation;
+mod backup;
+mod diff;
+mod lua_highlight;
+mod lua_lexer;
+mod user_templates;
+mod generators;
+mod normalize;
+mod batch;
+mod report;
+mod budget;
+mod payload;
+mod shape_ids;
+mod script;
+mod arrange;
+mod naming;
+mod variants;
+mod encoding;
+pub mod visit;
+mod canonical;
+mod widget;
+#[cfg(target_arch = "wasm32")]
+mod embed;
 mod parser;
 mod serializer;
 
 // Re-export public items
-pub use parser::{parse_shapes_content, parse_shapes_file, ParseError, ParserErrorKind};
-pub use serializer::serialize_shapes_file;
+pub use parser::{
+    parse_shapes_content, parse_shapes_content_recovering, parse_shapes_content_with_limits, parse_shapes_file,
+    parse_shapes_file_with_limits, ParseError, ParseLimits, ParserErrorKind, RecoveredShapes, SkippedShape,
+};
+pub use serializer::{serialize_shapes_file, SerializeOptions};
 pub use shape_editor::ShapeEditor;
+pub use widget::ShapeEditorWidget;
+pub use validation::{strip_summary_comment, summary_comment, validate_payloads, validate_shape, validate_shapes_file, Diagnostic, Element, Fix, Rule, Severity};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global allocator.
 #[cfg(all(feature = "wee_alloc", target_arch = "wasm32"))]
@@ -35,20 +71,25 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 #[cfg(target_arch = "wasm32")]
 static mut SHAPE_EDITOR_INSTANCE: Option<*mut ShapeEditor> = None;
 
-// This is the entry point for the web app
+// This is the entry point for the web app. The handle lets a page that embeds the
+// editor load shapes, read them back and follow edits.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn start(canvas_id: &str) -> Result<(), JsValue> {
+pub fn start(canvas_id: &str) -> Result<embed::EditorHandle, JsValue> {
     // This provides better error messages in debug mode.
     // It's disabled in release mode so it doesn't bloat up the file size.
     #[cfg(debug_assertions)]
     console_error_panic_hook::set_once();
 
-    // Initialize logging for wasm
+    // Initialize logging for wasm; records also go to the in-app log console
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-    console_log::init_with_level(log::Level::Debug).expect("Failed to initialize logging");
+    log_console::init(log::LevelFilter::Debug);
+    // Chains to the console hook above after saving the shapes
+    recovery::install_panic_hook();
 
-    let app = ShapeEditor::new();
+    let handle = embed::handle(canvas_id);
+    let mut app = ShapeEditor::new();
+    app.embed = Some(handle.state());
     
     // Store a reference to the shape editor for file input callbacks
     unsafe {
@@ -79,7 +120,29 @@ pub fn start(canvas_id: &str) -> Result<(), JsValue> {
         .expect("Failed to start eframe");
     });
     
-    Ok(())
+    Ok(handle)
+}
+
+/// Parse the text of a shapes.lua file into plain objects laid out like
+/// [`ast::ShapesFile`], for pages that only need the parser. Throws the parse error.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = parseShapes)]
+pub fn parse_shapes_js(lua: &str) -> Result<JsValue, JsValue> {
+    let shapes_file = parse_shapes_content(lua).map_err(|e| JsValue::from_str(&e))?;
+    let json = serde_json::to_string(&shapes_file).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    js_sys::JSON::parse(&json)
+}
+
+/// Write objects laid out like [`ast::ShapesFile`], as returned by `parseShapes`,
+/// back out as shapes.lua text. Throws if the objects don't have that layout.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = serializeShapes)]
+pub fn serialize_shapes_js(shapes: JsValue) -> Result<String, JsValue> {
+    let json = js_sys::JSON::stringify(&shapes)?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("Expected an object with a shapes array"))?;
+    let shapes_file: ast::ShapesFile = serde_json::from_str(&json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(serialize_shapes_file(&shapes_file))
 }
 
 // Set up the file input handler
@@ -111,8 +174,9 @@ fn setup_file_input_handler() -> Result<(), JsValue> {
                     
                     // Create a closure for the onload event
                     let onload_callback = Closure::wrap(Box::new(move |_: Event| {
+                        // Read as bytes, since files not in UTF-8 would come out garbled as text
                         let result = reader_clone.result().unwrap();
-                        let text = result.as_string().unwrap();
+                        let text = crate::encoding::decode(&js_sys::Uint8Array::new(&result).to_vec());
                         
                         // Call the shape editor's handle_file_content method
                         unsafe {
@@ -126,8 +190,7 @@ fn setup_file_input_handler() -> Result<(), JsValue> {
                     // Set the onload handler
                     reader.set_onload(Some(onload_callback.as_ref().unchecked_ref()));
                     
-                    // Start reading the file as text
-                    reader.read_as_text(&file).unwrap();
+                    reader.read_as_array_buffer(&file).unwrap();
                     
                     // Leak the closure to keep it alive
                     onload_callback.forget();
@@ -0,0 +1,106 @@
+// Logger that keeps the latest records in memory for the in-app log console,
+// while still passing them on to the terminal (native) or browser console (WASM)
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+
+// Oldest records are dropped beyond this many
+const CAPACITY: usize = 500;
+
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+static ENTRIES: Lazy<Mutex<VecDeque<LogEntry>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+// The library only installs the logger from its WASM entry point
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+struct TeeLogger {
+    level: LevelFilter,
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: env_logger::Logger,
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Ok(mut entries) = ENTRIES.lock() {
+            if entries.len() == CAPACITY {
+                entries.pop_front();
+            }
+            entries.push_back(LogEntry {
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.log(record);
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let text = wasm_bindgen::JsValue::from_str(&format!("{} {}", record.target(), record.args()));
+            match record.level() {
+                Level::Error => web_sys::console::error_1(&text),
+                Level::Warn => web_sys::console::warn_1(&text),
+                Level::Info => web_sys::console::info_1(&text),
+                Level::Debug | Level::Trace => web_sys::console::log_1(&text),
+            }
+        }
+    }
+
+    fn flush(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.inner.flush();
+    }
+}
+
+/// Install the logger. Records up to `level` go to the console buffer; natively
+/// `RUST_LOG` can further narrow what is printed to the terminal.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn init(level: LevelFilter) {
+    let logger = TeeLogger {
+        level,
+        #[cfg(not(target_arch = "wasm32"))]
+        inner: env_logger::Builder::new()
+            .filter_level(level)
+            .parse_default_env()
+            .build(),
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Copy of the buffered records, oldest first. A copy rather than a guard, so
+/// code that logs while the console is drawn can't deadlock.
+pub fn entries() -> Vec<LogEntry> {
+    ENTRIES.lock().map(|entries| entries.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Number of buffered warnings and errors
+pub fn problem_count() -> usize {
+    ENTRIES.lock()
+        .map(|entries| entries.iter().filter(|entry| entry.level <= Level::Warn).count())
+        .unwrap_or(0)
+}
+
+pub fn clear() {
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.clear();
+    }
+}
@@ -0,0 +1,127 @@
+// Token colouring for the Lua shown in the editor, from the tokens of the shared
+// lexer; it doesn't need the text to be valid Lua.
+use eframe::egui::text::{LayoutJob, TextFormat};
+use eframe::egui::{Color32, FontId};
+use full_moon::tokenizer::TokenType;
+
+use crate::lua_lexer::LuaToken;
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+// Port type names as they appear in the third field of a port
+const PORT_TYPES: &[&str] = &[
+    "DEFAULT", "THRUSTER_IN", "THRUSTER_OUT", "WEAPON_IN", "WEAPON_OUT", "MISSILE", "LAUNCHER", "ROOT", "NONE",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Plain,
+    Keyword,
+    Number,
+    String,
+    Comment,
+    PortType,
+}
+
+impl Token {
+    fn color(self) -> Color32 {
+        match self {
+            Token::Plain => Color32::from_rgb(212, 212, 212),
+            Token::Keyword => Color32::from_rgb(198, 120, 221),
+            Token::Number => Color32::from_rgb(209, 154, 102),
+            Token::String => Color32::from_rgb(152, 195, 121),
+            Token::Comment => Color32::from_rgb(106, 153, 85),
+            Token::PortType => Color32::from_rgb(86, 182, 194),
+        }
+    }
+}
+
+/// Lay out Lua source with each token coloured by its kind
+pub fn highlight(text: &str, font_id: FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let mut append = |token: Token, range: std::ops::Range<usize>| {
+        job.append(&text[range], 0.0, TextFormat {
+            font_id: font_id.clone(),
+            color: token.color(),
+            italics: token == Token::Comment,
+            ..Default::default()
+        });
+    };
+
+    // Neighbouring tokens of a kind go into one section
+    let mut run: Option<(Token, std::ops::Range<usize>)> = None;
+    for lua_token in crate::lua_lexer::tokens(text) {
+        let token = classify(text, &lua_token);
+        match &mut run {
+            Some((kind, range)) if *kind == token => range.end = lua_token.span.end,
+            _ => {
+                if let Some((kind, range)) = run.replace((token, lua_token.span)) {
+                    append(kind, range);
+                }
+            }
+        }
+    }
+    if let Some((kind, range)) = run {
+        append(kind, range);
+    }
+    job
+}
+
+/// The source line a parser error points at, as its 1-based number and text
+pub fn error_snippet<'a>(source: &'a str, error: &str) -> Option<(usize, &'a str)> {
+    let after = &error[error.find("line ")? + "line ".len()..];
+    let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+    let line: usize = after[..digits].parse().ok()?;
+    Some((line, source.lines().nth(line.checked_sub(1)?)?))
+}
+
+fn classify(text: &str, lua_token: &LuaToken) -> Token {
+    let word = &text[lua_token.span.clone()];
+    match &lua_token.kind {
+        Some(TokenType::SingleLineComment { .. } | TokenType::MultiLineComment { .. }) => Token::Comment,
+        Some(TokenType::StringLiteral { .. }) => Token::String,
+        Some(TokenType::Number { .. }) => Token::Number,
+        Some(TokenType::Identifier { .. } | TokenType::Symbol { .. }) if KEYWORDS.contains(&word) => Token::Keyword,
+        Some(TokenType::Identifier { .. }) if PORT_TYPES.contains(&word) => Token::PortType,
+        _ => Token::Plain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Text and colour of each section
+    fn sections(text: &str) -> Vec<(&str, Color32)> {
+        let job = highlight(text, FontId::monospace(12.0));
+        job.sections.iter().map(|section| (&text[section.byte_range.clone()], section.format.color)).collect()
+    }
+
+    #[test]
+    fn long_brackets_end_at_their_own_level() {
+        assert_eq!(sections("--[==[ a ]] b ]==]\nx = [[ \"c\" ]] 1"), vec![
+            ("--[==[ a ]] b ]==]", Token::Comment.color()),
+            ("\nx = ", Token::Plain.color()),
+            ("[[ \"c\" ]]", Token::String.color()),
+            (" ", Token::Plain.color()),
+            ("1", Token::Number.color()),
+        ]);
+    }
+
+    #[test]
+    fn keywords_and_port_types() {
+        assert_eq!(sections("{0, 0.5, THRUSTER_IN} nil"), vec![
+            ("{", Token::Plain.color()),
+            ("0", Token::Number.color()),
+            (", ", Token::Plain.color()),
+            ("0.5", Token::Number.color()),
+            (", ", Token::Plain.color()),
+            ("THRUSTER_IN", Token::PortType.color()),
+            ("} ", Token::Plain.color()),
+            ("nil", Token::Keyword.color()),
+        ]);
+    }
+}
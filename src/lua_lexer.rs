@@ -0,0 +1,80 @@
+// Lua source split into tokens by full_moon's tokenizer, for every scan that has to
+// step over strings and comments. Going through the parser's own lexer keeps them in
+// agreement on where a string or comment ends, `--[==[ ... ]==]` included. Unlike a
+// parse it carries on past characters Lua has no token for, so broken or half-typed
+// files still lex.
+use std::ops::Range;
+
+use full_moon::tokenizer::{Lexer, LexerResult, Symbol, TokenType};
+use full_moon::LuaVersion;
+
+pub struct LuaToken {
+    // None for a character Lua has no token for
+    pub kind: Option<TokenType>,
+    // Byte range in the source
+    pub span: Range<usize>,
+}
+
+impl LuaToken {
+    pub fn is_symbol(&self, symbol: Symbol) -> bool {
+        matches!(&self.kind, Some(TokenType::Symbol { symbol: s }) if *s == symbol)
+    }
+
+    // Whitespace and comments
+    pub fn is_trivia(&self) -> bool {
+        self.kind.as_ref().is_some_and(TokenType::is_trivia)
+    }
+}
+
+/// Every token of `source` in order, whitespace and comments included. An unclosed
+/// quoted string ends with its line, an unclosed long string or comment runs to the
+/// end of the source.
+pub fn tokens(source: &str) -> impl Iterator<Item = LuaToken> {
+    let mut lexer = Lexer::new_lazy(source, LuaVersion::lua51());
+    std::iter::from_fn(move || {
+        let (kind, (start, end)) = match lexer.process_next()? {
+            LexerResult::Ok(token) | LexerResult::Recovered(token, _) => {
+                (Some(token.token_type().clone()), (token.start_position(), token.end_position()))
+            }
+            LexerResult::Fatal(errors) => (None, errors.first()?.range()),
+        };
+        Some(LuaToken { kind, span: start.bytes()..end.bytes() })
+    })
+    .filter(|token| !matches!(token.kind, Some(TokenType::Eof)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(&str, bool)> {
+        tokens(source)
+            .filter(|token| !matches!(token.kind, Some(TokenType::Whitespace { .. })))
+            .map(|token| (&source[token.span.clone()], token.kind.is_some()))
+            .collect()
+    }
+
+    #[test]
+    fn leveled_long_brackets_end_at_their_own_level() {
+        let source = "--[==[ a ]] b ]==] {x=[=[ ]] ]=]}";
+        assert_eq!(kinds(source), vec![
+            ("--[==[ a ]] b ]==]", true),
+            ("{", true),
+            ("x", true),
+            ("=", true),
+            ("[=[ ]] ]=]", true),
+            ("}", true),
+        ]);
+    }
+
+    #[test]
+    fn unknown_characters_are_passed_over() {
+        assert_eq!(kinds("{1 @ 2}"), vec![("{", true), ("1", true), ("@", false), ("2", true), ("}", true)]);
+    }
+
+    #[test]
+    fn unclosed_strings_stop_at_the_line_or_the_end() {
+        assert_eq!(kinds("{\"abc\n}"), vec![("{", true), ("\"abc\n", true), ("}", true)]);
+        assert_eq!(kinds("x --[[ never closed }"), vec![("x", true), ("--[[ never closed }", true)]);
+    }
+}
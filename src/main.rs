@@ -9,6 +9,32 @@ mod parser;
 mod serializer;
 mod project_generator;
 mod translations;
+mod keybindings;
+mod import_job;
+mod export_job;
+mod settings;
+mod toasts;
+mod log_console;
+mod recovery;
+mod blocks;
+mod validation;
+mod backup;
+mod diff;
+mod lua_highlight;
+mod lua_lexer;
+mod user_templates;
+mod generators;
+mod normalize;
+mod batch;
+mod report;
+mod budget;
+mod payload;
+mod shape_ids;
+mod script;
+mod arrange;
+mod naming;
+mod variants;
+mod encoding;
 
 use eframe::{self, egui};
 use shape_editor::ShapeEditor;
@@ -16,18 +42,12 @@ use std::env;
 use log::{info, error, LevelFilter};
 
 fn main() {
-    // Initialize logging
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        env_logger::Builder::new()
-            .filter_level(LevelFilter::Info)
-            .init();
-    }
+    // Initialize logging; records are also kept for the in-app log console
+    log_console::init(LevelFilter::Info);
     #[cfg(target_arch = "wasm32")]
-    {
-        console_log::init_with_level(log::Level::Info).expect("Failed to initialize logger");
-        console_error_panic_hook::set_once();
-    }
+    console_error_panic_hook::set_once();
+    // After other hooks, so the shapes are saved before they run
+    recovery::install_panic_hook();
     
     info!("Application starting up");
     
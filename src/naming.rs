@@ -0,0 +1,178 @@
+// Names for shapes worked out from their outlines: the kind of polygon, and the side
+// ratio for rectangles, e.g. "Right_Trapezoid" or "Rect_1x2"
+use crate::data_structures::{Shape, Vertex};
+
+// Sides within this fraction of each other count as equal
+const LENGTH_TOLERANCE: f32 = 0.01;
+// Angles within this many degrees count as equal
+const ANGLE_TOLERANCE: f32 = 0.5;
+// Largest side of a rectangle named by its ratio, as in Rect_1x4
+const MAX_RATIO: u32 = 8;
+
+pub fn suggest_name(shape: &Shape) -> Option<String> {
+    let corners = corners(&shape.vertices);
+    if corners.len() < 3 {
+        return None;
+    }
+
+    let sides: Vec<f32> = (0..corners.len()).map(|i| distance(&corners[i], &corners[(i + 1) % corners.len()])).collect();
+    let angles = interior_angles(&corners);
+    let concave = angles.iter().any(|&angle| angle > 180.0 + ANGLE_TOLERANCE);
+
+    Some(match corners.len() {
+        _ if concave => format!("Concave_{}", polygon_name(corners.len())),
+        3 => triangle_name(&sides, &angles),
+        4 => quadrilateral_name(&corners, &sides, &angles),
+        n if all_equal(&sides, lengths_equal) && all_equal(&angles, angles_equal) => polygon_name(n),
+        n => format!("Irregular_{}", polygon_name(n)),
+    })
+}
+
+// Vertices where the outline turns, without repeated points or points partway along
+// a straight edge
+fn corners(vertices: &[Vertex]) -> Vec<Vertex> {
+    let mut points: Vec<Vertex> = Vec::new();
+    for v in vertices {
+        if points.last().is_none_or(|last| distance(last, v) > 1e-4) {
+            points.push(v.clone());
+        }
+    }
+    while points.len() > 1 && distance(&points[0], &points[points.len() - 1]) <= 1e-4 {
+        points.pop();
+    }
+
+    // Drop straight-through points until none are left
+    loop {
+        let n = points.len();
+        if n < 3 {
+            return points;
+        }
+        let straight = (0..n).find(|&i| {
+            let (prev, cur, next) = (&points[(i + n - 1) % n], &points[i], &points[(i + 1) % n]);
+            let turn = turn_angle(prev, cur, next);
+            turn.abs() < ANGLE_TOLERANCE
+        });
+        match straight {
+            Some(i) => {
+                points.remove(i);
+            }
+            None => return points,
+        }
+    }
+}
+
+// Signed change of direction at `cur`, in degrees
+fn turn_angle(prev: &Vertex, cur: &Vertex, next: &Vertex) -> f32 {
+    let (ax, ay) = (cur.x - prev.x, cur.y - prev.y);
+    let (bx, by) = (next.x - cur.x, next.y - cur.y);
+    (ax * by - ay * bx).atan2(ax * bx + ay * by).to_degrees()
+}
+
+// Interior angles in degrees, whichever way the outline winds
+fn interior_angles(corners: &[Vertex]) -> Vec<f32> {
+    let n = corners.len();
+    let turns: Vec<f32> = (0..n)
+        .map(|i| turn_angle(&corners[(i + n - 1) % n], &corners[i], &corners[(i + 1) % n]))
+        .collect();
+    // The turns add up to +360 for counter-clockwise outlines and -360 for clockwise
+    let sign = if turns.iter().sum::<f32>() >= 0.0 { 1.0 } else { -1.0 };
+    turns.iter().map(|turn| 180.0 - sign * turn).collect()
+}
+
+fn triangle_name(sides: &[f32], angles: &[f32]) -> String {
+    let equal_sides = (0..3).filter(|&i| lengths_equal(sides[i], sides[(i + 1) % 3])).count();
+    let right = angles.iter().any(|&angle| angles_equal(angle, 90.0));
+    match (equal_sides, right) {
+        (3, _) => "Equilateral_Triangle",
+        (1, true) => "Right_Isosceles_Triangle",
+        (_, true) => "Right_Triangle",
+        (1, false) => "Isosceles_Triangle",
+        _ => "Triangle",
+    }
+    .to_string()
+}
+
+fn quadrilateral_name(corners: &[Vertex], sides: &[f32], angles: &[f32]) -> String {
+    let all_right = angles.iter().all(|&angle| angles_equal(angle, 90.0));
+    let equal_sides = all_equal(sides, lengths_equal);
+    let parallel = |i: usize| parallel(&corners[i], &corners[(i + 1) % 4], &corners[(i + 2) % 4], &corners[(i + 3) % 4]);
+
+    if all_right {
+        return if equal_sides { "Square".to_string() } else { rectangle_name(sides[0], sides[1]) };
+    }
+    match (parallel(0), parallel(1)) {
+        (true, true) if equal_sides => "Rhombus".to_string(),
+        (true, true) => "Parallelogram".to_string(),
+        (true, false) | (false, true) => {
+            // The legs are the two sides that aren't parallel
+            let legs = if parallel(0) { (sides[1], sides[3]) } else { (sides[0], sides[2]) };
+            if angles.iter().any(|&angle| angles_equal(angle, 90.0)) {
+                "Right_Trapezoid".to_string()
+            } else if lengths_equal(legs.0, legs.1) {
+                "Isosceles_Trapezoid".to_string()
+            } else {
+                "Trapezoid".to_string()
+            }
+        }
+        (false, false) => {
+            let kite = (lengths_equal(sides[0], sides[1]) && lengths_equal(sides[2], sides[3]))
+                || (lengths_equal(sides[1], sides[2]) && lengths_equal(sides[3], sides[0]));
+            if kite { "Kite".to_string() } else { "Quadrilateral".to_string() }
+        }
+    }
+}
+
+// Rect_1x2 for sides in a small whole-number ratio, otherwise the sizes
+fn rectangle_name(a: f32, b: f32) -> String {
+    let (short, long) = if a <= b { (a, b) } else { (b, a) };
+    for width in 1..=MAX_RATIO {
+        let length = long / short * width as f32;
+        let rounded = length.round();
+        if rounded <= MAX_RATIO as f32 && (length - rounded).abs() < LENGTH_TOLERANCE * length {
+            return format!("Rect_{}x{}", width, rounded as u32);
+        }
+    }
+    format!("Rect_{}x{}", trim_number(short), trim_number(long))
+}
+
+fn polygon_name(corners: usize) -> String {
+    match corners {
+        3 => "Triangle".to_string(),
+        4 => "Quadrilateral".to_string(),
+        5 => "Pentagon".to_string(),
+        6 => "Hexagon".to_string(),
+        7 => "Heptagon".to_string(),
+        8 => "Octagon".to_string(),
+        n => format!("{}-gon", n),
+    }
+}
+
+// Whether side a-b is parallel to side c-d
+fn parallel(a: &Vertex, b: &Vertex, c: &Vertex, d: &Vertex) -> bool {
+    let (ux, uy) = (b.x - a.x, b.y - a.y);
+    let (vx, vy) = (d.x - c.x, d.y - c.y);
+    let angle = (ux * vy - uy * vx).atan2(ux * vx + uy * vy).to_degrees().abs();
+    angle.min(180.0 - angle) < ANGLE_TOLERANCE
+}
+
+fn all_equal(values: &[f32], equal: impl Fn(f32, f32) -> bool) -> bool {
+    values.iter().all(|&value| equal(value, values[0]))
+}
+
+fn lengths_equal(a: f32, b: f32) -> bool {
+    (a - b).abs() <= LENGTH_TOLERANCE * a.max(b)
+}
+
+fn angles_equal(a: f32, b: f32) -> bool {
+    (a - b).abs() <= ANGLE_TOLERANCE
+}
+
+fn distance(a: &Vertex, b: &Vertex) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+// 12.5 rather than 12.50, and 10 rather than 10.0
+fn trim_number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,116 @@
+// Rescaling of traced or imported shapes onto the game's sizes: the outline is scaled
+// so a chosen edge comes out at a canonical length, moved onto the origin, and its
+// coordinates and ports rounded.
+use crate::data_structures::{Port, Shape, Vertex};
+use crate::geometry::round_to;
+
+// Edges within this fraction of each other's length count as the same length
+const LENGTH_TOLERANCE: f32 = 0.02;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceEdge {
+    // The length shared by the most edges
+    MostCommon,
+    Shortest,
+    Longest,
+    Edge(usize),
+}
+
+// Point of the scaled outline that ends up on the origin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    // Scale about the origin and leave the position alone
+    Keep,
+    Centroid,
+    BoundsCenter,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NormalizeParams {
+    pub reference: ReferenceEdge,
+    pub target_length: f32,
+    pub anchor: Anchor,
+    // Vertices are rounded to multiples of this after scaling; 0 leaves them unrounded
+    pub vertex_step: f32,
+    // Move ports to the nearest common fraction of their edge
+    pub round_ports: bool,
+}
+
+impl Default for NormalizeParams {
+    fn default() -> Self {
+        Self {
+            reference: ReferenceEdge::MostCommon,
+            target_length: 10.0,
+            anchor: Anchor::Centroid,
+            vertex_step: 0.01,
+            round_ports: true,
+        }
+    }
+}
+
+/// Length of the reference edge of scale 1; None if the shape has no such edge
+pub fn reference_length(shape: &Shape, reference: ReferenceEdge) -> Option<f32> {
+    let lengths: Vec<f32> = (0..shape.vertices.len())
+        .map(|edge| shape.edge_length(edge))
+        .filter(|&length| length > 1e-4)
+        .collect();
+
+    match reference {
+        ReferenceEdge::Shortest => lengths.into_iter().reduce(f32::min),
+        ReferenceEdge::Longest => lengths.into_iter().reduce(f32::max),
+        ReferenceEdge::Edge(edge) => Some(shape.edge_length(edge)).filter(|&length| length > 1e-4),
+        // Mean of the largest group of near-equal lengths, the longer group on a tie
+        ReferenceEdge::MostCommon => lengths.iter()
+            .map(|&length| {
+                let group: Vec<f32> = lengths.iter()
+                    .copied()
+                    .filter(|&other| (other - length).abs() <= length * LENGTH_TOLERANCE)
+                    .collect();
+                (group.len(), group.iter().sum::<f32>() / group.len() as f32)
+            })
+            .max_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)))
+            .map(|(_, mean)| mean),
+    }
+}
+
+/// Copy of `shape` with every scale resized by the same factor, each moved onto the
+/// anchor and rounded. None if the reference edge can't be measured.
+pub fn normalize_shape(shape: &Shape, params: &NormalizeParams) -> Option<Shape> {
+    let factor = params.target_length / reference_length(shape, params.reference)?;
+
+    let mut normalized = shape.clone();
+    transform(&mut normalized.vertices, factor, params);
+    for scale in &mut normalized.scales {
+        transform(&mut scale.vertices, factor, params);
+    }
+    if params.round_ports {
+        let scale_ports = normalized.scales.iter_mut().flat_map(|scale| scale.ports.iter_mut());
+        for port in normalized.ports.iter_mut().chain(scale_ports) {
+            port.position = Port::round_position(port.position);
+        }
+    }
+    Some(normalized)
+}
+
+fn transform(vertices: &mut [Vertex], factor: f32, params: &NormalizeParams) {
+    for v in vertices.iter_mut() {
+        v.x *= factor;
+        v.y *= factor;
+    }
+
+    let origin = match params.anchor {
+        Anchor::Keep => None,
+        Anchor::Centroid => Shape { vertices: vertices.to_vec(), ..Shape::new(0) }.centroid(),
+        Anchor::BoundsCenter => vertices.iter()
+            .map(|v| (v.x, v.y, v.x, v.y))
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+            .map(|(min_x, min_y, max_x, max_y)| Vertex { x: (min_x + max_x) / 2.0, y: (min_y + max_y) / 2.0 }),
+    };
+    let origin = origin.unwrap_or(Vertex { x: 0.0, y: 0.0 });
+
+    for v in vertices.iter_mut() {
+        // Adding 0.0 turns a rounded -0 into 0, which would be written out as "-0"
+        v.x = round_to(v.x - origin.x, params.vertex_step) + 0.0;
+        v.y = round_to(v.y - origin.y, params.vertex_step) + 0.0;
+    }
+}
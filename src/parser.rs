@@ -6,7 +6,7 @@ use full_moon::{
     visitors::Visitor,
     node::Node,
 };
-use full_moon::tokenizer::Symbol::Minus;
+use full_moon::tokenizer::{Symbol::{self, Minus}, TokenType};
 
 use crate::ast::{ShapesFile, Shape, Scale, Vertex, Port, PortType, ShroudComponent, CannonProperties, ThrusterProperties, FragmentProperties};
 
@@ -39,23 +39,79 @@ impl From<String> for ParseError {
     }
 }
 
+/// Bounds on the input the parser accepts, so that a hostile or broken file gives an
+/// error instead of exhausting memory or the stack. Files from the web editor are
+/// untrusted, so the defaults are far above any real shapes.lua but well below that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Size of the file in bytes
+    pub max_file_size: u64,
+    pub max_shapes: usize,
+    pub max_vertices_per_scale: usize,
+    /// Deepest nesting of tables, brackets and unary minus signs
+    pub max_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_file_size: 16 * 1024 * 1024,
+            max_shapes: 10_000,
+            max_vertices_per_scale: 1_000,
+            max_depth: 64,
+        }
+    }
+}
+
+impl ParseLimits {
+    pub(crate) fn check_size(&self, size: u64) -> Result<(), String> {
+        if size > self.max_file_size {
+            return Err(format!("Input is {} bytes, more than the limit of {}", size, self.max_file_size));
+        }
+        Ok(())
+    }
+}
+
 /// Parse a Lua shapes file from a file path
 pub fn parse_shapes_file(path: &Path) -> Result<ShapesFile, ParseError> {
-    let content = fs::read_to_string(path)?;
-    parse_shapes_content(&content).map_err(|e| e.into())
+    parse_shapes_file_with_limits(path, &ParseLimits::default())
+}
+
+/// Parse a Lua shapes file from a file path, refusing files beyond `limits`
+pub fn parse_shapes_file_with_limits(path: &Path, limits: &ParseLimits) -> Result<ShapesFile, ParseError> {
+    // Checked before reading so an oversized file is never loaded
+    limits.check_size(fs::metadata(path)?.len())?;
+    let content = crate::encoding::decode(&fs::read(path)?);
+    parse_shapes_content_with_limits(&content, limits).map_err(|e| e.into())
 }
 
 /// Parse a Lua shapes file into our AST representation
 pub fn parse_shapes_content(lua_content: &str) -> Result<ShapesFile, String> {
+    parse_shapes_content_with_limits(lua_content, &ParseLimits::default())
+}
+
+/// Parse a Lua shapes file into our AST representation, refusing input beyond `limits`
+pub fn parse_shapes_content_with_limits(lua_content: &str, limits: &ParseLimits) -> Result<ShapesFile, String> {
+    limits.check_size(lua_content.len() as u64)?;
+    
     // Attempt to fix common syntax issues
     let processed_content = fix_lua_syntax(lua_content);
 
+    // The Lua parser recurses for every nested table or expression
+    let depth = nesting_depth(&processed_content);
+    if depth > limits.max_depth {
+        return Err(format!("Input nests {} levels deep, more than the limit of {}", depth, limits.max_depth));
+    }
+
     let valid_lua = format!("return {}", processed_content);
     let ast = match parse(&valid_lua) {
         Ok(ast) => ast,
-        Err(_) => {
-            // Try fallback legacy parser
-            return legacy_parse_shapes(lua_content);
+        Err(errors) => {
+            // Try fallback legacy parser; if that fails too, report what the Lua parser found
+            return legacy_parse_shapes(lua_content, limits).map_err(|legacy_error| {
+                let diagnostics: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                format!("{}\n\nLua parser errors:\n{}", legacy_error, diagnostics.join("\n"))
+            });
         }
     };
     
@@ -111,13 +167,17 @@ pub fn parse_shapes_content(lua_content: &str) -> Result<ShapesFile, String> {
     }
     
     if let Some(table) = shapes_table {
+        let count = table.fields().len();
+        if count > limits.max_shapes {
+            return Err(format!("File has {} shapes, more than the limit of {}", count, limits.max_shapes));
+        }
         let mut shapes_file = ShapesFile { shapes: Vec::new() };
         
         // Process each field in the table as a shape
         for field in table.fields() {
             if let ast::Field::NoKey(expr) = field {
                 if let ast::Expression::TableConstructor(shape_table) = expr {
-                    if let Some(shape) = extract_shape(shape_table) {
+                    if let Some(shape) = extract_shape(shape_table, limits)? {
                         shapes_file.shapes.push(shape);
                     }
                 }
@@ -125,13 +185,202 @@ pub fn parse_shapes_content(lua_content: &str) -> Result<ShapesFile, String> {
         }
         
         if shapes_file.shapes.is_empty() {
-            return legacy_parse_shapes(lua_content);
+            return legacy_parse_shapes(lua_content, limits);
         }
         
         return Ok(shapes_file);
     }
     
-    legacy_parse_shapes(lua_content)
+    legacy_parse_shapes(lua_content, limits)
+}
+
+/// A shape table the recovering parser left out, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedShape {
+    /// Line and column where the shape's table starts, both from 1
+    pub line: usize,
+    pub column: usize,
+    /// The ID, if the table got as far as having one
+    pub shape_id: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for SkippedShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.shape_id {
+            Some(id) => write!(f, "Line {}, column {}: shape {}: {}", self.line, self.column, id, self.message),
+            None => write!(f, "Line {}, column {}: {}", self.line, self.column, self.message),
+        }
+    }
+}
+
+/// What the recovering parser made of a file: the shapes that parsed, and the ones
+/// it had to skip
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredShapes {
+    pub shapes_file: ShapesFile,
+    pub skipped: Vec<SkippedShape>,
+}
+
+/// Parse a Lua shapes file one shape table at a time, so a malformed shape is skipped
+/// and reported instead of failing the whole file. Only input beyond `limits`, or
+/// with no table of shapes at all, is an error.
+pub fn parse_shapes_content_recovering(lua_content: &str, limits: &ParseLimits) -> Result<RecoveredShapes, String> {
+    limits.check_size(lua_content.len() as u64)?;
+    let depth = nesting_depth(lua_content);
+    if depth > limits.max_depth {
+        return Err(format!("Input nests {} levels deep, more than the limit of {}", depth, limits.max_depth));
+    }
+    
+    let braces = braces(lua_content);
+    let Some(&(open, _)) = braces.iter().find(|&&(_, opens)| opens) else {
+        return Err("No table of shapes found".to_string());
+    };
+    
+    let mut recovered = RecoveredShapes { shapes_file: ShapesFile { shapes: Vec::new() }, skipped: Vec::new() };
+    let mut from = open + 1;
+    while let Some(start) = next_entry(&braces, from) {
+        // Runs to the end of the input if the table is never closed
+        let end = matching_brace(&braces, start).map_or(lua_content.len(), |close| close + 1);
+        let entry = &lua_content[start..end];
+        from = end;
+        
+        let error = match parse_shape_entry(entry, limits) {
+            Ok(shape) => {
+                if recovered.shapes_file.shapes.len() == limits.max_shapes {
+                    return Err(format!("File has more than the limit of {} shapes", limits.max_shapes));
+                }
+                recovered.shapes_file.shapes.push(shape);
+                continue;
+            }
+            Err(error) => error,
+        };
+        
+        // A missing brace swallows the shapes after it; carry on from the next one
+        if let Some(resume) = next_shape_start(entry) {
+            from = start + resume;
+        }
+        let (line, column) = line_column(lua_content, start);
+        recovered.skipped.push(SkippedShape { line, column, shape_id: leading_id(entry), message: error });
+    }
+    
+    Ok(recovered)
+}
+
+// One shape table on its own, as `{id, {scales...}, properties...}`
+fn parse_shape_entry(entry: &str, limits: &ParseLimits) -> Result<Shape, String> {
+    let ast = parse(&format!("return {}", fix_lua_syntax(entry)))
+        .map_err(|errors| errors.first().map_or("Syntax error".to_string(), |e| e.error_message().to_string()))?;
+    
+    let table = match ast.nodes().last_stmt() {
+        Some(ast::LastStmt::Return(ret)) => match ret.returns().iter().next() {
+            Some(ast::Expression::TableConstructor(table)) => table,
+            _ => return Err("Not a table".to_string()),
+        },
+        _ => return Err("Not a table".to_string()),
+    };
+    extract_shape(table, limits)?.ok_or_else(|| "The table doesn't start with a numeric shape ID".to_string())
+}
+
+// Offsets of the braces in Lua source outside strings and comments, each with
+// whether it opens a table
+fn braces(content: &str) -> Vec<(usize, bool)> {
+    crate::lua_lexer::tokens(content)
+        .filter_map(|token| {
+            if token.is_symbol(Symbol::LeftBrace) {
+                Some((token.span.start, true))
+            } else if token.is_symbol(Symbol::RightBrace) {
+                Some((token.span.start, false))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Offset of the next table among the entries of the shapes table, starting at `from`;
+// None once the shapes table closes. Anything else between entries is passed over.
+fn next_entry(braces: &[(usize, bool)], from: usize) -> Option<usize> {
+    let next = braces.partition_point(|&(i, _)| i < from);
+    braces.get(next).filter(|&&(_, opens)| opens).map(|&(i, _)| i)
+}
+
+// Offset of the brace closing the table that opens at `open`
+fn matching_brace(braces: &[(usize, bool)], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for &(i, opens) in &braces[braces.partition_point(|&(i, _)| i < open)..] {
+        if opens {
+            depth += 1;
+        } else {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+// Offset within a bad entry of a later line that starts another shape, as in "{301,"
+fn next_shape_start(entry: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in entry.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        if offset > 0 && leading_id(line.trim_start()).is_some() {
+            return Some(offset + indent);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// The ID at the start of a shape table, as in "{300," or "{300 --name"
+fn leading_id(entry: &str) -> Option<usize> {
+    let rest = entry.strip_prefix('{')?.trim_start();
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let after = rest[digits..].trim_start();
+    if digits == 0 || !(after.starts_with(',') || after.starts_with("--")) {
+        return None;
+    }
+    rest[..digits].parse().ok()
+}
+
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+// Deepest nesting of brackets in Lua source, counting a run of minus signs (nested
+// unary operators) as that many more levels. Strings and comments are skipped.
+fn nesting_depth(content: &str) -> usize {
+    let mut depth: usize = 0;
+    let mut deepest = 0;
+    let mut minus_run = 0;
+    
+    for token in crate::lua_lexer::tokens(content) {
+        let Some(TokenType::Symbol { symbol }) = &token.kind else {
+            if !token.is_trivia() {
+                minus_run = 0;
+            }
+            continue;
+        };
+        match symbol {
+            Symbol::Minus => {
+                minus_run += 1;
+                deepest = deepest.max(depth + minus_run);
+                continue;
+            }
+            Symbol::LeftBrace | Symbol::LeftParen | Symbol::LeftBracket => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            Symbol::RightBrace | Symbol::RightParen | Symbol::RightBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        minus_run = 0;
+    }
+    deepest
 }
 
 // Function to fix common Lua syntax issues
@@ -142,15 +391,28 @@ fn fix_lua_syntax(content: &str) -> String {
     fixed = fixed.replace("}\n\t{", "},\n\t{");
     fixed = fixed.replace("}\n{", "},\n{");
     
-    // Fix launcher_radial property formatting
-    fixed = fixed.replace("launcher_radial=", "launcher_radial = ");
-    fixed = fixed.replace("launcher_radial", "launcher_radial = true");
+    // Fix launcher_radial property formatting: a bare flag means true, but one that
+    // already has a value is left alone
+    let mut result = String::with_capacity(fixed.len());
+    let mut rest = fixed.as_str();
+    while let Some(start) = rest.find("launcher_radial") {
+        let (before, after) = rest.split_at(start + "launcher_radial".len());
+        result.push_str(before);
+        // Left alone inside a comment, such as a shape name
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let commented = before[line_start..].contains("--");
+        if !commented && !after.trim_start().starts_with('=') {
+            result.push_str(" = true");
+        }
+        rest = after;
+    }
+    result.push_str(rest);
     
-    fixed
+    result
 }
 
 // A simpler, more direct approach to parse shapes from Lua files
-fn legacy_parse_shapes(content: &str) -> Result<ShapesFile, String> {
+fn legacy_parse_shapes(content: &str, limits: &ParseLimits) -> Result<ShapesFile, String> {
     let mut shapes = Vec::new();
     let lines = content.lines().collect::<Vec<_>>();
     
@@ -169,9 +431,13 @@ fn legacy_parse_shapes(content: &str) -> Result<ShapesFile, String> {
         if line.starts_with("{") && line.contains(",") {
             let parts = line.trim_matches(|c| c == '{' || c == '}' || c == ',').split(',').collect::<Vec<_>>();
             if !parts.is_empty() {
-                if let Ok(id) = parts[0].trim().parse::<usize>() {
+                if let Some(id) = parse_number::<usize>(parts[0]) {
                     // Found a shape with ID
                     let (shape, new_index) = parse_shape(id, &lines, i);
+                    check_vertex_counts(&shape, limits)?;
+                    if shapes.len() == limits.max_shapes {
+                        return Err(format!("File has more than the limit of {} shapes", limits.max_shapes));
+                    }
                     shapes.push(shape);
                     i = new_index;
                     continue;
@@ -185,6 +451,22 @@ fn legacy_parse_shapes(content: &str) -> Result<ShapesFile, String> {
     Ok(ShapesFile { shapes })
 }
 
+fn check_vertex_counts(shape: &Shape, limits: &ParseLimits) -> Result<(), String> {
+    for scale in &shape.scales {
+        check_vertex_count(shape.id, scale.verts.len(), limits)?;
+    }
+    Ok(())
+}
+
+fn check_vertex_count(shape_id: usize, count: usize, limits: &ParseLimits) -> Result<(), String> {
+    if count > limits.max_vertices_per_scale {
+        return Err(format!(
+            "Shape {} has a scale with {} vertices, more than the limit of {}", shape_id, count, limits.max_vertices_per_scale
+        ));
+    }
+    Ok(())
+}
+
 // Parse a single shape from the lines starting at the given index
 fn parse_shape(id: usize, lines: &[&str], start_index: usize) -> (Shape, usize) {
     let mut scales = Vec::new();
@@ -206,7 +488,7 @@ fn parse_shape(id: usize, lines: &[&str], start_index: usize) -> (Shape, usize)
         
         // Looking for scale definitions
         if line.contains("verts") && line.contains("{") {
-            let (scale, new_index) = parse_scale(&lines, i);
+            let (scale, new_index) = parse_scale(id, lines, i);
             if !scale.verts.is_empty() {
                 scales.push(scale);
             }
@@ -240,7 +522,7 @@ fn parse_shape(id: usize, lines: &[&str], start_index: usize) -> (Shape, usize)
 }
 
 // Parse a scale definition from the lines starting at the given index
-fn parse_scale(lines: &[&str], start_index: usize) -> (Scale, usize) {
+fn parse_scale(shape_id: usize, lines: &[&str], start_index: usize) -> (Scale, usize) {
     let mut verts = Vec::new();
     let mut ports = Vec::new();
     let mut i = start_index;
@@ -263,7 +545,7 @@ fn parse_scale(lines: &[&str], start_index: usize) -> (Scale, usize) {
         if in_verts && line.contains("{") && line.contains(",") {
             let coords = line.trim_matches(|c| c == '{' || c == '}' || c == ',').split(',').collect::<Vec<_>>();
             if coords.len() >= 2 {
-                if let (Ok(x), Ok(y)) = (coords[0].trim().parse::<f32>(), coords[1].trim().parse::<f32>()) {
+                if let (Some(x), Some(y)) = (parse_number::<f32>(coords[0]), parse_number::<f32>(coords[1])) {
                     verts.push(Vertex { x, y });
                 }
             }
@@ -271,22 +553,21 @@ fn parse_scale(lines: &[&str], start_index: usize) -> (Scale, usize) {
         
         // Parse ports
         if in_ports && line.contains("{") && line.contains(",") {
-            let parts = line.trim_matches(|c| c == '{' || c == '}' || c == ',').split(',').collect::<Vec<_>>();
-            if parts.len() >= 2 {
-                if let (Ok(edge), Ok(position)) = (parts[0].trim().parse::<usize>(), parts[1].trim().parse::<f32>()) {
-                    let port_type = if parts.len() >= 3 {
-                        let type_str = parts[2].trim();
-                        Some(PortType::from_str(type_str))
-                    } else {
-                        None
-                    };
-                    
-                    ports.push(Port {
-                        edge,
-                        position,
-                        port_type,
-                    });
-                }
+            let parts = line.trim_matches(|c| c == '{' || c == '}' || c == ',').split(',').map(str::trim).collect::<Vec<_>>();
+            // The type is a third number or a name anywhere among the values
+            let (numbers, names): (Vec<&str>, Vec<&str>) = parts.into_iter()
+                .filter(|part| !part.is_empty())
+                .partition(|part| parse_number::<f32>(part).is_some());
+            let edge = numbers.first().and_then(|text| parse_number::<usize>(text));
+            let position = numbers.get(1).and_then(|text| parse_number::<f32>(text));
+            if let (Some(edge), Some(position)) = (edge, position) {
+                let port_type = names.first().or(numbers.get(2)).map(|text| port_type(shape_id, text));
+                
+                ports.push(Port {
+                    edge,
+                    position,
+                    port_type,
+                });
             }
         }
         
@@ -301,190 +582,313 @@ fn parse_scale(lines: &[&str], start_index: usize) -> (Scale, usize) {
     (Scale { verts, ports }, i)
 }
 
-/// Extract a shape from a Lua table constructor
-fn extract_shape(table: &ast::TableConstructor) -> Option<Shape> {
-    let mut id = None;
-    let name = None;
-    let mut scales = Vec::new();
-    let mut launcher_radial = None;
+/// Extract a shape from a Lua table constructor; None if it has no ID
+fn extract_shape(table: &ast::TableConstructor, limits: &ParseLimits) -> Result<Option<Shape>, String> {
+    let mut fields = table.fields().pairs();
     
-    // Process each field in the shape table
-    for (i, field) in table.fields().into_iter().enumerate() {
-        match field {
-            ast::Field::NoKey(expr) => {
-                // First field should be the ID
-                if i == 0 {
-                    if let ast::Expression::Number(num) = expr {
-                        if let Ok(id_val) = num.token().to_string().parse::<usize>() {
-                            id = Some(id_val);
-                        }
-                    }
+    // First field is the ID, with the name in a comment after it
+    let Some(first) = fields.next() else { return Ok(None) };
+    let Some(id) = no_key(first.value()).and_then(number::<usize>) else { return Ok(None) };
+    let name = first.punctuation()
+        .into_iter()
+        .flat_map(|comma| comma.trailing_trivia())
+        .find_map(|trivia| match trivia.token_type() {
+            TokenType::SingleLineComment { comment } => Some(comment.trim().to_string()),
+            _ => None,
+        })
+        .filter(|name| !name.is_empty());
+    
+    let mut shape = Shape {
+        id,
+        name,
+        scales: Vec::new(),
+        launcher_radial: None,
+        mirror_of: None,
+        group: None,
+        features: None,
+        fill_color: None,
+        fill_color1: None,
+        line_color: None,
+        durability: None,
+        density: None,
+        grow_rate: None,
+        shroud: None,
+        cannon: None,
+        thruster: None,
+    };
+    
+    for pair in fields {
+        match pair.value() {
+            // Second field is the scales table
+            ast::Field::NoKey(ast::Expression::TableConstructor(scales_table)) if shape.scales.is_empty() => {
+                for scale_table in scales_table.fields().iter().filter_map(no_key).filter_map(table_of) {
+                    shape.scales.push(extract_scale(scale_table, id, limits)?);
                 }
-                // Second field should be the scales table
-                else if i == 1 {
-                    if let ast::Expression::TableConstructor(scales_table) = expr {
-                        // Each entry in the scales table is a scale
-                        for scale_field in scales_table.fields().into_iter() {
-                            if let ast::Field::NoKey(expr) = scale_field {
-                                if let ast::Expression::TableConstructor(scale_table) = expr {
-                                    let mut verts = Vec::new();
-                                    let mut ports = Vec::new();
-                                    
-                                    // Iterate through fields in the scale table
-                                    for def_field in scale_table.fields().into_iter() {
-                                        if let ast::Field::NameKey { key, value, .. } = def_field {
-                                            let key_str = key.token().to_string();
-                                            
-                                            // Parse vertices
-                                            if key_str == "verts" {
-                                                if let ast::Expression::TableConstructor(verts_table) = value {
-                                                    for vert_field in verts_table.fields().into_iter() {
-                                                        if let ast::Field::NoKey(expr) = vert_field {
-                                                            if let ast::Expression::TableConstructor(vert_table) = expr {
-                                                                let mut x = None;
-                                                                let mut y = None;
-                                                                
-                                                                for (m, coord) in vert_table.fields().into_iter().enumerate() {
-                                                                    if let ast::Field::NoKey(expr) = coord {
-                                                                        match expr {
-                                                                            // Handle regular numbers
-                                                                            ast::Expression::Number(num) => {
-                                                                                let val = num.token().to_string().parse::<f32>().ok();
-                                                                                if m == 0 {
-                                                                                    x = val;
-                                                                                } else if m == 1 {
-                                                                                    y = val;
-                                                                                }
-                                                                            },
-                                                                            // Handle negative numbers (represented as UnaryOperator with Minus)
-                                                                            ast::Expression::UnaryOperator { unop, expression } => {
-                                                                                if *unop.token().token_type() == (full_moon::tokenizer::TokenType::Symbol { symbol: Minus }) {
-                                                                                    if let ast::Expression::Number(num) = &**expression {
-                                                                                        let val_str = num.token().to_string();
-                                                                                        if let Ok(val) = val_str.parse::<f32>() {
-                                                                                            let neg_val = -val;
-                                                                                            if m == 0 {
-                                                                                                x = Some(neg_val);
-                                                                                            } else if m == 1 {
-                                                                                                y = Some(neg_val);
-                                                                                            }
-                                                                                        }
-                                                                                    }
-                                                                                }
-                                                                            },
-                                                                            _ => {}
-                                                                        }
-                                                                    }
-                                                                }
-                                                                
-                                                                if let (Some(x), Some(y)) = (x, y) {
-                                                                    verts.push(Vertex { x, y });
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            } 
-                                            // Parse ports
-                                            else if key_str == "ports" {
-                                                if let ast::Expression::TableConstructor(ports_table) = value {
-                                                    for port_field in ports_table.fields().into_iter() {
-                                                        if let ast::Field::NoKey(expr) = port_field {
-                                                            if let ast::Expression::TableConstructor(port_table) = expr {
-                                                                let mut edge = None;
-                                                                let mut position = None;
-                                                                let mut port_type = None;
-                                                                
-                                                                for (m, field) in port_table.fields().into_iter().enumerate() {
-                                                                    if let ast::Field::NoKey(expr) = field {
-                                                                        if m == 0 {
-                                                                            if let ast::Expression::Number(num) = expr {
-                                                                                edge = num.token().to_string().parse::<usize>().ok();
-                                                                            }
-                                                                        } else if m == 1 {
-                                                                            if let ast::Expression::Number(num) = expr {
-                                                                                position = num.token().to_string().parse::<f32>().ok();
-                                                                            }
-                                                                        } else if m == 2 {
-                                                                            // Handle port type - don't try to use the String variant which may not exist
-                                                                            
-                                                                            // Use inspect pattern to capture the token value without assuming variant
-                                                                            let type_str = match expr {
-                                                                                ast::Expression::Symbol(token) => token.token().to_string(),
-                                                                                // Use string representation for any other variant
-                                                                                _ => format!("{:?}", expr),
-                                                                            };
-                                                                            port_type = Some(PortType::from_str(&type_str));
-                                                                        }
-                                                                    }
-                                                                }
-                                                                
-                                                                if let (Some(edge), Some(position)) = (edge, position) {
-                                                                    ports.push(Port {
-                                                                        edge,
-                                                                        position,
-                                                                        port_type,
-                                                                    });
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    
-                                    scales.push(Scale { verts, ports });
-                                }
+            },
+            // Properties of the shape itself, under the names the serializer writes
+            ast::Field::NameKey { key, value, .. } => {
+                match key.token().to_string().as_str() {
+                    // A bare flag was turned into "= true" by fix_lua_syntax
+                    "launcher_radial" => shape.launcher_radial = Some(boolean(value).unwrap_or(true)),
+                    "mirror_of" => shape.mirror_of = number(value),
+                    "group" => shape.group = number(value),
+                    "features" => shape.features = word(value).map(|features| {
+                        features.split('|').map(|feature| feature.trim().to_string()).collect()
+                    }),
+                    "fillColor" => shape.fill_color = color(value),
+                    "fillColor1" => shape.fill_color1 = color(value),
+                    "lineColor" => shape.line_color = color(value),
+                    "durability" => shape.durability = number(value),
+                    "density" => shape.density = number(value),
+                    "growRate" => shape.grow_rate = number(value),
+                    "shroud" => shape.shroud = table_of(value).map(|components| {
+                        components.fields().iter().filter_map(no_key).filter_map(table_of).map(extract_shroud).collect()
+                    }),
+                    "cannon" => shape.cannon = table_of(value).map(extract_cannon),
+                    "thruster" => shape.thruster = table_of(value).map(|thruster| {
+                        let mut properties = ThrusterProperties { force: 0.0, power: 0.0, color: None };
+                        for (key, value) in named_fields(thruster) {
+                            match key.as_str() {
+                                "force" => properties.force = number(value).unwrap_or(0.0),
+                                "power" => properties.power = number(value).unwrap_or(0.0),
+                                "color" => properties.color = color(value),
+                                _ => {}
                             }
                         }
-                    }
+                        properties
+                    }),
+                    _ => {}
                 }
             },
-            // Handle named properties at the shape level like "launcher_radial"
-            ast::Field::NameKey { key, value, .. } => {
-                let key_str = key.token().to_string();
-                
-                if key_str == "launcher_radial" {
-                    // Default to true if the property exists
-                    launcher_radial = Some(true);
-                    
-                    // Try to extract more specific value if available
-                    if let ast::Expression::Symbol(symbol) = value {
-                        let val_str = symbol.token().to_string();
-                        if val_str == "false" {
-                            launcher_radial = Some(false);
-                        }
+            _ => {}
+        }
+    }
+    
+    Ok(Some(shape))
+}
+
+fn extract_scale(table: &ast::TableConstructor, shape_id: usize, limits: &ParseLimits) -> Result<Scale, String> {
+    let mut scale = Scale { verts: Vec::new(), ports: Vec::new() };
+    
+    for (key, value) in named_fields(table) {
+        let Some(list) = table_of(value) else { continue };
+        if key == "verts" {
+            check_vertex_count(shape_id, list.fields().len(), limits)?;
+            for vert in list.fields().iter().filter_map(no_key).filter_map(table_of) {
+                let coords = positional(vert);
+                if let (Some(x), Some(y)) = (coords.first().and_then(|c| number(c)), coords.get(1).and_then(|c| number(c))) {
+                    scale.verts.push(Vertex { x, y });
+                }
+            }
+        } else if key == "ports" {
+            for port in list.fields().iter().filter_map(no_key).filter_map(table_of) {
+                // The type is a third number, or a name such as THRUSTER_OUT or
+                // "THRUSTER_OUT" anywhere among the values
+                let (numbers, others): (Vec<_>, Vec<_>) = positional(port).into_iter().partition(|v| number::<f32>(v).is_some());
+                let edge = numbers.first().and_then(|v| number(v));
+                let position = numbers.get(1).and_then(|v| number(v));
+                if let (Some(edge), Some(position)) = (edge, position) {
+                    let name = others.into_iter().find_map(word);
+                    let port_type = name.or_else(|| numbers.get(2).and_then(|v| number::<f32>(v)).map(|n| n.to_string()))
+                        .map(|text| port_type(shape_id, &text));
+                    scale.ports.push(Port { edge, position, port_type });
+                }
+            }
+        }
+    }
+    
+    Ok(scale)
+}
+
+fn extract_shroud(table: &ast::TableConstructor) -> ShroudComponent {
+    let mut component = ShroudComponent {
+        size: (0.0, 0.0),
+        offset: (0.0, 0.0, 0.0),
+        taper: 0.0,
+        count: 0,
+        angle: 0.0,
+        tri_color_id: 0,
+        tri_color1_id: 0,
+        line_color_id: 0,
+        shape: String::new(),
+    };
+    
+    for (key, value) in named_fields(table) {
+        let numbers: Vec<f32> = table_of(value).map(positional).unwrap_or_default()
+            .into_iter()
+            .map(|v| number(v).unwrap_or(0.0))
+            .collect();
+        let at = |i: usize| numbers.get(i).copied().unwrap_or(0.0);
+        match key.as_str() {
+            "size" => component.size = (at(0), at(1)),
+            "offset" => component.offset = (at(0), at(1), at(2)),
+            "taper" => component.taper = number(value).unwrap_or(0.0),
+            "count" => component.count = number(value).unwrap_or(0),
+            "angle" => component.angle = number(value).unwrap_or(0.0),
+            "tri_color_id" => component.tri_color_id = number(value).unwrap_or(0),
+            "tri_color1_id" => component.tri_color1_id = number(value).unwrap_or(0),
+            "line_color_id" => component.line_color_id = number(value).unwrap_or(0),
+            "shape" => component.shape = word(value).unwrap_or_default(),
+            _ => {}
+        }
+    }
+    component
+}
+
+fn extract_cannon(table: &ast::TableConstructor) -> CannonProperties {
+    let mut cannon = CannonProperties {
+        damage: 0.0,
+        power: 0.0,
+        rounds_per_sec: 0.0,
+        muzzle_vel: 0.0,
+        range: 0.0,
+        spread: 0.0,
+        rounds_per_burst: None,
+        burstyness: None,
+        color: None,
+        explosive: None,
+        fragment: None,
+    };
+    
+    for (key, value) in named_fields(table) {
+        match key.as_str() {
+            "damage" => cannon.damage = number(value).unwrap_or(0.0),
+            "power" => cannon.power = number(value).unwrap_or(0.0),
+            "roundsPerSec" => cannon.rounds_per_sec = number(value).unwrap_or(0.0),
+            "muzzleVel" => cannon.muzzle_vel = number(value).unwrap_or(0.0),
+            "range" => cannon.range = number(value).unwrap_or(0.0),
+            "spread" => cannon.spread = number(value).unwrap_or(0.0),
+            "roundsPerBurst" => cannon.rounds_per_burst = number(value),
+            "burstyness" => cannon.burstyness = number(value),
+            "color" => cannon.color = color(value),
+            "explosive" => cannon.explosive = word(value),
+            "fragment" => cannon.fragment = table_of(value).map(|fragment| {
+                let mut properties = FragmentProperties {
+                    rounds_per_burst: 0,
+                    muzzle_vel: 0.0,
+                    spread: 0.0,
+                    pattern: None,
+                    damage: 0.0,
+                    range: 0.0,
+                    color: None,
+                };
+                for (key, value) in named_fields(fragment) {
+                    match key.as_str() {
+                        "roundsPerBurst" => properties.rounds_per_burst = number(value).unwrap_or(0),
+                        "muzzleVel" => properties.muzzle_vel = number(value).unwrap_or(0.0),
+                        "spread" => properties.spread = number(value).unwrap_or(0.0),
+                        "pattern" => properties.pattern = word(value),
+                        "damage" => properties.damage = number(value).unwrap_or(0.0),
+                        "range" => properties.range = number(value).unwrap_or(0.0),
+                        "color" => properties.color = color(value),
+                        _ => {}
                     }
-                    // Any other cases simply use the default true value
                 }
-                // Add more property handlers here as needed
-            },
-            // Handle any other field types we don't explicitly handle
+                properties
+            }),
             _ => {}
         }
     }
+    cannon
+}
+
+fn no_key(field: &ast::Field) -> Option<&ast::Expression> {
+    match field {
+        ast::Field::NoKey(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+fn table_of(expr: &ast::Expression) -> Option<&ast::TableConstructor> {
+    match expr {
+        ast::Expression::TableConstructor(table) => Some(table),
+        _ => None,
+    }
+}
+
+// Values of the fields without a key, in order
+fn positional(table: &ast::TableConstructor) -> Vec<&ast::Expression> {
+    table.fields().iter().filter_map(no_key).collect()
+}
+
+// `name = value` fields
+fn named_fields(table: &ast::TableConstructor) -> impl Iterator<Item = (String, &ast::Expression)> {
+    table.fields().iter().filter_map(|field| match field {
+        ast::Field::NameKey { key, value, .. } => Some((key.token().to_string(), value)),
+        _ => None,
+    })
+}
+
+// A number literal, possibly negated
+fn number<T: std::str::FromStr>(expr: &ast::Expression) -> Option<T> {
+    match expr {
+        ast::Expression::Number(num) => parse_number(&num.token().to_string()),
+        ast::Expression::UnaryOperator { unop, expression } if *unop.token().token_type() == (TokenType::Symbol { symbol: Minus }) => {
+            match &**expression {
+                ast::Expression::Number(num) => parse_number(&format!("-{}", num.token())),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// A number as Lua writes it: decimal, with an exponent as in 1e-3, or hex as in
+// 0x1F. Whole numbers written either way also read as integers.
+fn parse_number<T: std::str::FromStr>(text: &str) -> Option<T> {
+    let text = text.trim();
+    if let Ok(value) = text.parse() {
+        return Some(value);
+    }
     
-    if let Some(id) = id {
-        Some(Shape {
-            id,
-            name,
-            scales,
-            launcher_radial,
-            mirror_of: None,
-            group: None,
-            features: None,
-            fill_color: None,
-            fill_color1: None,
-            line_color: None,
-            durability: None,
-            density: None,
-            grow_rate: None,
-            shroud: None,
-            cannon: None,
-            thruster: None,
-        })
-    } else {
-        None
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
+    };
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()? as f64,
+        None => digits.parse::<f64>().ok()?,
+    };
+    let value = if negative { -magnitude } else { magnitude };
+    
+    // Written out again plainly, so 1e3 reads as an integer and 0x10 as anything
+    let plain = if value.fract() == 0.0 && value.abs() < 1e15 { (value as i64).to_string() } else { value.to_string() };
+    plain.parse().ok()
+}
+
+// Colors are usually written in hex, 0xAARRGGBB
+fn color(expr: &ast::Expression) -> Option<u32> {
+    number(expr)
+}
+
+fn boolean(expr: &ast::Expression) -> Option<bool> {
+    match expr {
+        ast::Expression::Symbol(symbol) => match symbol.token().to_string().as_str() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The port type `text` names, warning about values that aren't one and treating
+// them as DEFAULT
+fn port_type(shape_id: usize, text: &str) -> PortType {
+    PortType::parse(text).unwrap_or_else(|| {
+        log::warn!("Shape {}: unknown port type {}, using DEFAULT", shape_id, text.trim());
+        PortType::Default
+    })
+}
+
+// A quoted string or a bare identifier such as a port type or explosive name
+fn word(expr: &ast::Expression) -> Option<String> {
+    match expr {
+        ast::Expression::String(token) => match token.token().token_type() {
+            TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+            _ => None,
+        },
+        ast::Expression::Var(ast::Var::Name(name)) => Some(name.token().to_string()),
+        _ => None,
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,52 @@
+// What launchers spawn: a link from a shape with launcher or missile ports to the
+// shape of the block it launches, so the editor can show the payload in place and
+// check it exists. blocks.lua says the same through `replicateBlock`; a link set in
+// the editor takes precedence over it.
+use serde::{Deserialize, Serialize};
+
+use crate::arrange::{mate, Placement};
+use crate::data_structures::{PortType, Shape, Vertex};
+
+/// Shape `launcher_id` launches blocks of shape `payload_id`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadLink {
+    pub launcher_id: usize,
+    pub payload_id: usize,
+}
+
+/// Ports a payload comes out of
+pub fn is_launch_port(port_type: &PortType) -> bool {
+    matches!(port_type, PortType::Launcher | PortType::Missile)
+}
+
+pub fn has_launch_ports(shape: &Shape) -> bool {
+    shape.ports.iter().any(|port| is_launch_port(&port.port_type))
+}
+
+/// Where the payload sits on each launch port of the launcher, in the launcher's own
+/// coordinates. The payload joins by a port of the matching kind (its missile port on
+/// a launcher port and the other way round), else by its first port; a payload without
+/// ports is centered on the launch port.
+pub fn ghost_placements(launcher: &Shape, payload: &Shape) -> Vec<Placement> {
+    let origin = Placement::new(launcher.id, Vertex { x: 0.0, y: 0.0 });
+    (0..launcher.ports.len())
+        .filter(|&i| is_launch_port(&launcher.ports[i].port_type))
+        .filter_map(|i| {
+            let wanted = match launcher.ports[i].port_type {
+                PortType::Launcher => PortType::Missile,
+                _ => PortType::Launcher,
+            };
+            let payload_port = payload.ports.iter()
+                .position(|port| port.port_type == wanted)
+                .or_else(|| (!payload.ports.is_empty()).then_some(0));
+            match payload_port.and_then(|port| mate(launcher, &origin, i, payload, port)) {
+                Some(mate) => Some(mate.placement),
+                None => {
+                    let at = launcher.port_position(i)?;
+                    let center = payload.centroid().unwrap_or(Vertex { x: 0.0, y: 0.0 });
+                    Some(Placement::new(payload.id, Vertex { x: at.x - center.x, y: at.y - center.y }))
+                }
+            }
+        })
+        .collect()
+}
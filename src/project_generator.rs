@@ -6,49 +6,53 @@ use std::io::Write;
 
 // Main function to generate a new Reassembly mod project
 pub fn generate_project(project_name: &str) -> Result<(), io::Error> {
+    generate_project_at(&PathBuf::from(project_name), project_name)
+}
+
+// Generate the project in `project_dir`, with `project_name` as its title
+pub fn generate_project_at(project_dir: &Path, project_name: &str) -> Result<(), io::Error> {
     println!("Generating Reassembly mod project: {}", project_name);
     
     // Create the project directory
-    let project_dir = PathBuf::from(project_name);
     if project_dir.exists() {
         return Err(io::Error::new(
             io::ErrorKind::AlreadyExists,
-            format!("Project directory '{}' already exists", project_name)
+            format!("Project directory '{}' already exists", project_dir.display())
         ));
     }
     
-    fs::create_dir(&project_dir)?;
+    fs::create_dir_all(project_dir)?;
     
     // Create necessary sub-directories
     fs::create_dir(project_dir.join("ships"))?;
     fs::create_dir(project_dir.join("extra_ships"))?;
     
     // Create the shapes.lua file
-    create_shapes_lua(&project_dir)?;
+    create_shapes_lua(project_dir)?;
     
     // Create shape reference with common patterns
-    create_shape_reference(&project_dir)?;
+    create_shape_reference(project_dir)?;
     
     // Create the blocks.lua file (template)
-    create_blocks_lua(&project_dir)?;
+    create_blocks_lua(project_dir)?;
     
     // Create factions.lua file (template)
-    create_factions_lua(&project_dir)?;
+    create_factions_lua(project_dir)?;
     
     // Create regions.lua file (template)
-    create_regions_lua(&project_dir)?;
+    create_regions_lua(project_dir)?;
     
     // Create a sample starter ship file
-    create_sample_ship(&project_dir)?;
+    create_sample_ship(project_dir)?;
     
     // Create a README.md file with instructions
-    create_readme(&project_dir, project_name)?;
+    create_readme(project_dir, project_name)?;
     
     // Create cvars.txt file
-    create_cvars(&project_dir)?;
+    create_cvars(project_dir)?;
     
     // Create preview.png placeholder reminder
-    create_preview_reminder(&project_dir)?;
+    create_preview_reminder(project_dir)?;
     
     println!("Project created successfully. Open the README.md file for instructions.");
     
@@ -302,17 +306,13 @@ fn create_preview_reminder(project_dir: &Path) -> Result<(), io::Error> {
     Ok(())
 }
 
-// Create a reference file with common shape patterns
-fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
-    let path = project_dir.join("shape_reference.lua");
-    let mut file = fs::File::create(path)?;
-    
-    write!(file, "{}", r#"-- This file contains reference shapes that you can use as templates
+// Reference shapes written to shape_reference.lua, also offered as templates in the editor
+const SHAPE_REFERENCE: &str = r#"-- This file contains reference shapes that you can use as templates
 -- Copy these into your shapes.lua file as needed and modify them
 -- Note: Shape IDs should be in the range 100-10000
 
 -- Triangle
-{5002  --Triangle
+{5002, --Triangle
     {
         {
             verts={
@@ -330,7 +330,7 @@ fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
 },
 
 -- Hexagon
-{5003  --Hexagon
+{5003, --Hexagon
     {
         {
             verts={
@@ -354,7 +354,7 @@ fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
 },
 
 -- Octagon
-{5004  --Octagon
+{5004, --Octagon
     {
         {
             verts={
@@ -382,7 +382,7 @@ fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
 },
 
 -- Specialized Thruster Shape
-{5005  --Thruster
+{5005, --Thruster
     {
         {
             verts={
@@ -406,7 +406,7 @@ fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
 },
 
 -- Weapon Shape with specialized ports
-{5006  --Weapon
+{5006, --Weapon
     {
         {
             verts={
@@ -428,7 +428,27 @@ fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
         }
     }
 }
-"#)?;
+"#;
+
+/// The shapes from shape_reference.lua, named after their comments
+pub fn reference_templates() -> Vec<crate::ast::Shape> {
+    // The file lists the shapes one after another, without the table around them
+    let mut shapes = crate::parser::parse_shapes_content(&format!("{{\n{}\n}}", SHAPE_REFERENCE))
+        .map(|shapes_file| shapes_file.shapes)
+        .unwrap_or_default();
+    for shape in &mut shapes {
+        let prefix = format!("{{{}, --", shape.id);
+        shape.name = SHAPE_REFERENCE.lines().find_map(|line| line.strip_prefix(&prefix)).map(str::to_string);
+    }
+    shapes
+}
+
+// Create a reference file with common shape patterns
+fn create_shape_reference(project_dir: &Path) -> Result<(), io::Error> {
+    let path = project_dir.join("shape_reference.lua");
+    let mut file = fs::File::create(path)?;
+    
+    write!(file, "{}", SHAPE_REFERENCE)?;
     
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,112 @@
+// Crash recovery: a panic hook writes the shapes being edited to a recovery file
+// (localStorage on WASM), and the next start offers to restore them
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::data_structures::SharedShape;
+use crate::serializer::serialize_shapes_file;
+use crate::shape_editor::ShapeEditor;
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "reassembly_shape_editor.recovery";
+
+// Shapes as of the last frame. Shapes are copy-on-write handles, so keeping them
+// here costs a reference count per shape, not a copy.
+static LATEST_SHAPES: Lazy<Mutex<Vec<SharedShape>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Remember the current shapes for the panic hook; called once per frame
+pub fn track(shapes: &[SharedShape]) {
+    if let Ok(mut latest) = LATEST_SHAPES.lock() {
+        if latest.as_slice() != shapes {
+            *latest = shapes.to_vec();
+        }
+    }
+}
+
+/// Dump the tracked shapes when the app panics, then run the previously installed hook
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn install_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // try_lock: the panic may have happened while the lock was held
+        if let Ok(latest) = LATEST_SHAPES.try_lock() {
+            if !latest.is_empty() {
+                match write_stored(&shapes_to_lua(&latest)) {
+                    Ok(()) => eprintln!("Shapes saved for recovery"),
+                    Err(e) => eprintln!("Failed to save shapes for recovery: {}", e),
+                }
+            }
+        }
+        previous_hook(info);
+    }));
+}
+
+/// Shapes dumped by a crash in an earlier session, as Lua
+pub fn load() -> Option<String> {
+    read_stored().filter(|content| !content.trim().is_empty())
+}
+
+/// Remove the recovery dump once it has been restored or declined
+pub fn discard() {
+    if let Err(e) = remove_stored() {
+        log::warn!("Failed to remove recovery file: {}", e);
+    }
+}
+
+pub(crate) fn shapes_to_lua(shapes: &[SharedShape]) -> String {
+    let shapes_file = crate::ast::ShapesFile {
+        shapes: shapes.iter().map(|shape| ShapeEditor::convert_to_ast_shape(shape)).collect(),
+    };
+    serialize_shapes_file(&shapes_file)
+}
+
+/// Location of the recovery file, next to the settings
+#[cfg(not(target_arch = "wasm32"))]
+pub fn recovery_path() -> Option<std::path::PathBuf> {
+    Some(crate::settings::config_dir()?.join("recovery.lua"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stored() -> Option<String> {
+    std::fs::read_to_string(recovery_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stored(content: &str) -> Result<(), String> {
+    let path = recovery_path().ok_or("No config directory found")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_stored() -> Result<(), String> {
+    match recovery_path() {
+        Some(path) if path.exists() => std::fs::remove_file(path).map_err(|e| e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| "localStorage is not available".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_stored() -> Option<String> {
+    local_storage().ok()?.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stored(content: &str) -> Result<(), String> {
+    local_storage()?.set_item(STORAGE_KEY, content).map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_stored() -> Result<(), String> {
+    local_storage()?.remove_item(STORAGE_KEY).map_err(|e| format!("{:?}", e))
+}
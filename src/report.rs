@@ -0,0 +1,123 @@
+// Per-shape metrics written out as CSV or JSON, for balancing and review in a
+// spreadsheet or script outside the editor. Sizes are those of scale 1.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::data_structures::{PortType, Shape};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ShapeMetrics {
+    pub id: usize,
+    pub name: String,
+    pub vertices: usize,
+    pub ports: usize,
+    // Every port type, with 0 for the ones the shape doesn't use
+    pub ports_by_type: BTreeMap<String, usize>,
+    pub area: f32,
+    // None for a shape without vertices
+    pub bounds: Option<Bounds>,
+    // Scale 1 counts, so a shape without extra scales has 1
+    pub scales: usize,
+}
+
+#[derive(Serialize)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Bounds {
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+}
+
+pub fn shape_metrics(shape: &Shape) -> ShapeMetrics {
+    let mut ports_by_type: BTreeMap<String, usize> = PortType::ALL.iter().map(|port_type| (port_type.to_string(), 0)).collect();
+    for port in &shape.ports {
+        *ports_by_type.entry(port.port_type.to_string()).or_default() += 1;
+    }
+
+    let bounds = shape.vertices.iter()
+        .map(|v| Bounds { min_x: v.x, min_y: v.y, max_x: v.x, max_y: v.y })
+        .reduce(|a, b| Bounds {
+            min_x: a.min_x.min(b.min_x),
+            min_y: a.min_y.min(b.min_y),
+            max_x: a.max_x.max(b.max_x),
+            max_y: a.max_y.max(b.max_y),
+        });
+
+    ShapeMetrics {
+        id: shape.id,
+        name: shape.name.clone(),
+        vertices: shape.vertices.len(),
+        ports: shape.ports.len(),
+        ports_by_type,
+        area: shape.signed_area().abs(),
+        bounds,
+        scales: shape.scales.len() + 1,
+    }
+}
+
+pub fn report<'a>(shapes: impl IntoIterator<Item = &'a Shape>, format: ReportFormat) -> String {
+    let metrics: Vec<ShapeMetrics> = shapes.into_iter().map(shape_metrics).collect();
+    match format {
+        ReportFormat::Csv => to_csv(&metrics),
+        // Nothing in the metrics can fail to serialize
+        ReportFormat::Json => serde_json::to_string_pretty(&metrics).unwrap_or_default(),
+    }
+}
+
+// One row per shape; the port type columns follow PortType::ALL
+fn to_csv(metrics: &[ShapeMetrics]) -> String {
+    let mut header = vec!["id".to_string(), "name".to_string(), "vertices".to_string(), "ports".to_string()];
+    header.extend(PortType::ALL.iter().map(|port_type| format!("ports_{}", port_type.to_string().to_lowercase())));
+    header.extend(["area", "min_x", "min_y", "max_x", "max_y", "width", "height", "scales"].map(str::to_string));
+
+    let mut csv = header.join(",") + "\n";
+    for shape in metrics {
+        let mut row = vec![shape.id.to_string(), csv_field(&shape.name), shape.vertices.to_string(), shape.ports.to_string()];
+        row.extend(PortType::ALL.iter().map(|port_type| shape.ports_by_type[&port_type.to_string()].to_string()));
+        row.push(shape.area.to_string());
+        match &shape.bounds {
+            Some(bounds) => row.extend(
+                [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y, bounds.width(), bounds.height()].map(|n| n.to_string()),
+            ),
+            None => row.extend(std::iter::repeat_n(String::new(), 6)),
+        }
+        row.push(shape.scales.to_string());
+        csv += &(row.join(",") + "\n");
+    }
+    csv
+}
+
+// Quoted when it holds a separator, quote or line break, with quotes doubled
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
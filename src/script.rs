@@ -0,0 +1,398 @@
+// Scripts for the console, in Rhai, run over the session's shapes. A script sees the
+// shapes as maps in the array `shapes`, changes them directly or through the helper
+// methods, and what it leaves in `shapes` replaces the session's shapes. A script
+// that fails changes nothing.
+//
+// Rhai hands `for` loops and plain function arguments copies, so a shape is changed
+// in place through `shapes[i]`, as in `shapes[i].add_port(0, 0.5)`.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, FLOAT, INT};
+
+use crate::data_structures::{Port, PortType, Shape, ShapeScale, Vertex};
+
+// Operations a script may do before it is stopped, so that an endless loop can't
+// hang the editor
+const MAX_OPERATIONS: u64 = 5_000_000;
+
+/// Reference shown in the console
+pub const HELP: &str = "\
+shapes                          array of shapes: #{id, name, verts, ports, scales, launcher_radial}
+  verts                         array of #{x: .., y: ..}
+  ports                         array of #{edge: .., position: .., type: \"DEFAULT\"}; edges count from 0
+  scales                        scales 2, 3, ..., each #{verts, ports}
+shape.add_vertex(x, y)          append a vertex to the outline
+shape.add_port(edge, position [, type])
+port.set_port_type(type)        type is a name such as \"THRUSTER_OUT\"
+shape.translate(dx, dy)
+shape.rotate(degrees)           counter-clockwise about the pivot
+shape.scale(factor [, factor_y])
+shape.mirror(\"x\" | \"y\")         negate the x or y coordinates, keeping the winding
+rotate and scale work about the pivot placed on the canvas, or the origin if there is none
+`for` loops get copies: change a shape through shapes[i]
+print(..), throw \"message\", and the rest of Rhai's standard library
+
+Example: a port in the middle of every edge of every shape
+for i in 0..shapes.len() {
+    for edge in 0..shapes[i].verts.len() {
+        shapes[i].add_port(edge, 0.5);
+    }
+}";
+
+/// Outcome of running a script: what it printed, and the shapes it left or why it
+/// failed
+pub struct ScriptRun {
+    pub output: Vec<String>,
+    pub result: Result<Vec<Shape>, String>,
+}
+
+/// Run `source` over `shapes`; `rotate` and `scale` work about `pivot`
+pub fn run(source: &str, shapes: &[Shape], pivot: &Vertex) -> ScriptRun {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(pivot, &output);
+    let mut scope = Scope::new();
+    scope.push("shapes", shapes.iter().map(shape_to_map).map(Dynamic::from).collect::<Array>());
+
+    let result = engine.run_with_scope(&mut scope, source)
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            let shapes = scope.get_value::<Array>("shapes").ok_or("`shapes` is no longer an array")?;
+            shapes.into_iter()
+                .enumerate()
+                .map(|(i, value)| value_to_shape(value).map_err(|e| format!("shapes[{}]: {}", i, e)))
+                .collect()
+        });
+    let output = output.take();
+    ScriptRun { output, result }
+}
+
+fn engine(pivot: &Vertex, output: &Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let printed = Rc::clone(output);
+    engine.on_print(move |text| printed.borrow_mut().push(text.to_string()));
+    let printed = Rc::clone(output);
+    engine.on_debug(move |text, _, _| printed.borrow_mut().push(text.to_string()));
+
+    engine.register_fn("add_vertex", |shape: &mut Map, x: Dynamic, y: Dynamic| {
+        let vertex = Vertex { x: number(&x, "x")? as f32, y: number(&y, "y")? as f32 };
+        with_shape(shape, |shape| {
+            shape.vertices.push(vertex);
+            Ok(())
+        })
+    });
+    engine.register_fn("add_port", |shape: &mut Map, edge: Dynamic, position: Dynamic| {
+        add_port(shape, &edge, &position, PortType::Default)
+    });
+    engine.register_fn("add_port", |shape: &mut Map, edge: Dynamic, position: Dynamic, port_type: &str| {
+        add_port(shape, &edge, &position, port_type_named(port_type)?)
+    });
+    engine.register_fn("set_port_type", |port: &mut Map, port_type: &str| -> Result<(), Box<EvalAltResult>> {
+        let port_type = port_type_named(port_type)?;
+        port.insert("type".into(), port_type.to_string().into());
+        Ok(())
+    });
+    engine.register_fn("translate", |shape: &mut Map, dx: Dynamic, dy: Dynamic| {
+        let (dx, dy) = (number(&dx, "dx")? as f32, number(&dy, "dy")? as f32);
+        with_shape(shape, |shape| {
+            for (vertices, _) in shape.outlines_mut() {
+                for v in vertices.iter_mut() {
+                    v.x += dx;
+                    v.y += dy;
+                }
+            }
+            Ok(())
+        })
+    });
+    let about = pivot.clone();
+    engine.register_fn("rotate", move |shape: &mut Map, degrees: Dynamic| {
+        let degrees = number(&degrees, "degrees")? as f32;
+        with_shape(shape, |shape| {
+            shape.rotate_about(&about, degrees);
+            Ok(())
+        })
+    });
+    let about = pivot.clone();
+    engine.register_fn("scale", move |shape: &mut Map, factor: Dynamic| {
+        let factor = number(&factor, "factor")? as f32;
+        scale(shape, &about, factor, factor)
+    });
+    let about = pivot.clone();
+    engine.register_fn("scale", move |shape: &mut Map, sx: Dynamic, sy: Dynamic| {
+        scale(shape, &about, number(&sx, "factor")? as f32, number(&sy, "factor_y")? as f32)
+    });
+    engine.register_fn("mirror", |shape: &mut Map, axis: &str| {
+        let flip_x = match axis {
+            "x" => true,
+            "y" => false,
+            other => return Err(format!("mirror axis must be \"x\" or \"y\", not \"{}\"", other).into()),
+        };
+        with_shape(shape, |shape| {
+            shape.mirror(flip_x);
+            Ok(())
+        })
+    });
+    engine
+}
+
+// Shapes helpers: each reads the shape map into a Shape, changes that and writes the
+// result back into the same map, keeping any keys of the script's own
+
+fn with_shape(map: &mut Map, change: impl FnOnce(&mut Shape) -> Result<(), String>) -> Result<(), Box<EvalAltResult>> {
+    let mut shape = value_to_shape(Dynamic::from(map.clone()))?;
+    change(&mut shape)?;
+    map.extend(shape_to_map(&shape));
+    Ok(())
+}
+
+fn add_port(map: &mut Map, edge: &Dynamic, position: &Dynamic, port_type: PortType) -> Result<(), Box<EvalAltResult>> {
+    let edge = whole_number(edge, "edge")?;
+    let position = number(position, "position")? as f32;
+    with_shape(map, |shape| {
+        if edge >= shape.vertices.len() {
+            return Err(format!("shape {} has no edge {}", shape.id, edge));
+        }
+        shape.ports.push(Port { edge, position, port_type });
+        Ok(())
+    })
+}
+
+fn scale(map: &mut Map, pivot: &Vertex, sx: f32, sy: f32) -> Result<(), Box<EvalAltResult>> {
+    if sx <= 0.0 || sy <= 0.0 {
+        return Err("scale factors must be positive; use mirror to flip a shape".into());
+    }
+    with_shape(map, |shape| {
+        shape.scale_about(pivot, sx, sy);
+        Ok(())
+    })
+}
+
+fn port_type_named(name: &str) -> Result<PortType, String> {
+    PortType::from_string(name).ok_or_else(|| format!("unknown port type \"{}\"", name))
+}
+
+fn number(value: &Dynamic, what: &str) -> Result<f64, String> {
+    match value.as_float() {
+        Ok(n) => Ok(n),
+        Err(_) => value.as_int()
+            .map(|n| n as f64)
+            .map_err(|type_name| format!("`{}` must be a number, not {}", what, type_name)),
+    }
+}
+
+fn whole_number(value: &Dynamic, what: &str) -> Result<usize, String> {
+    let n = number(value, what)?;
+    if n.fract() != 0.0 || n < 0.0 {
+        return Err(format!("`{}` must be a whole number, not {}", what, n));
+    }
+    Ok(n as usize)
+}
+
+// Conversion between shapes and script maps
+
+fn map(fields: Vec<(&str, Dynamic)>) -> Map {
+    fields.into_iter().map(|(key, value)| (key.into(), value)).collect()
+}
+
+fn vertex_to_value(v: &Vertex) -> Dynamic {
+    map(vec![("x", Dynamic::from(v.x as FLOAT)), ("y", Dynamic::from(v.y as FLOAT))]).into()
+}
+
+fn port_to_value(port: &Port) -> Dynamic {
+    map(vec![
+        ("edge", Dynamic::from(port.edge as INT)),
+        ("position", Dynamic::from(port.position as FLOAT)),
+        ("type", port.port_type.to_string().into()),
+    ]).into()
+}
+
+fn outline_fields(vertices: &[Vertex], ports: &[Port]) -> Vec<(&'static str, Dynamic)> {
+    vec![
+        ("verts", vertices.iter().map(vertex_to_value).collect::<Array>().into()),
+        ("ports", ports.iter().map(port_to_value).collect::<Array>().into()),
+    ]
+}
+
+fn shape_to_map(shape: &Shape) -> Map {
+    let scales: Array = shape.scales.iter().map(|scale| map(outline_fields(&scale.vertices, &scale.ports)).into()).collect();
+    let mut fields = vec![
+        ("id", Dynamic::from(shape.id as INT)),
+        ("name", shape.name.clone().into()),
+        ("launcher_radial", shape.launcher_radial.into()),
+        ("scales", scales.into()),
+    ];
+    fields.extend(outline_fields(&shape.vertices, &shape.ports));
+    map(fields)
+}
+
+fn field(map: &Map, key: &str) -> Option<Dynamic> {
+    map.get(key).filter(|value| !value.is_unit()).cloned()
+}
+
+fn list_field(map: &Map, key: &str) -> Result<Array, String> {
+    match field(map, key) {
+        None => Ok(Array::new()),
+        Some(value) => {
+            let type_name = value.type_name();
+            value.try_cast::<Array>().ok_or_else(|| format!("`{}` must be an array, not {}", key, type_name))
+        }
+    }
+}
+
+fn as_map(value: Dynamic, what: &str) -> Result<Map, String> {
+    let type_name = value.type_name();
+    value.try_cast::<Map>().ok_or_else(|| format!("{} must be a map, not {}", what, type_name))
+}
+
+fn value_to_vertex(value: Dynamic) -> Result<Vertex, String> {
+    // Vertices may also be written [x, y], as in shapes.lua
+    if let Some(pair) = value.clone().try_cast::<Array>() {
+        let [x, y] = pair.as_slice() else {
+            return Err(format!("a vertex array must hold x and y, not {} values", pair.len()));
+        };
+        return Ok(Vertex { x: number(x, "x")? as f32, y: number(y, "y")? as f32 });
+    }
+    let vertex = as_map(value, "a vertex")?;
+    let coordinate = |key| field(&vertex, key).map_or(Err(format!("vertex has no `{}`", key)), |value| number(&value, key));
+    Ok(Vertex { x: coordinate("x")? as f32, y: coordinate("y")? as f32 })
+}
+
+fn value_to_port(value: Dynamic) -> Result<Port, String> {
+    let port = as_map(value, "a port")?;
+    let edge = field(&port, "edge").ok_or("port has no `edge`")?;
+    let position = field(&port, "position").ok_or("port has no `position`")?;
+    let port_type = match field(&port, "type") {
+        None => PortType::Default,
+        Some(name) => port_type_named(&name.into_string().map_err(|type_name| format!("port type must be a string, not {}", type_name))?)?,
+    };
+    Ok(Port { edge: whole_number(&edge, "edge")?, position: number(&position, "position")? as f32, port_type })
+}
+
+fn value_to_outline(map: &Map) -> Result<(Vec<Vertex>, Vec<Port>), String> {
+    let vertices = list_field(map, "verts")?.into_iter().map(value_to_vertex).collect::<Result<_, _>>()?;
+    let ports = list_field(map, "ports")?.into_iter().map(value_to_port).collect::<Result<_, _>>()?;
+    Ok((vertices, ports))
+}
+
+fn value_to_shape(value: Dynamic) -> Result<Shape, String> {
+    let map = as_map(value, "a shape")?;
+    let id = field(&map, "id").ok_or("shape has no id")?;
+    let id = whole_number(&id, "id")?;
+    let mut shape = Shape::new(id);
+    if let Some(name) = field(&map, "name") {
+        shape.name = name.into_string().map_err(|type_name| format!("shape {}: name must be a string, not {}", id, type_name))?;
+    }
+    shape.launcher_radial = field(&map, "launcher_radial").and_then(|value| value.as_bool().ok()).unwrap_or(false);
+    let in_shape = |e: String| format!("shape {}: {}", id, e);
+    (shape.vertices, shape.ports) = value_to_outline(&map).map_err(in_shape)?;
+    shape.scales = list_field(&map, "scales")
+        .map_err(in_shape)?
+        .into_iter()
+        .map(|scale| {
+            let (vertices, ports) = value_to_outline(&as_map(scale, "a scale")?)?;
+            Ok(ShapeScale { vertices, ports })
+        })
+        .collect::<Result<_, String>>()
+        .map_err(in_shape)?;
+    Ok(shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape_editor::ShapeEditor;
+
+    fn square(id: usize) -> Shape {
+        let mut shape = Shape::new(id);
+        shape.vertices = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .into_iter()
+            .map(|(x, y)| Vertex { x, y })
+            .collect();
+        shape
+    }
+
+    const ORIGIN: Vertex = Vertex { x: 0.0, y: 0.0 };
+
+    #[test]
+    fn help_example_adds_a_port_to_every_edge() {
+        let (_, example) = HELP.split_once("Example:").unwrap();
+        let (_, example) = example.split_once('\n').unwrap();
+        let run = run(example, &[square(1), square(2)], &ORIGIN);
+        let shapes = run.result.unwrap();
+        for shape in &shapes {
+            let ports: Vec<(usize, f32)> = shape.ports.iter().map(|port| (port.edge, port.position)).collect();
+            assert_eq!(ports, vec![(0, 0.5), (1, 0.5), (2, 0.5), (3, 0.5)]);
+        }
+    }
+
+    #[test]
+    fn helpers_change_the_shapes() {
+        let source = r#"
+            shapes[0].add_vertex(5, -5);
+            shapes[0].add_port(0, 0.25, "THRUSTER_OUT");
+            shapes[0].ports[0].set_port_type("WEAPON_IN");
+            shapes[0].translate(1, 2.5);
+            shapes[0].name = "moved";
+            print(shapes[0].verts.len());
+        "#;
+        let run = run(source, &[square(1)], &ORIGIN);
+        assert_eq!(run.output, vec!["5"]);
+        let shape = &run.result.unwrap()[0];
+        assert_eq!(shape.name, "moved");
+        assert_eq!(shape.vertices.last(), Some(&Vertex { x: 6.0, y: -2.5 }));
+        assert_eq!(shape.ports[0].port_type, PortType::WeaponIn);
+        assert_eq!((shape.ports[0].edge, shape.ports[0].position), (0, 0.25));
+    }
+
+    #[test]
+    fn bad_calls_fail() {
+        for source in [
+            "shapes[0].add_port(4, 0.5);",
+            "shapes[0].add_port(0, 0.5, \"NOPE\");",
+            "shapes[0].scale(-1);",
+            "shapes[0].mirror(\"z\");",
+            "shapes[0].verts[0].x = \"left\";",
+            "shapes = 1;",
+            "loop {}",
+        ] {
+            assert!(run(source, &[square(1)], &ORIGIN).result.is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn a_failing_script_leaves_the_shapes_unchanged() {
+        let mut editor = ShapeEditor::with_defaults();
+        editor.shapes = vec![square(1).into(), square(2).into()];
+        let before = editor.shapes.clone();
+        editor.script_source = "shapes[0].add_vertex(1, 2);\nshapes[1].mirror(\"x\");\nthrow \"stop\";".to_string();
+        editor.run_script();
+        assert_eq!(editor.shapes, before);
+        assert!(editor.script_error.as_deref().is_some_and(|e| e.contains("stop")));
+    }
+
+    #[test]
+    fn scripts_must_leave_unique_shapes_behind() {
+        let mut editor = ShapeEditor::with_defaults();
+        editor.shapes = vec![square(1).into(), square(2).into()];
+        let before = editor.shapes.clone();
+        for source in ["shapes.clear();", "shapes[1].id = 1;"] {
+            editor.script_source = source.to_string();
+            editor.run_script();
+            assert_eq!(editor.shapes, before, "{}", source);
+            assert!(editor.script_error.is_some(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn unchanged_shapes_stay_shared_with_the_history() {
+        let mut editor = ShapeEditor::with_defaults();
+        editor.shapes = vec![square(1).into(), square(2).into()];
+        let before = editor.shapes.clone();
+        editor.script_source = "shapes[1].translate(1, 0); shapes.insert(0, shapes[0]); shapes[0].id = 3;".to_string();
+        editor.run_script();
+        assert_eq!(editor.shapes.len(), 3);
+        assert!(editor.shapes[1].ptr_eq(&before[0]));
+        assert!(!editor.shapes[2].ptr_eq(&before[1]));
+    }
+}
@@ -1,186 +1,244 @@
 use crate::ast::{ShapesFile, Shape, Scale, Vertex, Port, PortType, ShroudComponent, CannonProperties, ThrusterProperties, FragmentProperties};
 
+/// How [`Shape::to_lua_string`] lays out a shape
+#[derive(Clone, Debug, PartialEq)]
+pub struct SerializeOptions {
+    /// Spaces per level of nesting
+    pub indent: usize,
+    /// Nesting level of the shape itself, e.g. 1 for a shape inside a file's outer braces
+    pub level: usize,
+    /// Follow every typed port with a comment spelling out its edge, position and type
+    pub port_comments: bool,
+    /// Number the scales in a comment after each one
+    pub scale_comments: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self { indent: 4, level: 0, port_comments: true, scale_comments: true }
+    }
+}
+
 /// Serializes a ShapesFile back to a Lua string
 pub fn serialize_shapes_file(shapes_file: &ShapesFile) -> String {
+    let options = SerializeOptions { level: 1, ..SerializeOptions::default() };
     let mut result = String::from("{\n");
     
     for (i, shape) in shapes_file.shapes.iter().enumerate() {
+        result.push_str(&shape.to_lua_string(&options));
+        if i < shapes_file.shapes.len() - 1 {
+            result.push_str(",\n");
+        } else {
+            result.push('\n');
+        }
+    }
+    
+    result.push_str("}\n");
+    result
+}
+
+impl Shape {
+    /// The shape's table as it appears in shapes.lua, from the opening brace to the
+    /// closing one, without a comma or line break after it
+    pub fn to_lua_string(&self, options: &SerializeOptions) -> String {
+        let pad = |depth: usize| " ".repeat((options.level + depth) * options.indent);
+        let mut result = String::new();
+        
         // Shape ID and optional name
-        result.push_str(&format!("    {{{},", shape.id));
+        result.push_str(&format!("{}{{{},", pad(0), self.id));
         
-        if let Some(name) = &shape.name {
+        if let Some(name) = &self.name {
             result.push_str(&format!(" --{}", name));
         }
         
-        result.push_str("\n");
+        result.push('\n');
         
         // Begin shape properties block
-        result.push_str("        {\n");
+        result.push_str(&format!("{}{{\n", pad(1)));
         
         // Scales - special handling to match expected format
-        for (j, scale) in shape.scales.iter().enumerate() {
-            result.push_str("            {\n");
+        for (j, scale) in self.scales.iter().enumerate() {
+            result.push_str(&format!("{}{{\n", pad(2)));
             
             // Vertices
-            result.push_str("                verts = {");
+            result.push_str(&format!("{}verts = {{", pad(3)));
             if scale.verts.is_empty() {
-                result.push_str("}");
+                result.push('}');
             } else {
-                result.push_str("\n");
+                result.push('\n');
                 for vert in &scale.verts {
-                    result.push_str(&format!("                    {{{}, {}}},\n", vert.x, vert.y));
+                    result.push_str(&format!("{}{{{}, {}}},\n", pad(4), vert.x, vert.y));
                 }
-                result.push_str("                }");
+                result.push_str(&format!("{}}}", pad(3)));
             }
             result.push_str(",\n");
             
             // Ports
-            result.push_str("                ports = {");
+            result.push_str(&format!("{}ports = {{", pad(3)));
             if scale.ports.is_empty() {
-                result.push_str("}");
+                result.push('}');
             } else {
-                result.push_str("\n");
+                result.push('\n');
                 for port in &scale.ports {
-                    if let Some(port_type) = &port.port_type {
-                        result.push_str(&format!("                    {{{}, {}, {}}},  -- Edge {}, position {}, type {}\n", 
-                                                port.edge, port.position, port_type.to_str(), port.edge, port.position, port_type.to_str()));
-                    } else {
-                        result.push_str(&format!("                    {{{}, {}}},\n", port.edge, port.position));
+                    match &port.port_type {
+                        Some(port_type) if options.port_comments => {
+                            result.push_str(&format!("{}{{{}, {}, {}}},  -- Edge {}, position {}, type {}\n",
+                                                    pad(4), port.edge, port.position, port_type.to_str(), port.edge, port.position, port_type.to_str()));
+                        }
+                        Some(port_type) => {
+                            result.push_str(&format!("{}{{{}, {}, {}}},\n", pad(4), port.edge, port.position, port_type.to_str()));
+                        }
+                        None => {
+                            result.push_str(&format!("{}{{{}, {}}},\n", pad(4), port.edge, port.position));
+                        }
                     }
                 }
-                result.push_str("                }");
+                result.push_str(&format!("{}}}", pad(3)));
             }
             
             // End of scale
-            if j < shape.scales.len() - 1 {
-                result.push_str(&format!("\n            }}, --scale {}\n", j+1));
+            let comma = if j < self.scales.len() - 1 { "," } else { "" };
+            if options.scale_comments {
+                result.push_str(&format!("\n{}}}{} --scale {}\n", pad(2), comma, j + 1));
             } else {
-                result.push_str(&format!("\n            }} --scale {}\n", j+1));
+                result.push_str(&format!("\n{}}}{}\n", pad(2), comma));
             }
         }
         
+        // End of the scales; the properties below belong to the shape itself
+        result.push_str(&format!("{}}},\n", pad(1)));
+        
         // Group
-        if let Some(group) = shape.group {
-            result.push_str(&format!("            group = {},\n", group));
+        if let Some(group) = self.group {
+            result.push_str(&format!("{}group = {},\n", pad(1), group));
         }
 
         // Features
-        if let Some(features) = &shape.features {
-            result.push_str(&format!("            features = \"{}\",\n", features.join("|")));
+        if let Some(features) = &self.features {
+            result.push_str(&format!("{}features = \"{}\",\n", pad(1), features.join("|")));
         }
 
         // Colors
-        if let Some(color) = shape.fill_color {
-            result.push_str(&format!("            fillColor = 0x{:08x},\n", color));
+        if let Some(color) = self.fill_color {
+            result.push_str(&format!("{}fillColor = 0x{:08x},\n", pad(1), color));
         }
-        if let Some(color) = shape.fill_color1 {
-            result.push_str(&format!("            fillColor1 = 0x{:08x},\n", color));
+        if let Some(color) = self.fill_color1 {
+            result.push_str(&format!("{}fillColor1 = 0x{:08x},\n", pad(1), color));
         }
-        if let Some(color) = shape.line_color {
-            result.push_str(&format!("            lineColor = 0x{:08x},\n", color));
+        if let Some(color) = self.line_color {
+            result.push_str(&format!("{}lineColor = 0x{:08x},\n", pad(1), color));
         }
 
         // Physical properties
-        if let Some(durability) = shape.durability {
-            result.push_str(&format!("            durability = {},\n", durability));
+        if let Some(durability) = self.durability {
+            result.push_str(&format!("{}durability = {},\n", pad(1), durability));
         }
-        if let Some(density) = shape.density {
-            result.push_str(&format!("            density = {},\n", density));
+        if let Some(density) = self.density {
+            result.push_str(&format!("{}density = {},\n", pad(1), density));
         }
-        if let Some(grow_rate) = shape.grow_rate {
-            result.push_str(&format!("            growRate = {},\n", grow_rate));
+        if let Some(grow_rate) = self.grow_rate {
+            result.push_str(&format!("{}growRate = {},\n", pad(1), grow_rate));
         }
 
         // Launcher radial property
-        if let Some(launcher_radial) = shape.launcher_radial {
+        if let Some(launcher_radial) = self.launcher_radial {
             if launcher_radial {
-                result.push_str("            launcher_radial = true,\n");
+                result.push_str(&format!("{}launcher_radial = true,\n", pad(1)));
             } else {
-                result.push_str("            launcher_radial = false,\n");
+                result.push_str(&format!("{}launcher_radial = false,\n", pad(1)));
             }
         }
 
         // Mirror reference
-        if let Some(mirror_of) = shape.mirror_of {
-            result.push_str(&format!("            mirror_of = {},\n", mirror_of));
+        if let Some(mirror_of) = self.mirror_of {
+            result.push_str(&format!("{}mirror_of = {},\n", pad(1), mirror_of));
         }
 
         // Shroud components
-        if let Some(shroud) = &shape.shroud {
-            result.push_str("            shroud = {\n");
+        if let Some(shroud) = &self.shroud {
+            result.push_str(&format!("{}shroud = {{\n", pad(1)));
             for component in shroud {
-                result.push_str(&format!("                {{size = {{{}, {}}}, offset = {{{}, {}, {}}}, taper = {}, count = {}, angle = {}, tri_color_id = {}, tri_color1_id = {}, line_color_id = {}, shape = {}}},\n",
+                result.push_str(&format!("{}{{size = {{{}, {}}}, offset = {{{}, {}, {}}}, taper = {}, count = {}, angle = {}, tri_color_id = {}, tri_color1_id = {}, line_color_id = {}, shape = {}}},\n",
+                    pad(2),
                     component.size.0, component.size.1,
                     component.offset.0, component.offset.1, component.offset.2,
                     component.taper, component.count, component.angle,
                     component.tri_color_id, component.tri_color1_id, component.line_color_id,
-                    component.shape));
+                    lua_word(&component.shape)));
             }
-            result.push_str("            },\n");
+            result.push_str(&format!("{}}},\n", pad(1)));
         }
 
         // Cannon properties
-        if let Some(cannon) = &shape.cannon {
-            result.push_str("            cannon = {\n");
-            result.push_str(&format!("                damage = {},\n", cannon.damage));
-            result.push_str(&format!("                power = {},\n", cannon.power));
-            result.push_str(&format!("                roundsPerSec = {},\n", cannon.rounds_per_sec));
-            result.push_str(&format!("                muzzleVel = {},\n", cannon.muzzle_vel));
-            result.push_str(&format!("                range = {},\n", cannon.range));
-            result.push_str(&format!("                spread = {},\n", cannon.spread));
+        if let Some(cannon) = &self.cannon {
+            result.push_str(&format!("{}cannon = {{\n", pad(1)));
+            result.push_str(&format!("{}damage = {},\n", pad(2), cannon.damage));
+            result.push_str(&format!("{}power = {},\n", pad(2), cannon.power));
+            result.push_str(&format!("{}roundsPerSec = {},\n", pad(2), cannon.rounds_per_sec));
+            result.push_str(&format!("{}muzzleVel = {},\n", pad(2), cannon.muzzle_vel));
+            result.push_str(&format!("{}range = {},\n", pad(2), cannon.range));
+            result.push_str(&format!("{}spread = {},\n", pad(2), cannon.spread));
             
             if let Some(rounds) = cannon.rounds_per_burst {
-                result.push_str(&format!("                roundsPerBurst = {},\n", rounds));
+                result.push_str(&format!("{}roundsPerBurst = {},\n", pad(2), rounds));
             }
             if let Some(burstyness) = cannon.burstyness {
-                result.push_str(&format!("                burstyness = {},\n", burstyness));
+                result.push_str(&format!("{}burstyness = {},\n", pad(2), burstyness));
             }
             if let Some(color) = cannon.color {
-                result.push_str(&format!("                color = 0x{:08x},\n", color));
+                result.push_str(&format!("{}color = 0x{:08x},\n", pad(2), color));
             }
             if let Some(explosive) = &cannon.explosive {
-                result.push_str(&format!("                explosive = {},\n", explosive));
+                result.push_str(&format!("{}explosive = {},\n", pad(2), lua_word(explosive)));
             }
             if let Some(fragment) = &cannon.fragment {
-                result.push_str("                fragment = {\n");
-                result.push_str(&format!("                    roundsPerBurst = {},\n", fragment.rounds_per_burst));
-                result.push_str(&format!("                    muzzleVel = {},\n", fragment.muzzle_vel));
-                result.push_str(&format!("                    spread = {},\n", fragment.spread));
+                result.push_str(&format!("{}fragment = {{\n", pad(2)));
+                result.push_str(&format!("{}roundsPerBurst = {},\n", pad(3), fragment.rounds_per_burst));
+                result.push_str(&format!("{}muzzleVel = {},\n", pad(3), fragment.muzzle_vel));
+                result.push_str(&format!("{}spread = {},\n", pad(3), fragment.spread));
                 if let Some(pattern) = &fragment.pattern {
-                    result.push_str(&format!("                    pattern = \"{}\",\n", pattern));
+                    result.push_str(&format!("{}pattern = \"{}\",\n", pad(3), pattern));
                 }
-                result.push_str(&format!("                    damage = {},\n", fragment.damage));
-                result.push_str(&format!("                    range = {},\n", fragment.range));
+                result.push_str(&format!("{}damage = {},\n", pad(3), fragment.damage));
+                result.push_str(&format!("{}range = {},\n", pad(3), fragment.range));
                 if let Some(color) = fragment.color {
-                    result.push_str(&format!("                    color = 0x{:08x},\n", color));
+                    result.push_str(&format!("{}color = 0x{:08x},\n", pad(3), color));
                 }
-                result.push_str("                },\n");
+                result.push_str(&format!("{}}},\n", pad(2)));
             }
-            result.push_str("            },\n");
+            result.push_str(&format!("{}}},\n", pad(1)));
         }
 
         // Thruster properties
-        if let Some(thruster) = &shape.thruster {
-            result.push_str("            thruster = {\n");
-            result.push_str(&format!("                force = {},\n", thruster.force));
-            result.push_str(&format!("                power = {},\n", thruster.power));
+        if let Some(thruster) = &self.thruster {
+            result.push_str(&format!("{}thruster = {{\n", pad(1)));
+            result.push_str(&format!("{}force = {},\n", pad(2), thruster.force));
+            result.push_str(&format!("{}power = {},\n", pad(2), thruster.power));
             if let Some(color) = thruster.color {
-                result.push_str(&format!("                color = 0x{:08x},\n", color));
+                result.push_str(&format!("{}color = 0x{:08x},\n", pad(2), color));
             }
-            result.push_str("            },\n");
+            result.push_str(&format!("{}}},\n", pad(1)));
         }
         
-        // End of shape properties block
-        result.push_str("        }");
-        
         // End of shape
-        if i < shapes_file.shapes.len() - 1 {
-            result.push_str("\n    },\n");
-        } else {
-            result.push_str("\n    }\n");
-        }
+        result.push_str(&format!("{}}}", pad(0)));
+        result
     }
-    
-    result.push_str("}\n");
-    result
-}
\ No newline at end of file
+}
+
+// Names such as SQUARE or PROXIMITY are written bare like in the game's own files;
+// anything else needs quotes to read back as one value
+fn lua_word(word: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+        "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+    let identifier = word.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&word);
+    if identifier {
+        word.to_string()
+    } else {
+        format!("\"{}\"", word)
+    }
+}
@@ -0,0 +1,274 @@
+// Editor preferences kept between sessions: a JSON file in the user's config
+// directory natively, localStorage in the browser
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::budget::BudgetHeuristics;
+use crate::keybindings::Keybindings;
+use crate::payload::PayloadLink;
+use crate::shape_ids::IdCategory;
+use crate::shape_editor::ShapeEditor;
+use crate::translations;
+use crate::variants::VariantLink;
+use crate::visual::{GridStyle, Guide, PanelLayout, PortPalette};
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "reassembly_shape_editor.settings";
+
+/// Seconds a change has to settle before it is written, so zooming or dragging a
+/// slider doesn't save on every frame
+pub const SAVE_DELAY: f64 = 1.0;
+
+/// Everything that is persisted. Fields missing from an older file keep their defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub language: String,
+    pub fallback_chain: Vec<String>,
+    pub translation_diagnostics: bool,
+    pub grid_size: f32,
+    pub show_grid: bool,
+    pub grid_style: GridStyle,
+    pub snap_to_grid: bool,
+    pub snap_port_fractions: bool,
+    pub respace_ports_on_split: bool,
+    pub show_rulers: bool,
+    pub guides: Vec<Guide>,
+    pub snap_to_guides: bool,
+    pub zoom: f32,
+    pub ui_scale: f32,
+    pub panel_layout: PanelLayout,
+    pub keybindings: Keybindings,
+    pub show_vertex_labels: bool,
+    pub show_port_labels: bool,
+    pub handle_scale: f32,
+    pub port_palette: PortPalette,
+    pub port_shape_markers: bool,
+    pub show_connection_preview: bool,
+    pub show_measurements: bool,
+    pub show_normals: bool,
+    pub show_firing_arcs: bool,
+    pub firing_arc_spread: f32,
+    pub firing_arc_range: f32,
+    pub firing_arc_range_scale: f32,
+    pub show_minimap: bool,
+    pub lod_preview: bool,
+    pub game_preview: bool,
+    pub preview_fill_color: Color32,
+    pub preview_fill_color1: Color32,
+    pub preview_line_color: Color32,
+    pub sort_ports_on_export: bool,
+    pub export_append: bool,
+    pub show_lua_preview: bool,
+    pub preview_export: bool,
+    pub mod_root: String,
+    pub import_path: String,
+    pub export_path: String,
+    pub recent_files: Vec<String>,
+    pub blocks_path: String,
+    pub update_blocks_on_renumber: bool,
+    pub budget_heuristics: BudgetHeuristics,
+    pub scale_waivers: Vec<usize>,
+    pub variant_links: Vec<VariantLink>,
+    pub payload_links: Vec<PayloadLink>,
+    pub id_categories: Vec<IdCategory>,
+    pub id_category: Option<usize>,
+    pub onboarding_done: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::from_editor(&ShapeEditor::with_defaults())
+    }
+}
+
+impl Settings {
+    /// Take the persisted values out of the editor
+    pub fn from_editor(app: &ShapeEditor) -> Self {
+        Self {
+            language: translations::get_current_language(),
+            fallback_chain: translations::get_fallback_chain(),
+            translation_diagnostics: translations::diagnostics_enabled(),
+            grid_size: app.grid_size,
+            show_grid: app.show_grid,
+            grid_style: app.grid_style.clone(),
+            snap_to_grid: app.snap_to_grid,
+            snap_port_fractions: app.snap_port_fractions,
+            respace_ports_on_split: app.respace_ports_on_split,
+            show_rulers: app.show_rulers,
+            guides: app.guides.clone(),
+            snap_to_guides: app.snap_to_guides,
+            zoom: app.zoom,
+            ui_scale: app.ui_scale,
+            panel_layout: app.panel_layout.clone(),
+            keybindings: app.keybindings.clone(),
+            show_vertex_labels: app.show_vertex_labels,
+            show_port_labels: app.show_port_labels,
+            handle_scale: app.handle_scale,
+            port_palette: app.port_palette,
+            port_shape_markers: app.port_shape_markers,
+            show_connection_preview: app.show_connection_preview,
+            show_measurements: app.show_measurements,
+            show_normals: app.show_normals,
+            show_firing_arcs: app.show_firing_arcs,
+            firing_arc_spread: app.firing_arc_spread,
+            firing_arc_range: app.firing_arc_range,
+            firing_arc_range_scale: app.firing_arc_range_scale,
+            show_minimap: app.show_minimap,
+            lod_preview: app.lod_preview,
+            game_preview: app.game_preview,
+            preview_fill_color: app.preview_fill_color,
+            preview_fill_color1: app.preview_fill_color1,
+            preview_line_color: app.preview_line_color,
+            sort_ports_on_export: app.sort_ports_on_export,
+            export_append: app.export_append,
+            show_lua_preview: app.show_lua_preview,
+            preview_export: app.preview_export,
+            mod_root: app.mod_root.clone(),
+            import_path: app.import_path.clone(),
+            export_path: app.export_path.clone(),
+            recent_files: app.recent_files.clone(),
+            blocks_path: app.blocks_path.clone(),
+            update_blocks_on_renumber: app.update_blocks_on_renumber,
+            budget_heuristics: app.budget_heuristics.clone(),
+            scale_waivers: app.scale_waivers.clone(),
+            variant_links: app.variant_links.clone(),
+            payload_links: app.payload_links.clone(),
+            id_categories: app.id_categories.clone(),
+            id_category: app.id_category,
+            onboarding_done: app.onboarding_done,
+        }
+    }
+
+    /// Put the persisted values back into the editor
+    pub fn apply(self, app: &mut ShapeEditor) {
+        // An unknown language (e.g. removed from translations.json) keeps the default
+        translations::set_language(&self.language);
+        app.fallback_chain_text = self.fallback_chain.join(", ");
+        translations::set_fallback_chain(self.fallback_chain);
+        translations::set_diagnostics(self.translation_diagnostics);
+
+        app.grid_size = self.grid_size;
+        app.show_grid = self.show_grid;
+        app.grid_style = self.grid_style;
+        app.snap_to_grid = self.snap_to_grid;
+        app.snap_port_fractions = self.snap_port_fractions;
+        app.respace_ports_on_split = self.respace_ports_on_split;
+        app.show_rulers = self.show_rulers;
+        app.guides = self.guides;
+        app.snap_to_guides = self.snap_to_guides;
+        app.zoom = self.zoom;
+        app.ui_scale = self.ui_scale;
+        app.panel_layout = self.panel_layout;
+        app.keybindings = self.keybindings.with_missing_defaults();
+        app.show_vertex_labels = self.show_vertex_labels;
+        app.show_port_labels = self.show_port_labels;
+        app.handle_scale = self.handle_scale;
+        app.port_palette = self.port_palette;
+        app.port_shape_markers = self.port_shape_markers;
+        app.show_connection_preview = self.show_connection_preview;
+        app.show_measurements = self.show_measurements;
+        app.show_normals = self.show_normals;
+        app.show_firing_arcs = self.show_firing_arcs;
+        app.firing_arc_spread = self.firing_arc_spread;
+        app.firing_arc_range = self.firing_arc_range;
+        app.firing_arc_range_scale = self.firing_arc_range_scale;
+        app.show_minimap = self.show_minimap;
+        app.lod_preview = self.lod_preview;
+        app.game_preview = self.game_preview;
+        app.preview_fill_color = self.preview_fill_color;
+        app.preview_fill_color1 = self.preview_fill_color1;
+        app.preview_line_color = self.preview_line_color;
+        app.sort_ports_on_export = self.sort_ports_on_export;
+        app.export_append = self.export_append;
+        app.show_lua_preview = self.show_lua_preview;
+        app.preview_export = self.preview_export;
+        app.mod_root = self.mod_root;
+        app.import_path = self.import_path;
+        app.export_path = self.export_path;
+        app.recent_files = self.recent_files;
+        app.blocks_path = self.blocks_path;
+        app.update_blocks_on_renumber = self.update_blocks_on_renumber;
+        app.budget_heuristics = self.budget_heuristics;
+        app.scale_waivers = self.scale_waivers;
+        app.variant_links = self.variant_links;
+        app.payload_links = self.payload_links;
+        // A category removed by hand from the file falls back to the whole range
+        app.id_category = self.id_category.filter(|&i| i < self.id_categories.len());
+        app.id_categories = self.id_categories;
+        app.onboarding_done = self.onboarding_done;
+    }
+
+    /// Read the saved settings, if there are any and they can be parsed
+    pub fn load() -> Option<Self> {
+        let json = read_stored()?;
+        match serde_json::from_str(&json) {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                log::warn!("Ignoring unreadable settings: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_stored(&json)
+    }
+}
+
+/// Directory the editor keeps its files in:
+/// * Linux: `$XDG_CONFIG_HOME/reassembly_shape_editor` (or `~/.config/...`)
+/// * macOS: `~/Library/Application Support/reassembly_shape_editor`
+/// * Windows: `%APPDATA%\reassembly_shape_editor`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_dir() -> Option<std::path::PathBuf> {
+    use std::env::var_os;
+    use std::path::PathBuf;
+
+    let config_dir = if cfg!(target_os = "windows") {
+        var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }?;
+
+    Some(config_dir.join("reassembly_shape_editor"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn settings_path() -> Option<std::path::PathBuf> {
+    Some(config_dir()?.join("settings.json"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stored() -> Option<String> {
+    std::fs::read_to_string(settings_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stored(json: &str) -> Result<(), String> {
+    let path = settings_path().ok_or("No config directory found")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_stored() -> Option<String> {
+    let storage = web_sys::window()?.local_storage().ok()??;
+    storage.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stored(json: &str) -> Result<(), String> {
+    let storage = web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or("localStorage is not available")?;
+    storage.set_item(STORAGE_KEY, json).map_err(|e| format!("{:?}", e))
+}
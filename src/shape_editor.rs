@@ -5,13 +5,29 @@ use std::io;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-use crate::data_structures::{Shape as AppShape, Vertex, Port, PortType};
-use crate::geometry::round_to;
+use crate::data_structures::{Shape as AppShape, Selection, ShapeScale, SharedShape, Vertex, Port, PortType, PortLayout};
+use crate::geometry::{round_to, AABBox};
 use crate::ui::*;
 use crate::visual::*;
-use crate::parser::{parse_shapes_content, ParseError};
 use crate::serializer::serialize_shapes_file;
+use crate::keybindings::{Action, Keybindings};
+use crate::import_job::{ImportEvent, ImportJob};
+use crate::export_job::{ExportDone, ExportJob, ExportRetry};
+use crate::settings::{Settings, SAVE_DELAY};
+use crate::toasts::Toasts;
+use crate::blocks::BlocksFile;
+use crate::validation::{Diagnostic, Fix, Rule, Severity};
+use crate::generators::{AsteroidParams, HullParams};
+use crate::normalize::NormalizeParams;
+use crate::batch::BatchOp;
+use crate::report::ReportFormat;
+use crate::budget::BudgetHeuristics;
+use crate::payload::PayloadLink;
+use crate::shape_ids::IdCategory;
+use crate::arrange::{Placement, Scene};
+use crate::variants::{VariantLink, VariantTransform};
 
 #[cfg(not(target_arch = "wasm32"))]
 use rfd::FileDialog;
@@ -19,22 +35,332 @@ use rfd::FileDialog;
 // Maximum size for undo history
 const MAX_UNDO_HISTORY: usize = 100;
 
+// Maximum number of remembered recently imported files
+const MAX_RECENT_FILES: usize = 10;
+
+// Seconds the Lua text has to stay unchanged before it is parsed back into the shape
+const LUA_PARSE_DELAY: f64 = 0.5;
+
+// Saved state of all shapes. Shapes are copy-on-write, so a snapshot shares every
+// shape with the editor and only the shapes edited afterwards get copied.
+#[derive(Clone)]
+struct Snapshot {
+    shapes: Vec<SharedShape>,
+    // Shape ID the blocks in blocks.lua were moved from and to between this state and
+    // the one next to it in the history, so that stepping over it moves them back
+    blocks_renumber: Option<(usize, usize)>,
+}
+
+impl Snapshot {
+    fn new(shapes: &[SharedShape]) -> Self {
+        Snapshot { shapes: shapes.to_vec(), blocks_renumber: None }
+    }
+}
+
+// Shape change that affects blocks.lua and waits for confirmation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeChange {
+    // Shape index and the new ID
+    Renumber(usize, usize),
+    Delete(usize),
+}
+
+// What to do with an imported shape whose ID is already in the session
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImportConflict {
+    KeepExisting,
+    #[default]
+    Replace,
+    NewId,
+}
+
+// Shapes read by an import, waiting for the user to pick which ones to keep
+pub struct ImportDialog {
+    pub path: String,
+    pub shapes: Vec<AppShape>,
+    // Indices into `shapes`
+    pub include: BTreeSet<usize>,
+    // Choices for shapes whose ID is taken, by index; missing means the default
+    pub conflicts: BTreeMap<usize, ImportConflict>,
+    // Drop the current shapes instead of adding to them
+    pub replace_session: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeneratorKind {
+    Asteroid,
+    Hull,
+}
+
+// Parameters of the Generate dialog, kept for each generator while switching
+pub struct GeneratorDialog {
+    pub kind: GeneratorKind,
+    pub asteroid: AsteroidParams,
+    pub hull: HullParams,
+}
+
+// Shape being rescaled by the normalize wizard, with the wizard's choices
+pub struct NormalizeDialog {
+    pub shape_idx: usize,
+    pub params: NormalizeParams,
+}
+
+// Choices of the batch operations dialog, kept while switching operations
+pub struct BatchDialog {
+    pub kind: BatchKind,
+    pub factor: f32,
+    pub grid_step: f32,
+    pub decimals: u32,
+    // Apply to every shape instead of the current one
+    pub all_shapes: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BatchKind {
+    Scale,
+    SnapToGrid,
+    Round,
+}
+
+impl BatchDialog {
+    pub fn new(grid_step: f32) -> Self {
+        BatchDialog { kind: BatchKind::Scale, factor: 2.0, grid_step, decimals: 2, all_shapes: true }
+    }
+    
+    pub fn op(&self) -> BatchOp {
+        match self.kind {
+            BatchKind::Scale => BatchOp::Scale(self.factor),
+            BatchKind::SnapToGrid => BatchOp::SnapToGrid(self.grid_step),
+            BatchKind::Round => BatchOp::Round(self.decimals),
+        }
+    }
+}
+
+// Mod folder to export into
+pub struct ModExportDialog {
+    pub root: String,
+    // Also write the loaded blocks.lua into the mod
+    pub include_blocks: bool,
+}
+
+// Serialized Lua of the current shape, redone only when the shape changes. Edits
+// to the text are parsed back into the shape once typing pauses.
+pub struct LuaPreview {
+    shape_idx: usize,
+    shape: SharedShape,
+    sort_ports: bool,
+    pub text: String,
+    // When the edited text is due to be parsed
+    parse_at: Option<f64>,
+    // Why the edited text couldn't be applied
+    pub error: Option<String>,
+}
+
+// What an export is about to write, held back until the diff against the file on
+// disk has been looked over
+pub struct ExportPreview {
+    pub path: String,
+    pub content: String,
+    // Empty if nothing would change
+    pub diff: Vec<crate::diff::DiffLine>,
+    // The file doesn't exist yet
+    pub new_file: bool,
+}
+
+// Which export is waiting on the validation check
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportKind {
+    // Every shape to the export file, through the preview if that's on
+    All,
+    // Every shape to shapes.lua in the working folder
+    ShapesLua,
+    // The shapes ticked in the export dialog
+    Selection,
+    // Into the folder picked in the mod export dialog
+    #[cfg(not(target_arch = "wasm32"))]
+    Mod,
+}
+
+// Problems found in the shapes an export is about to write. Errors stop the export;
+// with only warnings it goes ahead once confirmed.
+pub struct ExportCheck {
+    pub kind: ExportKind,
+    pub problems: Vec<Diagnostic>,
+}
+
+// Shapes picked for a partial export and where they go
+pub struct ExportDialog {
+    pub path: String,
+    // IDs of the shapes to write
+    pub include: BTreeSet<usize>,
+}
+
+// Another shape drawn read-only on the canvas next to the one being edited
+pub struct PinnedShape {
+    pub shape_id: usize,
+    pub visible: bool,
+    // Where it is drawn, in shape units from its own origin
+    pub offset: Vertex,
+}
+
 // Главная структура приложения
 pub struct ShapeEditor {
-    pub shapes: Vec<AppShape>,
+    pub shapes: Vec<SharedShape>,
     pub current_shape_idx: usize,
+    // What is selected in the current shape
+    pub selection: Selection,
+    // Shape the selection was made in, so that it is dropped when another becomes current
+    selection_shape: usize,
     pub grid_size: f32,
     pub show_grid: bool,
+    pub grid_style: GridStyle,
+    // Canvas label visibility and handle/label size multiplier
+    pub show_vertex_labels: bool,
+    pub show_port_labels: bool,
+    pub handle_scale: f32,
+    pub port_palette: PortPalette,
+    pub port_shape_markers: bool,
+    // Multiplier on top of the display's native scale factor
+    pub ui_scale: f32,
+    pub panel_layout: PanelLayout,
+    pub keybindings: Keybindings,
+    // Binding (index into keybindings) waiting for a key press in the settings tab
+    pub rebinding: Option<usize>,
+    // The shape ID as typed in the properties panel, applied on Enter or when the field
+    // loses focus
+    pub shape_id_text: String,
+    // Fallback languages as typed in the settings, e.g. "en"
+    pub fallback_chain_text: String,
     pub snap_to_grid: bool,
+    pub snap_port_fractions: bool,
+    // When a new vertex splits an edge, space the ports on both halves evenly instead
+    // of keeping them where they were
+    pub respace_ports_on_split: bool,
+    // Rulers along the top and left of the canvas, and the guides pulled out of them
+    pub show_rulers: bool,
+    pub guides: Vec<Guide>,
+    pub snap_to_guides: bool,
+    // Guide being moved on the canvas
+    pub dragged_guide: Option<usize>,
+    // Point rotating and scaling work about when it isn't the origin
+    pub pivot: Option<Vertex>,
+    // Angle and factor last used to rotate or scale a shape from the side panel
+    pub transform_angle: f32,
+    pub transform_factor: f32,
+    pub show_connection_preview: bool,
+    pub show_measurements: bool,
+    // Edge normals and port directions on the side the game takes as outside
+    pub show_normals: bool,
+    // Firing fans on WEAPON_OUT ports. Spread (half-angle, degrees) and range (game
+    // units) are used unless a block in blocks.lua gives its cannon's own values.
+    pub show_firing_arcs: bool,
+    pub firing_arc_spread: f32,
+    pub firing_arc_range: f32,
+    // Game ranges are far longer than a block, so they are drawn scaled down
+    pub firing_arc_range_scale: f32,
+    pub measure_tool: bool,
+    pub measure_start: Option<Vertex>,
+    pub measure_end: Option<Vertex>,
+    pub cursor_shape_pos: Option<Vertex>,
+    pub canvas_rect: Rect,
+    // Saved zoom and pan, recalled with Ctrl+1..3
+    pub view_bookmarks: [Option<(f32, Vec2)>; 3],
+    pub show_minimap: bool,
+    // Show the scale the game would pick for the current zoom instead of scale 1
+    pub lod_preview: bool,
+    // In-game look preview (block colors from blocks.lua conventions)
+    pub game_preview: bool,
+    pub preview_fill_color: Color32,
+    pub preview_fill_color1: Color32,
+    pub preview_line_color: Color32,
     pub zoom: f32,
     pub pan: Vec2,
     pub dragging: bool,
+    // How far the pointer has run ahead of the dragged vertex or port in precision drags
+    pub drag_pointer_offset: Vec2,
     pub last_mouse_pos: Pos2,
     pub export_path: String,
+    pub sort_ports_on_export: bool,
+    // Add the shapes to the end of an existing export file instead of overwriting it
+    pub export_append: bool,
+    // Show a diff against the file on disk before exporting
+    pub preview_export: bool,
+    pub export_preview: Option<ExportPreview>,
     pub import_path: String,
+    // The mod's blocks.lua, used to show which blocks use each shape
+    pub blocks_path: String,
+    pub blocks_file: Option<BlocksFile>,
+    pub pending_shape_change: Option<ShapeChange>,
+    pub export_dialog: Option<ExportDialog>,
+    pub import_dialog: Option<ImportDialog>,
+    pub mod_export_dialog: Option<ModExportDialog>,
+    pub export_check: Option<ExportCheck>,
+    pub generator_dialog: Option<GeneratorDialog>,
+    pub normalize_dialog: Option<NormalizeDialog>,
+    pub batch_dialog: Option<BatchDialog>,
+    // Starting points for new shape IDs, and the one in use; None starts at the bottom of the range
+    pub id_categories: Vec<IdCategory>,
+    pub id_category: Option<usize>,
+    // Mod folder of the last "Export to mod"
+    pub mod_root: String,
+    // Rewrite the `shape=` fields in blocks.lua when a shape used there is renumbered
+    pub update_blocks_on_renumber: bool,
+    // Results of the validation checks, rerun whenever the shapes change
+    pub problems: Vec<Diagnostic>,
+    // Shapes the problems were found in; None forces a recheck
+    problems_checked: Option<Vec<SharedShape>>,
+    pub show_problems: bool,
+    pub show_lua_preview: bool,
+    // Area, mass and cost totals across the file, with the values assumed for blocks
+    // that don't set their own
+    pub show_budget: bool,
+    pub budget_heuristics: BudgetHeuristics,
+    // Shapes from the generated shape_reference.lua, offered in the Templates section
+    pub reference_templates: Vec<AppShape>,
+    // The user's own templates, kept across sessions
+    pub user_templates: Vec<AppShape>,
+    // Name typed for saving the current shape as a template
+    pub template_name: String,
+    pub lua_preview: Option<LuaPreview>,
+    // Undo timeline below the canvas
+    pub show_history: bool,
+    // Script console: the script being written, and what the last run printed or failed with
+    pub show_script_console: bool,
+    pub script_source: String,
+    pub script_output: Vec<String>,
+    pub script_error: Option<String>,
+    // Shapes shown on the canvas alongside the current one
+    pub pinned_shapes: Vec<PinnedShape>,
+    // Arrange tab: shapes placed together to check how they fit, and its own view
+    pub arrange: Scene,
+    pub arrange_zoom: f32,
+    pub arrange_pan: Vec2,
+    // IDs of shapes whose scales may have different edge counts
+    pub scale_waivers: Vec<usize>,
+    // Shapes kept as transformed copies of others, with the version of each variant's
+    // source it was last redone from
+    pub variant_links: Vec<VariantLink>,
+    // Payloads picked for launchers; blocks.lua's replicateBlock covers the others
+    pub payload_links: Vec<PayloadLink>,
+    variant_synced: BTreeMap<usize, SharedShape>,
+    // Source shape ID and transform picked for making the current shape a variant
+    pub variant_source: Option<usize>,
+    pub variant_transform: VariantTransform,
+    // Recently imported files, most recent first
+    pub recent_files: Vec<String>,
+    // Settings as last written to disk, and when pending changes are due to be saved
+    saved_settings: Option<Settings>,
+    settings_save_at: Option<f64>,
+    // A setting may have changed since the settings were last compared
+    settings_dirty: bool,
+    // Whether the settings, crash recovery file and template library are kept on
+    // disk; only for the editor built by `new`, so other editors don't overwrite them
+    persist: bool,
     // Undo/redo history
-    undo_history: Vec<Vec<AppShape>>,
-    redo_history: Vec<Vec<AppShape>>,
+    undo_history: VecDeque<Snapshot>,
+    redo_history: Vec<Snapshot>,
+    // Set while a continuous edit (drag, slider, text field) is in progress
+    undo_transaction: bool,
     // Store state for middle-mouse zoom
     pub middle_drag_ongoing: bool,
     pub zoom_center: Pos2,
@@ -42,45 +368,203 @@ pub struct ShapeEditor {
     pub active_tab: usize,
     pub resources: i32,
     pub points: i32,
-    // Settings and UI state
-    pub status_message: Option<String>,
-    pub status_time: f32,
+    // Notifications shown in the corner of the window
+    pub toasts: Toasts,
+    // First-run tutorial, None once finished or skipped
+    pub onboarding: Option<Onboarding>,
+    pub onboarding_done: bool,
+    // Successful exports this session, watched by the tutorial
+    pub export_count: usize,
+    // Shapes dumped by a crash in the previous session, waiting to be restored or discarded
+    pub recovered_shapes: Option<String>,
+    // Shortcut cheat sheet overlay
+    pub show_shortcuts: bool,
+    // In-app log console and the least severe level it lists
+    pub show_log_console: bool,
+    pub log_console_level: log::LevelFilter,
     // Error dialog state
     pub show_error_dialog: bool,
     pub error_title: String,
     pub error_message: String,
+    // Shown in the dialog's collapsible details section; empty hides it
+    pub error_details: String,
+    // "Distribute ports on edge" tool state
+    pub distribute_edge: usize,
+    pub distribute_count: usize,
+    // Copied port layout for pasting onto another edge or shape
+    pub port_clipboard: Option<PortLayout>,
+    // Element the canvas context menu was opened on
+    pub context_target: Option<CanvasTarget>,
+    // Grid and shape fill geometry reused between frames
+    pub canvas_cache: CanvasCache,
+    // Import running in the background, shown in a progress window
+    pub import_job: Option<ImportJob>,
+    // Export being written in the background
+    pub export_job: Option<ExportJob>,
+    // Shared with the page's handle when the editor is embedded in one
+    #[cfg(target_arch = "wasm32")]
+    pub embed: Option<crate::embed::SharedEmbedState>,
 }
 
 impl ShapeEditor {
+    // Editor with the saved settings applied, which saves them back as they change
     pub fn new() -> Self {
+        let mut editor = Self::with_defaults();
+        editor.persist = true;
+        if let Some(settings) = Settings::load() {
+            settings.apply(&mut editor);
+        }
+        if !editor.onboarding_done {
+            editor.onboarding = Some(Onboarding::new());
+        }
+        editor.saved_settings = Some(Settings::from_editor(&editor));
+        editor.recovered_shapes = crate::recovery::load();
+        editor.user_templates = crate::user_templates::load();
+        editor
+    }
+    
+    // Editor with the built-in defaults, ignoring any saved settings and writing
+    // nothing to the config dir
+    pub fn with_defaults() -> Self {
         let mut shapes = Vec::new();
-        shapes.push(AppShape::new(1));
+        shapes.push(AppShape::new(1).into());
         
         Self {
-            shapes: shapes.clone(),
+            shapes,
             current_shape_idx: 0,
+            selection: Selection::default(),
+            selection_shape: 0,
             grid_size: 10.0,
             show_grid: true,
+            grid_style: GridStyle::default(),
+            show_vertex_labels: true,
+            show_port_labels: true,
+            handle_scale: 1.0,
+            port_palette: PortPalette::Standard,
+            port_shape_markers: false,
+            ui_scale: 1.0,
+            panel_layout: PanelLayout::default(),
+            keybindings: Keybindings::default(),
+            rebinding: None,
+            shape_id_text: String::new(),
+            fallback_chain_text: crate::translations::get_fallback_chain().join(", "),
             snap_to_grid: true,
+            snap_port_fractions: true,
+            respace_ports_on_split: false,
+            show_rulers: true,
+            guides: Vec::new(),
+            snap_to_guides: true,
+            dragged_guide: None,
+            pivot: None,
+            transform_angle: 90.0,
+            transform_factor: 2.0,
+            show_connection_preview: false,
+            show_measurements: false,
+            show_normals: true,
+            show_firing_arcs: false,
+            firing_arc_spread: 10.0,
+            firing_arc_range: 1000.0,
+            firing_arc_range_scale: 0.05,
+            measure_tool: false,
+            measure_start: None,
+            measure_end: None,
+            cursor_shape_pos: None,
+            canvas_rect: Rect::NOTHING,
+            view_bookmarks: [None; 3],
+            show_minimap: true,
+            lod_preview: false,
+            game_preview: false,
+            // Defaults match the blocks.lua template (fillColor, fillColor1, lineColor)
+            preview_fill_color: Color32::from_rgb(0x11, 0x30, 0x77),
+            preview_fill_color1: Color32::from_rgb(0x20, 0x50, 0x79),
+            preview_line_color: Color32::from_rgb(0x33, 0x90, 0xeb),
             zoom: 1.0,
             pan: Vec2::new(0.0, 0.0),
             dragging: false,
+            drag_pointer_offset: Vec2::ZERO,
             last_mouse_pos: Pos2::new(0.0, 0.0),
             export_path: "shapes.lua".to_string(),
+            sort_ports_on_export: false,
+            export_append: false,
+            preview_export: false,
+            export_preview: None,
             import_path: "shapes.lua".to_string(),
-            undo_history: vec![shapes],
+            blocks_path: "blocks.lua".to_string(),
+            blocks_file: None,
+            pending_shape_change: None,
+            export_dialog: None,
+            import_dialog: None,
+            mod_export_dialog: None,
+            export_check: None,
+            generator_dialog: None,
+            normalize_dialog: None,
+            batch_dialog: None,
+            id_categories: crate::shape_ids::default_categories(),
+            id_category: None,
+            mod_root: String::new(),
+            update_blocks_on_renumber: true,
+            problems: Vec::new(),
+            problems_checked: None,
+            show_problems: false,
+            show_lua_preview: false,
+            show_budget: false,
+            budget_heuristics: BudgetHeuristics::default(),
+            reference_templates: crate::project_generator::reference_templates().iter()
+                .map(Self::convert_from_ast_shape)
+                .collect(),
+            user_templates: Vec::new(),
+            template_name: String::new(),
+            lua_preview: None,
+            show_script_console: false,
+            show_history: false,
+            script_source: String::new(),
+            script_output: Vec::new(),
+            script_error: None,
+            pinned_shapes: Vec::new(),
+            arrange: Scene::default(),
+            arrange_zoom: 4.0,
+            arrange_pan: Vec2::new(0.0, 0.0),
+            scale_waivers: Vec::new(),
+            variant_links: Vec::new(),
+            payload_links: Vec::new(),
+            variant_synced: BTreeMap::new(),
+            variant_source: None,
+            variant_transform: VariantTransform::MirrorX,
+            recent_files: Vec::new(),
+            saved_settings: None,
+            settings_save_at: None,
+            settings_dirty: true,
+            persist: false,
+            undo_history: VecDeque::new(),
             redo_history: Vec::new(),
+            undo_transaction: false,
             middle_drag_ongoing: false,
             zoom_center: Pos2::ZERO,
             active_tab: 0,  // Default to Shapes tab
             resources: 500,
             points: 200,
-            status_message: None,
-            status_time: 0.0,
+            toasts: Toasts::default(),
+            onboarding: None,
+            onboarding_done: false,
+            export_count: 0,
+            recovered_shapes: None,
+            show_shortcuts: false,
+            show_log_console: false,
+            log_console_level: log::LevelFilter::Info,
             // Initialize error dialog state
             show_error_dialog: false,
             error_title: String::new(),
             error_message: String::new(),
+            error_details: String::new(),
+            distribute_edge: 0,
+            distribute_count: 2,
+            port_clipboard: None,
+            context_target: None,
+            canvas_cache: CanvasCache::default(),
+            import_job: None,
+            export_job: None,
+            #[cfg(target_arch = "wasm32")]
+            embed: None,
         }
     }
     
@@ -88,78 +572,204 @@ impl ShapeEditor {
     pub fn show_error(&mut self, title: &str, message: &str) {
         self.error_title = title.to_string();
         self.error_message = message.to_string();
+        self.error_details.clear();
         self.show_error_dialog = true;
     }
     
-    // Save current state to undo history
+    // Show an error dialog whose details section holds the full error, e.g. the
+    // parser diagnostics and file path, followed by a backtrace where available
+    pub fn show_error_with_details(&mut self, title: &str, message: &str, details: &str) {
+        self.show_error(title, message);
+        self.error_details = details.to_string();
+        
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+            self.error_details.push_str(&format!("\n\nBacktrace:\n{}", backtrace));
+        }
+    }
+    
+    fn matches_snapshot(&self, snapshot: &Snapshot) -> bool {
+        snapshot.shapes == self.shapes
+    }
+    
+    fn restore_snapshot(&mut self, snapshot: &Snapshot) {
+        self.shapes = snapshot.shapes.clone();
+        
+        // Make sure current_shape_idx is valid
+        if self.current_shape_idx >= self.shapes.len() && !self.shapes.is_empty() {
+            self.current_shape_idx = self.shapes.len() - 1;
+        }
+    }
+    
+    // Save current state to undo history, before a change is made
     pub fn save_state(&mut self) {
         self.redo_history.clear(); // Clear redo history when new action is performed
         
+        // The state before the transaction is already saved
+        if self.undo_transaction {
+            return;
+        }
+        
         // Only save if there's a difference from the last state
-        if let Some(last_state) = self.undo_history.last() {
-            if last_state == &self.shapes {
+        if let Some(last_state) = self.undo_history.back() {
+            if self.matches_snapshot(last_state) {
                 return; // No change, no need to save
             }
         }
         
-        self.undo_history.push(self.shapes.clone());
+        self.undo_history.push_back(Snapshot::new(&self.shapes));
         
         // Limit history size
         if self.undo_history.len() > MAX_UNDO_HISTORY {
-            self.undo_history.remove(0);
+            self.undo_history.pop_front();
+        }
+    }
+    
+    // Start a continuous edit: the state is saved once, and every change until the
+    // transaction ends collapses into that single undo step
+    pub fn begin_undo_transaction(&mut self) {
+        if !self.undo_transaction {
+            self.save_state();
+            self.undo_transaction = true;
+        }
+    }
+    
+    // Close the transaction once the mouse is released and no widget is being edited
+    fn update_undo_transaction(&mut self, ctx: &egui::Context) {
+        if self.undo_transaction && !ctx.input().pointer.any_down() && ctx.memory().focus().is_none() {
+            self.undo_transaction = false;
         }
     }
     
     // Undo last action
     pub fn undo(&mut self) {
-        if self.undo_history.len() > 1 { // Keep at least one state in undo history
-            // Save current state to redo
-            self.redo_history.push(self.shapes.clone());
+        self.undo_transaction = false;
+        
+        // Skip saved states identical to the current one (saved for edits that changed nothing)
+        while let Some(previous_state) = self.undo_history.pop_back() {
+            if self.matches_snapshot(&previous_state) {
+                continue;
+            }
             
-            // Pop the current state from undo (it's the one we're at)
-            self.undo_history.pop();
+            // Save current state to redo
+            let renumber = previous_state.blocks_renumber;
+            self.redo_history.push(Snapshot { blocks_renumber: renumber, ..Snapshot::new(&self.shapes) });
             
-            // Use the last state from undo
-            if let Some(previous_state) = self.undo_history.last() {
-                self.shapes = previous_state.clone();
-                
-                // Make sure current_shape_idx is valid
-                if self.current_shape_idx >= self.shapes.len() && !self.shapes.is_empty() {
-                    self.current_shape_idx = self.shapes.len() - 1;
-                }
+            self.restore_snapshot(&previous_state);
+            if let Some((old_id, new_id)) = renumber {
+                self.replay_blocks_renumber(new_id, old_id);
             }
+            break;
         }
     }
     
     // Redo previously undone action
     pub fn redo(&mut self) {
+        self.undo_transaction = false;
+        
         if let Some(next_state) = self.redo_history.pop() {
             // Save current state to undo
-            self.undo_history.push(self.shapes.clone());
+            let renumber = next_state.blocks_renumber;
+            self.undo_history.push_back(Snapshot { blocks_renumber: renumber, ..Snapshot::new(&self.shapes) });
             
             // Apply the redo state
-            self.shapes = next_state;
-            
-            // Make sure current_shape_idx is valid
-            if self.current_shape_idx >= self.shapes.len() && !self.shapes.is_empty() {
-                self.current_shape_idx = self.shapes.len() - 1;
+            self.restore_snapshot(&next_state);
+            if let Some((old_id, new_id)) = renumber {
+                self.replay_blocks_renumber(old_id, new_id);
             }
         }
     }
     
+    // Place of the current state in the history: the number of states before it,
+    // and the number of states in all, counting it
+    pub fn history_position(&self) -> (usize, usize) {
+        let before = self.undo_history.len();
+        (before, before + 1 + self.redo_history.len())
+    }
+    
+    // For each state after the first, the number of shapes added, removed or changed
+    // since the state before it. Unchanged shapes are shared between snapshots, so
+    // comparing pointers is enough.
+    pub fn history_changes(&self) -> Vec<usize> {
+        let states: Vec<&Vec<SharedShape>> = self.undo_history.iter()
+            .map(|state| &state.shapes)
+            .chain(std::iter::once(&self.shapes))
+            .chain(self.redo_history.iter().rev().map(|state| &state.shapes))
+            .collect();
+        states.windows(2)
+            .map(|pair| {
+                let (a, b) = (pair[0], pair[1]);
+                let changed = a.iter().zip(b.iter()).filter(|(a, b)| !a.ptr_eq(b)).count();
+                changed + a.len().abs_diff(b.len())
+            })
+            .collect()
+    }
+    
+    // Undo or redo until `target` states come before the current one
+    pub fn seek_history(&mut self, target: usize) {
+        while self.undo_history.len() > target {
+            self.undo();
+        }
+        while self.undo_history.len() < target && !self.redo_history.is_empty() {
+            self.redo();
+        }
+    }
+    
     // Преобразование координаты экрана в координату формы
     pub fn screen_to_shape_coords(&self, screen_pos: Pos2, rect: Rect) -> Vertex {
-        let center = rect.center();
-        let x = (screen_pos.x - center.x) / self.zoom - self.pan.x;
-        let y = (screen_pos.y - center.y) / self.zoom - self.pan.y;
+        let Vertex { x, y } = self.screen_to_shape_coords_unsnapped(screen_pos, rect);
         
-        if self.snap_to_grid {
-            Vertex {
-                x: round_to(x, self.grid_size),
-                y: round_to(y, self.grid_size),
-            }
-        } else {
-            Vertex { x, y }
+        // A guide within reach wins over the grid
+        let snap = |axis: GuideAxis, value: f32| self.guide_near(axis, value).unwrap_or(
+            if self.snap_to_grid { round_to(value, self.grid_size) } else { value }
+        );
+        Vertex {
+            x: snap(GuideAxis::X, x),
+            y: snap(GuideAxis::Y, y),
+        }
+    }
+    
+    // Position of the closest guide on the axis within snapping range of `value`. The
+    // guide being dragged doesn't snap to itself.
+    pub fn guide_near(&self, axis: GuideAxis, value: f32) -> Option<f32> {
+        if !self.snap_to_guides {
+            return None;
+        }
+        let range = GUIDE_SNAP_PIXELS / self.zoom;
+        self.guides.iter()
+            .enumerate()
+            .filter(|&(i, guide)| guide.axis == axis && self.dragged_guide != Some(i))
+            .map(|(_, guide)| guide.position)
+            .filter(|position| (position - value).abs() <= range)
+            .min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs()))
+    }
+    
+    // Where along the edge from `start` to `end` a point at fraction `t` snaps to, if the
+    // edge crosses a guide within snapping range of it
+    pub fn edge_guide_snap(&self, start: &Vertex, end: &Vertex, t: f32) -> Option<f32> {
+        if !self.snap_to_guides {
+            return None;
+        }
+        let length = ((end.x - start.x).powi(2) + (end.y - start.y).powi(2)).sqrt();
+        let range = GUIDE_SNAP_PIXELS / self.zoom;
+        self.guides.iter()
+            .filter_map(|guide| {
+                let (a, b) = match guide.axis {
+                    GuideAxis::X => (start.x, end.x),
+                    GuideAxis::Y => (start.y, end.y),
+                };
+                ((b - a).abs() > f32::EPSILON).then(|| (guide.position - a) / (b - a))
+            })
+            .filter(|crossing| (0.0..=1.0).contains(crossing) && (crossing - t).abs() * length <= range)
+            .min_by(|a, b| (a - t).abs().total_cmp(&(b - t).abs()))
+    }
+    
+    // Screen to shape coordinates without grid snapping
+    pub fn screen_to_shape_coords_unsnapped(&self, screen_pos: Pos2, rect: Rect) -> Vertex {
+        let center = rect.center();
+        Vertex {
+            x: (screen_pos.x - center.x) / self.zoom - self.pan.x,
+            y: (screen_pos.y - center.y) / self.zoom - self.pan.y,
         }
     }
     
@@ -172,15 +782,473 @@ impl ShapeEditor {
         }
     }
     
+    // Switch the ruler on or off, dropping any previous measurement
+    pub fn toggle_measure_tool(&mut self) {
+        self.measure_tool = !self.measure_tool;
+        self.measure_start = None;
+        self.measure_end = None;
+    }
+    
     // Добавление новой формы
     pub fn add_shape(&mut self) {
         self.save_state();
         
-        let id = self.shapes.len() + 1;
-        self.shapes.push(AppShape::new(id));
+        let id = self.next_shape_id();
+        self.shapes.push(AppShape::new(id).into());
         self.current_shape_idx = self.shapes.len() - 1;
     }
     
+    // Add a copy of a template as a new shape, keeping the template's ID if it is free
+    pub fn insert_template(&mut self, template: AppShape) {
+        let id = self.push_new_shape(template);
+        let name = &self.shapes[self.current_shape_idx].name;
+        self.toasts.success(format!("{} {} (ID: {})", crate::translations::t("template_inserted"), name, id));
+    }
+    
+    // Add a shape from the Generate dialog
+    pub fn add_generated_shape(&mut self, shape: AppShape) {
+        let id = self.push_new_shape(shape);
+        let name = &self.shapes[self.current_shape_idx].name;
+        self.toasts.success(format!("{} {} (ID: {})", crate::translations::t("shape_generated"), name, id));
+    }
+    
+    // Replace a shape's outlines and ports with the normalize wizard's result
+    pub fn normalize_shape(&mut self, shape_idx: usize, params: &NormalizeParams) {
+        let Some(shape) = self.shapes.get(shape_idx) else { return };
+        let Some(normalized) = crate::normalize::normalize_shape(shape, params) else {
+            self.toasts.warning(crate::translations::t("normalize_no_edge"));
+            return;
+        };
+        
+        self.save_state();
+        let name = normalized.name.clone();
+        self.shapes[shape_idx] = normalized.into();
+        self.toasts.success(format!("{} {}", crate::translations::t("shape_normalized"), name));
+    }
+    
+    // Apply a batch operation to the current shape or to all of them, and report what changed
+    pub fn apply_batch(&mut self, op: BatchOp, all_shapes: bool) {
+        let indices: Vec<usize> = if all_shapes {
+            (0..self.shapes.len()).collect()
+        } else if self.current_shape_idx < self.shapes.len() {
+            vec![self.current_shape_idx]
+        } else {
+            Vec::new()
+        };
+        let (results, summary) = crate::batch::apply_all(indices.iter().map(|&i| &*self.shapes[i]), op);
+        
+        let message = crate::batch::describe(&summary);
+        if summary.shapes_changed == 0 {
+            self.toasts.success(message);
+            return;
+        }
+        self.save_state();
+        for (i, result) in indices.into_iter().zip(results) {
+            // Unchanged shapes keep sharing their data with the undo history
+            if *self.shapes[i] != result {
+                self.shapes[i] = result.into();
+            }
+        }
+        self.toasts.success(message);
+    }
+    
+    // Run the console's script over all shapes; a script that fails, or leaves no shapes or two
+    // with one ID behind, leaves them as they were
+    pub fn run_script(&mut self) {
+        let shapes: Vec<AppShape> = self.shapes.iter().map(|shape| (**shape).clone()).collect();
+        let run = crate::script::run(&self.script_source, &shapes, &self.pivot_point());
+        self.script_output = run.output;
+        
+        // The same rules the editor keeps: at least one shape, and no two with one ID
+        let result = run.result.and_then(|result| {
+            if result.is_empty() {
+                return Err(crate::translations::t("script_no_shapes"));
+            }
+            let mut ids = BTreeSet::new();
+            match result.iter().find(|shape| !ids.insert(shape.id)) {
+                Some(shape) => Err(format!("{} {}", crate::translations::t("shape_id_taken"), shape.id)),
+                None => Ok(result),
+            }
+        });
+        
+        match result {
+            Ok(result) => {
+                self.script_error = None;
+                if result == shapes {
+                    self.toasts.success(crate::translations::t("script_no_changes"));
+                    return;
+                }
+                self.save_state();
+                // Shapes the script left alone keep sharing their data with the history
+                let new_shapes = result.into_iter().enumerate().map(|(i, shape)| {
+                    self.shapes.get(i)
+                        .filter(|old| ***old == shape)
+                        .or_else(|| self.shapes.iter().find(|old| ***old == shape))
+                        .cloned()
+                        .unwrap_or_else(|| shape.into())
+                }).collect();
+                self.shapes = new_shapes;
+                if self.current_shape_idx >= self.shapes.len() {
+                    self.current_shape_idx = self.shapes.len().saturating_sub(1);
+                }
+                self.sync_selection();
+                self.toasts.success(crate::translations::t("script_applied"));
+            }
+            Err(e) => {
+                self.toasts.error(crate::translations::t("script_failed"));
+                self.script_error = Some(e);
+            }
+        }
+    }
+    
+    // IDs taken by shapes in the session or referred to by the loaded blocks.lua
+    pub fn used_shape_ids(&self) -> BTreeSet<usize> {
+        let blocks_ids = self.blocks_file.iter().flat_map(|blocks_file| blocks_file.shape_ids());
+        self.shapes.iter().map(|shape| shape.id).chain(blocks_ids).collect()
+    }
+    
+    // ID for a new shape: the first free one from the chosen category's base, or past
+    // every used ID if the modding range is full
+    pub fn next_shape_id(&self) -> usize {
+        Self::allocate_shape_id(&self.used_shape_ids(), self.id_base())
+    }
+    
+    fn id_base(&self) -> usize {
+        self.id_category
+            .and_then(|i| self.id_categories.get(i))
+            .map_or(crate::shape_ids::MIN_SHAPE_ID, |category| category.base)
+    }
+    
+    fn allocate_shape_id(used: &BTreeSet<usize>, base: usize) -> usize {
+        crate::shape_ids::next_free_id(used, base)
+            .unwrap_or_else(|| used.last().map_or(crate::shape_ids::MIN_SHAPE_ID, |id| id + 1))
+    }
+    
+    // Append a shape and select it, moving it to a free ID if its own is taken
+    fn push_new_shape(&mut self, mut shape: AppShape) -> usize {
+        self.save_state();
+        
+        if self.shapes.iter().any(|existing| existing.id == shape.id) {
+            shape.id = self.next_shape_id();
+        }
+        let id = shape.id;
+        self.shapes.push(shape.into());
+        self.current_shape_idx = self.shapes.len() - 1;
+        id
+    }
+    
+    // Save a copy of a shape in the template library, replacing a template of the same name
+    pub fn save_as_template(&mut self, shape_idx: usize, name: &str) {
+        let Some(shape) = self.shapes.get(shape_idx) else { return };
+        let name = name.trim();
+        let mut template = (**shape).clone();
+        template.name = if name.is_empty() { shape.name.clone() } else { name.to_string() };
+        
+        let message = format!("{} {}", crate::translations::t("template_saved"), template.name);
+        match self.user_templates.iter().position(|existing| existing.name == template.name) {
+            Some(i) => self.user_templates[i] = template,
+            None => self.user_templates.push(template),
+        }
+        if self.write_user_templates() {
+            self.toasts.success(message);
+        }
+    }
+    
+    pub fn delete_user_template(&mut self, i: usize) {
+        if i < self.user_templates.len() {
+            self.user_templates.remove(i);
+            self.write_user_templates();
+        }
+    }
+    
+    fn write_user_templates(&mut self) -> bool {
+        if !self.persist {
+            return true;
+        }
+        match crate::user_templates::save(&self.user_templates) {
+            Ok(()) => true,
+            Err(e) => {
+                log::error!("Failed to save templates: {}", e);
+                self.toasts.error(format!("{}: {}", crate::translations::t("templates_save_failed"), e));
+                false
+            }
+        }
+    }
+    
+    // Delete or renumber a shape, asking first if blocks in the loaded blocks.lua use it
+    pub fn request_shape_change(&mut self, change: ShapeChange) {
+        let shape_idx = match change {
+            ShapeChange::Renumber(idx, _) | ShapeChange::Delete(idx) => idx,
+        };
+        if self.blocks_using_shape(shape_idx).is_empty() {
+            self.apply_shape_change(change);
+        } else {
+            self.pending_shape_change = Some(change);
+        }
+    }
+    
+    pub fn apply_shape_change(&mut self, change: ShapeChange) {
+        self.pending_shape_change = None;
+        match change {
+            ShapeChange::Renumber(idx, new_id) => {
+                let old_id = self.shapes.get(idx).map(|shape| shape.id);
+                if self.renumber_shape(idx, new_id) && self.update_blocks_on_renumber {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(old_id) = old_id {
+                        // The state saved by renumber_shape remembers to move the blocks back
+                        if self.update_blocks_shape_id(old_id, new_id)
+                            && let Some(state) = self.undo_history.back_mut()
+                        {
+                            state.blocks_renumber = Some((old_id, new_id));
+                        }
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    let _ = old_id;
+                }
+            }
+            ShapeChange::Delete(idx) => self.delete_shape(idx),
+        }
+    }
+    
+    // Returns false if the ID is unchanged, out of range or already taken
+    pub fn renumber_shape(&mut self, shape_idx: usize, new_id: usize) -> bool {
+        if shape_idx >= self.shapes.len() || self.shapes[shape_idx].id == new_id {
+            return false;
+        }
+        let range = crate::shape_ids::MIN_SHAPE_ID..=crate::shape_ids::MAX_SHAPE_ID;
+        if !range.contains(&new_id) {
+            self.toasts.warning(format!(
+                "{} {}–{}",
+                crate::translations::t("shape_id_out_of_range"),
+                range.start(),
+                range.end()
+            ));
+            return false;
+        }
+        if self.shapes.iter().any(|shape| shape.id == new_id) {
+            self.toasts.warning(format!("{} {}", crate::translations::t("shape_id_taken"), new_id));
+            return false;
+        }
+        
+        self.save_state();
+        let old_id = std::mem::replace(&mut self.shapes[shape_idx].id, new_id);
+        for pin in self.pinned_shapes.iter_mut().filter(|pin| pin.shape_id == old_id) {
+            pin.shape_id = new_id;
+        }
+        for link in &mut self.variant_links {
+            if link.source_id == old_id {
+                link.source_id = new_id;
+            }
+            if link.variant_id == old_id {
+                link.variant_id = new_id;
+            }
+        }
+        if let Some(synced) = self.variant_synced.remove(&old_id) {
+            self.variant_synced.insert(new_id, synced);
+        }
+        for link in &mut self.payload_links {
+            if link.launcher_id == old_id {
+                link.launcher_id = new_id;
+            }
+            if link.payload_id == old_id {
+                link.payload_id = new_id;
+            }
+        }
+        self.problems_checked = None;
+        true
+    }
+    
+    // Place a copy of a shape in the arrange scene, right of everything already there
+    pub fn add_to_arrangement(&mut self, shape_idx: usize) {
+        let Some(shape) = self.shapes.get(shape_idx) else { return };
+        let right = self.arrange.placements.iter()
+            .filter_map(|placement| {
+                let shape = crate::arrange::find_shape(&self.shapes, placement.shape_id)?;
+                placement.outline(shape).iter().map(|v| v.x).reduce(f32::max)
+            })
+            .reduce(f32::max);
+        let left = shape.vertices.iter().map(|v| v.x).reduce(f32::min).unwrap_or(0.0);
+        let x = right.map_or(0.0, |right| right - left + 2.0);
+        self.arrange.add(Placement::new(shape.id, Vertex { x, y: 0.0 }));
+    }
+    
+    // Name a shape after the kind of polygon it is
+    pub fn suggest_name(&mut self, shape_idx: usize) {
+        let Some(shape) = self.shapes.get(shape_idx) else { return };
+        match crate::naming::suggest_name(shape) {
+            Some(name) if name != shape.name => {
+                self.save_state();
+                self.shapes[shape_idx].name = name;
+            }
+            Some(_) => {}
+            None => self.toasts.warning(crate::translations::t("suggest_name_failed")),
+        }
+    }
+    
+    // Make a shape a variant of another, redoing it from the source right away
+    pub fn link_variant(&mut self, variant_idx: usize, source_id: usize, transform: VariantTransform) {
+        let Some(variant_id) = self.shapes.get(variant_idx).map(|shape| shape.id) else { return };
+        if crate::variants::creates_cycle(&self.variant_links, source_id, variant_id) {
+            self.toasts.warning(crate::translations::t("variant_cycle"));
+            return;
+        }
+        let Some(source) = self.shapes.iter().find(|shape| shape.id == source_id).cloned() else { return };
+        
+        self.save_state();
+        self.variant_links.retain(|link| link.variant_id != variant_id);
+        self.variant_links.push(VariantLink { source_id, variant_id, transform, auto_update: true });
+        self.shapes[variant_idx] = crate::variants::derive(&source, &self.shapes[variant_idx], transform).into();
+        self.variant_synced.insert(variant_id, source);
+    }
+    
+    pub fn unlink_variant(&mut self, variant_id: usize) {
+        self.variant_links.retain(|link| link.variant_id != variant_id);
+        self.variant_synced.remove(&variant_id);
+    }
+    
+    pub fn set_variant_auto_update(&mut self, variant_id: usize, auto_update: bool) {
+        for link in self.variant_links.iter_mut().filter(|link| link.variant_id == variant_id) {
+            link.auto_update = auto_update;
+        }
+    }
+    
+    // Redo a variant from its source now, whether or not it updates by itself
+    pub fn update_variant(&mut self, variant_id: usize) {
+        let Some(link) = self.variant_links.iter().find(|link| link.variant_id == variant_id).cloned() else { return };
+        let Some(source) = self.shapes.iter().find(|shape| shape.id == link.source_id).cloned() else { return };
+        let Some(variant_idx) = self.shapes.iter().position(|shape| shape.id == variant_id) else { return };
+        
+        let derived = crate::variants::derive(&source, &self.shapes[variant_idx], link.transform);
+        if derived != *self.shapes[variant_idx] {
+            self.save_state();
+            self.shapes[variant_idx] = derived.into();
+        }
+        self.variant_synced.insert(variant_id, source);
+    }
+    
+    // Redo the variants that update by themselves when their source has changed since.
+    // Running every frame puts the change into the same undo step as the source's. A
+    // source seen for the first time, as after a restart, is taken to be in step.
+    fn sync_variants(&mut self) {
+        for i in 0..self.variant_links.len() {
+            let link = self.variant_links[i].clone();
+            if !link.auto_update {
+                continue;
+            }
+            let Some(source) = self.shapes.iter().find(|shape| shape.id == link.source_id).cloned() else { continue };
+            let previous = self.variant_synced.insert(link.variant_id, source.clone());
+            if previous.is_none_or(|previous| previous.ptr_eq(&source)) {
+                continue;
+            }
+            
+            if let Some(variant_idx) = self.shapes.iter().position(|shape| shape.id == link.variant_id) {
+                let derived = crate::variants::derive(&source, &self.shapes[variant_idx], link.transform);
+                if derived != *self.shapes[variant_idx] {
+                    self.shapes[variant_idx] = derived.into();
+                }
+            }
+        }
+    }
+    
+    // Pin a shape to the canvas, or unpin it if it is pinned
+    pub fn toggle_pin(&mut self, shape_idx: usize) {
+        let Some(shape_id) = self.shapes.get(shape_idx).map(|shape| shape.id) else { return };
+        if let Some(pos) = self.pinned_shapes.iter().position(|pin| pin.shape_id == shape_id) {
+            self.pinned_shapes.remove(pos);
+        } else {
+            self.pinned_shapes.push(PinnedShape {
+                shape_id,
+                visible: true,
+                offset: Vertex { x: 0.0, y: 0.0 },
+            });
+        }
+    }
+    
+    // Point the blocks using a renumbered shape at its new ID and save blocks.lua;
+    // false if no block used it
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_blocks_shape_id(&mut self, old_id: usize, new_id: usize) -> bool {
+        let blocks_file = match &mut self.blocks_file {
+            Some(blocks_file) => blocks_file,
+            None => return false,
+        };
+        if blocks_file.renumber_shape(old_id, new_id) == 0 {
+            return false;
+        }
+        
+        match blocks_file.save() {
+            Ok(()) => {
+                let message = format!("{} {}", crate::translations::t("blocks_updated"), blocks_file.path);
+                self.toasts.success(message);
+            }
+            Err(e) => self.show_error("Blocks Error", &e),
+        }
+        true
+    }
+    
+    // Move the blocks along with a renumber being undone or redone
+    fn replay_blocks_renumber(&mut self, from_id: usize, to_id: usize) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_blocks_shape_id(from_id, to_id);
+        #[cfg(target_arch = "wasm32")]
+        let _ = (from_id, to_id);
+    }
+    
+    // Remove a shape; the last one is kept so the editor always has a shape to show
+    pub fn delete_shape(&mut self, shape_idx: usize) {
+        if shape_idx >= self.shapes.len() || self.shapes.len() < 2 {
+            return;
+        }
+        
+        self.save_state();
+        self.shapes.remove(shape_idx);
+        if self.current_shape_idx >= self.shapes.len() || self.current_shape_idx > shape_idx {
+            self.current_shape_idx = self.current_shape_idx.saturating_sub(1);
+        }
+    }
+    
+    // Labels of the blocks in the loaded blocks.lua that use a shape
+    pub fn blocks_using_shape(&self, shape_idx: usize) -> Vec<String> {
+        match (&self.blocks_file, self.shapes.get(shape_idx)) {
+            (Some(blocks_file), Some(shape)) => blocks_file.blocks_using(shape.id)
+                .into_iter()
+                .map(|block| block.label())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_blocks_file(&mut self) {
+        match BlocksFile::load(&self.blocks_path) {
+            Ok(blocks_file) => {
+                self.toasts.success(format!(
+                    "{} {} ({})",
+                    crate::translations::t("blocks_loaded"),
+                    blocks_file.path,
+                    blocks_file.blocks.len()
+                ));
+                self.blocks_file = Some(blocks_file);
+                // Payloads may come from the new file
+                self.problems_checked = None;
+            }
+            Err(e) => self.show_error("Blocks Error", &e),
+        }
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn select_blocks_file(&mut self) -> bool {
+        if let Some(path) = FileDialog::new()
+            .add_filter("Lua files", &["lua"])
+            .pick_file() {
+                if let Some(path_str) = path.to_str() {
+                    self.blocks_path = path_str.to_string();
+                    return true;
+                }
+            }
+        false
+    }
+    
     // Add or update a vertex
     pub fn add_or_update_vertex(&mut self, shape_idx: usize, vertex: Vertex, vertex_idx: Option<usize>) {
         self.save_state();
@@ -191,7 +1259,7 @@ impl ShapeEditor {
             }
         } else {
             self.shapes[shape_idx].vertices.push(vertex);
-            self.shapes[shape_idx].selected_vertex = Some(self.shapes[shape_idx].vertices.len() - 1);
+            self.selection.vertex = Some(self.shapes[shape_idx].vertices.len() - 1);
         }
     }
     
@@ -203,9 +1271,9 @@ impl ShapeEditor {
             self.shapes[shape_idx].vertices.remove(vertex_idx);
             
             // Update selected vertex
-            if let Some(selected) = self.shapes[shape_idx].selected_vertex {
+            if let Some(selected) = self.selection.vertex {
                 if selected >= vertex_idx {
-                    self.shapes[shape_idx].selected_vertex = if selected > 0 { Some(selected - 1) } else { None };
+                    self.selection.vertex = if selected > 0 { Some(selected - 1) } else { None };
                 }
             }
             
@@ -231,67 +1299,780 @@ impl ShapeEditor {
         }
     }
     
+    // Make a vertex the shape's vertex 0, which the game orients the shape by
+    pub fn set_first_vertex(&mut self, shape_idx: usize, vertex_idx: usize) {
+        if vertex_idx == 0 || vertex_idx >= self.shapes[shape_idx].vertices.len() {
+            return;
+        }
+        self.save_state();
+        let n = self.shapes[shape_idx].vertices.len();
+        self.shapes[shape_idx].rotate_vertices(vertex_idx);
+        self.selection.vertex = self.selection.vertex.map(|v| (v + n - vertex_idx) % n);
+    }
+    
+    // Move a vertex one place up or down the vertex list; the selection follows it
+    pub fn move_vertex(&mut self, shape_idx: usize, vertex_idx: usize, down: bool) {
+        // Swapped with the vertex after it, or the one before swapped with it
+        let Some(first) = (if down { Some(vertex_idx) } else { vertex_idx.checked_sub(1) }) else { return };
+        if first + 1 >= self.shapes[shape_idx].vertices.len() {
+            return;
+        }
+        self.save_state();
+        self.shapes[shape_idx].swap_with_next_vertex(first);
+        self.selection.vertex = match self.selection.vertex {
+            Some(v) if v == first => Some(first + 1),
+            Some(v) if v == first + 1 => Some(first),
+            other => other,
+        };
+    }
+    
+    // Insert a vertex at the middle of the edge before or after a vertex. One inserted
+    // before vertex 0 becomes the new vertex 0 rather than the last vertex.
+    pub fn insert_vertex(&mut self, shape_idx: usize, vertex_idx: usize, after: bool) {
+        let n = self.shapes[shape_idx].vertices.len();
+        if n < 2 || vertex_idx >= n {
+            return;
+        }
+        if after {
+            self.split_edge(shape_idx, vertex_idx, 0.5);
+        } else {
+            self.split_edge(shape_idx, (vertex_idx + n - 1) % n, 0.5);
+            if vertex_idx == 0 {
+                // The new vertex, selected by split_edge, is the last one
+                self.shapes[shape_idx].rotate_vertices(n);
+                self.selection.vertex = Some(0);
+            }
+        }
+    }
+    
+    // Insert a vertex on an edge at fraction `t`, moving the edge's ports onto
+    // the two halves so they stay where they were, or spacing them evenly on each
+    // half if `respace_ports_on_split` is set
+    pub fn split_edge(&mut self, shape_idx: usize, edge_idx: usize, t: f32) {
+        let n = self.shapes[shape_idx].vertices.len();
+        if edge_idx >= n || t <= 0.0 || t >= 1.0 {
+            return;
+        }
+        
+        self.save_state();
+        
+        let shape = &mut self.shapes[shape_idx];
+        let v1 = shape.vertices[edge_idx].clone();
+        let v2 = shape.vertices[(edge_idx + 1) % n].clone();
+        let new_vertex = Vertex {
+            x: v1.x + (v2.x - v1.x) * t,
+            y: v1.y + (v2.y - v1.y) * t,
+        };
+        
+        // Insert new vertex after edge_idx
+        shape.vertices.insert(edge_idx + 1, new_vertex);
+        self.selection.select_vertex(Some(edge_idx + 1));
+        
+        // Adjust ports on this edge
+        for port in &mut shape.ports {
+            if port.edge == edge_idx {
+                if port.position > t {
+                    // Port is after the new vertex, move it to new edge
+                    port.edge = edge_idx + 1;
+                    port.position = (port.position - t) / (1.0 - t);
+                } else {
+                    // Port is before new vertex, keep it on same edge but rescale
+                    port.position /= t;
+                }
+            } else if port.edge > edge_idx {
+                // Increment edge index for all ports after this edge
+                port.edge += 1;
+            }
+        }
+        
+        if self.respace_ports_on_split {
+            shape.respace_ports(edge_idx);
+            shape.respace_ports(edge_idx + 1);
+        }
+    }
+    
     // Add a port
     pub fn add_port(&mut self, shape_idx: usize, port: Port) {
         self.save_state();
         self.shapes[shape_idx].ports.push(port);
     }
     
-    // Remove a port
-    pub fn remove_port(&mut self, shape_idx: usize, port_idx: usize) {
-        if port_idx < self.shapes[shape_idx].ports.len() {
-            self.save_state();
-            
-            self.shapes[shape_idx].ports.remove(port_idx);
-            
-            // Update selected port
-            if let Some(selected) = self.shapes[shape_idx].selected_port {
-                if selected >= port_idx {
-                    self.shapes[shape_idx].selected_port = if selected > 0 { Some(selected - 1) } else { None };
-                }
-            }
+    // Remove a port
+    pub fn remove_port(&mut self, shape_idx: usize, port_idx: usize) {
+        if port_idx < self.shapes[shape_idx].ports.len() {
+            self.save_state();
+            
+            self.shapes[shape_idx].ports.remove(port_idx);
+            
+            // Update selected port
+            if let Some(selected) = self.selection.port {
+                if selected >= port_idx {
+                    self.selection.port = if selected > 0 { Some(selected - 1) } else { None };
+                }
+            }
+            self.selection.ports.clear();
+        }
+    }
+    
+    // Remove several ports at once as a single undo step
+    pub fn remove_ports(&mut self, shape_idx: usize, port_indices: &[usize]) {
+        let mut indices: Vec<usize> = port_indices.iter()
+            .copied()
+            .filter(|&i| i < self.shapes[shape_idx].ports.len())
+            .collect();
+        if indices.is_empty() {
+            return;
+        }
+        
+        self.save_state();
+        
+        // Remove from the back so earlier indices stay valid
+        indices.sort_unstable();
+        indices.dedup();
+        for idx in indices.into_iter().rev() {
+            self.shapes[shape_idx].ports.remove(idx);
+        }
+        
+        self.selection.select_port(None);
+    }
+    
+    // Change the type of several ports at once as a single undo step
+    pub fn set_port_types(&mut self, shape_idx: usize, port_indices: &[usize], port_type: PortType) {
+        self.save_state();
+        
+        for &idx in port_indices {
+            if let Some(port) = self.shapes[shape_idx].ports.get_mut(idx) {
+                port.port_type = port_type.clone();
+            }
+        }
+    }
+    
+    // Replace all ports on an edge with `count` equally spaced default ports
+    pub fn distribute_ports_on_edge(&mut self, shape_idx: usize, edge: usize, count: usize) {
+        if edge >= self.shapes[shape_idx].vertices.len() {
+            return;
+        }
+        
+        self.save_state();
+        
+        self.shapes[shape_idx].distribute_ports(edge, count);
+        self.selection.select_port(None);
+    }
+    
+    // Replace the ports on every edge, one port per grid unit of edge length
+    pub fn auto_port_all_edges(&mut self, shape_idx: usize) {
+        if self.shapes[shape_idx].vertices.len() < 2 {
+            return;
+        }
+        
+        self.save_state();
+        
+        let grid_size = self.grid_size.max(1.0);
+        let shape = &mut self.shapes[shape_idx];
+        for edge in 0..shape.vertices.len() {
+            let count = (shape.edge_length(edge) / grid_size).round().max(1.0) as usize;
+            shape.distribute_ports(edge, count);
+        }
+        
+        // Keep ports grouped by edge so the list reads in order
+        shape.ports.sort_by_key(|p| p.edge);
+        self.selection.select_port(None);
+    }
+    
+    // Sort the shape's ports by (edge, position)
+    pub fn sort_ports(&mut self, shape_idx: usize) {
+        self.save_state();
+        self.shapes[shape_idx].sort_ports();
+        self.selection.select_port(None);
+    }
+    
+    // Copy the ports of one edge
+    pub fn copy_edge_ports(&mut self, shape_idx: usize, edge: usize) {
+        let ports = self.shapes[shape_idx].ports.iter()
+            .filter(|p| p.edge == edge)
+            .map(|p| (p.position, p.port_type.clone()))
+            .collect();
+        self.port_clipboard = Some(PortLayout::Edge(ports));
+    }
+    
+    // Copy all ports of a shape
+    pub fn copy_shape_ports(&mut self, shape_idx: usize) {
+        self.port_clipboard = Some(PortLayout::Shape(self.shapes[shape_idx].ports.clone()));
+    }
+    
+    // Apply the copied port layout: an edge layout replaces the ports on `edge`,
+    // a shape layout replaces all ports (skipping edges the target doesn't have)
+    pub fn paste_port_layout(&mut self, shape_idx: usize, edge: usize) {
+        let layout = match &self.port_clipboard {
+            Some(layout) => layout.clone(),
+            None => return,
+        };
+        let edge_count = self.shapes[shape_idx].vertices.len();
+        // Nothing to paste onto, so no undo step either
+        if matches!(layout, PortLayout::Edge(_)) && edge >= edge_count {
+            return;
+        }
+        
+        self.save_state();
+        
+        let shape = &mut self.shapes[shape_idx];
+        match layout {
+            PortLayout::Edge(ports) => {
+                shape.ports.retain(|p| p.edge != edge);
+                for (position, port_type) in ports {
+                    shape.ports.push(Port { edge, position, port_type });
+                }
+            },
+            PortLayout::Shape(ports) => {
+                shape.ports = ports.into_iter().filter(|p| p.edge < edge_count).collect();
+            },
+        }
+        self.selection.select_port(None);
+    }
+    
+    // Allow or disallow different edge counts between the scales of a shape
+    pub fn set_scale_waiver(&mut self, shape_id: usize, waived: bool) {
+        self.scale_waivers.retain(|&id| id != shape_id);
+        if waived {
+            self.scale_waivers.push(shape_id);
+        }
+        self.problems_checked = None;
+    }
+    
+    // Shape ID of what a launcher spawns: the payload picked in the editor, else the
+    // one blocks.lua gives
+    pub fn payload_of(&self, shape_id: usize) -> Option<usize> {
+        self.payload_links.iter()
+            .find(|link| link.launcher_id == shape_id)
+            .map(|link| link.payload_id)
+            .or_else(|| self.blocks_file.as_ref()?.payload_for_shape(shape_id))
+    }
+    
+    // Pick the payload of a launcher; None goes back to what blocks.lua says
+    pub fn set_payload(&mut self, launcher_id: usize, payload_id: Option<usize>) {
+        self.payload_links.retain(|link| link.launcher_id != launcher_id);
+        if let Some(payload_id) = payload_id {
+            self.payload_links.push(PayloadLink { launcher_id, payload_id });
+        }
+        self.problems_checked = None;
+    }
+    
+    // (launcher, payload) for every shape with launch ports and a payload
+    fn payloads(&self) -> Vec<(usize, usize)> {
+        self.shapes.iter()
+            .filter(|shape| crate::payload::has_launch_ports(shape))
+            .filter_map(|shape| Some((shape.id, self.payload_of(shape.id)?)))
+            .collect()
+    }
+    
+    // Rerun the validation checks if the shapes changed since the last run
+    fn refresh_problems(&mut self) {
+        if self.problems_checked.as_ref() == Some(&self.shapes) {
+            return;
+        }
+        
+        let shapes_file = crate::ast::ShapesFile {
+            shapes: self.shapes.iter().map(|shape| Self::convert_to_ast_shape(shape)).collect(),
+        };
+        self.problems = Self::validate(&shapes_file, &self.scale_waivers);
+        self.problems.extend(crate::validation::validate_payloads(&shapes_file, &self.payloads()));
+        self.problems_checked = Some(self.shapes.clone());
+    }
+    
+    // The validation checks, less the problems waived for particular shapes
+    fn validate(shapes_file: &crate::ast::ShapesFile, scale_waivers: &[usize]) -> Vec<Diagnostic> {
+        let mut problems = crate::validation::validate_shapes_file(shapes_file);
+        // Waived shapes have scales that deliberately differ in detail
+        problems.retain(|problem| {
+            problem.rule != Rule::ScaleEdgeCount || !scale_waivers.contains(&problem.shape_id)
+        });
+        problems
+    }
+    
+    // Apply the fix offered for a problem to the shape it was found in
+    pub fn apply_fix(&mut self, shape_id: usize, fix: Fix) {
+        let Some(shape_idx) = self.shapes.iter().position(|shape| shape.id == shape_id) else { return };
+        self.save_state();
+        
+        let mut ast_shape = Self::convert_to_ast_shape(&self.shapes[shape_idx]);
+        fix.apply(&mut ast_shape);
+        self.shapes[shape_idx] = Self::convert_from_ast_shape(&ast_shape).into();
+        self.toasts.success(fix.description());
+    }
+    
+    // Reverse the vertex order of every scale, keeping each port where it was
+    pub fn reverse_winding(&mut self, shape_idx: usize) {
+        let Some(shape) = self.shapes.get(shape_idx) else { return };
+        let mut ast_shape = Self::convert_to_ast_shape(shape);
+        self.save_state();
+        
+        for scale in 0..ast_shape.scales.len() {
+            Fix::ReverseWinding { scale }.apply(&mut ast_shape);
+        }
+        self.shapes[shape_idx] = Self::convert_from_ast_shape(&ast_shape).into();
+    }
+    
+    // Where rotating and scaling work about: the placed pivot, else the origin
+    pub fn pivot_point(&self) -> Vertex {
+        self.pivot.clone().unwrap_or(Vertex { x: 0.0, y: 0.0 })
+    }
+    
+    // Turn every scale of the shape counter-clockwise about the pivot
+    pub fn rotate_shape(&mut self, shape_idx: usize, degrees: f32) {
+        if shape_idx >= self.shapes.len() {
+            return;
+        }
+        self.save_state();
+        let pivot = self.pivot_point();
+        self.shapes[shape_idx].rotate_about(&pivot, degrees);
+    }
+    
+    // Stretch every scale of the shape away from the pivot
+    pub fn scale_shape(&mut self, shape_idx: usize, factor: f32) {
+        if shape_idx >= self.shapes.len() || factor <= 0.0 {
+            return;
+        }
+        self.save_state();
+        let pivot = self.pivot_point();
+        self.shapes[shape_idx].scale_about(&pivot, factor, factor);
+    }
+    
+    // Copy the ports of scale 1 onto an additional scale (0 = scale 2)
+    pub fn project_ports_to_scale(&mut self, shape_idx: usize, scale: usize) {
+        if self.shapes.get(shape_idx).map_or(true, |shape| scale >= shape.scales.len()) {
+            return;
+        }
+        
+        self.save_state();
+        let skipped = self.shapes[shape_idx].project_ports_to_scale(scale);
+        
+        let scale_label = format!("{} {}", crate::translations::t("scale"), scale + 2);
+        if skipped.is_empty() {
+            self.toasts.success(format!("{}: {}", crate::translations::t("ports_projected"), scale_label));
+        } else {
+            let edges = skipped.iter().map(|edge| format!("E{}", edge)).collect::<Vec<_>>().join(", ");
+            self.toasts.warning(format!("{}: {} ({})", crate::translations::t("ports_projected_skipped"), scale_label, edges));
+        }
+    }
+    
+    // Drop the selection when another shape has become current, and whatever in it the
+    // current shape no longer has
+    fn sync_selection(&mut self) {
+        if self.selection_shape != self.current_shape_idx {
+            self.selection = Selection::default();
+            self.selection_shape = self.current_shape_idx;
+        }
+        if let Some(shape) = self.shapes.get(self.current_shape_idx) {
+            self.selection.clamp_to(shape);
+        }
+    }
+    
+    // Cycle vertex selection forwards or backwards through the current shape
+    pub fn cycle_vertex_selection(&mut self, backwards: bool) {
+        if let Some(shape) = self.shapes.get(self.current_shape_idx) {
+            if let Some(next) = cycle_index(self.selection.vertex, shape.vertices.len(), backwards) {
+                self.selection.select_vertex(Some(next));
+            }
+        }
+    }
+    
+    // Cycle port selection forwards or backwards through the current shape
+    pub fn cycle_port_selection(&mut self, backwards: bool) {
+        if let Some(shape) = self.shapes.get(self.current_shape_idx) {
+            if let Some(next) = cycle_index(self.selection.port, shape.ports.len(), backwards) {
+                self.selection.select_port(Some(next));
+            }
+        }
+    }
+    
+    // Handle zoom at specific position
+    pub fn zoom_at(&mut self, screen_pos: Pos2, rect: Rect, delta: f32) {
+        let old_zoom = self.zoom;
+        
+        // Adjust zoom
+        self.zoom = (self.zoom * (1.0 + delta * 0.1)).clamp(0.1, 10.0);
+        
+        // Calculate world position before zoom
+        let center = rect.center();
+        let before_x = (screen_pos.x - center.x) / old_zoom;
+        let before_y = (screen_pos.y - center.y) / old_zoom;
+        
+        // Calculate world position after zoom
+        let after_x = (screen_pos.x - center.x) / self.zoom;
+        let after_y = (screen_pos.y - center.y) / self.zoom;
+        
+        // Adjust panning to keep the world position constant under cursor
+        self.pan.x += after_x - before_x;
+        self.pan.y += after_y - before_y;
+    }
+    
+    // Set zoom and pan so that the box fills the canvas with a small margin
+    pub fn frame_bounds(&mut self, bounds: &AABBox) {
+        let size = self.canvas_rect.size();
+        if !size.x.is_finite() || size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+        let radius = bounds.get_radius();
+        let center = bounds.get_center();
+        
+        if radius.x > 0.0 || radius.y > 0.0 {
+            let zoom_x = if radius.x > 0.0 { size.x / (2.0 * radius.x) } else { f32::MAX };
+            let zoom_y = if radius.y > 0.0 { size.y / (2.0 * radius.y) } else { f32::MAX };
+            self.zoom = (zoom_x.min(zoom_y) * 0.85).clamp(0.1, 10.0);
+        }
+        self.pan = vec2(-center.x, -center.y);
+    }
+    
+    // Back to the default zoom and pan
+    pub fn reset_view(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+    }
+    
+    pub fn save_view_bookmark(&mut self, slot: usize) {
+        if let Some(bookmark) = self.view_bookmarks.get_mut(slot) {
+            *bookmark = Some((self.zoom, self.pan));
+            self.toasts.info(format!("{} {}", crate::translations::t("view_bookmark_saved"), slot + 1));
+        }
+    }
+    
+    pub fn recall_view_bookmark(&mut self, slot: usize) {
+        if let Some(Some((zoom, pan))) = self.view_bookmarks.get(slot) {
+            self.zoom = *zoom;
+            self.pan = *pan;
+        }
+    }
+    
+    // Scale shown by the LOD preview: 0 (scale 1) at normal zoom, and one scale
+    // further each time the view is zoomed out by half
+    pub fn lod_scale_index(&self, shape_idx: usize) -> usize {
+        let extra_scales = match self.shapes.get(shape_idx) {
+            Some(shape) if self.lod_preview => shape.scales.len(),
+            _ => return 0,
+        };
+        let level = (1.0 / self.zoom.max(1e-3)).log2().round().max(0.0) as usize;
+        level.min(extra_scales)
+    }
+    
+    // Frame the whole current shape
+    pub fn zoom_to_fit(&mut self) {
+        let Some(shape) = self.shapes.get(self.current_shape_idx) else { return };
+        if shape.vertices.is_empty() {
+            return;
+        }
+        let mut bounds = AABBox::new(crate::geometry::Vec2::ZERO, crate::geometry::Vec2::ZERO);
+        let points: Vec<_> = shape.vertices.iter()
+            .map(|v| crate::geometry::Vec2::new(v.x, v.y))
+            .collect();
+        bounds.insert_poly(&points);
+        self.frame_bounds(&bounds);
+    }
+    
+    // Frame the selected vertex or port(s), padded by a couple of grid cells
+    pub fn zoom_to_selection(&mut self) {
+        let Some(shape) = self.shapes.get(self.current_shape_idx) else { return };
+        let mut points = Vec::new();
+        if let Some(v) = self.selection.vertex.and_then(|idx| shape.vertices.get(idx)) {
+            points.push(v.clone());
+        }
+        points.extend(self.selection.port_indices(shape.ports.len()).into_iter().filter_map(|idx| shape.port_position(idx)));
+        if points.is_empty() {
+            return;
+        }
+        
+        let mut bounds = AABBox::new(crate::geometry::Vec2::ZERO, crate::geometry::Vec2::ZERO);
+        for v in &points {
+            bounds.insert_circle(crate::geometry::Vec2::new(v.x, v.y), self.grid_size * 2.0);
+        }
+        self.frame_bounds(&bounds);
+    }
+    
+    // Validate what an export would write and go ahead only if nothing is wrong.
+    // Otherwise the problems are shown first: errors stop the export, warnings
+    // need confirming.
+    pub fn request_export(&mut self, kind: ExportKind) {
+        if self.export_job.is_some() {
+            self.toasts.warning(crate::translations::t("export_busy"));
+            return;
+        }
+        let shapes_file = match kind {
+            ExportKind::Selection => {
+                let Some(dialog) = &self.export_dialog else { return };
+                self.export_file(self.shapes.iter().filter(|shape| dialog.include.contains(&shape.id)).map(|shape| &**shape))
+            }
+            _ => self.export_file(self.shapes.iter().map(|shape| &**shape)),
+        };
+        
+        let mut problems = Self::validate(&shapes_file, &self.scale_waivers);
+        problems.extend(crate::validation::validate_payloads(&shapes_file, &self.payloads()));
+        if problems.is_empty() {
+            self.run_export(kind);
+        } else {
+            self.export_check = Some(ExportCheck { kind, problems });
+        }
+    }
+    
+    // Export despite the warnings shown in the check; never with errors
+    pub fn confirm_export_check(&mut self) {
+        let Some(check) = self.export_check.take() else { return };
+        if check.problems.iter().all(|problem| problem.severity == Severity::Warning) {
+            self.run_export(check.kind);
+        }
+    }
+    
+    fn run_export(&mut self, kind: ExportKind) {
+        match kind {
+            ExportKind::All => {
+                // With the preview on, the file is written from the preview window
+                #[cfg(not(target_arch = "wasm32"))]
+                if self.preview_export {
+                    self.preview_export_shapes();
+                    return;
+                }
+                self.export_shapes();
+            }
+            ExportKind::ShapesLua => {
+                let shapes_file = self.export_file(self.shapes.iter().map(|shape| &**shape));
+                let message = format!("{} shapes.lua", crate::translations::t("shapes_exported"));
+                self.export_shapes_to("shapes.lua".to_string(), shapes_file, message, None);
+            }
+            ExportKind::Selection => self.export_dialog_shapes(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ExportKind::Mod => self.export_to_mod(),
+        }
+    }
+    
+    // Экспорт всех форм в файл shapes.lua
+    pub fn export_shapes(&mut self) {
+        let shapes_file = self.export_file(self.shapes.iter().map(|shape| &**shape));
+        let message = format!("{} {}", crate::translations::t("shapes_exported"), self.export_path);
+        self.export_shapes_to(self.export_path.clone(), shapes_file, message, None);
+    }
+    
+    // Write the shapes to `path` (a download with that name on WASM) in the background,
+    // replacing the file or adding to its end; `message` is shown once it's written
+    fn export_shapes_to(&mut self, path: String, shapes_file: crate::ast::ShapesFile, message: String, retry: Option<ExportRetry>) {
+        let append = self.export_append;
+        let waivers = self.scale_waivers.clone();
+        let job_path = path.clone();
+        self.export_job = Some(ExportJob::spawn(path, retry, move || {
+            let lua_content = Self::export_content(&job_path, &shapes_file, append, &waivers)?;
+            Self::write_export(&job_path, &lua_content)?;
+            Ok(ExportDone::Written(message))
+        }));
+    }
+    
+    // Work out in the background the diff of what `export_shapes_to` would write
+    // against the file as it is now, for the preview window
+    #[cfg(not(target_arch = "wasm32"))]
+    fn preview_export_to(&mut self, path: String, shapes_file: crate::ast::ShapesFile, retry: Option<ExportRetry>) {
+        let append = self.export_append;
+        let waivers = self.scale_waivers.clone();
+        let job_path = path.clone();
+        self.export_job = Some(ExportJob::spawn(path, retry, move || {
+            let content = Self::export_content(&job_path, &shapes_file, append, &waivers)?;
+            let new_file = !Path::new(&job_path).exists();
+            let current = if new_file { String::new() } else { fs::read_to_string(&job_path)? };
+            
+            Ok(ExportDone::Preview(ExportPreview {
+                diff: crate::diff::unified_diff(&current, &content, 3),
+                path: job_path,
+                content,
+                new_file,
+            }))
+        }));
+    }
+    
+    // Open the preview of exporting all shapes to the export file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn preview_export_shapes(&mut self) {
+        let shapes_file = self.export_file(self.shapes.iter().map(|shape| &**shape));
+        self.preview_export_to(self.export_path.clone(), shapes_file, None);
+    }
+    
+    // Write the previewed export as it was shown
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn confirm_export_preview(&mut self) {
+        let Some(preview) = self.export_preview.take() else { return };
+        let message = format!("{} {}", crate::translations::t("shapes_exported"), preview.path);
+        self.export_job = Some(ExportJob::spawn(preview.path.clone(), None, move || {
+            Self::write_export(&preview.path, &preview.content)?;
+            Ok(ExportDone::Written(message))
+        }));
+    }
+    
+    // Finish the background export once it's done
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some(job) = &self.export_job else { return };
+        let Some(result) = job.poll() else {
+            // Keep checking while the worker runs
+            ctx.request_repaint();
+            return;
+        };
+        let Some(job) = self.export_job.take() else { return };
+        
+        match result {
+            Ok(ExportDone::Written(message)) => {
+                self.export_count += 1;
+                self.toasts.success(message);
+            }
+            Ok(ExportDone::WrittenToMod(root)) => {
+                self.export_count += 1;
+                self.toasts.success(format!("{} {}", crate::translations::t("exported_to_mod"), root));
+                self.mod_root = root;
+                self.mark_settings_dirty();
+            }
+            Ok(ExportDone::Preview(preview)) => self.export_preview = Some(preview),
+            Err(e) => {
+                let label = match job.retry {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(ExportRetry::Mod(_)) => "Mod folder",
+                    _ => "File",
+                };
+                let details = format!("{}: {}\n\n{:?}", label, job.path, e);
+                self.show_error_with_details(&crate::translations::t("error_export"), &e.to_string(), &details);
+                // Open the dialog again so the path can be fixed
+                match job.retry {
+                    Some(ExportRetry::Selection(dialog)) => self.export_dialog = Some(dialog),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    Some(ExportRetry::Mod(dialog)) => self.mod_export_dialog = Some(dialog),
+                    None => {}
+                }
+            }
+        }
+    }
+    
+    // The shapes as an export writes them
+    fn export_file<'a>(&self, shapes: impl IntoIterator<Item = &'a AppShape>) -> crate::ast::ShapesFile {
+        crate::ast::ShapesFile {
+            shapes: shapes.into_iter().map(|shape| self.export_ast_shape(shape)).collect(),
+        }
+    }
+    
+    // A shape as the export writes it
+    fn export_ast_shape(&self, app_shape: &AppShape) -> crate::ast::Shape {
+        if self.sort_ports_on_export {
+            let mut sorted = app_shape.clone();
+            sorted.sort_ports();
+            Self::convert_to_ast_shape(&sorted)
+        } else {
+            Self::convert_to_ast_shape(app_shape)
+        }
+    }
+    
+    // Reserialize the current shape for the Lua panel if it changed
+    fn refresh_lua_preview(&mut self) {
+        if !self.show_lua_preview {
+            return;
+        }
+        let Some(shape) = self.shapes.get(self.current_shape_idx) else {
+            self.lua_preview = None;
+            return;
+        };
+        // Pending edits are dropped only when another shape is selected
+        if self.lua_preview.as_ref().is_some_and(|preview| {
+            preview.shape_idx == self.current_shape_idx
+                && (preview.parse_at.is_some()
+                    || (preview.shape == *shape && preview.sort_ports == self.sort_ports_on_export))
+        }) {
+            return;
+        }
+        
+        let shapes_file = crate::ast::ShapesFile { shapes: vec![self.export_ast_shape(shape)] };
+        self.lua_preview = Some(LuaPreview {
+            shape_idx: self.current_shape_idx,
+            shape: shape.clone(),
+            sort_ports: self.sort_ports_on_export,
+            text: serialize_shapes_file(&shapes_file),
+            parse_at: None,
+            error: None,
+        });
+    }
+    
+    // The Lua text was edited; parse it once typing pauses
+    pub fn lua_text_changed(&mut self, now: f64) {
+        if let Some(preview) = &mut self.lua_preview {
+            preview.parse_at = Some(now + LUA_PARSE_DELAY);
         }
     }
     
-    // Handle zoom at specific position
-    pub fn zoom_at(&mut self, screen_pos: Pos2, rect: Rect, delta: f32) {
-        let old_zoom = self.zoom;
+    // Parse the edited Lua text into the current shape when it is due. The text is
+    // left as typed; errors are kept to show next to it.
+    fn apply_lua_edit(&mut self, ctx: &egui::Context) {
+        let now = ctx.input().time;
+        let Some(preview) = &mut self.lua_preview else { return };
+        match preview.parse_at {
+            Some(parse_at) if now >= parse_at => preview.parse_at = None,
+            Some(_) => {
+                // Keep frames coming so the parse isn't held back until the next input
+                ctx.request_repaint();
+                return;
+            }
+            None => return,
+        }
         
-        // Adjust zoom
-        self.zoom = (self.zoom * (1.0 + delta * 0.1)).clamp(0.1, 10.0);
+        let parsed = crate::parser::parse_shapes_content(&preview.text).and_then(|shapes_file| {
+            match <[crate::ast::Shape; 1]>::try_from(shapes_file.shapes) {
+                Ok([shape]) => Ok(shape),
+                Err(shapes) => Err(format!("Expected exactly one shape, found {}", shapes.len())),
+            }
+        });
+        let ast_shape = match parsed {
+            Ok(ast_shape) => ast_shape,
+            Err(e) => {
+                preview.error = Some(e);
+                return;
+            }
+        };
         
-        // Calculate world position before zoom
-        let center = rect.center();
-        let before_x = (screen_pos.x - center.x) / old_zoom;
-        let before_y = (screen_pos.y - center.y) / old_zoom;
+        let shape_idx = preview.shape_idx;
+        if self.shapes.iter().enumerate().any(|(i, shape)| i != shape_idx && shape.id == ast_shape.id) {
+            preview.error = Some(format!("{} {}", crate::translations::t("shape_id_taken"), ast_shape.id));
+            return;
+        }
+        preview.error = None;
         
-        // Calculate world position after zoom
-        let after_x = (screen_pos.x - center.x) / self.zoom;
-        let after_y = (screen_pos.y - center.y) / self.zoom;
+        let shape = Self::convert_from_ast_shape(&ast_shape);
+        let Some(current) = self.shapes.get(shape_idx) else { return };
+        if **current == shape {
+            return;
+        }
+        // Keep the selection while it still points at something
+        self.selection.clamp_to(&shape);
         
-        // Adjust panning to keep the world position constant under cursor
-        self.pan.x += after_x - before_x;
-        self.pan.y += after_y - before_y;
+        self.save_state();
+        self.shapes[shape_idx] = shape.into();
+        
+        // The text stays as typed instead of being reformatted under the cursor
+        if let Some(preview) = &mut self.lua_preview {
+            preview.shape = self.shapes[shape_idx].clone();
+        }
     }
     
-    // Экспорт всех форм в файл shapes.lua
-    pub fn export_shapes(&self) -> Result<(), std::io::Error> {
-        // Convert shapes to AST shapes for export
-        let mut ast_shapes = Vec::new();
-        for app_shape in &self.shapes {
-            ast_shapes.push(self.convert_to_ast_shape(app_shape));
+    // Lua text an export of these shapes to `path` would write. Runs on the export
+    // thread, so it takes what it needs from the editor as arguments.
+    fn export_content(path: &str, shapes_file: &crate::ast::ShapesFile, append: bool, scale_waivers: &[usize]) -> Result<String, std::io::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if append && Path::new(path).exists() {
+            let (content, whole_file) = Self::append_to_shapes_file(path, shapes_file)?;
+            return Ok(crate::validation::summary_comment(&Self::validate(&whole_file, scale_waivers)) + content.as_str());
         }
-        
-        // Create shapes file
-        let shapes_file = crate::ast::ShapesFile { shapes: ast_shapes };
+        #[cfg(target_arch = "wasm32")]
+        let _ = (path, append);
         
         // Serialize to Lua format
-        let lua_content = serialize_shapes_file(&shapes_file);
+        let lua_content = serialize_shapes_file(shapes_file);
         
+        // The file opens with what the validation checks made of it
+        Ok(crate::validation::summary_comment(&Self::validate(shapes_file, scale_waivers)) + lua_content.as_str())
+    }
+    
+    fn write_export(path: &str, lua_content: &str) -> Result<(), std::io::Error> {
         // Write to file
         #[cfg(not(target_arch = "wasm32"))]
         {
-            match fs::write(&self.export_path, lua_content) {
+            crate::backup::backup_before_write(path)?;
+            match fs::write(path, lua_content) {
                 Ok(_) => Ok(()),
                 Err(e) => {
                     // This error will be displayed in the UI via the error dialog
@@ -302,14 +2083,138 @@ impl ShapeEditor {
         
         #[cfg(target_arch = "wasm32")]
         {
-            self.download_file(&lua_content);
+            Self::download_file(path, lua_content);
             Ok(())
         }
     }
     
+    // Contents of an existing shapes file with the exported shapes it doesn't have yet
+    // added at the end, and all the shapes the file then has. The file is left as it is
+    // apart from that and its old validation summary; a shape whose ID the file already
+    // has for something else is refused.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn append_to_shapes_file(path: &str, shapes_file: &crate::ast::ShapesFile) -> Result<(String, crate::ast::ShapesFile), std::io::Error> {
+        let source = crate::encoding::decode(&fs::read(path)?);
+        let existing = crate::validation::strip_summary_comment(&source);
+        let parsed = crate::parser::parse_shapes_content(existing)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Can't append to a file that doesn't parse: {}", e)))?;
+        
+        // Compared as written out, so that float noise from the file's text doesn't count
+        let written = |shape: &crate::ast::Shape| serialize_shapes_file(&crate::ast::ShapesFile { shapes: vec![shape.clone()] });
+        let mut new_shapes = Vec::new();
+        let mut taken = Vec::new();
+        for shape in &shapes_file.shapes {
+            match parsed.shape_by_id(shape.id) {
+                None => new_shapes.push(shape.clone()),
+                Some(old) if written(old) != written(shape) => taken.push(shape.id.to_string()),
+                Some(_) => {}
+            }
+        }
+        if !taken.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The file already has different shapes with IDs {}", taken.join(", ")),
+            ));
+        }
+        if new_shapes.is_empty() {
+            return Ok((existing.to_string(), parsed));
+        }
+        let new_shapes = crate::ast::ShapesFile { shapes: new_shapes };
+        let lua_content = serialize_shapes_file(&new_shapes);
+        
+        let (close, comma_at) = crate::blocks::table_end(existing)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No closing brace found"))?;
+        // The serialized entries without the file's own outer braces
+        let entries = lua_content.trim_end().strip_prefix('{').and_then(|s| s.strip_suffix('}')).unwrap_or("").trim_start_matches('\n');
+        
+        let mut content = String::with_capacity(existing.len() + entries.len() + 2);
+        match comma_at {
+            Some(comma_at) => {
+                content.push_str(&existing[..comma_at]);
+                content.push(',');
+                content.push_str(&existing[comma_at..close]);
+            }
+            None => content.push_str(&existing[..close]),
+        }
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(entries);
+        content.push_str(&existing[close..]);
+        let mut whole_file = parsed;
+        whole_file.shapes.extend(new_shapes.shapes);
+        Ok((content, whole_file))
+    }
+    
+    // Write shapes.lua (and the loaded blocks.lua if asked to) into a mod folder,
+    // generating a new mod there first if the folder doesn't exist
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_to_mod(&mut self) {
+        let Some(dialog) = self.mod_export_dialog.take() else { return };
+        let root = dialog.root.clone();
+        let shapes_file = self.export_file(self.shapes.iter().map(|shape| &**shape));
+        let blocks = self.blocks_file.as_ref()
+            .filter(|_| dialog.include_blocks)
+            .map(|blocks_file| blocks_file.source.clone());
+        let waivers = self.scale_waivers.clone();
+        
+        self.export_job = Some(ExportJob::spawn(root.clone(), Some(ExportRetry::Mod(dialog)), move || {
+            let root_path = Path::new(&root);
+            let generated = !root_path.exists();
+            if generated {
+                let name = root_path.file_name().map_or(root.clone(), |name| name.to_string_lossy().to_string());
+                crate::project_generator::generate_project_at(root_path, &name)?;
+            }
+            
+            let shapes_path = root_path.join("shapes.lua").to_string_lossy().to_string();
+            let lua_content = Self::export_content(&shapes_path, &shapes_file, false, &waivers)?;
+            Self::write_export(&shapes_path, &lua_content)?;
+            
+            let blocks_path = root_path.join("blocks.lua").to_string_lossy().to_string();
+            if let Some(source) = &blocks {
+                crate::backup::backup_before_write(&blocks_path)?;
+                fs::write(&blocks_path, source)?;
+            }
+            
+            // The generator's templates were just written, there is nothing to keep
+            if generated {
+                fs::remove_file(crate::backup::backup_path(&shapes_path, 0)).ok();
+                fs::remove_file(crate::backup::backup_path(&blocks_path, 0)).ok();
+            }
+            Ok(ExportDone::WrittenToMod(root))
+        }));
+    }
+    
+    // Put back the export file as it was before the last export
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore_export_backup(&mut self) {
+        match crate::backup::restore(&self.export_path) {
+            Ok(()) => self.toasts.success(format!("{} {}", crate::translations::t("backup_restored"), self.export_path)),
+            Err(e) => self.show_error_with_details("Backup Error", &e.to_string(), &format!("File: {}\n\n{:?}", self.export_path, e)),
+        }
+    }
+    
+    // Write the shapes ticked in the export dialog to the dialog's file
+    pub fn export_dialog_shapes(&mut self) {
+        let Some(dialog) = self.export_dialog.take() else { return };
+        let shapes_file = self.export_file(self.shapes.iter()
+            .filter(|shape| dialog.include.contains(&shape.id))
+            .map(|shape| &**shape));
+        let path = dialog.path.clone();
+        
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.preview_export {
+            self.preview_export_to(path, shapes_file, Some(ExportRetry::Selection(dialog)));
+            return;
+        }
+        
+        let message = format!("{} {} ({})", crate::translations::t("shapes_exported"), path, dialog.include.len());
+        self.export_shapes_to(path, shapes_file, message, Some(ExportRetry::Selection(dialog)));
+    }
+    
     // Download file in browser (WebAssembly target)
     #[cfg(target_arch = "wasm32")]
-    fn download_file(&self, content: &str) {
+    fn download_file(file_name: &str, content: &str) {
         use wasm_bindgen::JsCast;
         use js_sys::Reflect;
         use wasm_bindgen::JsValue;
@@ -347,7 +2252,7 @@ impl ShapeEditor {
         
         // Set up the anchor to trigger download
         a_element.set_attribute("href", &url).unwrap();
-        a_element.set_attribute("download", &self.export_path).unwrap();
+        a_element.set_attribute("download", file_name).unwrap();
         a_element.style().set_property("display", "none").unwrap();
         
         // Add to document, click, and remove
@@ -369,56 +2274,219 @@ impl ShapeEditor {
     }
     
     // Import shapes from Lua file
-    pub fn import_shapes(&mut self) -> Result<(), io::Error> {
-        self.save_state();
-        
+    // Start importing `import_path` in the background; shapes appear as they are converted
+    pub fn import_shapes(&mut self) {
         #[cfg(not(target_arch = "wasm32"))]
         {
-            let content = match fs::read_to_string(&self.import_path) {
-                Ok(content) => content,
-                Err(e) => {
-                    self.show_error("Import Error", &format!("Failed to read file: {}", e));
-                    return Err(e);
-                }
-            };
-            
-            match self.parse_lua_shapes(&content) {
-                Ok(shapes) => {
-                    if !shapes.is_empty() {
-                        self.shapes = shapes;
-                        self.current_shape_idx = 0;
+            self.import_job = Some(ImportJob::spawn(self.import_path.clone()));
+        }
+        
+        // For WebAssembly, file reading is handled through the file input element
+        // The actual reading happens in handle_file_content
+    }
+    
+    // Apply whatever the running import produced since the last frame
+    fn poll_import(&mut self, ctx: &egui::Context) {
+        let Some(job) = &mut self.import_job else { return };
+        let events = job.poll();
+        ctx.request_repaint();
+        
+        for event in events {
+            match event {
+                ImportEvent::Finished => {
+                    let Some(job) = self.import_job.take() else { continue };
+                    if !job.path.is_empty() {
+                        self.remember_recent_file(&job.path);
                     }
-                    Ok(())
-                },
-                Err(e) => {
-                    self.show_error("Import Error", &format!("Failed to parse shapes: {}", e));
-                    Err(io::Error::new(io::ErrorKind::InvalidData, e))
+                    if !job.skipped.is_empty() {
+                        for skipped in &job.skipped {
+                            log::warn!("{}: {}", job.path, skipped);
+                        }
+                        self.toasts.warning(format!("{} {}", job.skipped.len(), crate::translations::t("import_shapes_skipped")));
+                    }
+                    if job.shapes.is_empty() {
+                        self.toasts.warning(format!("{} {}", crate::translations::t("no_shapes_imported"), job.path));
+                    } else {
+                        // Everything is ticked; the dialog decides what gets in
+                        self.import_dialog = Some(ImportDialog {
+                            include: (0..job.shapes.len()).collect(),
+                            conflicts: BTreeMap::new(),
+                            shapes: job.shapes,
+                            path: job.path,
+                            replace_session: false,
+                        });
+                    }
+                }
+                ImportEvent::Failed(message, details) => {
+                    let path = self.import_job.take().map(|job| job.path).unwrap_or_default();
+                    self.show_error_with_details("Import Error", &message, &format!("File: {}\n\n{}", path, details));
                 }
             }
         }
+    }
+    
+    // Put a path at the front of the recent files list
+    pub fn remember_recent_file(&mut self, path: &str) {
+        self.mark_settings_dirty();
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+    
+    // Note that a setting may have changed outside of the editor's own input handling
+    pub fn mark_settings_dirty(&mut self) {
+        self.settings_dirty = true;
+    }
+    
+    // Save the settings SAVE_DELAY seconds after they first differ from the saved ones
+    fn persist_settings(&mut self, ctx: &egui::Context) {
+        if !self.persist {
+            return;
+        }
+        // Settings change on a click, key or scroll, or at the end of a drag, and in
+        // the few places that mark them dirty. Other frames, pointer moves included,
+        // don't build them just to compare.
+        let input = ctx.input();
+        let now = input.time;
+        if input.pointer.any_released() || input.events.iter().any(|event| !matches!(event, egui::Event::PointerMoved(_))) {
+            self.settings_dirty = true;
+        }
+        drop(input);
+        
+        if std::mem::take(&mut self.settings_dirty) {
+            if self.saved_settings.as_ref() == Some(&Settings::from_editor(self)) {
+                self.settings_save_at = None;
+            } else {
+                self.settings_save_at.get_or_insert(now + SAVE_DELAY);
+            }
+        }
         
-        #[cfg(target_arch = "wasm32")]
-        {
-            // For WebAssembly, file reading is handled through the file input element
-            // The actual reading happens in handle_file_content
-            // Here we just return success
-            Ok(())
+        let Some(save_at) = self.settings_save_at else { return };
+        if now >= save_at {
+            self.write_settings(Settings::from_editor(self));
+        } else {
+            // Keep frames coming so the save isn't held back until the next input
+            ctx.request_repaint();
+        }
+    }
+    
+    // Write pending settings right away, e.g. when the window is closing
+    pub fn flush_settings(&mut self) {
+        if !self.persist {
+            return;
+        }
+        let settings = Settings::from_editor(self);
+        if self.saved_settings.as_ref() != Some(&settings) {
+            self.write_settings(settings);
+        }
+    }
+    
+    fn write_settings(&mut self, settings: Settings) {
+        if let Err(e) = settings.save() {
+            log::error!("Failed to save settings: {}", e);
+            self.toasts.error(format!("{}: {}", crate::translations::t("settings_save_failed"), e));
+        }
+        // Also on failure, so a broken config dir doesn't retry every second
+        self.saved_settings = Some(settings);
+        self.settings_save_at = None;
+    }
+    
+    // Replace the shapes with the ones dumped by the last crash; undo brings back the current ones
+    pub fn restore_recovered_shapes(&mut self) {
+        let Some(content) = self.recovered_shapes.take() else { return };
+        
+        match crate::parser::parse_shapes_content(&content) {
+            Ok(shapes_file) => {
+                self.save_state();
+                self.shapes = shapes_file.shapes.iter()
+                    .map(|shape| Self::convert_from_ast_shape(shape).into())
+                    .collect();
+                self.current_shape_idx = 0;
+                self.toasts.success(crate::translations::t("recovery_restored"));
+                crate::recovery::discard();
+            }
+            Err(e) => {
+                // Keep the dump so it can still be recovered by hand
+                self.show_error_with_details("Recovery Error", "Failed to parse the recovered shapes", &e);
+            }
+        }
+    }
+    
+    pub fn discard_recovered_shapes(&mut self) {
+        self.recovered_shapes = None;
+        crate::recovery::discard();
+    }
+    
+    // Stop the running import; nothing reaches the session before the import dialog
+    pub fn cancel_import(&mut self) {
+        self.import_job = None;
+    }
+    
+    // Bring the shapes ticked in the import dialog into the session. Shapes whose
+    // ID is taken are resolved as chosen in the dialog, unless the whole session
+    // is replaced.
+    pub fn apply_import_dialog(&mut self) {
+        let Some(dialog) = self.import_dialog.take() else { return };
+        let chosen: Vec<(usize, AppShape)> = dialog.shapes.into_iter()
+            .enumerate()
+            .filter(|(i, _)| dialog.include.contains(i))
+            .collect();
+        if chosen.is_empty() {
+            return;
+        }
+        
+        self.save_state();
+        let mut count = 0;
+        if dialog.replace_session {
+            count = chosen.len();
+            self.shapes = chosen.into_iter().map(|(_, shape)| shape.into()).collect();
+            self.current_shape_idx = 0;
+        } else {
+            // New IDs avoid every ID in the session, in blocks.lua and in the file
+            let mut used = self.used_shape_ids();
+            used.extend(chosen.iter().map(|(_, shape)| shape.id));
+            let base = self.id_base();
+            let mut first_idx = None;
+            
+            for (i, mut shape) in chosen {
+                let existing = self.shapes.iter().position(|existing| existing.id == shape.id);
+                let idx = match (existing, dialog.conflicts.get(&i).copied().unwrap_or_default()) {
+                    (Some(_), ImportConflict::KeepExisting) => continue,
+                    (Some(idx), ImportConflict::Replace) => {
+                        self.shapes[idx] = shape.into();
+                        idx
+                    }
+                    (Some(_), ImportConflict::NewId) | (None, _) => {
+                        if existing.is_some() {
+                            shape.id = Self::allocate_shape_id(&used, base);
+                            used.insert(shape.id);
+                        }
+                        self.shapes.push(shape.into());
+                        self.shapes.len() - 1
+                    }
+                };
+                count += 1;
+                first_idx.get_or_insert(idx);
+            }
+            self.current_shape_idx = first_idx.unwrap_or(self.current_shape_idx);
         }
+        
+        self.toasts.success(format!("{} {} ({})", crate::translations::t("shapes_imported"), dialog.path, count));
     }
     
     // Convert from data_structures::Shape to ast::Shape
-    pub fn convert_to_ast_shape(&self, app_shape: &AppShape) -> crate::ast::Shape {
-        let mut scales = Vec::new();
-        let scale = crate::ast::Scale {
-            verts: app_shape.vertices.iter().map(|v| crate::ast::Vertex { x: v.x, y: v.y }).collect(),
-            ports: app_shape.ports.iter().map(|p| crate::ast::Port { 
+    pub fn convert_to_ast_shape(app_shape: &AppShape) -> crate::ast::Shape {
+        let to_ast_scale = |vertices: &[Vertex], ports: &[Port]| crate::ast::Scale {
+            verts: vertices.iter().map(|v| crate::ast::Vertex { x: v.x, y: v.y }).collect(),
+            ports: ports.iter().map(|p| crate::ast::Port { 
                 edge: p.edge, 
                 position: p.position, 
                 port_type: Some(crate::ast::PortType::from_str(&p.port_type.to_string()))
             }).collect(),
         };
         
-        scales.push(scale);
+        let mut scales = vec![to_ast_scale(&app_shape.vertices, &app_shape.ports)];
+        scales.extend(app_shape.scales.iter().map(|scale| to_ast_scale(&scale.vertices, &scale.ports)));
         
         crate::ast::Shape {
             id: app_shape.id,
@@ -441,48 +2509,43 @@ impl ShapeEditor {
     }
     
     // Convert from ast::Shape to data_structures::Shape
-    pub fn convert_from_ast_shape(&self, ast_shape: &crate::ast::Shape) -> AppShape {
+    pub fn convert_from_ast_shape(ast_shape: &crate::ast::Shape) -> AppShape {
         let mut app_shape = AppShape::new(ast_shape.id);
         
         if let Some(name) = &ast_shape.name {
             app_shape.name = name.clone();
         }
         
-        // Use the first scale for vertices and ports
-        if !ast_shape.scales.is_empty() {
-            let scale = &ast_shape.scales[0];
-            
-            // Convert vertices
-            for vert in &scale.verts {
-                app_shape.vertices.push(Vertex {
-                    x: vert.x,
-                    y: vert.y,
-                });
-            }
-            
-            // Convert ports
-            for port in &scale.ports {
-                app_shape.ports.push(Port {
-                    edge: port.edge,
-                    position: port.position,
-                    port_type: if let Some(pt) = &port.port_type {
-                        match pt {
-                            crate::ast::PortType::Default => PortType::Default,
-                            crate::ast::PortType::ThrusterIn => PortType::ThrusterIn,
-                            crate::ast::PortType::ThrusterOut => PortType::ThrusterOut,
-                            crate::ast::PortType::Missile => PortType::Missile,
-                            crate::ast::PortType::Launcher => PortType::Launcher,
-                            crate::ast::PortType::WeaponIn => PortType::WeaponIn,
-                            crate::ast::PortType::WeaponOut => PortType::WeaponOut,
-                            crate::ast::PortType::Root => PortType::Root,
-                            crate::ast::PortType::None => PortType::None,
-                        }
-                    } else {
-                        PortType::Default
-                    },
-                });
-            }
+        let from_ast_scale = |scale: &crate::ast::Scale| ShapeScale {
+            vertices: scale.verts.iter().map(|vert| Vertex { x: vert.x, y: vert.y }).collect(),
+            ports: scale.ports.iter().map(|port| Port {
+                edge: port.edge,
+                position: port.position,
+                port_type: if let Some(pt) = &port.port_type {
+                    match pt {
+                        crate::ast::PortType::Default => PortType::Default,
+                        crate::ast::PortType::ThrusterIn => PortType::ThrusterIn,
+                        crate::ast::PortType::ThrusterOut => PortType::ThrusterOut,
+                        crate::ast::PortType::Missile => PortType::Missile,
+                        crate::ast::PortType::Launcher => PortType::Launcher,
+                        crate::ast::PortType::WeaponIn => PortType::WeaponIn,
+                        crate::ast::PortType::WeaponOut => PortType::WeaponOut,
+                        crate::ast::PortType::Root => PortType::Root,
+                        crate::ast::PortType::None => PortType::None,
+                    }
+                } else {
+                    PortType::Default
+                },
+            }).collect(),
+        };
+        
+        // The first scale is the one edited on the canvas; the others are kept alongside
+        let mut scales = ast_shape.scales.iter().map(from_ast_scale);
+        if let Some(first) = scales.next() {
+            app_shape.vertices = first.vertices;
+            app_shape.ports = first.ports;
         }
+        app_shape.scales = scales.collect();
         
         // Set launcher_radial property
         if let Some(launcher_radial) = ast_shape.launcher_radial {
@@ -492,34 +2555,6 @@ impl ShapeEditor {
         app_shape
     }
     
-    // Parse shapes from Lua string using the ast module
-    fn parse_lua_shapes(&self, content: &str) -> Result<Vec<AppShape>, io::Error> {
-        match parse_shapes_content(content) {
-            Ok(shapes_file) => {
-                let mut app_shapes = Vec::new();
-                println!("Successfully parsed {} shapes", shapes_file.shapes.len());
-                
-                for ast_shape in &shapes_file.shapes {
-                    let app_shape = self.convert_from_ast_shape(ast_shape);
-                    println!("Converted shape ID: {}, Name: {}, Vertices: {}, Ports: {}, launcher_radial: {}", 
-                             app_shape.id, 
-                             app_shape.name, 
-                             app_shape.vertices.len(), 
-                             app_shape.ports.len(),
-                             app_shape.launcher_radial);
-                    app_shapes.push(app_shape);
-                }
-                
-                Ok(app_shapes)
-            }
-            Err(e) => {
-                println!("Failed to parse shapes: {}", e);
-                // Convert parse error to IO error with the message
-                Err(io::Error::new(io::ErrorKind::InvalidData, e))
-            }
-        }
-    }
-    
     // Original legacy parser
     fn parse_lua_shapes_legacy(&self, content: &str) -> Result<Vec<AppShape>, io::Error> {
         let mut shapes = Vec::new();
@@ -561,9 +2596,8 @@ impl ShapeEditor {
                             name,
                             vertices: Vec::new(),
                             ports: Vec::new(),
-                            selected_vertex: None,
-                            selected_port: None,
                             launcher_radial: false,
+                            scales: Vec::new(),
                         });
                     }
                 }
@@ -675,16 +2709,57 @@ impl ShapeEditor {
     
     #[cfg(not(target_arch = "wasm32"))]
     pub fn select_export_file(&mut self) -> bool {
-        if let Some(path) = FileDialog::new()
+        match Self::pick_save_path() {
+            Some(path) => {
+                self.export_path = path;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    // Ask for a folder, e.g. a mod's root
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_folder() -> Option<String> {
+        FileDialog::new()
+            .pick_folder()
+            .and_then(|path| path.to_str().map(str::to_string))
+    }
+    
+    // Write the metrics of all shapes to a file the user picks; downloaded in the browser
+    pub fn export_report(&mut self, format: ReportFormat) {
+        let content = crate::report::report(self.shapes.iter().map(|shape| &**shape), format);
+        let file_name = format!("shapes_report.{}", format.extension());
+        
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let Some(path) = FileDialog::new()
+                .add_filter(&format.extension().to_uppercase(), &[format.extension()])
+                .set_file_name(&file_name)
+                .save_file() else { return };
+            if let Err(e) = fs::write(&path, content) {
+                let details = format!("File: {}\n\n{:?}", path.display(), e);
+                self.show_error_with_details(&crate::translations::t("error_export"), &e.to_string(), &details);
+                return;
+            }
+            self.toasts.success(format!("{} {}", crate::translations::t("report_exported"), path.display()));
+        }
+        
+        #[cfg(target_arch = "wasm32")]
+        {
+            Self::download_file(&file_name, &content);
+            self.toasts.success(format!("{} {}", crate::translations::t("report_exported"), file_name));
+        }
+    }
+    
+    // Ask for a .lua file to write to
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_save_path() -> Option<String> {
+        FileDialog::new()
             .add_filter("Lua files", &["lua"])
             .set_directory("/")
-            .save_file() {
-                if let Some(path_str) = path.to_str() {
-                    self.export_path = path_str.to_string();
-                    return true;
-                }
-            }
-        false
+            .save_file()
+            .and_then(|path| path.to_str().map(str::to_string))
     }
     
     #[cfg(target_arch = "wasm32")]
@@ -750,53 +2825,85 @@ impl ShapeEditor {
     // Handle file content from Web input
     #[cfg(target_arch = "wasm32")]
     pub fn handle_file_content(&mut self, content: String, filename: String) {
-        self.import_path = filename;
-        
-        match self.parse_lua_shapes(&content) {
-            Ok(shapes) => {
-                if !shapes.is_empty() {
-                    self.save_state();
-                    self.shapes = shapes;
-                    self.current_shape_idx = 0;
-                    self.status_message = Some(format!("{} {}", crate::translations::t("shapes_imported"), self.import_path));
-                    self.status_time = 3.0;
-                }
-            },
-            Err(e) => {
-                self.show_error("Import Error", &format!("Failed to parse shapes: {}", e));
+        self.import_path = filename.clone();
+        self.mark_settings_dirty();
+        let content = crate::encoding::normalize(&content);
+        
+        match ImportJob::from_content(&content, filename) {
+            Ok(job) => self.import_job = Some(job),
+            Err((message, details)) => {
+                let details = format!("File: {}\n\n{}", self.import_path, details);
+                self.show_error_with_details("Import Error", &message, &details);
             }
         }
     }
 }
 
-// Implementing eframe::App trait
-impl eframe::App for ShapeEditor {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Apply dark theme
-        configure_visuals(ctx);
+impl ShapeEditor {
+    // One frame of the editor, with its panels laid out inside `ui`. Dialogs and
+    // toasts float over the whole window.
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let ctx = &ui.ctx().clone();
+        
+        self.sync_selection();
         
         // Process keyboard shortcuts
         self.process_keyboard_shortcuts(ctx);
+        let tab_cycled = self.process_selection_cycling(ctx);
+        
+        self.apply_lua_edit(ctx);
+        self.sync_variants();
+        self.refresh_problems();
+        self.refresh_lua_preview();
         
         // Render UI components based on the active tab
-        render_nav_bar(ctx, self);
+        render_nav_bar(ui, self);
         
         if self.active_tab == 0 {
             // Shapes tab
-            render_top_panel(ctx, self);
-            render_side_panel(ctx, self);
-            render_central_panel(ctx, self);
+            render_top_panel(ui, self);
+            render_side_panel(ui, self);
+            render_status_bar(ui, self);
+            render_log_console(ui, self);
+            render_history_timeline(ui, self);
+            render_problems_panel(ui, self);
+            render_budget_panel(ui, self);
+            render_script_console(ui, self);
+            render_lua_preview(ui, self);
+            render_central_panel(ui, self);
+            render_onboarding(ctx, self);
+            render_import_progress(ctx, self);
         } else if self.active_tab == 1 {
             // Settings tab
-            render_settings_panel(ctx, self);
+            render_log_console(ui, self);
+            render_settings_panel(ui, self);
+        } else if self.active_tab == 2 {
+            render_arrange_tab(ui, self);
         }
         
+        render_shortcuts_overlay(ctx, self);
+        render_toasts(ctx, self);
+        render_export_progress(ctx, self);
+        render_recovery_prompt(ctx, self);
+        render_shape_change_confirm(ctx, self);
+        render_export_dialog(ctx, self);
+        render_export_check(ctx, self);
+        #[cfg(not(target_arch = "wasm32"))]
+        render_export_preview(ctx, self);
+        render_import_dialog(ctx, self);
+        render_generator_dialog(ctx, self);
+        render_normalize_dialog(ctx, self);
+        render_batch_dialog(ctx, self);
+        #[cfg(not(target_arch = "wasm32"))]
+        render_mod_export_dialog(ctx, self);
+        
         // Show error dialog if needed
         if self.show_error_dialog {
             if show_error_dialog(
                 ctx, 
-                self.error_title.clone(), 
-                self.error_message.clone(), 
+                &self.error_title, 
+                &self.error_message, 
+                &self.error_details, 
                 &mut self.show_error_dialog
             ) {
                 // Dialog was closed
@@ -804,10 +2911,48 @@ impl eframe::App for ShapeEditor {
             }
         }
         
-        // Request continuous redraw while status message is showing
-        if self.status_time > 0.0 {
-            ctx.request_repaint();
+        self.update_undo_transaction(ctx);
+        self.poll_import(ctx);
+        self.poll_export(ctx);
+        self.persist_settings(ctx);
+        if self.persist {
+            crate::recovery::track(&self.shapes);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(state) = self.embed.clone() {
+            crate::embed::sync(self, &state, ctx);
+        }
+        
+        // Tab also moves widget focus in egui; drop it so the cycling doesn't
+        // leave a text field focused
+        if tab_cycled {
+            ctx.memory().stop_text_input();
+        }
+    }
+}
+
+// Implementing eframe::App trait
+impl eframe::App for ShapeEditor {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Apply dark theme
+        configure_visuals(ctx);
+        
+        // Apply the UI scale, but not mid-drag so the scale slider doesn't jump under the cursor
+        let native_scale = frame.info().native_pixels_per_point.unwrap_or(1.0);
+        let pixels_per_point = native_scale * self.ui_scale;
+        if (ctx.pixels_per_point() - pixels_per_point).abs() > 0.001 && !ctx.input().pointer.any_down() {
+            ctx.set_pixels_per_point(pixels_per_point);
         }
+        
+        // The editor fills the window
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show(ctx, |ui| self.show(ui));
+    }
+    
+    fn on_exit_event(&mut self) -> bool {
+        self.flush_settings();
+        true
     }
 }
 
@@ -815,15 +2960,116 @@ impl eframe::App for ShapeEditor {
 impl ShapeEditor {
     // Process keyboard shortcuts for undo/redo and other functions
     fn process_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
+        // Don't fire shortcuts while a new key is being assigned in the settings
+        if self.active_tab != 1 {
+            self.rebinding = None;
+        }
+        if self.rebinding.is_some() {
+            return;
+        }
+        
         // Undo/Redo shortcuts
-        if ctx.input().key_pressed(egui::Key::Z) && ctx.input().modifiers.ctrl {
-            if ctx.input().modifiers.shift {
-                self.redo();
-            } else {
-                self.undo();
-            }
-        } else if ctx.input().key_pressed(egui::Key::Y) && ctx.input().modifiers.ctrl {
+        if self.keybindings.pressed(Action::Undo, &ctx.input()) {
+            self.undo();
+        } else if self.keybindings.pressed(Action::Redo, &ctx.input()) {
             self.redo();
         }
+        
+        // The rest are plain keys, so leave them alone while a text field is focused,
+        // and on the arrange tab, which has keys of its own
+        if ctx.memory().focus().is_some() || self.active_tab == 2 {
+            return;
+        }
+        
+        // Escape closes the shortcuts overlay before it reaches the canvas
+        if self.show_shortcuts && ctx.input().key_pressed(egui::Key::Escape) {
+            self.show_shortcuts = false;
+            ctx.input_mut().consume_key(egui::Modifiers::NONE, egui::Key::Escape);
+            return;
+        }
+        
+        // WASD / arrow keys pan the canvas
+        if !ctx.input().modifiers.command {
+            self.process_keyboard_panning(ctx);
+        }
+        
+        let action = self.keybindings.pressed_action(&ctx.input(), |action| !matches!(
+            action,
+            Action::Undo | Action::Redo | Action::DeleteSelection | Action::ClearSelection
+        ));
+        match action {
+            Some(Action::ZoomToFit) => self.zoom_to_fit(),
+            Some(Action::ZoomToSelection) => self.zoom_to_selection(),
+            Some(Action::ToggleMeasureTool) => self.toggle_measure_tool(),
+            Some(Action::ResetView) => self.reset_view(),
+            Some(Action::ToggleShortcuts) => self.show_shortcuts = !self.show_shortcuts,
+            Some(Action::RecallBookmark(slot)) => self.recall_view_bookmark(slot),
+            Some(Action::SaveBookmark(slot)) => self.save_view_bookmark(slot),
+            // Set the type of the selected port(s)
+            Some(Action::SetPortType(type_idx)) => {
+                if let Some(shape) = self.shapes.get(self.current_shape_idx) {
+                    let selected = self.selection.port_indices(shape.ports.len());
+                    if !selected.is_empty() && type_idx < PortType::ALL.len() {
+                        let shape_idx = self.current_shape_idx;
+                        self.set_port_types(shape_idx, &selected, PortType::ALL[type_idx].clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    // Pans while WASD or an arrow key is held, at a constant on-screen speed
+    fn process_keyboard_panning(&mut self, ctx: &egui::Context) {
+        const PAN_SPEED: f32 = 400.0; // pixels per second
+        
+        let input = ctx.input();
+        let held = |keys: [egui::Key; 2]| keys.iter().any(|&key| input.key_down(key));
+        let mut direction = Vec2::ZERO;
+        if held([egui::Key::W, egui::Key::ArrowUp]) { direction.y += 1.0; }
+        if held([egui::Key::S, egui::Key::ArrowDown]) { direction.y -= 1.0; }
+        if held([egui::Key::A, egui::Key::ArrowLeft]) { direction.x += 1.0; }
+        if held([egui::Key::D, egui::Key::ArrowRight]) { direction.x -= 1.0; }
+        
+        if direction == Vec2::ZERO {
+            return;
+        }
+        let speed = if input.modifiers.shift { PAN_SPEED * 3.0 } else { PAN_SPEED };
+        self.pan += direction.normalized() * speed * input.unstable_dt.min(0.1) / self.zoom;
+        
+        // Keep frames coming while the key is held
+        drop(input);
+        ctx.request_repaint();
+    }
+    
+    // Tab / Shift+Tab cycles through vertices, Ctrl+Tab / Ctrl+Shift+Tab through ports.
+    // Returns true if the key press was used for cycling.
+    fn process_selection_cycling(&mut self, ctx: &egui::Context) -> bool {
+        // Leave Tab alone while a widget (e.g. a text field) has keyboard focus
+        if ctx.memory().focus().is_some() || !ctx.input().key_pressed(egui::Key::Tab) {
+            return false;
+        }
+        
+        let modifiers = ctx.input().modifiers;
+        if modifiers.ctrl {
+            self.cycle_port_selection(modifiers.shift);
+        } else {
+            self.cycle_vertex_selection(modifiers.shift);
+        }
+        true
+    }
+}
+
+// Next index when cycling through `len` items, wrapping at both ends
+fn cycle_index(current: Option<usize>, len: usize, backwards: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
     }
-} 
\ No newline at end of file
+    
+    Some(match current {
+        Some(idx) if backwards => (idx + len - 1) % len,
+        Some(idx) => (idx + 1) % len,
+        None if backwards => len - 1,
+        None => 0,
+    })
+}
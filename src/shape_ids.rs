@@ -0,0 +1,33 @@
+// IDs for new shapes. Mod shapes live in a fixed ID range, and a new ID must not
+// clash with a shape in the session or with one that the loaded blocks.lua refers
+// to. Categories keep related shapes together, e.g. armor from 5100 up.
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// IDs the game accepts for mod shapes
+pub const MIN_SHAPE_ID: usize = 100;
+pub const MAX_SHAPE_ID: usize = 10_000;
+
+/// Named starting point for the IDs of one kind of shape
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdCategory {
+    pub name: String,
+    pub base: usize,
+}
+
+pub fn default_categories() -> Vec<IdCategory> {
+    vec![
+        IdCategory { name: "Armor".to_string(), base: 5100 },
+        IdCategory { name: "Weapons".to_string(), base: 5200 },
+    ]
+}
+
+/// Lowest unused ID from `base` to the end of the range, then from the start of the
+/// range up to `base`. None when every ID in the range is taken.
+pub fn next_free_id(used: &BTreeSet<usize>, base: usize) -> Option<usize> {
+    let base = base.clamp(MIN_SHAPE_ID, MAX_SHAPE_ID);
+    (base..=MAX_SHAPE_ID)
+        .chain(MIN_SHAPE_ID..base)
+        .find(|id| !used.contains(id))
+}
@@ -0,0 +1,92 @@
+// Queue of short notifications shown stacked in the corner of the window
+
+// Toasts beyond this many are dropped, oldest first
+const MAX_TOASTS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    // Seconds a toast stays up; problems stay longer so they can be read
+    fn duration(&self) -> f32 {
+        match self {
+            ToastKind::Info | ToastKind::Success => 3.0,
+            ToastKind::Warning => 5.0,
+            ToastKind::Error => 8.0,
+        }
+    }
+}
+
+pub struct Toast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub text: String,
+    // Seconds until the toast disappears
+    pub remaining: f32,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    pub fn push(&mut self, kind: ToastKind, text: impl Into<String>) {
+        self.toasts.push(Toast {
+            id: self.next_id,
+            kind,
+            text: text.into(),
+            remaining: kind.duration(),
+        });
+        self.next_id += 1;
+
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Info, text);
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Success, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Error, text);
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|toast| toast.id != id);
+    }
+
+    // Count down all toasts except the paused one (e.g. under the mouse) and drop expired ones
+    pub fn tick(&mut self, dt: f32, paused: Option<u64>) {
+        for toast in &mut self.toasts {
+            if Some(toast.id) != paused {
+                toast.remaining -= dt;
+            }
+        }
+        self.toasts.retain(|toast| toast.remaining > 0.0);
+    }
+
+    // Oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+}
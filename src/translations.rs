@@ -1,10 +1,11 @@
 // Fixed for WASM support
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
 #[cfg(not(target_arch = "wasm32"))]
 use std::io::Read;
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 
 type TranslationMap = HashMap<String, HashMap<String, String>>;
@@ -14,6 +15,17 @@ static CURRENT_LANGUAGE: Lazy<RwLock<String>> = Lazy::new(|| {
     RwLock::new(String::from("en"))
 });
 
+/// Languages tried after the current one before falling back to the key itself
+static FALLBACK_CHAIN: Lazy<RwLock<Vec<String>>> = Lazy::new(|| {
+    RwLock::new(vec![String::from("en")])
+});
+
+/// Whether lookups missing in the current language are collected and logged
+static DIAGNOSTICS: AtomicBool = AtomicBool::new(false);
+
+/// Keys missing for the current language, collected while diagnostics are on
+static MISSING_KEYS: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
 /// Loaded translations
 static TRANSLATIONS: Lazy<RwLock<TranslationMap>> = Lazy::new(|| {
     RwLock::new(load_translations().unwrap_or_else(|_| {
@@ -42,14 +54,25 @@ fn load_translations() -> Result<TranslationMap, Box<dyn std::error::Error>> {
     Ok(translations)
 }
 
-/// Get a translation for the given key in the current language
+/// Get a translation for the given key in the current language, falling back
+/// through the fallback chain and finally to the key itself
 pub fn t(key: &str) -> String {
     let lang = CURRENT_LANGUAGE.read().unwrap().clone();
     
     if let Ok(translations) = TRANSLATIONS.read() {
-        if let Some(lang_map) = translations.get(&lang) {
-            if let Some(value) = lang_map.get(key) {
-                return value.clone();
+        if let Some(value) = translations.get(&lang).and_then(|lang_map| lang_map.get(key)) {
+            return value.clone();
+        }
+        
+        if DIAGNOSTICS.load(Ordering::Relaxed) {
+            record_missing_key(&lang, key);
+        }
+        
+        if let Ok(chain) = FALLBACK_CHAIN.read() {
+            for fallback in chain.iter().filter(|fallback| **fallback != lang) {
+                if let Some(value) = translations.get(fallback).and_then(|lang_map| lang_map.get(key)) {
+                    return value.clone();
+                }
             }
         }
     }
@@ -57,12 +80,60 @@ pub fn t(key: &str) -> String {
     key.to_string()
 }
 
+fn record_missing_key(lang: &str, key: &str) {
+    if let Ok(mut missing) = MISSING_KEYS.lock() {
+        // Log each key only once, t() runs every frame
+        if missing.insert(key.to_string()) {
+            log::warn!("Missing translation for \"{}\" in language \"{}\"", key, lang);
+        }
+    }
+}
+
+/// Set the languages tried after the current one, in order
+pub fn set_fallback_chain(chain: Vec<String>) {
+    if let Ok(mut current_chain) = FALLBACK_CHAIN.write() {
+        *current_chain = chain;
+    }
+}
+
+/// Get the languages tried after the current one
+pub fn get_fallback_chain() -> Vec<String> {
+    FALLBACK_CHAIN.read().map(|chain| chain.clone()).unwrap_or_default()
+}
+
+/// Enable or disable collecting missing translation keys
+pub fn set_diagnostics(enabled: bool) {
+    DIAGNOSTICS.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        clear_missing_keys();
+    }
+}
+
+/// Whether missing translation keys are being collected
+pub fn diagnostics_enabled() -> bool {
+    DIAGNOSTICS.load(Ordering::Relaxed)
+}
+
+/// Keys looked up so far that the current language does not have, sorted
+pub fn missing_keys() -> Vec<String> {
+    MISSING_KEYS.lock().map(|missing| missing.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Forget the collected missing keys
+pub fn clear_missing_keys() {
+    if let Ok(mut missing) = MISSING_KEYS.lock() {
+        missing.clear();
+    }
+}
+
 /// Set the current language
 pub fn set_language(lang: &str) -> bool {
     if let Ok(translations) = TRANSLATIONS.read() {
         if translations.contains_key(lang) {
             if let Ok(mut current_lang) = CURRENT_LANGUAGE.write() {
                 *current_lang = lang.to_string();
+                // The collected keys belong to the previous language
+                clear_missing_keys();
                 return true;
             }
         }
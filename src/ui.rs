@@ -2,36 +2,140 @@
 use eframe::egui;
 use egui::*;
 
-use crate::data_structures::{Vertex, Port, PortType};
-use crate::shape_editor::ShapeEditor;
+use crate::data_structures::{Vertex, Port, PortType, PortLayout};
+use crate::shape_editor::{BatchDialog, BatchKind, ExportDialog, ExportKind, GeneratorDialog, GeneratorKind, ImportConflict, NormalizeDialog, ShapeChange, ShapeEditor};
+use crate::generators::{AsteroidParams, HullParams};
+use crate::normalize::{Anchor, NormalizeParams, ReferenceEdge};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shape_editor::ModExportDialog;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::diff::LineKind;
+use crate::report::ReportFormat;
+use crate::shape_ids::IdCategory;
+use crate::arrange::{find_shape, Scene};
+use crate::variants::VariantTransform;
 use crate::translations::t;
+use crate::keybindings::{Action, KeyBinding, FIXED_SHORTCUTS, SHORTCUT_CATEGORIES};
+use crate::import_job::ImportStage;
+use crate::toasts::ToastKind;
+use crate::validation::{Element, Severity};
 use crate::{ visual::*};
-use crate::geometry::{area_for_poly, Vec2};
+use crate::geometry::{area_for_poly, intersect_poly_point, intersect_segment_segment, is_convex_poly, Vec2};
+
+// Side length of the standard square block used by the connection preview
+const TEST_BLOCK_SIZE: f32 = 10.0;
+
+// Grid sizes that keep snapped points on the game's block lattice: the standard
+// block, its halves and quarters, and the larger blocks built from it
+const GRID_PRESETS: &[(&str, f32)] = &[
+    ("grid_eighth_block", TEST_BLOCK_SIZE / 8.0),
+    ("grid_quarter_block", TEST_BLOCK_SIZE / 4.0),
+    ("grid_half_block", TEST_BLOCK_SIZE / 2.0),
+    ("grid_block", TEST_BLOCK_SIZE),
+    ("grid_double_block", TEST_BLOCK_SIZE * 2.0),
+    ("grid_quad_block", TEST_BLOCK_SIZE * 4.0),
+];
+
+// Turn applied by the rotate buttons and Q/E in the arrange tab, in degrees
+const ARRANGE_ROTATION_STEP: f32 = 15.0;
+// How close, on screen, a dropped placement's port has to be to another's to snap
+const ARRANGE_SNAP_PIXELS: f32 = 12.0;
+
+// Part of the pointer's movement a vertex or port follows while Ctrl+Alt is held
+const PRECISION_DRAG_SCALE: f32 = 0.1;
+
+// Height of the ruler along the top of the canvas and width of the one down the left,
+// which is wider to fit its labels
+const RULER_HEIGHT: f32 = 18.0;
+const RULER_WIDTH: f32 = 34.0;
+// Closest the labelled ruler ticks get to each other, in pixels
+const RULER_LABEL_SPACING: f32 = 60.0;
+// How close, on screen, the pointer has to be to a guide to pick it up
+const GUIDE_GRAB_PIXELS: f32 = 5.0;
+
+// Canvas element targeted by the right-click context menu
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CanvasTarget {
+    Vertex(usize),
+    // Edge index and the clicked position along it
+    Edge(usize, f32),
+    Port(usize),
+    // Empty canvas, at this point in shape coordinates
+    Canvas(f32, f32),
+}
 
 // Render game-style navigation bar
-pub fn render_nav_bar(ctx: &egui::Context, app: &mut ShapeEditor) {
+pub fn render_nav_bar(ui: &mut egui::Ui, app: &mut ShapeEditor) {
     egui::TopBottomPanel::top("nav_bar")
         .frame(Frame::none().fill(Color32::from_rgba_unmultiplied(20, 20, 20, 220)))
-        .show(ctx, |ui| {
+        .show_inside(ui, |ui| {
             ui.horizontal_centered(|ui| {
                 // Use the game-style tab buttons for main navigation
                 if game_tab_button(ui, &t("shapes"), app.active_tab == 0).clicked() {
                     app.active_tab = 0;
                 }
+                if game_tab_button(ui, &t("arrange"), app.active_tab == 2).on_hover_text(t("arrange_hint")).clicked() {
+                    app.active_tab = 2;
+                }
                 if game_tab_button(ui, &t("settings"), app.active_tab == 1).clicked() {
                     app.active_tab = 1;
                 }
+                
+                // Log console toggle, with the number of warnings and errors
+                ui.with_layout(Layout::right_to_left(), |ui| {
+                    let problems = crate::log_console::problem_count();
+                    let label = if problems > 0 {
+                        format!("{} ({})", t("log_console"), problems)
+                    } else {
+                        t("log_console")
+                    };
+                    if game_tab_button(ui, &label, app.show_log_console).clicked() {
+                        app.show_log_console = !app.show_log_console;
+                    }
+                    
+                    // Problems panel toggle, with the number of problems found in the shapes
+                    let label = if app.problems.is_empty() {
+                        t("problems")
+                    } else {
+                        format!("{} ({})", t("problems"), app.problems.len())
+                    };
+                    if game_tab_button(ui, &label, app.show_problems).clicked() {
+                        app.show_problems = !app.show_problems;
+                    }
+                    
+                    if game_tab_button(ui, &t("budget"), app.show_budget).on_hover_text(t("budget_hint")).clicked() {
+                        app.show_budget = !app.show_budget;
+                    }
+                    
+                    if game_tab_button(ui, &t("lua_preview"), app.show_lua_preview).clicked() {
+                        app.show_lua_preview = !app.show_lua_preview;
+                    }
+                    
+                    if game_tab_button(ui, &t("script_console"), app.show_script_console).clicked() {
+                        app.show_script_console = !app.show_script_console;
+                    }
+                    
+                    if game_tab_button(ui, &t("history"), app.show_history).clicked() {
+                        app.show_history = !app.show_history;
+                    }
+                    
+                    let shortcuts_hint = format!("{} ({})", t("shortcuts"), app.keybindings.describe(Action::ToggleShortcuts));
+                    if game_tab_button(ui, "?", app.show_shortcuts).on_hover_text(shortcuts_hint).clicked() {
+                        app.show_shortcuts = !app.show_shortcuts;
+                    }
+                });
             });
         });
     
     // Show the section title
     egui::TopBottomPanel::top("section_title")
         .frame(Frame::none().fill(Color32::TRANSPARENT))
-        .show(ctx, |ui| {
+        .show_inside(ui, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(5.0);
                 let title = match app.active_tab {
                     1 => t("settings"),
+                    2 => t("arrange"),
                     _ => t("current_construction")
                 };
                 ui.heading(&title);
@@ -41,17 +145,42 @@ pub fn render_nav_bar(ctx: &egui::Context, app: &mut ShapeEditor) {
 }
 
 // Render top panel with controls for zoom, grid, and export
-pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
+pub fn render_top_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
     let top_panel_frame = ui_panel_frame();
     
     egui::TopBottomPanel::top("top_panel")
         .frame(top_panel_frame)
-        .show(ctx, |ui| {
+        .show_inside(ui, |ui| {
         // First row: basic controls
         ui.horizontal(|ui| {
-            if styled_button(ui, &t("new_shape")).clicked() {
+            let new_shape = styled_button(ui, &t("new_shape")).on_hover_ui(|ui| {
+                ui.label(format!("{} {}", t("next_shape_id"), app.next_shape_id()));
+            });
+            if new_shape.clicked() {
                 app.add_shape();
             }
+            // Where new shape IDs start
+            let category_label = |category: Option<&IdCategory>| match category {
+                Some(category) => format!("{} ({}+)", category.name, category.base),
+                None => format!("{} ({}+)", t("id_category_any"), crate::shape_ids::MIN_SHAPE_ID),
+            };
+            egui::ComboBox::from_id_source("id_category")
+                .selected_text(category_label(app.id_category.and_then(|i| app.id_categories.get(i))))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut app.id_category, None, category_label(None));
+                    for (i, category) in app.id_categories.iter().enumerate() {
+                        ui.selectable_value(&mut app.id_category, Some(i), category_label(Some(category)));
+                    }
+                })
+                .response
+                .on_hover_text(t("id_category_hint"));
+            if styled_button(ui, &t("generate_shape")).clicked() && app.generator_dialog.is_none() {
+                app.generator_dialog = Some(GeneratorDialog {
+                    kind: GeneratorKind::Asteroid,
+                    asteroid: AsteroidParams::default(),
+                    hull: HullParams::default(),
+                });
+            }
             
             ui.add_space(20.0);
             
@@ -59,6 +188,15 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 ui.horizontal(|ui| {
                     ui.label(&t("zoom"));
                     ui.add(egui::Slider::new(&mut app.zoom, 0.1..=5.0).fixed_decimals(2));
+                    if styled_button(ui, &t("zoom_fit")).on_hover_text(app.keybindings.describe(Action::ZoomToFit)).clicked() {
+                        app.zoom_to_fit();
+                    }
+                    if styled_button(ui, &t("zoom_selection")).on_hover_text(app.keybindings.describe(Action::ZoomToSelection)).clicked() {
+                        app.zoom_to_selection();
+                    }
+                    if styled_button(ui, &t("reset_view")).on_hover_text(app.keybindings.describe(Action::ResetView)).clicked() {
+                        app.reset_view();
+                    }
                 });
             });
             
@@ -68,6 +206,17 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 ui.vertical(|ui| {
                     styled_checkbox(ui, &mut app.show_grid, &t("show_grid"));
                     styled_checkbox(ui, &mut app.snap_to_grid, &t("snap_to_grid"));
+                    styled_checkbox(ui, &mut app.show_rulers, &t("show_rulers")).on_hover_text(t("show_rulers_hint"));
+                    styled_checkbox(ui, &mut app.snap_to_guides, &t("snap_to_guides"));
+                    styled_checkbox(ui, &mut app.snap_port_fractions, &t("snap_port_fractions"));
+                    styled_checkbox(ui, &mut app.respace_ports_on_split, &t("respace_ports_on_split"))
+                        .on_hover_text(t("respace_ports_on_split_hint"));
+                    styled_checkbox(ui, &mut app.show_connection_preview, &t("connection_preview"));
+                    styled_checkbox(ui, &mut app.show_measurements, &t("show_measurements"));
+                    styled_checkbox(ui, &mut app.show_normals, &t("show_normals")).on_hover_text(t("show_normals_hint"));
+                    styled_checkbox(ui, &mut app.show_firing_arcs, &t("firing_arcs"));
+                    styled_checkbox(ui, &mut app.show_minimap, &t("show_minimap"));
+                    styled_checkbox(ui, &mut app.lod_preview, &t("lod_preview")).on_hover_text(t("lod_preview_hint"));
                 });
             });
             
@@ -76,7 +225,39 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
             ui.group(|ui| {
                 ui.horizontal(|ui| {
                     ui.label(&t("grid_size"));
-                    ui.add(egui::Slider::new(&mut app.grid_size, 1.0..=50.0).step_by(1.0));
+                    let preset = GRID_PRESETS.iter().find(|(_, size)| (app.grid_size - size).abs() < 1e-3);
+                    egui::ComboBox::from_id_source("grid_preset")
+                        .selected_text(preset.map_or_else(|| t("grid_custom"), |(key, size)| format!("{} ({})", t(key), size)))
+                        .show_ui(ui, |ui| {
+                            for &(key, size) in GRID_PRESETS {
+                                ui.selectable_value(&mut app.grid_size, size, format!("{} ({})", t(key), size));
+                            }
+                        });
+                    // Quarter steps so every preset stays reachable from the slider
+                    ui.add(egui::Slider::new(&mut app.grid_size, 1.0..=50.0).step_by(0.25));
+                });
+            });
+            
+            ui.add_space(20.0);
+            
+            let measure_label = if app.measure_tool { t("measure_tool_on") } else { t("measure_tool") };
+            if styled_button(ui, &measure_label).on_hover_text(app.keybindings.describe(Action::ToggleMeasureTool)).clicked() {
+                app.toggle_measure_tool();
+            }
+            
+            ui.add_space(20.0);
+            
+            // In-game look preview and its block colors
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.checkbox(&mut app.game_preview, &t("game_preview"));
+                    if app.game_preview {
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_srgba(&mut app.preview_fill_color).on_hover_text("fillColor");
+                            ui.color_edit_button_srgba(&mut app.preview_fill_color1).on_hover_text("fillColor1");
+                            ui.color_edit_button_srgba(&mut app.preview_line_color).on_hover_text("lineColor");
+                        });
+                    }
                 });
             });
         });
@@ -94,38 +275,63 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                         app.select_export_file();
                     }
                     
+                    ui.checkbox(&mut app.sort_ports_on_export, &t("sort_ports_on_export"));
+                    // Browsers can't read the file back to append to it
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.checkbox(&mut app.export_append, &t("export_append")).on_hover_text(t("export_append_hint"));
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.checkbox(&mut app.preview_export, t("preview_export")).on_hover_text(t("preview_export_hint"));
+                    
+                    // With the preview on, the file is written from the preview window
                     if styled_button(ui, &t("export")).clicked() {
-                        if let Err(e) = app.export_shapes() {
-                            app.show_error(&t("error_export"), &e.to_string());
-                        } else {
-                            app.status_message = Some(format!("{} {}", t("shapes_exported"), app.export_path));
-                            app.status_time = 3.0;
+                        app.request_export(ExportKind::All);
+                    }
+                    
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let has_backup = crate::backup::has_backup(&app.export_path);
+                        let backup = crate::backup::backup_path(&app.export_path, 0);
+                        if ui.add_enabled_ui(has_backup, |ui| styled_button(ui, &t("restore_backup"))).inner
+                            .on_hover_text(format!("{}\n{}", t("restore_backup_hint"), backup.display()))
+                            .clicked() {
+                            app.restore_export_backup();
                         }
                     }
+                    
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if styled_button(ui, &t("export_to_mod")).clicked() {
+                        app.mod_export_dialog = Some(ModExportDialog {
+                            root: app.mod_root.clone(),
+                            include_blocks: app.blocks_file.is_some(),
+                        });
+                    }
+                    
+                    if styled_button(ui, &t("export_selection")).clicked() {
+                        app.export_dialog = Some(ExportDialog {
+                            path: app.export_path.clone(),
+                            include: app.shapes.iter().map(|shape| shape.id).collect(),
+                        });
+                    }
                 });
             });
             
             ui.add_space(10.0);
             
             if styled_button(ui, &t("export_lua")).clicked() {
-                // Temporarily save the original path
-                let original_path = app.export_path.clone();
-                
-                // Set path to shapes.lua for the export
-                app.export_path = "shapes.lua".to_string();
-                
-                // Export shapes
-                if let Err(e) = app.export_shapes() {
-                    app.show_error(&t("error_export"), &e.to_string());
-                } else {
-                    app.status_message = Some(format!("{} shapes.lua", t("shapes_exported")));
-                    app.status_time = 3.0;
-                }
-                
-                // Restore the original path
-                app.export_path = original_path;
+                app.request_export(ExportKind::ShapesLua);
             }
             
+            ui.menu_button(t("export_report"), |ui| {
+                for format in [ReportFormat::Csv, ReportFormat::Json] {
+                    if ui.button(format.extension().to_uppercase()).clicked() {
+                        ui.close_menu();
+                        app.export_report(format);
+                    }
+                }
+            })
+            .response
+            .on_hover_text(t("export_report_hint"));
+            
             ui.add_space(20.0);
             
             // Import controls
@@ -139,21 +345,29 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                         app.select_import_file();
                     }
                     
-                    if styled_button(ui, &t("import")).clicked() {
-                        if let Err(_e) = app.import_shapes() {
-                            // Error handling is now done in import_shapes()
-                            // Show errors via the dialog
-                        } else {
-                            app.status_message = Some(format!("{} {}", t("shapes_imported"), app.import_path));
-                            app.status_time = 3.0;
-                        }
+                    // Browsers only hand out file names, so there is nothing to reopen there
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if !app.recent_files.is_empty() {
+                        ui.menu_button("🕘", |ui| {
+                            for path in app.recent_files.clone() {
+                                if ui.button(&path).clicked() {
+                                    app.import_path = path;
+                                    ui.close_menu();
+                                }
+                            }
+                        }).response.on_hover_text(t("recent_files"));
+                    }
+                    
+                    // Errors and the import dialog come up when the import finishes
+                    if styled_button(ui, &t("import")).clicked() && app.import_job.is_none() {
+                        app.import_shapes();
                     }
                 });
             });
             
             ui.add_space(10.0);
             
-            if styled_button(ui, &t("import_lua")).clicked() {
+            if styled_button(ui, &t("import_lua")).clicked() && app.import_job.is_none() {
                 // Temporarily save the original path
                 let original_path = app.import_path.clone();
                 
@@ -161,264 +375,841 @@ pub fn render_top_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 app.import_path = "shapes.lua".to_string();
                 
                 // Import shapes
-                if let Err(_e) = app.import_shapes() {
-                    // Error handling is now done in import_shapes()
-                    // Show errors via the dialog
-                } else {
-                    app.status_message = Some(format!("{} shapes.lua", t("shapes_imported")));
-                    app.status_time = 3.0;
-                }
+                app.import_shapes();
                 
                 // Restore the original path
                 app.import_path = original_path;
             }
         });
+
+        // Third row: the mod's blocks.lua, to see which blocks use each shape
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(&t("blocks_file"));
+                    ui.add(egui::TextEdit::singleline(&mut app.blocks_path).desired_width(200.0));
+
+                    if styled_button(ui, &t("browse")).clicked() && app.select_blocks_file() {
+                        app.load_blocks_file();
+                    }
+
+                    if styled_button(ui, &t("load")).clicked() {
+                        app.load_blocks_file();
+                    }
+                });
+            });
+        });
     });
 }
 
 // Render side panel with shape, vertex, and port controls
-pub fn render_side_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
+// Scrollable list that only lays out the rows in view, so long lists stay cheap.
+// Rows are assumed to share one height, measured from the rows drawn last frame.
+fn virtual_list(ui: &mut Ui, id_source: &str, max_height: f32, total_rows: usize, mut add_row: impl FnMut(&mut Ui, usize)) {
+    let id = ui.make_persistent_id(id_source);
+    let row_height = ui.memory().data.get_temp::<f32>(id).unwrap_or(ui.spacing().interact_size.y);
+    let spacing = ui.spacing().item_spacing.y;
+    
+    egui::ScrollArea::vertical()
+        .id_source(id_source)
+        .max_height(max_height)
+        .show_rows(ui, row_height, total_rows, |ui, range| {
+            let mut measured = 0.0f32;
+            for i in range {
+                let top = ui.cursor().top();
+                add_row(ui, i);
+                measured = measured.max(ui.cursor().top() - top - spacing);
+            }
+            if measured > 0.0 && (measured - row_height).abs() > 0.5 {
+                ui.memory().data.insert_temp(id, measured);
+                ui.ctx().request_repaint();
+            }
+        });
+}
+
+// Side panel section with a heading that collapses it; the open state lives in the
+// editor's panel layout rather than egui memory so it can be saved with the settings
+fn collapsible_section(ui: &mut Ui, title: &str, open: &mut bool, add_body: impl FnOnce(&mut Ui)) {
+    let response = egui::CollapsingHeader::new(RichText::new(title).heading())
+        .open(Some(*open))
+        .show(ui, add_body);
+    if response.header_response.clicked() {
+        *open = !*open;
+    }
+}
+
+pub fn render_side_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
     let side_panel_frame = ui_panel_frame();
     
     // Collection of edits to apply after the UI is rendered
     enum ShapeEdit {
         UpdateName(String),
+        SuggestName,
         UpdateVertex(usize, Vertex),
         RemoveVertex(usize),
+        MoveVertex(usize, bool),
+        SetFirstVertex(usize),
+        InsertVertex(usize, bool),
         AddPort(Port),
         UpdatePort(usize, Port),
         RemovePort(usize),
         SelectVertex(Option<usize>),
         SelectPort(Option<usize>),
+        TogglePortSelection(usize),
+        SetSelectedPortsType(PortType),
+        RemoveSelectedPorts,
+        DistributePorts(usize, usize),
+        AutoPortAllEdges,
+        SortPorts,
+        CopyEdgePorts(usize),
+        CopyShapePorts,
+        PastePortLayout(usize),
         ToggleLauncherRadial(bool),
+        ChangeId(usize),
+        DeleteShape,
+        ProjectPorts(usize),
+        SetScaleWaiver(bool),
+        Rotate(f32),
+        Scale(f32),
+        LinkVariant(usize, VariantTransform),
+        UnlinkVariant,
+        UpdateVariant,
+        SetVariantAutoUpdate(bool),
+        SetPayload(Option<usize>),
     }
     
     let mut edits = Vec::new();
+    let mut insert_template = None;
+    let mut delete_template = None;
+    let mut save_template = false;
+    let mut open_normalize = false;
+    let mut open_batch = false;
+    let mut reverse_winding = false;
+    let mut toggle_pin = None;
     
-    egui::SidePanel::left("side_panel")
+    let panel = egui::SidePanel::left("side_panel")
         .frame(side_panel_frame)
-        .default_width(220.0)
-        .show(ctx, |ui| {
+        .resizable(true)
+        .default_width(app.panel_layout.side_panel_width)
+        .width_range(160.0..=600.0)
+        .show_inside(ui, |ui| {
         // Apply heading style
-        ui.heading(&t("shapes"));
-        
-        ui.push_id("shapes_list", |ui| {
-            // Frame for the shapes list
-            egui::Frame::none()
-                .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
-                .inner_margin(6.0)
-                .rounding(4.0)
-                .show(ui, |ui| {
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        for (i, shape) in app.shapes.iter().enumerate() {
+        collapsible_section(ui, &t("shapes"), &mut app.panel_layout.shapes_open, |ui| {
+            ui.push_id("shapes_list", |ui| {
+                // Frame for the shapes list
+                egui::Frame::none()
+                    .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                    .inner_margin(6.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        virtual_list(ui, "shapes_scroll", 200.0, app.shapes.len(), |ui, i| {
                             let selected = i == app.current_shape_idx;
-                            // Custom styling for selected labels
-                            let selectable = ui.selectable_label(selected, &shape.name);
-                            if selectable.clicked() {
-                                app.current_shape_idx = i;
-                            }
-                        }
+                            let pinned = app.pinned_shapes.iter().any(|pin| pin.shape_id == app.shapes[i].id);
+                            let used_by: Vec<String> = app.blocks_file.as_ref()
+                                .map(|blocks_file| blocks_file.blocks_using(app.shapes[i].id).iter().map(|block| block.label()).collect())
+                                .unwrap_or_default();
+                            ui.horizontal(|ui| {
+                                if ui.selectable_label(pinned, "📌").on_hover_text(t("pin_shape_hint")).clicked() {
+                                    toggle_pin = Some(i);
+                                }
+                                // Custom styling for selected labels
+                                let selectable = if used_by.is_empty() {
+                                    ui.selectable_label(selected, &app.shapes[i].name)
+                                } else {
+                                    ui.selectable_label(selected, format!("{} [{}]", app.shapes[i].name, used_by.len()))
+                                        .on_hover_text(format!("{}:\n{}", t("used_by_blocks"), used_by.join("\n")))
+                                };
+                                if selectable.clicked() {
+                                    app.current_shape_idx = i;
+                                }
+                            });
+                        });
                     });
-                });
+                
+                // Pinned shapes, each shown or hidden and moved on its own
+                if !app.pinned_shapes.is_empty() {
+                    ui.add_space(4.0);
+                    ui.strong(t("pinned_shapes"));
+                    let mut unpin = None;
+                    for (i, pin) in app.pinned_shapes.iter_mut().enumerate() {
+                        let name = app.shapes.iter()
+                            .find(|shape| shape.id == pin.shape_id)
+                            .map_or_else(|| pin.shape_id.to_string(), |shape| shape.name.clone());
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut pin.visible, "👁");
+                            ui.label(name);
+                            ui.label("X:");
+                            ui.add(egui::DragValue::new(&mut pin.offset.x).speed(0.1).fixed_decimals(1));
+                            ui.label("Y:");
+                            ui.add(egui::DragValue::new(&mut pin.offset.y).speed(0.1).fixed_decimals(1));
+                            ui.with_layout(Layout::right_to_left(), |ui| {
+                                if styled_button(ui, "X").on_hover_text(t("unpin_shape")).clicked() {
+                                    unpin = Some(i);
+                                }
+                            });
+                        });
+                    }
+                    if let Some(i) = unpin {
+                        app.pinned_shapes.remove(i);
+                    }
+                }
+            });
         });
         
         ui.add_space(10.0);
         
-        if !app.shapes.is_empty() {
-            let current_shape_idx = app.current_shape_idx;
-            let shape = &app.shapes[current_shape_idx];
-            
-            ui.heading(&t("shape_properties"));
-            
-            // Shape properties frame
-            egui::Frame::none()
-                .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
-                .inner_margin(6.0)
-                .rounding(4.0)
-                .show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        ui.strong("ID:");
-                        ui.label(shape.id.to_string());
+        collapsible_section(ui, &t("templates"), &mut app.panel_layout.templates_open, |ui| {
+            for (i, template) in app.reference_templates.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    shape_thumbnail(ui, template, 32.0);
+                    ui.label(&template.name);
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if styled_button(ui, &t("insert")).clicked() {
+                            insert_template = Some(app.reference_templates[i].clone());
+                        }
                     });
-                    
-                    ui.add_space(4.0);
-                    
-                    ui.horizontal(|ui| {
-                        ui.strong(&format!("{}:", t("shape_name")));
-                        let mut name = shape.name.clone();
-                        if ui.add(egui::TextEdit::singleline(&mut name).desired_width(140.0)).changed() {
-                            edits.push(ShapeEdit::UpdateName(name));
+                });
+            }
+            
+            ui.separator();
+            ui.strong(t("my_templates"));
+            if app.user_templates.is_empty() {
+                ui.label(RichText::new(t("no_user_templates")).weak());
+            }
+            for (i, template) in app.user_templates.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    shape_thumbnail(ui, template, 32.0);
+                    ui.label(&template.name);
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if styled_button(ui, "🗑").on_hover_text(t("delete_template")).clicked() {
+                            delete_template = Some(i);
+                        }
+                        if styled_button(ui, &t("insert")).clicked() {
+                            insert_template = Some(app.user_templates[i].clone());
                         }
                     });
-                    
-                    ui.add_space(4.0);
-                    
-                    ui.horizontal(|ui| {
-                        ui.strong(&format!("{}:", t("radial_launcher")));
-                        let mut launcher_radial = shape.launcher_radial;
-                        if ui.checkbox(&mut launcher_radial, "").changed() {
-                            edits.push(ShapeEdit::ToggleLauncherRadial(launcher_radial));
+                });
+            }
+            
+            if !app.shapes.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut app.template_name)
+                        .hint_text(app.shapes[app.current_shape_idx].name.as_str())
+                        .desired_width(120.0));
+                    if styled_button(ui, &t("save_as_template")).clicked() {
+                        save_template = true;
+                    }
+                });
+            }
+        });
+        
+        ui.add_space(10.0);
+        
+        collapsible_section(ui, &t("guides"), &mut app.panel_layout.guides_open, |ui| {
+            // Guides at exact values, for lines the rulers are too coarse to place
+            let mut remove_guide = None;
+            for (i, guide) in app.guides.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut guide.axis, GuideAxis::X, "X");
+                    ui.selectable_value(&mut guide.axis, GuideAxis::Y, "Y");
+                    ui.add(egui::DragValue::new(&mut guide.position).speed(0.1).max_decimals(3));
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if styled_button(ui, "X").on_hover_text(t("remove_guide")).clicked() {
+                            remove_guide = Some(i);
                         }
                     });
                 });
+            }
+            if let Some(i) = remove_guide {
+                app.guides.remove(i);
+            }
+            if app.guides.is_empty() {
+                ui.label(RichText::new(t("no_guides")).weak());
+            }
             
-            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if styled_button(ui, &t("add_x_guide")).clicked() {
+                    app.guides.push(Guide { axis: GuideAxis::X, position: 0.0 });
+                }
+                if styled_button(ui, &t("add_y_guide")).clicked() {
+                    app.guides.push(Guide { axis: GuideAxis::Y, position: 0.0 });
+                }
+                ui.add_enabled_ui(!app.guides.is_empty(), |ui| {
+                    if styled_button(ui, &t("clear_guides")).clicked() {
+                        app.guides.clear();
+                    }
+                });
+            });
+        });
+        
+        ui.add_space(10.0);
+        
+        if !app.shapes.is_empty() {
+            let current_shape_idx = app.current_shape_idx;
+            let shape = &app.shapes[current_shape_idx];
+            let selection = &app.selection;
+            let used_by = app.blocks_using_shape(current_shape_idx);
+            let can_delete = app.shapes.len() > 1;
+            let scale_waived = app.scale_waivers.contains(&shape.id);
+            let launches = crate::payload::has_launch_ports(shape);
+            let payload = app.payload_of(shape.id);
+            let payload_linked = app.payload_links.iter().any(|link| link.launcher_id == shape.id);
+            let blocks_payload = app.blocks_file.as_ref().and_then(|blocks_file| blocks_file.payload_for_shape(shape.id));
             
-            ui.heading(&t("vertices"));
-            ui.push_id("vertices_list", |ui| {
-                // Custom frame for vertex list
+            collapsible_section(ui, &t("shape_properties"), &mut app.panel_layout.properties_open, |ui| {
+                // Shape properties frame
                 egui::Frame::none()
                     .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
                     .inner_margin(6.0)
                     .rounding(4.0)
                     .show(ui, |ui| {
-                        egui::ScrollArea::vertical()
-                            .max_height(150.0)
-                            .show(ui, |ui| {
-                                let vertices = &shape.vertices;
-                                
-                                for (i, vertex) in vertices.iter().enumerate() {
-                                    ui.horizontal(|ui| {
-                                        let selected = shape.selected_vertex == Some(i);
-                                        if ui.selectable_label(selected, format!("V{}", i)).clicked() {
-                                            edits.push(ShapeEdit::SelectVertex(Some(i)));
+                        ui.horizontal(|ui| {
+                            ui.strong("ID:");
+                            // Typed into a buffer so that half-typed IDs never renumber the
+                            // shape; Escape puts the current ID back
+                            let field_id = ui.id().with("shape_id");
+                            if !ui.memory().has_focus(field_id) {
+                                app.shape_id_text = shape.id.to_string();
+                            }
+                            let response = ui.add(egui::TextEdit::singleline(&mut app.shape_id_text)
+                                .id(field_id)
+                                .desired_width(60.0));
+                            if response.lost_focus() && !ui.input().key_pressed(egui::Key::Escape) {
+                                match app.shape_id_text.trim().parse() {
+                                    Ok(id) if id != shape.id => edits.push(ShapeEdit::ChangeId(id)),
+                                    Ok(_) => {}
+                                    Err(_) => app.toasts.warning(format!("{} {}", t("invalid_shape_id"), app.shape_id_text.trim())),
+                                }
+                            }
+                            
+                            ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                                if ui.add_enabled(can_delete, egui::Button::new(t("delete_shape"))).clicked() {
+                                    edits.push(ShapeEdit::DeleteShape);
+                                }
+                            });
+                        });
+                        
+                        if !used_by.is_empty() {
+                            ui.add_space(4.0);
+                            ui.collapsing(format!("{}: {}", t("used_by_blocks"), used_by.len()), |ui| {
+                                for label in &used_by {
+                                    ui.label(label);
+                                }
+                            });
+                        }
+                    
+                        ui.add_space(4.0);
+                    
+                        ui.horizontal(|ui| {
+                            ui.strong(&format!("{}:", t("shape_name")));
+                            let mut name = shape.name.clone();
+                            if ui.add(egui::TextEdit::singleline(&mut name).desired_width(140.0)).changed() {
+                                edits.push(ShapeEdit::UpdateName(name));
+                            }
+                            if styled_button(ui, "✨").on_hover_text(t("suggest_name")).clicked() {
+                                edits.push(ShapeEdit::SuggestName);
+                            }
+                        });
+                    
+                        ui.add_space(4.0);
+                    
+                        ui.horizontal(|ui| {
+                            ui.strong(&format!("{}:", t("radial_launcher")));
+                            let mut launcher_radial = shape.launcher_radial;
+                            if ui.checkbox(&mut launcher_radial, "").changed() {
+                                edits.push(ShapeEdit::ToggleLauncherRadial(launcher_radial));
+                            }
+                        });
+                        
+                        ui.add_space(4.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.strong(&format!("{}:", t("scales")));
+                            ui.label((shape.scales.len() + 1).to_string());
+                        });
+                        
+                        ui.add_space(4.0);
+                        
+                        ui.horizontal(|ui| {
+                            if styled_button(ui, &t("normalize_shape")).on_hover_text(t("normalize_shape_hint")).clicked() {
+                                open_normalize = true;
+                            }
+                            if styled_button(ui, &t("batch_operations")).on_hover_text(t("batch_operations_hint")).clicked() {
+                                open_batch = true;
+                            }
+                        });
+                        
+                        // Rotate and scale about the pivot, which the game doesn't know about:
+                        // there the shape always turns about its origin
+                        ui.add_space(4.0);
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut app.transform_angle).speed(1.0).suffix("°"));
+                            if styled_button(ui, &t("rotate_shape")).clicked() {
+                                edits.push(ShapeEdit::Rotate(app.transform_angle));
+                            }
+                            ui.add(egui::DragValue::new(&mut app.transform_factor).speed(0.01).clamp_range(0.01..=100.0).prefix("×"));
+                            if styled_button(ui, &t("scale_shape")).clicked() {
+                                edits.push(ShapeEdit::Scale(app.transform_factor));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.strong(format!("{}:", t("pivot"))).on_hover_text(t("pivot_hint"));
+                            let mut pivot = app.pivot.clone().unwrap_or(Vertex { x: 0.0, y: 0.0 });
+                            ui.label("X:");
+                            let changed_x = ui.add(egui::DragValue::new(&mut pivot.x).speed(0.1).max_decimals(3)).changed();
+                            ui.label("Y:");
+                            let changed_y = ui.add(egui::DragValue::new(&mut pivot.y).speed(0.1).max_decimals(3)).changed();
+                            if changed_x || changed_y {
+                                app.pivot = Some(pivot);
+                            }
+                            ui.add_enabled_ui(app.pivot.is_some(), |ui| {
+                                if styled_button(ui, "⟲").on_hover_text(t("reset_pivot")).clicked() {
+                                    app.pivot = None;
+                                }
+                            });
+                        });
+                        if shape.is_winding_reversed() {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(Color32::from_rgb(230, 120, 60), format!("⚠ {}", t("winding_reversed")));
+                                if styled_button(ui, &t("reverse_winding")).clicked() {
+                                    reverse_winding = true;
+                                }
+                            });
+                        }
+                        
+                        if !shape.scales.is_empty() {
+                            let mut waived = scale_waived;
+                            if ui.checkbox(&mut waived, t("waive_scale_edge_counts")).on_hover_text(t("waive_scale_edge_counts_hint")).changed() {
+                                edits.push(ShapeEdit::SetScaleWaiver(waived));
+                            }
+                        }
+                        
+                        // Variant of another shape, or the form to make it one
+                        ui.add_space(4.0);
+                        let shape_name = |id: usize| app.shapes.iter()
+                            .find(|shape| shape.id == id)
+                            .map_or_else(|| id.to_string(), |shape| shape.name.clone());
+                        if let Some(link) = app.variant_links.iter().find(|link| link.variant_id == shape.id) {
+                            ui.label(format!("{}: {} ({})", t("variant_of"), shape_name(link.source_id), link.transform.describe()));
+                            ui.horizontal(|ui| {
+                                let mut auto_update = link.auto_update;
+                                if ui.checkbox(&mut auto_update, t("variant_auto_update")).on_hover_text(t("variant_auto_update_hint")).changed() {
+                                    edits.push(ShapeEdit::SetVariantAutoUpdate(auto_update));
+                                }
+                                if styled_button(ui, &t("variant_update")).clicked() {
+                                    edits.push(ShapeEdit::UpdateVariant);
+                                }
+                                if styled_button(ui, &t("variant_unlink")).clicked() {
+                                    edits.push(ShapeEdit::UnlinkVariant);
+                                }
+                            });
+                        } else {
+                            ui.collapsing(t("make_variant"), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}:", t("variant_source")));
+                                    let selected = app.variant_source.map_or_else(|| "-".to_string(), shape_name);
+                                    egui::ComboBox::from_id_source("variant_source")
+                                        .selected_text(selected)
+                                        .width(140.0)
+                                        .show_ui(ui, |ui| {
+                                            for other in app.shapes.iter().filter(|other| other.id != shape.id) {
+                                                ui.selectable_value(&mut app.variant_source, Some(other.id), &other.name);
+                                            }
+                                        });
+                                });
+                                ui.horizontal(|ui| {
+                                    egui::ComboBox::from_id_source("variant_transform")
+                                        .selected_text(app.variant_transform.kind_label())
+                                        .show_ui(ui, |ui| {
+                                            for kind in VariantTransform::KINDS {
+                                                if ui.selectable_label(app.variant_transform.same_kind(kind), kind.kind_label()).clicked() {
+                                                    app.variant_transform = kind;
+                                                }
+                                            }
+                                        });
+                                    match &mut app.variant_transform {
+                                        VariantTransform::Rotate(degrees) => {
+                                            ui.add(egui::DragValue::new(degrees).speed(1.0).suffix("°"));
                                         }
-                                        
-                                        ui.add_space(5.0);
-                                        
-                                        ui.label("X:");
-                                        let mut x = vertex.x;
-                                        let changed_x = ui.add(egui::DragValue::new(&mut x).speed(0.1).fixed_decimals(1)).changed();
-                                        
-                                        ui.add_space(5.0);
-                                        
-                                        ui.label("Y:");
-                                        let mut y = vertex.y;
-                                        let changed_y = ui.add(egui::DragValue::new(&mut y).speed(0.1).fixed_decimals(1)).changed();
-                                        
-                                        if changed_x || changed_y {
-                                            edits.push(ShapeEdit::UpdateVertex(i, Vertex { x, y }));
+                                        VariantTransform::Scale(factor) => {
+                                            ui.add(egui::DragValue::new(factor).speed(0.01).clamp_range(0.01..=100.0).suffix("x"));
                                         }
-                                        
-                                        ui.with_layout(egui::Layout::right_to_left(), |ui| {
-                                            // Delete button styling
-                                            if styled_button(ui, "X").clicked() {
-                                                edits.push(ShapeEdit::RemoveVertex(i));
+                                        VariantTransform::MirrorX | VariantTransform::MirrorY => {}
+                                    }
+                                });
+                                let source = app.variant_source.filter(|&id| id != shape.id && app.shapes.iter().any(|other| other.id == id));
+                                let link = ui.add_enabled_ui(source.is_some(), |ui| styled_button(ui, &t("variant_link"))).inner.on_hover_text(t("variant_link_hint"));
+                                if let Some(source) = source.filter(|_| link.clicked()) {
+                                    edits.push(ShapeEdit::LinkVariant(source, app.variant_transform));
+                                }
+                            });
+                        }
+                        let variants: Vec<String> = app.variant_links.iter()
+                            .filter(|link| link.source_id == shape.id)
+                            .map(|link| shape_name(link.variant_id))
+                            .collect();
+                        if !variants.is_empty() {
+                            ui.label(format!("{}: {}", t("variants"), variants.join(", ")));
+                        }
+                        
+                        // What the launch ports fire: picked here, or what blocks.lua says
+                        if launches {
+                            ui.add_space(4.0);
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}:", t("payload")));
+                                let from_blocks = match blocks_payload {
+                                    Some(id) => format!("{} ({})", t("payload_from_blocks"), shape_name(id)),
+                                    None => t("payload_none"),
+                                };
+                                let selected = match payload.filter(|_| payload_linked) {
+                                    Some(id) => shape_name(id),
+                                    None => from_blocks.clone(),
+                                };
+                                ui.add_enabled_ui(app.blocks_file.is_some() || payload_linked, |ui| {
+                                    egui::ComboBox::from_id_source("payload")
+                                        .selected_text(selected)
+                                        .width(140.0)
+                                        .show_ui(ui, |ui| {
+                                            if ui.selectable_label(!payload_linked, &from_blocks).clicked() {
+                                                edits.push(ShapeEdit::SetPayload(None));
+                                            }
+                                            for other in app.shapes.iter().filter(|other| other.id != shape.id) {
+                                                let picked = payload_linked && payload == Some(other.id);
+                                                if ui.selectable_label(picked, format!("{} (ID: {})", other.name, other.id)).clicked() {
+                                                    edits.push(ShapeEdit::SetPayload(Some(other.id)));
+                                                }
                                             }
                                         });
-                                    });
-                                }
+                                }).response.on_hover_text(t("payload_hint")).on_disabled_hover_text(t("payload_needs_blocks"));
                             });
+                            if let Some(id) = payload.filter(|&id| !app.shapes.iter().any(|other| other.id == id)) {
+                                ui.colored_label(Color32::from_rgb(230, 180, 60), format!("⚠ {} {}", t("payload_missing"), id));
+                            }
+                        }
                     });
             });
             
             ui.add_space(10.0);
             
-            ui.heading(&t("ports"));
-            ui.push_id("ports_list", |ui| {
-                // Custom frame for ports list
-                egui::Frame::none()
-                    .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
-                    .inner_margin(6.0)
-                    .rounding(4.0)
-                    .show(ui, |ui| {
-                        egui::ScrollArea::vertical()
-                            .max_height(180.0)
-                            .show(ui, |ui| {
-                                let ports = &shape.ports;
+            collapsible_section(ui, &t("vertices"), &mut app.panel_layout.vertices_open, |ui| {
+                ui.push_id("vertices_list", |ui| {
+                    // Custom frame for vertex list
+                    egui::Frame::none()
+                        .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                        .inner_margin(6.0)
+                        .rounding(4.0)
+                        .show(ui, |ui| {
+                            let vertices = &shape.vertices;
+                            virtual_list(ui, "vertices_scroll", 150.0, vertices.len(), |ui, i| {
+                                let vertex = &vertices[i];
+                                ui.horizontal(|ui| {
+                                    let selected = selection.vertex == Some(i);
+                                    if ui.selectable_label(selected, format!("V{}", i)).clicked() {
+                                        edits.push(ShapeEdit::SelectVertex(Some(i)));
+                                    }
                                 
-                                for (i, port) in ports.iter().enumerate() {
-                                    ui.push_id(i, |ui| {
-                                        // Port frame for each port
-                                        egui::Frame::none()
-                                            .inner_margin(4.0)
-                                            .fill(if shape.selected_port == Some(i) {
-                                                Color32::from_rgba_unmultiplied(40, 40, 50, 230)
-                                            } else {
-                                                Color32::TRANSPARENT
-                                            })
-                                            .show(ui, |ui| {
-                                                let mut port_updated = false;
-                                                let mut new_port = port.clone();
-                                                
-                                                ui.horizontal(|ui| {
-                                                    let selected = shape.selected_port == Some(i);
-                                                    if ui.selectable_label(selected, format!("P{}", i)).clicked() {
-                                                        edits.push(ShapeEdit::SelectPort(Some(i)));
-                                                    }
-                                                    
-                                                    ui.add_space(5.0);
-                                                    
-                                                    ui.label(&format!("{}:", t("edge")));
-                                                    if ui.add(egui::DragValue::new(&mut new_port.edge).speed(0.1)).changed() {
-                                                        port_updated = true;
-                                                    }
-                                                    
-                                                    ui.add_space(5.0);
-                                                    
-                                                    ui.label(&format!("{}:", t("position")));
-                                                    if ui.add(egui::DragValue::new(&mut new_port.position).speed(0.01)
-                                                        .clamp_range(0.0..=1.0).fixed_decimals(2)).changed() {
-                                                        port_updated = true;
-                                                    }
-                                                });
-                                                
-                                                ui.horizontal(|ui| {
-                                                    ui.label(&format!("{}:", t("type")));
-                                                    ui.add_space(5.0);
-                                                    
-                                                    if egui::ComboBox::from_id_source(format!("port_type_{}", i))
-                                                        .selected_text(new_port.port_type.to_string())
-                                                        .width(120.0)
-                                                        .show_ui(ui, |ui| {
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::Default, "DEFAULT");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::ThrusterIn, "THRUSTER_IN");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::ThrusterOut, "THRUSTER_OUT");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::Missile, "MISSILE");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::Launcher, "LAUNCHER");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::WeaponIn, "WEAPON_IN");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::WeaponOut, "WEAPON_OUT");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::Root, "ROOT");
-                                                            ui.selectable_value(&mut new_port.port_type, PortType::None, "NONE");
-                                                        })
-                                                        .response
-                                                        .changed()
-                                                    {
-                                                        port_updated = true;
-                                                    }
-                                                    
-                                                    ui.with_layout(egui::Layout::right_to_left(), |ui| {
-                                                        // Delete button styling
-                                                        if styled_button(ui, "X").clicked() {
-                                                            edits.push(ShapeEdit::RemovePort(i));
-                                                        }
-                                                    });
-                                                });
-                                                
-                                                if port_updated {
-                                                    edits.push(ShapeEdit::UpdatePort(i, new_port.clone()));
-                                                }
-                                            });
-                                        
-                                        ui.add_space(2.0);
-                                    });
-                                }
+                                    ui.add_space(5.0);
                                 
-                                ui.add_space(5.0);
+                                    ui.label("X:");
+                                    let mut x = vertex.x;
+                                    let changed_x = ui.add(egui::DragValue::new(&mut x).speed(0.1).fixed_decimals(1)).changed();
+                                
+                                    ui.add_space(5.0);
+                                
+                                    ui.label("Y:");
+                                    let mut y = vertex.y;
+                                    let changed_y = ui.add(egui::DragValue::new(&mut y).speed(0.1).fixed_decimals(1)).changed();
+                                
+                                    if changed_x || changed_y {
+                                        edits.push(ShapeEdit::UpdateVertex(i, Vertex { x, y }));
+                                    }
+                                
+                                    ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                                        // Delete button styling
+                                        if styled_button(ui, "X").clicked() {
+                                            edits.push(ShapeEdit::RemoveVertex(i));
+                                        }
+                                        if ui.add_enabled_ui(i > 0, |ui| styled_button(ui, "⏮")).inner.on_hover_text(t("set_first_vertex")).clicked() {
+                                            edits.push(ShapeEdit::SetFirstVertex(i));
+                                        }
+                                        if ui.add_enabled_ui(i + 1 < vertices.len(), |ui| styled_button(ui, "⬇")).inner.on_hover_text(t("move_down")).clicked() {
+                                            edits.push(ShapeEdit::MoveVertex(i, true));
+                                        }
+                                        if ui.add_enabled_ui(i > 0, |ui| styled_button(ui, "⬆")).inner.on_hover_text(t("move_up")).clicked() {
+                                            edits.push(ShapeEdit::MoveVertex(i, false));
+                                        }
+                                    });
+                                });
+                            });
+                            
+                            // Insert at the middle of an edge next to the selected vertex
+                            if let Some(i) = selection.vertex.filter(|&i| i < vertices.len() && vertices.len() > 1) {
+                                ui.horizontal(|ui| {
+                                    if styled_button(ui, &t("insert_before")).on_hover_text(format!("V{}", i)).clicked() {
+                                        edits.push(ShapeEdit::InsertVertex(i, false));
+                                    }
+                                    if styled_button(ui, &t("insert_after")).on_hover_text(format!("V{}", i)).clicked() {
+                                        edits.push(ShapeEdit::InsertVertex(i, true));
+                                    }
+                                });
+                            }
+                        });
+                });
+            });
+            
+            ui.add_space(10.0);
+            
+            collapsible_section(ui, &t("ports"), &mut app.panel_layout.ports_open, |ui| {
+                // Bulk actions when several ports are selected (Shift+click)
+                let selected_ports = selection.port_indices(shape.ports.len());
+                if selected_ports.len() > 1 {
+                    egui::Frame::none()
+                        .fill(Color32::from_rgba_unmultiplied(40, 40, 50, 230))
+                        .inner_margin(6.0)
+                        .rounding(4.0)
+                        .show(ui, |ui| {
+                            ui.label(format!("{}: {}", t("ports_selected"), selected_ports.len()));
+                            ui.horizontal(|ui| {
+                                ui.label(&format!("{}:", t("type")));
+                                egui::ComboBox::from_id_source("bulk_port_type")
+                                    .selected_text(t("set_type"))
+                                    .width(120.0)
+                                    .show_ui(ui, |ui| {
+                                        for port_type in PortType::ALL {
+                                            if ui.selectable_label(false, port_type.to_string()).on_hover_text(port_type.description()).clicked() {
+                                                edits.push(ShapeEdit::SetSelectedPortsType(port_type));
+                                            }
+                                        }
+                                    });
+                            });
+                            if styled_button(ui, &t("delete_selected")).clicked() {
+                                edits.push(ShapeEdit::RemoveSelectedPorts);
+                            }
+                        });
+                    ui.add_space(4.0);
+                }
+            
+                ui.push_id("ports_list", |ui| {
+                    // Custom frame for ports list
+                    egui::Frame::none()
+                        .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                        .inner_margin(6.0)
+                        .rounding(4.0)
+                        .show(ui, |ui| {
+                            let ports = &shape.ports;
+                            virtual_list(ui, "ports_scroll", 180.0, ports.len(), |ui, i| {
+                                let port = &ports[i];
+                                ui.push_id(i, |ui| {
+                                    // Port frame for each port
+                                    egui::Frame::none()
+                                        .inner_margin(4.0)
+                                        .fill(if selection.is_port_selected(i) {
+                                            Color32::from_rgba_unmultiplied(40, 40, 50, 230)
+                                        } else {
+                                            Color32::TRANSPARENT
+                                        })
+                                        .show(ui, |ui| {
+                                            let mut port_updated = false;
+                                            let mut new_port = port.clone();
+                                        
+                                            ui.horizontal(|ui| {
+                                                let selected = selection.is_port_selected(i);
+                                                if ui.selectable_label(selected, format!("P{}", i)).clicked() {
+                                                    if ui.input().modifiers.shift {
+                                                        edits.push(ShapeEdit::TogglePortSelection(i));
+                                                    } else {
+                                                        edits.push(ShapeEdit::SelectPort(Some(i)));
+                                                    }
+                                                }
+                                            
+                                                ui.add_space(5.0);
+                                            
+                                                ui.label(&format!("{}:", t("edge")));
+                                                if ui.add(egui::DragValue::new(&mut new_port.edge).speed(0.1)).changed() {
+                                                    port_updated = true;
+                                                }
+                                            
+                                                ui.add_space(5.0);
+                                            
+                                                ui.label(&format!("{}:", t("position")));
+                                                if ui.add(egui::DragValue::new(&mut new_port.position).speed(0.01)
+                                                    .clamp_range(0.0..=1.0).fixed_decimals(2)).changed() {
+                                                    port_updated = true;
+                                                }
+                                            });
+                                        
+                                            ui.horizontal(|ui| {
+                                                ui.label(&format!("{}:", t("type")));
+                                                ui.add_space(5.0);
+                                            
+                                                let description = new_port.port_type.description();
+                                                if egui::ComboBox::from_id_source(format!("port_type_{}", i))
+                                                    .selected_text(new_port.port_type.to_string())
+                                                    .width(120.0)
+                                                    .show_ui(ui, |ui| {
+                                                        for port_type in PortType::ALL {
+                                                            let label = port_type.to_string();
+                                                            let description = port_type.description();
+                                                            ui.selectable_value(&mut new_port.port_type, port_type, label)
+                                                                .on_hover_text(description);
+                                                        }
+                                                    })
+                                                    .response
+                                                    .on_hover_text(description)
+                                                    .changed()
+                                                {
+                                                    port_updated = true;
+                                                }
+                                            
+                                                ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                                                    // Delete button styling
+                                                    if styled_button(ui, "X").clicked() {
+                                                        edits.push(ShapeEdit::RemovePort(i));
+                                                    }
+                                                });
+                                            });
+                                        
+                                            if port_updated {
+                                                edits.push(ShapeEdit::UpdatePort(i, new_port.clone()));
+                                            }
+                                        });
+                                
+                                    ui.add_space(2.0);
+                                });
+                            });
+                            
+                            ui.add_space(5.0);
+                        
+                            // Style add button using our custom button
+                            if styled_button(ui, &t("add_port")).clicked() && !shape.vertices.is_empty() {
+                                edits.push(ShapeEdit::AddPort(Port {
+                                    edge: 0,
+                                    position: 0.5,
+                                    port_type: PortType::Default,
+                                }));
+                            }
+                        });
+                });
+            
+                // Absolute data for the selected port and its edge
+                if let Some(port_idx) = selection.port {
+                    if let (Some(port), Some(pos)) = (shape.ports.get(port_idx), shape.port_position(port_idx)) {
+                        ui.add_space(10.0);
+                        ui.heading(&t("port_inspector"));
+                        egui::Frame::none()
+                            .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                            .inner_margin(6.0)
+                            .rounding(4.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("port_inspector_grid").num_columns(2).show(ui, |ui| {
+                                    ui.strong(&format!("{}:", t("absolute_position")));
+                                    ui.monospace(format!("{:.3}, {:.3}", pos.x, pos.y));
+                                    ui.end_row();
+                                
+                                    ui.strong(&format!("{}:", t("edge")));
+                                    ui.monospace(format!("{} ({}-{})", port.edge, port.edge, (port.edge + 1) % shape.vertices.len()));
+                                    ui.end_row();
+                                
+                                    ui.strong(&format!("{}:", t("edge_length")));
+                                    ui.monospace(format!("{:.3}", shape.edge_length(port.edge)));
+                                    ui.end_row();
+                                
+                                    ui.strong(&format!("{}:", t("edge_angle")));
+                                    ui.monospace(format!("{:.2}°", shape.edge_angle(port.edge)));
+                                    ui.end_row();
                                 
-                                // Style add button using our custom button
-                                if styled_button(ui, &t("add_port")).clicked() && !shape.vertices.is_empty() {
-                                    edits.push(ShapeEdit::AddPort(Port {
-                                        edge: 0,
-                                        position: 0.5,
-                                        port_type: PortType::Default,
-                                    }));
+                                    ui.strong(&format!("{}:", t("distance_along_edge")));
+                                    ui.monospace(format!("{:.3}", shape.edge_length(port.edge) * port.position));
+                                    ui.end_row();
+                                });
+                            });
+                    }
+                }
+            
+                ui.add_space(10.0);
+            
+                // Edge port tools: distribute, auto-port and copy/paste layouts
+                if !shape.vertices.is_empty() {
+                    let max_edge = shape.vertices.len() - 1;
+                    egui::Frame::none()
+                        .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                        .inner_margin(6.0)
+                        .rounding(4.0)
+                        .show(ui, |ui| {
+                            ui.strong(&t("port_tools"));
+                            ui.horizontal(|ui| {
+                                ui.label(&format!("{}:", t("edge")));
+                                ui.add(egui::DragValue::new(&mut app.distribute_edge).speed(0.1).clamp_range(0..=max_edge));
+                            
+                                ui.add_space(5.0);
+                            
+                                ui.label(&format!("{}:", t("count")));
+                                ui.add(egui::DragValue::new(&mut app.distribute_count).speed(0.1).clamp_range(1..=16));
+                            });
+                            if styled_button(ui, &t("distribute_ports")).clicked() {
+                                edits.push(ShapeEdit::DistributePorts(app.distribute_edge.min(max_edge), app.distribute_count));
+                            }
+                        
+                            ui.add_space(4.0);
+                        
+                            // One port per grid unit on every edge
+                            if styled_button(ui, &t("auto_port_all_edges")).clicked() {
+                                edits.push(ShapeEdit::AutoPortAllEdges);
+                            }
+                        
+                            if styled_button(ui, &t("sort_ports")).clicked() {
+                                edits.push(ShapeEdit::SortPorts);
+                            }
+                        
+                            ui.add_space(4.0);
+                        
+                            // Copy port layout between edges and shapes
+                            let edge = app.distribute_edge.min(max_edge);
+                            ui.horizontal(|ui| {
+                                if styled_button(ui, &t("copy_edge_ports")).clicked() {
+                                    edits.push(ShapeEdit::CopyEdgePorts(edge));
+                                }
+                                if styled_button(ui, &t("copy_shape_ports")).clicked() {
+                                    edits.push(ShapeEdit::CopyShapePorts);
                                 }
                             });
-                    });
+                            if let Some(layout) = &app.port_clipboard {
+                                let (label, count) = match layout {
+                                    PortLayout::Edge(ports) => (t("paste_to_edge"), ports.len()),
+                                    PortLayout::Shape(ports) => (t("paste_to_shape"), ports.len()),
+                                };
+                                if styled_button(ui, &format!("{} ({})", label, count)).clicked() {
+                                    edits.push(ShapeEdit::PastePortLayout(edge));
+                                }
+                            }
+                            
+                            // Copy the ports of scale 1 onto the other scales
+                            if !shape.scales.is_empty() {
+                                ui.add_space(4.0);
+                                ui.label(&format!("{}:", t("project_ports")));
+                                for scale in 0..shape.scales.len() {
+                                    let mismatches = shape.scale_port_mismatches(scale);
+                                    ui.horizontal(|ui| {
+                                        if styled_button(ui, &format!("→ {} {}", t("scale"), scale + 2)).clicked() {
+                                            edits.push(ShapeEdit::ProjectPorts(scale));
+                                        }
+                                        if mismatches.is_empty() {
+                                            ui.label(RichText::new(t("port_counts_match")).weak());
+                                        } else {
+                                            let edges = mismatches.iter().map(|edge| format!("E{}", edge)).collect::<Vec<_>>().join(", ");
+                                            ui.colored_label(Color32::from_rgb(230, 180, 60), format!("⚠ {}", edges))
+                                                .on_hover_text(t("port_counts_differ"));
+                                        }
+                                    });
+                                }
+                            }
+                        });
+                }
             });
         }
     });
+    app.panel_layout.side_panel_width = panel.response.rect.width();
     
     // Apply all collected edits
     if !edits.is_empty() {
@@ -426,12 +1217,16 @@ pub fn render_side_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
         
         for edit in edits {
             match edit {
+                // Typing and dragging values collapse into one undo step
                 ShapeEdit::UpdateName(name) => {
-                    app.save_state();
+                    app.begin_undo_transaction();
                     app.shapes[current_shape_idx].name = name;
                 },
+                ShapeEdit::SuggestName => {
+                    app.suggest_name(current_shape_idx);
+                },
                 ShapeEdit::UpdateVertex(idx, vertex) => {
-                    app.save_state();
+                    app.begin_undo_transaction();
                     if idx < app.shapes[current_shape_idx].vertices.len() {
                         app.shapes[current_shape_idx].vertices[idx] = vertex;
                     }
@@ -439,11 +1234,20 @@ pub fn render_side_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 ShapeEdit::RemoveVertex(idx) => {
                     app.remove_vertex(current_shape_idx, idx);
                 },
+                ShapeEdit::MoveVertex(idx, down) => {
+                    app.move_vertex(current_shape_idx, idx, down);
+                },
+                ShapeEdit::SetFirstVertex(idx) => {
+                    app.set_first_vertex(current_shape_idx, idx);
+                },
+                ShapeEdit::InsertVertex(idx, after) => {
+                    app.insert_vertex(current_shape_idx, idx, after);
+                },
                 ShapeEdit::AddPort(port) => {
                     app.add_port(current_shape_idx, port);
                 },
                 ShapeEdit::UpdatePort(idx, port) => {
-                    app.save_state();
+                    app.begin_undo_transaction();
                     if idx < app.shapes[current_shape_idx].ports.len() {
                         app.shapes[current_shape_idx].ports[idx] = port;
                     }
@@ -452,24 +1256,225 @@ pub fn render_side_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                     app.remove_port(current_shape_idx, idx);
                 },
                 ShapeEdit::SelectVertex(idx) => {
-                    app.shapes[current_shape_idx].selected_vertex = idx;
-                    app.shapes[current_shape_idx].selected_port = None;
+                    app.selection.select_vertex(idx);
                 },
                 ShapeEdit::SelectPort(idx) => {
-                    app.shapes[current_shape_idx].selected_port = idx;
-                    app.shapes[current_shape_idx].selected_vertex = None;
+                    app.selection.select_port(idx);
+                },
+                ShapeEdit::TogglePortSelection(idx) => {
+                    app.selection.toggle_port(idx);
+                },
+                ShapeEdit::SetSelectedPortsType(port_type) => {
+                    let indices = app.selection.port_indices(app.shapes[current_shape_idx].ports.len());
+                    app.set_port_types(current_shape_idx, &indices, port_type);
+                },
+                ShapeEdit::RemoveSelectedPorts => {
+                    let indices = app.selection.port_indices(app.shapes[current_shape_idx].ports.len());
+                    app.remove_ports(current_shape_idx, &indices);
+                },
+                ShapeEdit::DistributePorts(edge, count) => {
+                    app.distribute_ports_on_edge(current_shape_idx, edge, count);
+                },
+                ShapeEdit::AutoPortAllEdges => {
+                    app.auto_port_all_edges(current_shape_idx);
+                },
+                ShapeEdit::SortPorts => {
+                    app.sort_ports(current_shape_idx);
+                },
+                ShapeEdit::CopyEdgePorts(edge) => {
+                    app.copy_edge_ports(current_shape_idx, edge);
+                },
+                ShapeEdit::CopyShapePorts => {
+                    app.copy_shape_ports(current_shape_idx);
+                },
+                ShapeEdit::PastePortLayout(edge) => {
+                    app.paste_port_layout(current_shape_idx, edge);
                 },
                 ShapeEdit::ToggleLauncherRadial(launcher_radial) => {
                     app.save_state();
                     app.shapes[current_shape_idx].launcher_radial = launcher_radial;
                 },
+                ShapeEdit::ChangeId(id) => {
+                    app.request_shape_change(ShapeChange::Renumber(current_shape_idx, id));
+                },
+                ShapeEdit::LinkVariant(source_id, transform) => {
+                    app.link_variant(current_shape_idx, source_id, transform);
+                },
+                ShapeEdit::UnlinkVariant => {
+                    let shape_id = app.shapes[current_shape_idx].id;
+                    app.unlink_variant(shape_id);
+                },
+                ShapeEdit::UpdateVariant => {
+                    let shape_id = app.shapes[current_shape_idx].id;
+                    app.update_variant(shape_id);
+                },
+                ShapeEdit::SetVariantAutoUpdate(auto_update) => {
+                    let shape_id = app.shapes[current_shape_idx].id;
+                    app.set_variant_auto_update(shape_id, auto_update);
+                },
+                ShapeEdit::SetPayload(payload_id) => {
+                    let shape_id = app.shapes[current_shape_idx].id;
+                    app.set_payload(shape_id, payload_id);
+                },
+                ShapeEdit::SetScaleWaiver(waived) => {
+                    let shape_id = app.shapes[current_shape_idx].id;
+                    app.set_scale_waiver(shape_id, waived);
+                },
+                ShapeEdit::Rotate(degrees) => {
+                    app.rotate_shape(current_shape_idx, degrees);
+                },
+                ShapeEdit::Scale(factor) => {
+                    app.scale_shape(current_shape_idx, factor);
+                },
+                ShapeEdit::ProjectPorts(scale) => {
+                    app.project_ports_to_scale(current_shape_idx, scale);
+                },
+                ShapeEdit::DeleteShape => {
+                    app.request_shape_change(ShapeChange::Delete(current_shape_idx));
+                },
             }
         }
     }
+    
+    if let Some(template) = insert_template {
+        app.insert_template(template);
+    }
+    if let Some(i) = delete_template {
+        app.delete_user_template(i);
+    }
+    if save_template {
+        let name = std::mem::take(&mut app.template_name);
+        app.save_as_template(app.current_shape_idx, &name);
+    }
+    if open_normalize {
+        app.normalize_dialog = Some(NormalizeDialog {
+            shape_idx: app.current_shape_idx,
+            params: NormalizeParams::default(),
+        });
+    }
+    if open_batch {
+        app.batch_dialog = Some(BatchDialog::new(app.grid_size));
+    }
+    if reverse_winding {
+        app.reverse_winding(app.current_shape_idx);
+    }
+    if let Some(i) = toggle_pin {
+        app.toggle_pin(i);
+    }
+}
+
+// Bottom status bar with cursor position, zoom, selection and grid size
+pub fn render_status_bar(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    egui::TopBottomPanel::bottom("status_bar")
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            // Distances from the origin, as the game turns the shape about it
+            let from_origin = |v: &Vertex| (v.x * v.x + v.y * v.y).sqrt();
+            let cursor = match &app.cursor_shape_pos {
+                Some(pos) => format!("{:.2}, {:.2} (r {:.2})", pos.x, pos.y, from_origin(pos)),
+                None => "—".to_string(),
+            };
+            ui.label(format!("{}: {}", t("cursor"), cursor)).on_hover_text(t("distance_from_origin"));
+            ui.separator();
+            if let Some(pivot) = &app.pivot {
+                ui.label(format!("{}: {:.2}, {:.2} (r {:.2})", t("pivot"), pivot.x, pivot.y, from_origin(pivot)))
+                    .on_hover_text(t("distance_from_origin"));
+                ui.separator();
+            }
+            ui.label(format!("{}: {:.0}%", t("zoom"), app.zoom * 100.0));
+            ui.separator();
+            ui.label(format!("{}: {}", t("grid_size"), app.grid_size));
+            ui.separator();
+            
+            let selection = match app.shapes.get(app.current_shape_idx) {
+                Some(shape) => {
+                    let selected_ports = app.selection.port_indices(shape.ports.len());
+                    // A selection left pointing past the end of a list reads as none
+                    let vertex = app.selection.vertex.and_then(|idx| Some((idx, shape.vertices.get(idx)?)));
+                    let port = app.selection.port.and_then(|idx| Some((idx, shape.ports.get(idx)?)));
+                    if let Some((idx, v)) = vertex {
+                        format!("{} {} ({:.2}, {:.2})", t("vertex"), idx, v.x, v.y)
+                    } else if selected_ports.len() > 1 {
+                        format!("{}: {}", t("ports_selected"), selected_ports.len())
+                    } else if let Some((idx, port)) = port {
+                        format!("{} {} ({} {}, {:.3}, {})", t("port"), idx, t("edge"), port.edge, port.position, port.port_type.to_string())
+                    } else {
+                        t("nothing_selected")
+                    }
+                }
+                None => t("nothing_selected"),
+            };
+            ui.label(format!("{}: {}", t("selection"), selection));
+        });
+    });
+}
+
+// Undo history as a row of ticks, one per saved state; clicking or dragging along it
+// undoes or redoes to that state, so the canvas follows while scrubbing. Taller ticks
+// changed more shapes.
+pub fn render_history_timeline(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    if !app.show_history {
+        return;
+    }
+    
+    egui::TopBottomPanel::bottom("history_timeline")
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+            let (current, total) = app.history_position();
+            let changes = app.history_changes();
+            
+            ui.horizontal(|ui| {
+                ui.strong(t("history"));
+                ui.label(format!("{} / {}", current + 1, total));
+                
+                let width = ui.available_width();
+                let (rect, response) = ui.allocate_exact_size(vec2(width, 28.0), Sense::click_and_drag());
+                let painter = ui.painter_at(rect);
+                let track = rect.shrink2(vec2(8.0, 4.0));
+                let x = |i: usize| if total > 1 {
+                    track.left() + track.width() * i as f32 / (total - 1) as f32
+                } else {
+                    track.center().x
+                };
+                
+                painter.line_segment([pos2(track.left(), track.bottom()), pos2(track.right(), track.bottom())], Stroke::new(1.0, Color32::from_gray(90)));
+                for i in 0..total {
+                    let changed = if i == 0 { 0 } else { changes.get(i - 1).copied().unwrap_or(0) };
+                    let height = (6.0 + changed.min(10) as f32 * 1.4).min(track.height());
+                    let color = match i.cmp(&current) {
+                        std::cmp::Ordering::Less => Color32::from_gray(170),
+                        std::cmp::Ordering::Equal => Color32::from_rgb(255, 200, 60),
+                        std::cmp::Ordering::Greater => Color32::from_gray(80),
+                    };
+                    painter.line_segment([pos2(x(i), track.bottom()), pos2(x(i), track.bottom() - height)], Stroke::new(2.0, color));
+                }
+                painter.circle_filled(pos2(x(current), track.bottom()), 4.0, Color32::from_rgb(255, 200, 60));
+                
+                let nearest = |pos: Pos2| if total > 1 {
+                    (((pos.x - track.left()) / track.width()) * (total - 1) as f32).round().clamp(0.0, (total - 1) as f32) as usize
+                } else {
+                    0
+                };
+                if let Some(pos) = response.hover_pos() {
+                    let i = nearest(pos);
+                    let text = match i.cmp(&current) {
+                        std::cmp::Ordering::Less => format!("{} {}", current - i, t("history_steps_back")),
+                        std::cmp::Ordering::Equal => t("history_current"),
+                        std::cmp::Ordering::Greater => format!("{} {}", i - current, t("history_steps_forward")),
+                    };
+                    response.clone().on_hover_text(text);
+                }
+                if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked() || response.dragged()) {
+                    app.seek_history(nearest(pos));
+                }
+            });
+        });
 }
 
 // Render central panel with the canvas for shape editing
-pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
+pub fn render_central_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    let ctx = &ui.ctx().clone();
     // Central panel with custom styling - dark background
     let central_panel_frame = Frame::none()
         .fill(Color32::from_rgb(0, 0, 0)) // Pure black background
@@ -477,9 +1482,15 @@ pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
     
     egui::CentralPanel::default()
         .frame(central_panel_frame)
-        .show(ctx, |ui| {
+        .show_inside(ui, |ui| {
         let response = ui.allocate_response(ui.available_size(), egui::Sense::click_and_drag());
         let rect = response.rect;
+        app.canvas_rect = rect;
+        
+        // Track the cursor in shape space for the status bar
+        app.cursor_shape_pos = ui.ctx().pointer_hover_pos()
+            .filter(|pos| rect.contains(*pos))
+            .map(|pos| app.screen_to_shape_coords_unsnapped(pos, rect));
         
         // Handle mouse wheel for zooming
         if let Some(pos) = ui.ctx().pointer_interact_pos() {
@@ -512,6 +1523,13 @@ pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
             }
         }
         
+        // Space + left drag pans like in most graphics tools
+        if ui.ctx().input().key_down(egui::Key::Space) && response.dragged_by(egui::PointerButton::Primary) {
+            let delta = response.drag_delta();
+            app.pan.x += delta.x / app.zoom;
+            app.pan.y += delta.y / app.zoom;
+        }
+        
         // Обработка перетаскивания холста правой кнопкой мыши (legacy support)
         if response.dragged_by(egui::PointerButton::Secondary) {
             let delta = response.drag_delta();
@@ -527,13 +1545,90 @@ pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 render_grid(&ui.painter(), app, rect);
             }
             
-            // Рисуем форму, если есть хотя бы две вершины
-            if app.shapes[shape_idx].vertices.len() > 1 {
-                render_shape(&ui.painter(), ctx, app, shape_idx, rect);
+            // Other shapes pinned for context, under the one being edited
+            if !app.pinned_shapes.is_empty() {
+                render_pinned_shapes(ui.painter(), app, rect);
+            }
+            
+            // Zoomed out far enough for another scale: show it read-only in place of scale 1
+            let lod_scale = app.lod_scale_index(shape_idx);
+            if lod_scale > 0 {
+                render_lod_scale(&ui.painter(), app, shape_idx, lod_scale, rect);
+            } else if app.shapes[shape_idx].is_degenerate() {
+                // No fill for shapes the game can't build, so they don't pass for real blocks
+                if app.shapes[shape_idx].vertices.len() > 1 {
+                    render_shape(ui.painter(), ctx, app, shape_idx, rect);
+                }
+                render_degenerate_shape(ui.painter(), app, shape_idx, rect);
+            } else if app.game_preview {
+                render_shape_game_preview(ui.painter(), app, shape_idx, rect);
+            } else {
+                render_shape_fill(ui.painter(), app, shape_idx, rect);
+                render_shape(ui.painter(), ctx, app, shape_idx, rect);
+            }
+            
+            // Direction launched blocks leave radial launchers in
+            if app.shapes[shape_idx].launcher_radial && !app.game_preview {
+                render_launcher_radial(&ui.painter(), app, shape_idx, rect);
+            }
+            
+            // What the launch ports fire, sitting where it leaves them
+            if !app.game_preview && crate::payload::has_launch_ports(&app.shapes[shape_idx]) {
+                render_payload_ghosts(ui.painter(), app, shape_idx, rect);
+            }
+            
+            // Firing fans of WEAPON_OUT ports, cut off where the shape blocks them
+            if app.show_firing_arcs && app.shapes[shape_idx].vertices.len() > 2 {
+                render_firing_arcs(&ui.painter(), app, shape_idx, rect);
+            }
+            
+            // Ghost test blocks attached to the selected ports
+            if app.show_connection_preview {
+                render_connection_preview(&ui.painter(), app, shape_idx, rect);
+            }
+            
+            // Which side of each edge the game takes as outside
+            if app.show_normals && !app.game_preview && lod_scale == 0 && app.shapes[shape_idx].vertices.len() > 2 {
+                render_edge_normals(ui.painter(), app, shape_idx, rect);
+            }
+            
+            // Edge lengths and interior angles
+            if app.show_measurements && app.shapes[shape_idx].vertices.len() > 1 {
+                render_measurements(&ui.painter(), app, shape_idx, rect);
+            }
+            
+            // Ruler between two arbitrary points
+            if app.measure_tool {
+                render_measure_tool(&ui.painter(), app, rect);
+            }
+            
+            // Guide lines across the whole canvas
+            if !app.guides.is_empty() {
+                render_guides(ui.painter(), app, rect);
+            }
+            
+            // Pivot placed away from the origin, tied back to it
+            if app.pivot.is_some() && !app.game_preview {
+                render_pivot(ui.painter(), app, rect);
+            }
+            
+            // Отрисовка вершин (скрыты в режиме предпросмотра)
+            if !app.game_preview && lod_scale == 0 {
+                render_vertices(&ui.painter(), app, shape_idx, rect);
             }
             
-            // Отрисовка вершин
-            render_vertices(&ui.painter(), app, shape_idx, rect);
+            // Explain the type of the port under the cursor
+            if !response.dragged() {
+                if let Some(hover_pos) = response.hover_pos() {
+                    if let Some(port_idx) = port_at(app, shape_idx, hover_pos, rect) {
+                        let port_type = &app.shapes[shape_idx].ports[port_idx].port_type;
+                        egui::show_tooltip_at_pointer(ctx, egui::Id::new("canvas_port_tooltip"), |ui| {
+                            ui.strong(port_type.to_string());
+                            ui.label(port_type.description());
+                        });
+                    }
+                }
+            }
             
             // Отображение информации о форме
             let info_text = format!(
@@ -544,16 +1639,25 @@ pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 app.shapes[shape_idx].ports.len()
             );
             
+            // Rulers along the top and left, with the text moved clear of them
+            let inset = if app.show_rulers {
+                render_rulers(ui.painter(), app, rect);
+                vec2(RULER_WIDTH, RULER_HEIGHT)
+            } else {
+                egui::Vec2::ZERO
+            };
+            
             ui.painter().text(
-                rect.min + vec2(10.0, 10.0),
+                rect.min + inset + vec2(10.0, 10.0),
                 Align2::LEFT_TOP,
                 info_text,
                 FontId::proportional(14.0),
                 Color32::WHITE,
             );
             
-            // Display keybind help in the bottom right
-            let keybind_text = "Ctrl+Z: Отменить | Ctrl+Y: Повторить | Alt+Клик: Добавить порт | Ctrl+Клик: Добавить вершину на грани | Esc: Отменить выделение | Delete: Удалить выделенное";
+            // Display where to find the keybind help in the bottom right
+            // The full list is in the shortcuts overlay
+            let keybind_text = format!("{}: {}", app.keybindings.describe(Action::ToggleShortcuts), t("shortcuts"));
             ui.painter().text(
                 rect.right_bottom() - vec2(10.0, 10.0),
                 Align2::RIGHT_BOTTOM,
@@ -562,52 +1666,248 @@ pub fn render_central_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 Color32::from_rgba_unmultiplied(200, 200, 200, 180),
             );
             
-            // Обработка клика на холсте для добавления или выбора вершины
-            handle_canvas_clicks(app, response, rect, shape_idx);
+            // Port type hotkey mapping, shown while a port is selected
+            if app.selection.port.is_some() {
+                let mapping = PortType::ALL.iter()
+                    .enumerate()
+                    .map(|(i, port_type)| format!("{}: {}", i + 1, port_type.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                ui.painter().text(
+                    rect.right_bottom() - vec2(10.0, 28.0),
+                    Align2::RIGHT_BOTTOM,
+                    mapping,
+                    FontId::proportional(12.0),
+                    Color32::from_rgba_unmultiplied(200, 200, 200, 180),
+                );
+            }
+            
+            // Overview of the whole shape in the top right corner
+            if app.show_minimap {
+                render_minimap(ctx, app, shape_idx, rect);
+            }
+            
+            // Контекстное меню по правому клику
+            handle_context_menu(app, &response, rect, shape_idx);
+            
+            // Guides and rulers take the pointer before the shape does
+            if !handle_guides(app, &response, rect, shape_idx) {
+                // Обработка клика на холсте для добавления или выбора вершины
+                handle_canvas_clicks(app, response, rect, shape_idx);
+            }
         }
     });
 }
 
-// Helper function to render the grid
-fn render_grid(painter: &Painter, app: &ShapeEditor, rect: Rect) {
-    let grid_color = Color32::from_rgba_premultiplied(100, 100, 100, 100);
+// Key for the cached grid: the grid only changes when the view or its settings do
+#[derive(Clone, Debug, PartialEq)]
+struct GridCacheKey {
+    rect: Rect,
+    zoom: f32,
+    pan: egui::Vec2,
+    grid_size: f32,
+    game_preview: bool,
+    style: GridStyle,
+}
+
+// Canvas geometry that is expensive to rebuild and rarely changes, kept between frames
+#[derive(Default)]
+pub struct CanvasCache {
+    grid_key: Option<GridCacheKey>,
+    grid_mesh: Mesh,
+    fill_key: Option<(Rect, f32, egui::Vec2, Vec<Vertex>)>,
+    fill_mesh: Mesh,
+}
+
+// Helper function to render the grid, rebuilt only when the view or grid settings change
+fn render_grid(painter: &Painter, app: &mut ShapeEditor, rect: Rect) {
+    let key = GridCacheKey {
+        rect,
+        zoom: app.zoom,
+        pan: app.pan,
+        grid_size: app.grid_size,
+        game_preview: app.game_preview,
+        style: app.grid_style.clone(),
+    };
+    if app.canvas_cache.grid_key.as_ref() != Some(&key) {
+        app.canvas_cache.grid_mesh = build_grid_mesh(app, rect);
+        app.canvas_cache.grid_key = Some(key);
+    }
+    painter.add(egui::Shape::mesh(app.canvas_cache.grid_mesh.clone()));
+}
+
+// Grid lines are horizontal or vertical, so each one is a plain rectangle in the mesh
+fn add_axis_aligned_line(mesh: &mut Mesh, a: Pos2, b: Pos2, stroke: Stroke) {
+    mesh.add_colored_rect(Rect::from_two_pos(a, b).expand(stroke.width / 2.0), stroke.color);
+}
+
+fn build_grid_mesh(app: &ShapeEditor, rect: Rect) -> Mesh {
+    let mut mesh = Mesh::default();
+    let style = &app.grid_style;
+    
+    // The game preview uses the in-game block grid instead of the editor grid
+    let grid_size = if app.game_preview { TEST_BLOCK_SIZE } else { app.grid_size };
+    let subdivisions = style.subdivisions.max(1) as i32;
     
-    let min_x = ((rect.min.x - rect.center().x) / app.zoom - app.pan.x) / app.grid_size;
-    let max_x = ((rect.max.x - rect.center().x) / app.zoom - app.pan.x) / app.grid_size;
-    let min_y = ((rect.min.y - rect.center().y) / app.zoom - app.pan.y) / app.grid_size;
-    let max_y = ((rect.max.y - rect.center().y) / app.zoom - app.pan.y) / app.grid_size;
+    let min_x = ((rect.min.x - rect.center().x) / app.zoom - app.pan.x) / grid_size;
+    let max_x = ((rect.max.x - rect.center().x) / app.zoom - app.pan.x) / grid_size;
+    let min_y = ((rect.min.y - rect.center().y) / app.zoom - app.pan.y) / grid_size;
+    let max_y = ((rect.max.y - rect.center().y) / app.zoom - app.pan.y) / grid_size;
     
     let min_x = min_x.floor() as i32;
     let max_x = max_x.ceil() as i32;
     let min_y = min_y.floor() as i32;
     let max_y = max_y.ceil() as i32;
     
+    // Minor lines closer than a few pixels only add noise
+    let show_minor = grid_size * app.zoom >= 4.0;
+    let line_stroke = |i: i32| {
+        if subdivisions > 1 && i % subdivisions == 0 {
+            Some(Stroke::new(1.0, style.major_color))
+        } else if show_minor {
+            Some(Stroke::new(1.0, style.minor_color))
+        } else {
+            None
+        }
+    };
+    
     // Draw vertical grid lines
     for x in min_x..=max_x {
-        let x_pos = x as f32 * app.grid_size;
-        let start = app.shape_to_screen_coords(&Vertex { x: x_pos, y: min_y as f32 * app.grid_size }, rect);
-        let end = app.shape_to_screen_coords(&Vertex { x: x_pos, y: max_y as f32 * app.grid_size }, rect);
-        painter.line_segment([start, end], Stroke::new(1.0, grid_color));
+        let Some(stroke) = line_stroke(x) else { continue };
+        let x_pos = x as f32 * grid_size;
+        let start = app.shape_to_screen_coords(&Vertex { x: x_pos, y: min_y as f32 * grid_size }, rect);
+        let end = app.shape_to_screen_coords(&Vertex { x: x_pos, y: max_y as f32 * grid_size }, rect);
+        add_axis_aligned_line(&mut mesh, start, end, stroke);
     }
     
     // Draw horizontal grid lines
     for y in min_y..=max_y {
-        let y_pos = y as f32 * app.grid_size;
-        let start = app.shape_to_screen_coords(&Vertex { x: min_x as f32 * app.grid_size, y: y_pos }, rect);
-        let end = app.shape_to_screen_coords(&Vertex { x: max_x as f32 * app.grid_size, y: y_pos }, rect);
-        painter.line_segment([start, end], Stroke::new(1.0, grid_color));
+        let Some(stroke) = line_stroke(y) else { continue };
+        let y_pos = y as f32 * grid_size;
+        let start = app.shape_to_screen_coords(&Vertex { x: min_x as f32 * grid_size, y: y_pos }, rect);
+        let end = app.shape_to_screen_coords(&Vertex { x: max_x as f32 * grid_size, y: y_pos }, rect);
+        add_axis_aligned_line(&mut mesh, start, end, stroke);
     }
     
     // Draw coordinate axes
-    let origin = app.shape_to_screen_coords(&Vertex { x: 0.0, y: 0.0 }, rect);
-    let x_axis = app.shape_to_screen_coords(&Vertex { x: max_x as f32 * app.grid_size, y: 0.0 }, rect);
-    let y_axis = app.shape_to_screen_coords(&Vertex { x: 0.0, y: max_y as f32 * app.grid_size }, rect);
+    if style.emphasize_axes {
+        let origin = app.shape_to_screen_coords(&Vertex { x: 0.0, y: 0.0 }, rect);
+        let x_axis = app.shape_to_screen_coords(&Vertex { x: max_x as f32 * grid_size, y: 0.0 }, rect);
+        let y_axis = app.shape_to_screen_coords(&Vertex { x: 0.0, y: max_y as f32 * grid_size }, rect);
+        
+        add_axis_aligned_line(&mut mesh, origin, x_axis, Stroke::new(2.0, Color32::RED));
+        add_axis_aligned_line(&mut mesh, origin, y_axis, Stroke::new(2.0, Color32::GREEN));
+    }
+    
+    mesh
+}
+
+// Render the shape the way the game draws blocks: fillColor at the center blending
+// to fillColor1 at the edges, with a lineColor outline and plain port markers
+fn render_shape_game_preview(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let points: Vec<Pos2> = shape.vertices.iter()
+        .map(|v| app.shape_to_screen_coords(v, rect))
+        .collect();
+    
+    if points.len() > 2 {
+        let sum = points.iter().fold(egui::Vec2::ZERO, |acc, p| acc + p.to_vec2());
+        let center = (sum / points.len() as f32).to_pos2();
+        
+        let mut mesh = egui::epaint::Mesh::default();
+        mesh.colored_vertex(center, app.preview_fill_color);
+        for point in &points {
+            mesh.colored_vertex(*point, app.preview_fill_color1);
+        }
+        for i in 0..points.len() as u32 {
+            mesh.add_triangle(0, i + 1, (i + 1) % points.len() as u32 + 1);
+        }
+        painter.add(egui::Shape::mesh(mesh));
+    }
+    
+    let outline = Stroke::new(1.5, app.preview_line_color);
+    for i in 0..points.len() {
+        painter.line_segment([points[i], points[(i + 1) % points.len()]], outline);
+    }
     
-    painter.line_segment([origin, x_axis], Stroke::new(2.0, Color32::RED));
-    painter.line_segment([origin, y_axis], Stroke::new(2.0, Color32::GREEN));
+    for (port_idx, port) in shape.ports.iter().enumerate() {
+        if let Some(pos) = shape.port_position(port_idx) {
+            let screen_pos = app.shape_to_screen_coords(&pos, rect);
+            draw_port(painter, screen_pos, &port.port_type, app.selection.is_port_selected(port_idx));
+        }
+    }
 }
 
 // Helper function to render the shape
+// Fill the shape with triangles fanned out from its center. The mesh is cached and
+// only rebuilt when the vertices or the view change.
+fn render_shape_fill(painter: &Painter, app: &mut ShapeEditor, shape_idx: usize, rect: Rect) {
+    let vertices = &app.shapes[shape_idx].vertices;
+    if vertices.len() < 3 {
+        return;
+    }
+    
+    let cache = &app.canvas_cache;
+    let up_to_date = matches!(&cache.fill_key, Some((r, zoom, pan, verts))
+        if *r == rect && *zoom == app.zoom && *pan == app.pan && verts == vertices);
+    
+    if !up_to_date {
+        let fill_color = Color32::from_rgba_premultiplied(30, 40, 80, 160);
+        let points: Vec<Pos2> = vertices.iter()
+            .map(|v| app.shape_to_screen_coords(v, rect))
+            .collect();
+        let sum = points.iter().fold(egui::Vec2::ZERO, |acc, p| acc + p.to_vec2());
+        let center = (sum / points.len() as f32).to_pos2();
+        
+        let mut mesh = Mesh::default();
+        mesh.colored_vertex(center, fill_color);
+        for &p in &points {
+            mesh.colored_vertex(p, fill_color);
+        }
+        let n = points.len() as u32;
+        for i in 0..n {
+            mesh.add_triangle(0, i + 1, (i + 1) % n + 1);
+        }
+        
+        let key = (rect, app.zoom, app.pan, vertices.clone());
+        app.canvas_cache.fill_mesh = mesh;
+        app.canvas_cache.fill_key = Some(key);
+    }
+    painter.add(egui::Shape::mesh(app.canvas_cache.fill_mesh.clone()));
+}
+
+// Error style for shapes with fewer than 3 vertices or no area: a dashed red outline
+// and a note saying why the shape won't export
+fn render_degenerate_shape(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let points: Vec<Pos2> = shape.vertices.iter()
+        .map(|v| app.shape_to_screen_coords(v, rect))
+        .collect();
+    let first = match points.first() {
+        Some(&first) => first,
+        None => return,
+    };
+    
+    let color = Color32::from_rgb(230, 90, 80);
+    if points.len() > 1 {
+        let mut outline = points.clone();
+        outline.push(first);
+        painter.extend(egui::Shape::dashed_line(&outline, Stroke::new(2.5, color), 8.0, 5.0));
+    }
+    for &point in &points {
+        painter.circle_stroke(point, 6.0 * app.handle_scale, Stroke::new(1.5, color));
+    }
+    
+    let reason = if points.len() < 3 { t("degenerate_too_few_vertices") } else { t("degenerate_no_area") };
+    painter.text(
+        first + vec2(10.0, 20.0),
+        Align2::LEFT_CENTER,
+        format!("⚠ {}", reason),
+        FontId::proportional(13.0),
+        color,
+    );
+}
+
 fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
     // Convert vertices to screen coordinates
     let mut points = Vec::new();
@@ -615,32 +1915,9 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
         points.push(app.shape_to_screen_coords(vertex, rect));
     }
     
-    let fill_color = Color32::from_rgba_premultiplied(30, 40, 80, 160);
     let stroke = Stroke::new(1.0, Color32::WHITE);
 
-    // Draw the shape as triangles from center
     if points.len() > 2 {
-        // Calculate center point
-        let center = points.iter().fold(Pos2::new(0.0, 0.0), |acc, pos| {
-            Pos2::new(acc.x + pos.x, acc.y + pos.y)
-        });
-        let center = Pos2::new(center.x / points.len() as f32, center.y / points.len() as f32);
-        
-        // Draw triangles from center to each edge
-        for i in 0..points.len() {
-            let p1 = points[i];
-            let p2 = points[(i + 1) % points.len()];
-            
-            let triangle = vec![center, p1, p2];
-            
-            // Fill triangle with transparent stroke
-            painter.add(egui::Shape::convex_polygon(
-                triangle,
-                fill_color,
-                Stroke::new(0.0, Color32::TRANSPARENT),
-            ));
-        }
-        
         // Draw shape outline
         for i in 0..points.len() {
             let start = points[i];
@@ -655,7 +1932,11 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
             
         if vertices.len() >= 3 {
             let area = area_for_poly(&vertices);
-            let area_text = format!("Area: {:.1}", area);
+            let area_text = if is_convex_poly(&vertices) {
+                format!("Area: {:.1}", area)
+            } else {
+                format!("Area: {:.1} (concave)", area)
+            };
             
             painter.text(
                 points[0] + vec2(-10.0, -20.0),
@@ -685,22 +1966,15 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
                 };
                 
                 // Check if this port is selected
-                let is_selected = app.shapes[shape_idx].selected_port == Some(port_idx);
+                let is_selected = app.selection.is_port_selected(port_idx);
                 
                 // Get port color based on type
-                let port_color = match port.port_type {
-                    PortType::Default => Color32::YELLOW,
-                    PortType::ThrusterIn | PortType::ThrusterOut => Color32::BLUE,
-                    PortType::Missile | PortType::Launcher => Color32::RED,
-                    PortType::WeaponIn | PortType::WeaponOut => Color32::LIGHT_BLUE,
-                    PortType::Root => Color32::GREEN,
-                    PortType::None => Color32::GRAY,
-                };
+                let port_color = app.port_palette.color(&port.port_type);
                 
                 // Draw port with glow animation
                 let time = ctx.input().time as f32;
                 let pulse = (time * 2.0).sin() * 0.5 + 0.5;
-                let size = 5.0 + pulse * 2.0;
+                let size = (5.0 + pulse * 2.0) * app.handle_scale;
                 
                 // Port glow - make it brighter if selected
                 let glow_color = if is_selected {
@@ -709,8 +1983,29 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
                     port_color.linear_multiply(0.3)
                 };
                 
-                painter.circle_filled(port_pos, size + 2.0, glow_color);
-                painter.circle_filled(port_pos, size, port_color);
+                painter.circle_filled(port_pos, size + 2.0 * app.handle_scale, glow_color);
+                if app.port_shape_markers {
+                    draw_port_shape_marker(painter, port_pos, size, &port.port_type, port_color);
+                } else {
+                    painter.circle_filled(port_pos, size, port_color);
+                }
+                
+                // Spawn direction: missiles and (non-radial) launchers fire along the edge normal
+                let launches = match port.port_type {
+                    PortType::Missile => true,
+                    PortType::Launcher => !app.shapes[shape_idx].launcher_radial,
+                    _ => false,
+                };
+                // The game's idea of outward, which flips with the winding
+                let direction = app.shapes[shape_idx].winding_normal(i)
+                    .map(|(nx, ny)| vec2(nx, ny));
+                if let Some(direction) = direction {
+                    if launches {
+                        painter.arrow(port_pos, direction * 24.0 * app.handle_scale, Stroke::new(1.5, port_color));
+                    } else if app.show_normals {
+                        painter.line_segment([port_pos, port_pos + direction * (size + 6.0 * app.handle_scale)], Stroke::new(2.0, port_color));
+                    }
+                }
                 
                 // Port label
                 let port_text = match port.port_type {
@@ -725,12 +2020,12 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
                     PortType::None => "N",
                 };
                 
-                if port_text != "" {
+                if app.show_port_labels && port_text != "" {
                     painter.text(
-                        port_pos + vec2(8.0, 0.0),
+                        port_pos + vec2(8.0 * app.handle_scale, 0.0),
                         Align2::LEFT_CENTER,
                         port_text,
-                        FontId::monospace(10.0),
+                        FontId::monospace(10.0 * app.handle_scale),
                         port_color,
                     );
                 }
@@ -751,11 +2046,500 @@ fn render_shape(painter: &Painter, ctx: &egui::Context, app: &ShapeEditor, shape
     }
 }
 
-// Helper function to render all vertices
-fn render_vertices(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+// A short normal from the middle of each edge, on the side the game treats as outside.
+// With the winding reversed they point inwards and are drawn as a warning.
+fn render_edge_normals(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let reversed = shape.is_winding_reversed();
+    let color = if reversed { Color32::from_rgb(230, 120, 60) } else { Color32::from_rgb(120, 200, 255) };
+    let n = shape.vertices.len();
+    
+    for edge in 0..n {
+        let Some((nx, ny)) = shape.winding_normal(edge) else { continue };
+        let a = &shape.vertices[edge];
+        let b = &shape.vertices[(edge + 1) % n];
+        let middle = app.shape_to_screen_coords(&Vertex { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 }, rect);
+        painter.arrow(middle, vec2(nx, ny) * 14.0 * app.handle_scale, Stroke::new(1.5, color));
+    }
+    
+    if let Some(center) = shape.centroid().filter(|_| reversed) {
+        painter.text(
+            app.shape_to_screen_coords(&center, rect),
+            Align2::CENTER_CENTER,
+            format!("⚠ {}", t("winding_reversed")),
+            FontId::proportional(13.0),
+            color,
+        );
+    }
+}
+
+// Outline and ports of one of the additional scales, as picked by the LOD preview
+fn render_lod_scale(painter: &Painter, app: &ShapeEditor, shape_idx: usize, lod_scale: usize, rect: Rect) {
+    let scale = &app.shapes[shape_idx].scales[lod_scale - 1];
+    let points: Vec<Pos2> = scale.vertices.iter()
+        .map(|v| app.shape_to_screen_coords(v, rect))
+        .collect();
+    let n = points.len();
+    let outline = Color32::from_rgb(200, 220, 255);
+    
+    if n > 2 {
+        painter.add(egui::Shape::convex_polygon(points.clone(), outline.linear_multiply(0.1), Stroke::none()));
+    }
+    if n > 1 {
+        for i in 0..n {
+            painter.line_segment([points[i], points[(i + 1) % n]], Stroke::new(2.0, outline));
+        }
+        for port in &scale.ports {
+            if port.edge >= n {
+                continue;
+            }
+            let (a, b) = (points[port.edge], points[(port.edge + 1) % n]);
+            let size = 5.0 * app.handle_scale;
+            let color = app.port_palette.color(&port.port_type);
+            if app.port_shape_markers {
+                draw_port_shape_marker(painter, a + (b - a) * port.position, size, &port.port_type, color);
+            } else {
+                painter.circle_filled(a + (b - a) * port.position, size, color);
+            }
+        }
+    }
+    
+    painter.text(
+        rect.center_top() + vec2(0.0, 10.0),
+        Align2::CENTER_TOP,
+        format!("{} {} ({:.2}x)", t("lod_showing_scale"), lod_scale + 1, app.zoom),
+        FontId::proportional(14.0),
+        outline,
+    );
+}
+
+// Outlines and ports of the visible pinned shapes, moved by their offsets and labelled
+// with their names; the current shape is drawn as usual even when pinned
+fn render_pinned_shapes(painter: &Painter, app: &ShapeEditor, rect: Rect) {
+    let outline = Color32::from_rgb(150, 160, 200);
+    for pin in app.pinned_shapes.iter().filter(|pin| pin.visible) {
+        let Some(shape_idx) = app.shapes.iter().position(|shape| shape.id == pin.shape_id) else { continue };
+        let shape = &app.shapes[shape_idx];
+        if shape_idx == app.current_shape_idx || shape.vertices.len() < 2 {
+            continue;
+        }
+        
+        let moved = |v: &Vertex| Vertex { x: v.x + pin.offset.x, y: v.y + pin.offset.y };
+        let points: Vec<Pos2> = shape.vertices.iter()
+            .map(|v| app.shape_to_screen_coords(&moved(v), rect))
+            .collect();
+        let n = points.len();
+        if n > 2 {
+            painter.add(egui::Shape::convex_polygon(points.clone(), outline.linear_multiply(0.08), Stroke::none()));
+        }
+        for i in 0..n {
+            painter.line_segment([points[i], points[(i + 1) % n]], Stroke::new(1.5, outline.linear_multiply(0.7)));
+        }
+        for port in shape.ports.iter().filter(|port| port.edge < n) {
+            let (a, b) = (points[port.edge], points[(port.edge + 1) % n]);
+            let color = app.port_palette.color(&port.port_type).linear_multiply(0.6);
+            painter.circle_filled(a + (b - a) * port.position, 3.0 * app.handle_scale, color);
+        }
+        
+        if let Some(center) = shape.centroid() {
+            painter.text(
+                app.shape_to_screen_coords(&moved(&center), rect),
+                Align2::CENTER_CENTER,
+                &shape.name,
+                FontId::proportional(12.0),
+                outline,
+            );
+        }
+    }
+}
+
+// Ghost of the payload on every LAUNCHER and MISSILE port, joined by its own port the
+// way the game attaches it; a payload that isn't in the file is marked at the ports
+fn render_payload_ghosts(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let launcher = &app.shapes[shape_idx];
+    let Some(payload_id) = app.payload_of(launcher.id) else { return };
+    let color = Color32::from_rgb(255, 170, 80);
+    
+    let Some(payload) = app.shapes.iter().find(|shape| shape.id == payload_id) else {
+        let missing = Color32::from_rgb(230, 90, 80);
+        for i in (0..launcher.ports.len()).filter(|&i| crate::payload::is_launch_port(&launcher.ports[i].port_type)) {
+            let Some(at) = launcher.port_position(i) else { continue };
+            painter.text(
+                app.shape_to_screen_coords(&at, rect) + vec2(8.0, -8.0),
+                Align2::LEFT_BOTTOM,
+                format!("⚠ {} {}", t("payload_missing"), payload_id),
+                FontId::proportional(12.0),
+                missing,
+            );
+        }
+        return;
+    };
+    
+    for (n, placement) in crate::payload::ghost_placements(launcher, payload).iter().enumerate() {
+        let mut points: Vec<Pos2> = placement.outline(payload).iter()
+            .map(|v| app.shape_to_screen_coords(v, rect))
+            .collect();
+        if points.len() > 2 {
+            painter.add(egui::Shape::convex_polygon(points.clone(), color.linear_multiply(0.08), Stroke::none()));
+        }
+        if let Some(&first) = points.first() {
+            points.push(first);
+            painter.extend(egui::Shape::dashed_line(&points, Stroke::new(1.5, color.linear_multiply(0.8)), 6.0, 4.0));
+        }
+        
+        // One label is enough when every port fires the same thing
+        if let Some(center) = payload.centroid().filter(|_| n == 0) {
+            painter.text(
+                app.shape_to_screen_coords(&placement.transform(&center), rect),
+                Align2::CENTER_CENTER,
+                &payload.name,
+                FontId::proportional(12.0),
+                color,
+            );
+        }
+    }
+}
+
+// With launcher_radial set, launched blocks leave along the line from the shape's
+// center through each LAUNCHER port: draw that ray and a short arc of the circle
+// the ports sit on
+fn render_launcher_radial(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let center = match shape.centroid() {
+        Some(center) => app.shape_to_screen_coords(&center, rect),
+        None => return,
+    };
+    let color = app.port_palette.color(&PortType::Launcher);
+    let stroke = Stroke::new(1.5, color);
+    
+    painter.circle_stroke(center, 3.0 * app.handle_scale, stroke);
+    
+    for (port_idx, port) in shape.ports.iter().enumerate() {
+        if port.port_type != PortType::Launcher {
+            continue;
+        }
+        let port_pos = match shape.port_position(port_idx) {
+            Some(pos) => app.shape_to_screen_coords(&pos, rect),
+            None => continue,
+        };
+        let offset = port_pos - center;
+        let radius = offset.length();
+        if radius < 1.0 {
+            continue;
+        }
+        let direction = offset / radius;
+        
+        painter.extend(egui::Shape::dashed_line(&[center, port_pos], Stroke::new(1.0, color.linear_multiply(0.6)), 4.0, 4.0));
+        painter.arrow(port_pos, direction * 30.0 * app.handle_scale, stroke);
+        
+        // ±20° of the launch circle around the port
+        let angle = direction.y.atan2(direction.x);
+        let arc: Vec<Pos2> = (0..=16)
+            .map(|i| {
+                let a = angle + (i as f32 / 16.0 - 0.5) * 40f32.to_radians();
+                center + vec2(a.cos(), a.sin()) * radius
+            })
+            .collect();
+        painter.add(egui::Shape::line(arc, Stroke::new(2.0, color.linear_multiply(0.5))));
+    }
+}
+
+// Fan of shots leaving each WEAPON_OUT port along its edge normal. Each ray stops
+// at the first edge of the shape it hits, so obstructed parts of the arc show up
+// as gaps in the fan.
+fn render_firing_arcs(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let n = shape.vertices.len();
+    let polygon: Vec<Vec2> = shape.vertices.iter().map(|v| Vec2::new(v.x, v.y)).collect();
+    
+    // The cannon of a block in blocks.lua using this shape wins over the defaults
+    let cannon = app.blocks_file.as_ref().and_then(|blocks_file| blocks_file.cannon_for_shape(shape.id)).unwrap_or_default();
+    let spread = cannon.spread.map_or(app.firing_arc_spread.to_radians(), |spread| spread.abs());
+    let range = cannon.range.unwrap_or(app.firing_arc_range) * app.firing_arc_range_scale;
+    if range <= 0.0 {
+        return;
+    }
+    
+    let color = app.port_palette.color(&PortType::WeaponOut);
+    let blocked_color = Color32::from_rgba_unmultiplied(255, 80, 80, 160);
+    
+    for (port_idx, port) in shape.ports.iter().enumerate() {
+        if port.port_type != PortType::WeaponOut {
+            continue;
+        }
+        let (pos, normal) = match (shape.port_position(port_idx), shape.edge_normal(port.edge)) {
+            (Some(pos), Some(normal)) => (Vec2::new(pos.x, pos.y), Vec2::new(normal.0, normal.1)),
+            _ => continue,
+        };
+        let to_screen = |p: Vec2| app.shape_to_screen_coords(&Vertex { x: p.x, y: p.y }, rect);
+        let origin = to_screen(pos);
+        
+        let rays = ((spread.to_degrees() / 2.0).ceil() as usize).clamp(1, 64) * 2;
+        let mut ends = Vec::with_capacity(rays + 1);
+        for i in 0..=rays {
+            let angle = (i as f32 / rays as f32 - 0.5) * 2.0 * spread;
+            let direction = Vec2::new(
+                normal.x * angle.cos() - normal.y * angle.sin(),
+                normal.x * angle.sin() + normal.y * angle.cos(),
+            );
+            let end = pos + direction * range;
+            // Nearest hit on any edge but the one the port sits on
+            let hit = (0..n)
+                .filter(|&edge| edge != port.edge)
+                .filter_map(|edge| segment_hit(pos, end, polygon[edge], polygon[(edge + 1) % n]))
+                .fold(None, |nearest: Option<f32>, t| Some(nearest.map_or(t, |nearest| nearest.min(t))));
+            ends.push((pos + direction * range * hit.unwrap_or(1.0), hit.is_some()));
+        }
+        
+        for pair in ends.windows(2) {
+            let (a, a_blocked) = pair[0];
+            let (b, b_blocked) = pair[1];
+            let fill = if a_blocked || b_blocked { blocked_color } else { color };
+            painter.add(egui::Shape::convex_polygon(
+                vec![origin, to_screen(a), to_screen(b)],
+                fill.linear_multiply(0.15),
+                Stroke::none(),
+            ));
+            painter.line_segment([to_screen(a), to_screen(b)], Stroke::new(1.0, fill));
+        }
+        // Edges of the fan
+        for &(end, blocked) in [ends[0], ends[rays]].iter() {
+            painter.line_segment([origin, to_screen(end)], Stroke::new(1.0, if blocked { blocked_color } else { color }));
+        }
+    }
+}
+
+// Fraction along `a1`-`a2` where it crosses `b1`-`b2`, if it does
+fn segment_hit(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<f32> {
+    let r = a2 - a1;
+    let s = b2 - b1;
+    let denominator = r.perp_dot(s);
+    if denominator.abs() < 1e-9 {
+        return None;
+    }
+    let t = (b1 - a1).perp_dot(s) / denominator;
+    let u = (b1 - a1).perp_dot(r) / denominator;
+    // Ignore hits right at the port, e.g. on the neighbouring edges' shared corners
+    (t > 1e-4 && t <= 1.0 && (0.0..=1.0).contains(&u)).then(|| t)
+}
+
+// Ghost-render a standard square block attached to each selected port, red if it
+// would overlap the shape and green if it connects cleanly
+fn render_connection_preview(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let n = shape.vertices.len();
+    let polygon: Vec<Vec2> = shape.vertices.iter().map(|v| Vec2::new(v.x, v.y)).collect();
+    
+    for port_idx in app.selection.port_indices(shape.ports.len()) {
+        let (pos, normal) = match (shape.port_position(port_idx), shape.edge_normal(shape.ports[port_idx].edge)) {
+            (Some(pos), Some(normal)) => (Vec2::new(pos.x, pos.y), Vec2::new(normal.0, normal.1)),
+            _ => continue,
+        };
+        let tangent = Vec2::new(-normal.y, normal.x);
+        let half = TEST_BLOCK_SIZE / 2.0;
+        
+        let corners = [
+            pos - tangent * half,
+            pos + tangent * half,
+            pos + tangent * half + normal * TEST_BLOCK_SIZE,
+            pos - tangent * half + normal * TEST_BLOCK_SIZE,
+        ];
+        
+        // Test a slightly inset copy so touching the attached edge doesn't count
+        let inset = TEST_BLOCK_SIZE * 0.01;
+        let test_corners: Vec<Vec2> = corners.iter()
+            .map(|&c| c + normal * inset + (pos + normal * half - c).normalize_or_zero() * inset)
+            .collect();
+        let edges_cross = (0..4).any(|i| {
+            let (a1, a2) = (test_corners[i], test_corners[(i + 1) % 4]);
+            (0..n).any(|j| intersect_segment_segment(a1, a2, polygon[j], polygon[(j + 1) % n]))
+        });
+        let overlaps = edges_cross || intersect_poly_point(&polygon, pos + normal * half);
+        
+        let color = if overlaps {
+            Color32::from_rgba_unmultiplied(255, 80, 80, 200)
+        } else {
+            Color32::from_rgba_unmultiplied(80, 255, 120, 200)
+        };
+        
+        let points: Vec<Pos2> = corners.iter()
+            .map(|c| app.shape_to_screen_coords(&Vertex { x: c.x, y: c.y }, rect))
+            .collect();
+        painter.add(egui::Shape::convex_polygon(
+            points.clone(),
+            color.linear_multiply(0.2),
+            Stroke::new(1.0, color),
+        ));
+        
+        // Mark the test block's own port at the middle of its attached edge
+        painter.circle_stroke(points[0] + (points[1] - points[0]) * 0.5, 4.0, Stroke::new(1.0, color));
+    }
+}
+
+// Annotates each edge with its length and each vertex with its interior angle
+fn render_measurements(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
+    let shape = &app.shapes[shape_idx];
+    let n = shape.vertices.len();
+    let font = FontId::proportional(11.0);
+    let edge_color = Color32::from_rgb(255, 220, 120);
+    let angle_color = Color32::from_rgb(140, 220, 255);
+    let screen: Vec<Pos2> = shape.vertices.iter()
+        .map(|v| app.shape_to_screen_coords(v, rect))
+        .collect();
+    
+    // A two-vertex shape has a single edge, not a closed loop
+    let edge_count = if n == 2 { 1 } else { n };
+    for edge in 0..edge_count {
+        let (a, b) = (screen[edge], screen[(edge + 1) % n]);
+        let mid = a + (b - a) * 0.5;
+        let offset = match shape.edge_normal(edge) {
+            Some((nx, ny)) => {
+                let tip = app.shape_to_screen_coords(&Vertex {
+                    x: (shape.vertices[edge].x + shape.vertices[(edge + 1) % n].x) / 2.0 + nx,
+                    y: (shape.vertices[edge].y + shape.vertices[(edge + 1) % n].y) / 2.0 + ny,
+                }, rect);
+                (tip - mid).normalized() * 12.0
+            }
+            None => vec2(0.0, -12.0),
+        };
+        painter.text(
+            mid + offset,
+            Align2::CENTER_CENTER,
+            format!("{:.2}", shape.edge_length(edge)),
+            font.clone(),
+            edge_color,
+        );
+    }
+    
+    if n < 3 {
+        return;
+    }
+    for i in 0..n {
+        let cur = screen[i];
+        let to_prev = (screen[(i + n - 1) % n] - cur).normalized();
+        let to_next = (screen[(i + 1) % n] - cur).normalized();
+        let angle = shape.interior_angle(i);
+        // The bisector of the two neighbours points inside for convex corners
+        let mut inward = to_prev + to_next;
+        if inward.length() < 1e-3 {
+            inward = vec2(-to_next.y, to_next.x);
+        }
+        let mut inward = inward.normalized();
+        if angle > 180.0 {
+            inward = -inward;
+        }
+        painter.text(
+            cur + inward * 16.0,
+            Align2::CENTER_CENTER,
+            format!("{:.1}°", angle),
+            font.clone(),
+            angle_color,
+        );
+    }
+}
+
+// Draws the measured segment with its distance and angle
+fn render_measure_tool(painter: &Painter, app: &ShapeEditor, rect: Rect) {
+    let (start, end) = match (&app.measure_start, &app.measure_end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return,
+    };
+    let color = Color32::from_rgb(255, 120, 255);
+    let a = app.shape_to_screen_coords(start, rect);
+    let b = app.shape_to_screen_coords(end, rect);
+    
+    painter.line_segment([a, b], Stroke::new(1.5, color));
+    painter.circle_stroke(a, 4.0, Stroke::new(1.0, color));
+    painter.circle_stroke(b, 4.0, Stroke::new(1.0, color));
+    
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let distance = (dx * dx + dy * dy).sqrt();
+    let angle = dy.atan2(dx).to_degrees();
+    painter.text(
+        b + vec2(10.0, -10.0),
+        Align2::LEFT_BOTTOM,
+        format!("{:.2}\n{:.1}°\nΔ {:.2}, {:.2}", distance, angle, dx, dy),
+        FontId::proportional(13.0),
+        color,
+    );
+}
+
+// Crosshair at the pivot, with a dashed line to the origin the game turns the shape about
+fn render_pivot(painter: &Painter, app: &ShapeEditor, rect: Rect) {
+    let Some(pivot) = &app.pivot else { return };
+    let color = Color32::from_rgb(255, 160, 40);
+    let at = app.shape_to_screen_coords(pivot, rect);
+    let origin = app.shape_to_screen_coords(&Vertex { x: 0.0, y: 0.0 }, rect);
+    
+    painter.extend(egui::Shape::dashed_line(&[origin, at], Stroke::new(1.0, color.linear_multiply(0.6)), 4.0, 4.0));
+    painter.circle_stroke(at, 6.0, Stroke::new(1.5, color));
+    painter.line_segment([at - vec2(10.0, 0.0), at + vec2(10.0, 0.0)], Stroke::new(1.0, color));
+    painter.line_segment([at - vec2(0.0, 10.0), at + vec2(0.0, 10.0)], Stroke::new(1.0, color));
+    painter.text(at + vec2(8.0, -8.0), Align2::LEFT_BOTTOM, t("pivot"), FontId::proportional(12.0), color);
+}
+
+// Corner overview with the visible viewport; click or drag on it to pan
+fn render_minimap(ctx: &egui::Context, app: &mut ShapeEditor, shape_idx: usize, rect: Rect) {
+    let size = vec2(160.0, 120.0);
+    let top = if app.show_rulers { RULER_HEIGHT } else { 0.0 };
+    let origin = rect.right_top() + vec2(-size.x - 10.0, top + 10.0);
+    
+    // Visible part of the canvas in shape coordinates
+    let view_min = app.screen_to_shape_coords_unsnapped(rect.min, rect);
+    let view_max = app.screen_to_shape_coords_unsnapped(rect.max, rect);
+    let mut bounds = Rect::from_min_max(pos2(view_min.x, view_min.y), pos2(view_max.x, view_max.y));
+    for v in &app.shapes[shape_idx].vertices {
+        bounds.extend_with(pos2(v.x, v.y));
+    }
+    let bounds = bounds.expand(app.grid_size);
+    
+    // Uniform scale so the shape isn't distorted, centered in the widget
+    let scale = (size.x / bounds.width()).min(size.y / bounds.height());
+    let offset = (size - bounds.size() * scale) * 0.5;
+    let to_minimap = |x: f32, y: f32| origin + offset + (pos2(x, y) - bounds.min) * scale;
+    
+    egui::Area::new("minimap")
+        .fixed_pos(origin)
+        .order(Order::Foreground)
+        .show(ctx, |ui| {
+        let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+        painter.rect(
+            response.rect,
+            4.0,
+            Color32::from_rgba_unmultiplied(16, 16, 16, 230),
+            Stroke::new(1.0, Color32::from_rgb(140, 140, 140)),
+        );
+        
+        let points: Vec<Pos2> = app.shapes[shape_idx].vertices.iter()
+            .map(|v| to_minimap(v.x, v.y))
+            .collect();
+        if points.len() > 2 {
+            painter.add(egui::Shape::closed_line(points, Stroke::new(1.0, Color32::from_rgb(100, 180, 255))));
+        } else if points.len() == 2 {
+            painter.line_segment([points[0], points[1]], Stroke::new(1.0, Color32::from_rgb(100, 180, 255)));
+        }
+        
+        painter.rect_stroke(
+            Rect::from_min_max(to_minimap(view_min.x, view_min.y), to_minimap(view_max.x, view_max.y)),
+            0.0,
+            Stroke::new(1.0, Color32::YELLOW),
+        );
+        
+        // Center the viewport on the point under the cursor
+        if response.clicked() || response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let target = bounds.min + (pos - origin - offset) / scale;
+                app.pan = vec2(-target.x, -target.y);
+            }
+        }
+    });
+}
+
+// Helper function to render all vertices
+fn render_vertices(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect: Rect) {
     for (i, v) in app.shapes[shape_idx].vertices.iter().enumerate() {
         let pos = app.shape_to_screen_coords(v, rect);
-        let is_selected = app.shapes[shape_idx].selected_vertex == Some(i);
+        let is_selected = app.selection.vertex == Some(i);
         let is_first = i == 0;
         
         // Special highlighting for first vertex
@@ -771,121 +2555,108 @@ fn render_vertices(painter: &Painter, app: &ShapeEditor, shape_idx: usize, rect:
             (Color32::DARK_BLUE, Color32::WHITE, 5.0)
         };
         
+        let size = size * app.handle_scale;
         painter.circle_filled(pos, size, fill_color);
         painter.circle_stroke(pos, size, Stroke::new(1.0, stroke_color));
         
         // Display vertex number
-        painter.text(
-            pos + vec2(10.0, 0.0),
-            Align2::LEFT_CENTER,
-            format!("{}", i),
-            FontId::monospace(14.0),
-            if is_selected { Color32::YELLOW } else { Color32::WHITE },
-        );
+        if app.show_vertex_labels {
+            painter.text(
+                pos + vec2(10.0 * app.handle_scale, 0.0),
+                Align2::LEFT_CENTER,
+                format!("{}", i),
+                FontId::monospace(14.0 * app.handle_scale),
+                if is_selected { Color32::YELLOW } else { Color32::WHITE },
+            );
+        }
     }
 }
 
 // Handle canvas clicks for adding/selecting vertices and ports
 fn handle_canvas_clicks(app: &mut ShapeEditor, response: Response, rect: Rect, shape_idx: usize) {
+    // A text field doesn't consume the keys typed into it, so Backspace there must not
+    // also reach the selection. Read before taking the input, which locks the context.
+    let keys_free = app.rebinding.is_none() && response.ctx.memory().focus().is_none();
     let input = response.ctx.input();
     
     // Handle Escape key to clear selection
-    if input.key_pressed(egui::Key::Escape) {
-        app.shapes[shape_idx].selected_vertex = None;
-        app.shapes[shape_idx].selected_port = None;
+    let clear_pressed = keys_free && app.keybindings.pressed(Action::ClearSelection, &input);
+    if clear_pressed {
+        app.selection.select_port(None);
     }
     
     // Handle Delete key to remove selected elements
-    if input.key_pressed(egui::Key::Delete) || input.key_pressed(egui::Key::Backspace) {
-        if let Some(vertex_idx) = app.shapes[shape_idx].selected_vertex {
+    if keys_free && app.keybindings.pressed(Action::DeleteSelection, &input) {
+        let selected_ports = app.selection.port_indices(app.shapes[shape_idx].ports.len());
+        if let Some(vertex_idx) = app.selection.vertex {
             app.remove_vertex(shape_idx, vertex_idx);
-        } else if let Some(port_idx) = app.shapes[shape_idx].selected_port {
+        } else if selected_ports.len() > 1 {
+            app.remove_ports(shape_idx, &selected_ports);
+        } else if let Some(port_idx) = app.selection.port {
             app.remove_port(shape_idx, port_idx);
         }
     }
     
+    // Space + drag is panning, handled by the central panel
+    if input.key_down(egui::Key::Space) {
+        return;
+    }
+    
+    // The measure tool takes over primary clicks and drags
+    if app.measure_tool {
+        if response.drag_started() {
+            let origin = input.pointer.press_origin().or(response.interact_pointer_pos());
+            app.measure_start = origin.map(|pos| app.screen_to_shape_coords(pos, rect));
+            app.measure_end = app.measure_start.clone();
+        }
+        if response.dragged_by(egui::PointerButton::Primary) {
+            if let Some(mouse_pos) = response.interact_pointer_pos() {
+                app.measure_end = Some(app.screen_to_shape_coords(mouse_pos, rect));
+            }
+        }
+        if clear_pressed {
+            app.measure_start = None;
+            app.measure_end = None;
+        }
+        return;
+    }
+    
     // Add or select vertex/port on click
     if response.clicked() {
         if let Some(mouse_pos) = response.interact_pointer_pos() {
             // Check if Alt is pressed for port creation mode
             let alt_pressed = input.modifiers.alt;
             
-            // First check for clicking on ports
-            let mut clicked_port_idx = None;
-            
-            for (i, port) in app.shapes[shape_idx].ports.iter().enumerate() {
-                let edge_idx = port.edge;
-                if edge_idx < app.shapes[shape_idx].vertices.len() {
-                    let v1 = &app.shapes[shape_idx].vertices[edge_idx];
-                    let v2 = &app.shapes[shape_idx].vertices[(edge_idx + 1) % app.shapes[shape_idx].vertices.len()];
-                    
-                    let start = app.shape_to_screen_coords(v1, rect);
-                    let end = app.shape_to_screen_coords(v2, rect);
-                    
-                    let t = port.position;
-                    let port_pos = Pos2 {
-                        x: start.x + (end.x - start.x) * t,
-                        y: start.y + (end.y - start.y) * t,
-                    };
-                    
-                    if (mouse_pos - port_pos).length() < 10.0 {
-                        clicked_port_idx = Some(i);
-                        break;
-                    }
-                }
-            }
-            
-            // Then check for clicking on vertices
-            let mut clicked_vertex_idx = None;
-            
-            if clicked_port_idx.is_none() {
-                for (i, v) in app.shapes[shape_idx].vertices.iter().enumerate() {
-                    let pos = app.shape_to_screen_coords(v, rect);
-                    if (mouse_pos - pos).length() < 10.0 {
-                        clicked_vertex_idx = Some(i);
-                        break;
-                    }
-                }
-            }
+            // First check for clicking on ports, then vertices
+            let clicked_port_idx = port_at(app, shape_idx, mouse_pos, rect);
+            let clicked_vertex_idx = if clicked_port_idx.is_none() {
+                vertex_at(app, shape_idx, mouse_pos, rect)
+            } else {
+                None
+            };
             
             // Check for clicking on an edge to add a port (when Alt is pressed or no vertex is clicked)
             let mut clicked_edge = None;
             let mut edge_position = 0.5; // Default position on edge
             
             if (clicked_vertex_idx.is_none() && clicked_port_idx.is_none()) || alt_pressed {
-                for i in 0..app.shapes[shape_idx].vertices.len() {
-                    let v1 = &app.shapes[shape_idx].vertices[i];
-                    let v2 = &app.shapes[shape_idx].vertices[(i + 1) % app.shapes[shape_idx].vertices.len()];
-                    
-                    let start = app.shape_to_screen_coords(v1, rect);
-                    let end = app.shape_to_screen_coords(v2, rect);
-                    
-                    // Check distance from point to line segment
-                    let closest = closest_point_on_line_segment(mouse_pos, start, end);
-                    let distance = (mouse_pos - closest).length();
-                    
-                    if distance < 10.0 {
-                        clicked_edge = Some(i);
-                        
-                        // Calculate normalized position along the edge
-                        let total_length = (end - start).length();
-                        if total_length > 0.0 {
-                            edge_position = (closest - start).length() / total_length;
-                        }
-                        break;
-                    }
+                if let Some((edge_idx, position)) = edge_at(app, shape_idx, mouse_pos, rect) {
+                    clicked_edge = Some(edge_idx);
+                    edge_position = position;
                 }
             }
             
             // Handle selections and creations
             if let Some(port_idx) = clicked_port_idx {
-                // Select port
-                app.shapes[shape_idx].selected_port = Some(port_idx);
-                app.shapes[shape_idx].selected_vertex = None;
+                // Select port, Shift+click adds to / removes from the selection
+                if input.modifiers.shift {
+                    app.selection.toggle_port(port_idx);
+                } else {
+                    app.selection.select_port(Some(port_idx));
+                }
             } else if let Some(vertex_idx) = clicked_vertex_idx {
                 // Select vertex
-                app.shapes[shape_idx].selected_vertex = Some(vertex_idx);
-                app.shapes[shape_idx].selected_port = None;
+                app.selection.select_vertex(Some(vertex_idx));
             } else if alt_pressed && clicked_edge.is_some() {
                 // Add a new port on edge when Alt is pressed
                 let edge_idx = clicked_edge.unwrap();
@@ -895,49 +2666,16 @@ fn handle_canvas_clicks(app: &mut ShapeEditor, response: Response, rect: Rect, s
                     port_type: PortType::Default,
                 });
                 // Select the new port
-                app.shapes[shape_idx].selected_port = Some(app.shapes[shape_idx].ports.len() - 1);
-                app.shapes[shape_idx].selected_vertex = None;
+                let new_port_idx = app.shapes[shape_idx].ports.len() - 1;
+                app.selection.select_port(Some(new_port_idx));
             } else if clicked_edge.is_some() && app.shapes[shape_idx].vertices.len() > 2 {
                 // Clicking on an edge can select it or add a vertex in the middle
                 if input.modifiers.ctrl {
-                    // Ctrl+Click on edge to add a vertex in the middle
-                    let edge_idx = clicked_edge.unwrap();
-                    let v1 = &app.shapes[shape_idx].vertices[edge_idx];
-                    let v2 = &app.shapes[shape_idx].vertices[(edge_idx + 1) % app.shapes[shape_idx].vertices.len()];
-                    
-                    // Create new vertex at the midpoint (or clicked position)
-                    let new_vertex = Vertex {
-                        x: v1.x + (v2.x - v1.x) * edge_position,
-                        y: v1.y + (v2.y - v1.y) * edge_position,
-                    };
-                    
-                    // Insert new vertex after edge_idx
-                    app.save_state();
-                    app.shapes[shape_idx].vertices.insert(edge_idx + 1, new_vertex);
-                    app.shapes[shape_idx].selected_vertex = Some(edge_idx + 1);
-                    app.shapes[shape_idx].selected_port = None;
-                    
-                    // Adjust ports on this edge
-                    for port in &mut app.shapes[shape_idx].ports {
-                        if port.edge == edge_idx {
-                            if port.position > edge_position {
-                                // Port is after the new vertex, move it to new edge
-                                port.edge = edge_idx + 1;
-                                // Adjust position to new edge scale
-                                port.position = (port.position - edge_position) / (1.0 - edge_position);
-                            } else {
-                                // Port is before new vertex, keep it on same edge but rescale
-                                port.position = port.position / edge_position;
-                            }
-                        } else if port.edge > edge_idx {
-                            // Increment edge index for all ports after this edge
-                            port.edge += 1;
-                        }
-                    }
+                    // Ctrl+Click on edge to add a vertex at the clicked position
+                    app.split_edge(shape_idx, clicked_edge.unwrap(), edge_position);
                 } else {
                     // Just clear selection when clicking empty space
-                    app.shapes[shape_idx].selected_vertex = None;
-                    app.shapes[shape_idx].selected_port = None;
+                    app.selection.select_port(None);
                 }
             } else {
                 // Add new vertex when clicking on empty space
@@ -951,25 +2689,40 @@ fn handle_canvas_clicks(app: &mut ShapeEditor, response: Response, rect: Rect, s
     let drag_ongoing = response.dragged_by(egui::PointerButton::Primary);
     let drag_started = response.drag_started();
     
-    if let Some(idx) = app.shapes[shape_idx].selected_vertex {
+    // Ctrl+Alt slows the drag down. The pointer's lead over the dragged point is kept
+    // when the keys are let go, so the point doesn't jump back under the cursor.
+    let precise = input.modifiers.ctrl && input.modifiers.alt;
+    if drag_started {
+        app.drag_pointer_offset = egui::Vec2::ZERO;
+    } else if drag_ongoing && precise {
+        app.drag_pointer_offset += input.pointer.delta() * (1.0 - PRECISION_DRAG_SCALE);
+    }
+    let pointer_pos = response.interact_pointer_pos().map(|pos| pos - app.drag_pointer_offset);
+    
+    if let Some(idx) = app.selection.vertex {
         if drag_ongoing {
-            if let Some(mouse_pos) = response.interact_pointer_pos() {
-                let shape_coords = app.screen_to_shape_coords(mouse_pos, rect);
+            if let Some(mouse_pos) = pointer_pos {
+                // Grid snapping would undo the fine movement
+                let shape_coords = if precise {
+                    app.screen_to_shape_coords_unsnapped(mouse_pos, rect)
+                } else {
+                    app.screen_to_shape_coords(mouse_pos, rect)
+                };
                 
                 if drag_started {
-                    // Save state only when drag starts
-                    app.save_state();
+                    // The whole drag is one undo step
+                    app.begin_undo_transaction();
                 }
                 
                 // Update vertex position
                 app.shapes[shape_idx].vertices[idx] = shape_coords;
             }
         }
-    } else if let Some(idx) = app.shapes[shape_idx].selected_port {
+    } else if let Some(idx) = app.selection.port {
         if drag_ongoing {
-            if let Some(mouse_pos) = response.interact_pointer_pos() {
+            if let Some(mouse_pos) = pointer_pos {
                 if drag_started {
-                    app.save_state();
+                    app.begin_undo_transaction();
                 }
                 
                 // Get the edge for this port
@@ -987,8 +2740,16 @@ fn handle_canvas_clicks(app: &mut ShapeEditor, response: Response, rect: Rect, s
                     let closest = closest_point_on_line_segment(mouse_pos, start, end);
                     let total_length = (end - start).length();
                     if total_length > 0.0 {
-                        let new_position = (closest - start).length() / total_length;
-                        app.shapes[shape_idx].ports[idx].position = new_position.clamp(0.0, 1.0);
+                        let mut new_position = ((closest - start).length() / total_length).clamp(0.0, 1.0);
+                        // Where the edge crosses a guide wins over the fraction snapping
+                        if !precise {
+                            if let Some(crossing) = app.edge_guide_snap(v1, v2, new_position) {
+                                new_position = crossing;
+                            } else if app.snap_port_fractions {
+                                new_position = Port::snap_position(new_position);
+                            }
+                        }
+                        app.shapes[shape_idx].ports[idx].position = new_position;
                     }
                 }
             }
@@ -996,37 +2757,700 @@ fn handle_canvas_clicks(app: &mut ShapeEditor, response: Response, rect: Rect, s
     }
 }
 
-// Helper function to find the closest point on a line segment
-fn closest_point_on_line_segment(p: Pos2, a: Pos2, b: Pos2) -> Pos2 {
-    let ap = Vec2::new(p.x - a.x, p.y - a.y);
-    let ab = Vec2::new(b.x - a.x, b.y - a.y);
-    
-    let ab_squared = ab.x * ab.x + ab.y * ab.y;
-    if ab_squared < 0.0001 {
-        return a; // Points A and B are the same
+// Right-click context menus for vertices, edges and ports
+fn handle_context_menu(app: &mut ShapeEditor, response: &Response, rect: Rect, shape_idx: usize) {
+    // Work out what was right-clicked before the menu opens
+    if response.secondary_clicked() {
+        app.context_target = response.interact_pointer_pos().and_then(|pos| {
+            port_at(app, shape_idx, pos, rect).map(CanvasTarget::Port)
+                .or_else(|| vertex_at(app, shape_idx, pos, rect).map(CanvasTarget::Vertex))
+                .or_else(|| edge_at(app, shape_idx, pos, rect).map(|(edge, t)| CanvasTarget::Edge(edge, t)))
+                .or_else(|| {
+                    let point = app.screen_to_shape_coords(pos, rect);
+                    Some(CanvasTarget::Canvas(point.x, point.y))
+                })
+        });
+        
+        match app.context_target {
+            Some(CanvasTarget::Port(idx)) => app.selection.select_port(Some(idx)),
+            Some(CanvasTarget::Vertex(idx)) => {
+                app.selection.select_vertex(Some(idx));
+            },
+            _ => {},
+        }
     }
     
-    let t = (ap.x * ab.x + ap.y * ab.y) / ab_squared;
-    if t < 0.0 {
-        return a; // Beyond point A on the line
-    } else if t > 1.0 {
-        return b; // Beyond point B on the line
-    }
+    let target = match app.context_target {
+        Some(target) => target,
+        None => return,
+    };
     
-    Pos2::new(
-        a.x + ab.x * t,
-        a.y + ab.y * t
-    )
+    response.clone().context_menu(|ui| {
+        let vertex_count = app.shapes[shape_idx].vertices.len();
+        
+        match target {
+            CanvasTarget::Vertex(idx) if idx < vertex_count => {
+                ui.strong(format!("V{}", idx));
+                
+                // Set coordinates directly
+                let vertex = app.shapes[shape_idx].vertices[idx].clone();
+                let (mut x, mut y) = (vertex.x, vertex.y);
+                ui.label(&t("set_coordinates"));
+                ui.horizontal(|ui| {
+                    ui.label("X:");
+                    let changed_x = ui.add(egui::DragValue::new(&mut x).speed(0.1).fixed_decimals(1)).changed();
+                    ui.label("Y:");
+                    let changed_y = ui.add(egui::DragValue::new(&mut y).speed(0.1).fixed_decimals(1)).changed();
+                    if changed_x || changed_y {
+                        app.begin_undo_transaction();
+                        app.add_or_update_vertex(shape_idx, Vertex { x, y }, Some(idx));
+                    }
+                });
+                
+                ui.separator();
+                
+                if vertex_count > 1 {
+                    if ui.button(&t("insert_before")).clicked() {
+                        app.insert_vertex(shape_idx, idx, false);
+                        ui.close_menu();
+                    }
+                    if ui.button(&t("insert_after")).clicked() {
+                        app.insert_vertex(shape_idx, idx, true);
+                        ui.close_menu();
+                    }
+                }
+                if ui.button(&t("delete")).clicked() {
+                    app.remove_vertex(shape_idx, idx);
+                    app.context_target = None;
+                    ui.close_menu();
+                }
+                if ui.button(t("place_pivot_here")).clicked() {
+                    app.pivot = Some(vertex);
+                    ui.close_menu();
+                }
+            },
+            CanvasTarget::Edge(edge, position) if edge < vertex_count => {
+                ui.strong(format!("{} {}", t("edge"), edge));
+                
+                if ui.button(&t("add_port")).clicked() {
+                    app.add_port(shape_idx, Port {
+                        edge,
+                        position,
+                        port_type: PortType::Default,
+                    });
+                    let new_port_idx = app.shapes[shape_idx].ports.len() - 1;
+                    app.selection.select_port(Some(new_port_idx));
+                    ui.close_menu();
+                }
+                if ui.button(&t("subdivide")).clicked() {
+                    app.split_edge(shape_idx, edge, 0.5);
+                    ui.close_menu();
+                }
+            },
+            CanvasTarget::Port(idx) if idx < app.shapes[shape_idx].ports.len() => {
+                ui.strong(format!("P{}", idx));
+                
+                ui.menu_button(&t("type"), |ui| {
+                    let current_type = app.shapes[shape_idx].ports[idx].port_type.clone();
+                    for port_type in PortType::ALL {
+                        let label = port_type.to_string();
+                        if ui.selectable_label(port_type == current_type, label).on_hover_text(port_type.description()).clicked() {
+                            app.set_port_types(shape_idx, &[idx], port_type);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                if ui.button(&t("remove_port")).clicked() {
+                    app.remove_port(shape_idx, idx);
+                    app.context_target = None;
+                    ui.close_menu();
+                }
+            },
+            CanvasTarget::Canvas(x, y) => {
+                if ui.button(t("place_pivot_here")).clicked() {
+                    app.pivot = Some(Vertex { x, y });
+                    ui.close_menu();
+                }
+                if app.pivot.is_some() && ui.button(t("reset_pivot")).clicked() {
+                    app.pivot = None;
+                    ui.close_menu();
+                }
+            },
+            // The element no longer exists (e.g. removed via undo)
+            _ => {
+                ui.close_menu();
+            },
+        }
+    });
+}
+
+// Areas of the top and left rulers on the canvas
+fn ruler_rects(rect: Rect) -> (Rect, Rect) {
+    let top = Rect::from_min_max(rect.min, pos2(rect.max.x, rect.min.y + RULER_HEIGHT));
+    let left = Rect::from_min_max(rect.min, pos2(rect.min.x + RULER_WIDTH, rect.max.y));
+    (top, left)
+}
+
+// Spacing of the labelled ruler ticks in shape units, 1, 2 or 5 times a power of ten,
+// and how many smaller steps each is divided into
+fn ruler_step(zoom: f32) -> (f32, u32) {
+    let min_step = RULER_LABEL_SPACING / zoom;
+    let magnitude = 10f32.powf(min_step.log10().floor());
+    match min_step / magnitude {
+        m if m <= 1.0 => (magnitude, 5),
+        m if m <= 2.0 => (2.0 * magnitude, 4),
+        m if m <= 5.0 => (5.0 * magnitude, 5),
+        _ => (10.0 * magnitude, 5),
+    }
+}
+
+// Tick label with as many decimals as the step needs
+fn ruler_label(value: f32, step: f32) -> String {
+    let decimals = (-step.log10()).ceil().max(0.0) as usize;
+    // Adding 0.0 turns -0 into 0
+    format!("{:.*}", decimals, value + 0.0)
+}
+
+// Rulers in shape coordinates along the top and left edges of the canvas, with the
+// cursor position marked on both
+fn render_rulers(painter: &Painter, app: &ShapeEditor, rect: Rect) {
+    let (top, left) = ruler_rects(rect);
+    let background = Color32::from_rgb(22, 22, 26);
+    let tick_color = Color32::from_rgb(130, 130, 140);
+    let label_color = Color32::from_rgb(180, 180, 190);
+    let cursor_color = Color32::from_rgb(255, 200, 60);
+    painter.rect_filled(top, 0.0, background);
+    painter.rect_filled(left, 0.0, background);
+    
+    let (step, subdivisions) = ruler_step(app.zoom);
+    let minor = step / subdivisions as f32;
+    let font = FontId::monospace(9.0);
+    let view_min = app.screen_to_shape_coords_unsnapped(rect.min + vec2(RULER_WIDTH, RULER_HEIGHT), rect);
+    let view_max = app.screen_to_shape_coords_unsnapped(rect.max, rect);
+    
+    for i in (view_min.x / minor).floor() as i64..=(view_max.x / minor).ceil() as i64 {
+        let value = i as f32 * minor;
+        let x = app.shape_to_screen_coords(&Vertex { x: value, y: 0.0 }, rect).x;
+        if x < left.max.x || x > top.max.x {
+            continue;
+        }
+        let major = i.rem_euclid(subdivisions as i64) == 0;
+        let length = if major { RULER_HEIGHT * 0.6 } else { RULER_HEIGHT * 0.25 };
+        painter.line_segment([pos2(x, top.max.y - length), pos2(x, top.max.y)], Stroke::new(1.0, tick_color));
+        if major {
+            painter.text(pos2(x + 2.0, top.min.y + 1.0), Align2::LEFT_TOP, ruler_label(value, step), font.clone(), label_color);
+        }
+    }
+    for i in (view_min.y / minor).floor() as i64..=(view_max.y / minor).ceil() as i64 {
+        let value = i as f32 * minor;
+        let y = app.shape_to_screen_coords(&Vertex { x: 0.0, y: value }, rect).y;
+        if y < top.max.y || y > left.max.y {
+            continue;
+        }
+        let major = i.rem_euclid(subdivisions as i64) == 0;
+        let length = if major { RULER_WIDTH * 0.3 } else { RULER_WIDTH * 0.12 };
+        painter.line_segment([pos2(left.max.x - length, y), pos2(left.max.x, y)], Stroke::new(1.0, tick_color));
+        if major {
+            painter.text(pos2(left.min.x + 2.0, y + 1.0), Align2::LEFT_TOP, ruler_label(value, step), font.clone(), label_color);
+        }
+    }
+    
+    if let Some(cursor) = &app.cursor_shape_pos {
+        let pos = app.shape_to_screen_coords(cursor, rect);
+        if pos.x > left.max.x {
+            painter.line_segment([pos2(pos.x, top.min.y), pos2(pos.x, top.max.y)], Stroke::new(1.0, cursor_color));
+        }
+        if pos.y > top.max.y {
+            painter.line_segment([pos2(left.min.x, pos.y), pos2(left.max.x, pos.y)], Stroke::new(1.0, cursor_color));
+        }
+    }
+    
+    painter.rect_filled(Rect::from_min_size(rect.min, vec2(RULER_WIDTH, RULER_HEIGHT)), 0.0, background);
+    painter.line_segment([pos2(left.max.x, top.max.y), pos2(top.max.x, top.max.y)], Stroke::new(1.0, tick_color));
+    painter.line_segment([pos2(left.max.x, top.max.y), pos2(left.max.x, left.max.y)], Stroke::new(1.0, tick_color));
+}
+
+// Guide lines across the canvas; the one being dragged is drawn brighter
+fn render_guides(painter: &Painter, app: &ShapeEditor, rect: Rect) {
+    for (i, guide) in app.guides.iter().enumerate() {
+        let color = if app.dragged_guide == Some(i) {
+            Color32::from_rgb(120, 230, 255)
+        } else {
+            Color32::from_rgba_unmultiplied(0, 190, 255, 150)
+        };
+        let line = guide_line(app, guide, rect);
+        painter.line_segment(line, Stroke::new(1.0, color));
+    }
+}
+
+// Ends of a guide line where it crosses the canvas
+fn guide_line(app: &ShapeEditor, guide: &Guide, rect: Rect) -> [Pos2; 2] {
+    match guide.axis {
+        GuideAxis::X => {
+            let x = app.shape_to_screen_coords(&Vertex { x: guide.position, y: 0.0 }, rect).x;
+            [pos2(x, rect.min.y), pos2(x, rect.max.y)]
+        }
+        GuideAxis::Y => {
+            let y = app.shape_to_screen_coords(&Vertex { x: 0.0, y: guide.position }, rect).y;
+            [pos2(rect.min.x, y), pos2(rect.max.x, y)]
+        }
+    }
+}
+
+// Index of the guide under the given screen position
+fn guide_at(app: &ShapeEditor, pos: Pos2, rect: Rect) -> Option<usize> {
+    app.guides.iter().position(|guide| {
+        let [start, _] = guide_line(app, guide, rect);
+        match guide.axis {
+            GuideAxis::X => (pos.x - start.x).abs() < GUIDE_GRAB_PIXELS,
+            GuideAxis::Y => (pos.y - start.y).abs() < GUIDE_GRAB_PIXELS,
+        }
+    })
+}
+
+// A click on a ruler adds a guide there and a drag pulls a new one out of it; a guide
+// dragged on the canvas moves along with the pointer, and one dropped back on a ruler
+// is removed. Returns true while the
+// pointer is busy with rulers or guides, so the click or drag doesn't reach the shape.
+fn handle_guides(app: &mut ShapeEditor, response: &Response, rect: Rect, shape_idx: usize) -> bool {
+    let (top, left) = ruler_rects(rect);
+    let on_ruler = |pos: Pos2| app.show_rulers && (top.contains(pos) || left.contains(pos));
+    // Guides from the top ruler fix X and those from the left one Y; the corner has neither
+    let ruler_axis = |pos: Pos2| match (top.contains(pos), left.contains(pos)) {
+        (true, false) if app.show_rulers => Some(GuideAxis::X),
+        (false, true) if app.show_rulers => Some(GuideAxis::Y),
+        _ => None,
+    };
+    let press_origin = response.ctx.input().pointer.press_origin();
+    if response.ctx.input().key_down(egui::Key::Space) {
+        return false;
+    }
+    
+    // Vertices and ports under the pointer come before the guides behind them
+    let drag_origin = press_origin.filter(|_| response.drag_started() && response.dragged_by(egui::PointerButton::Primary));
+    match drag_origin {
+        Some(origin) if on_ruler(origin) => {
+            if let Some(axis) = ruler_axis(origin) {
+                app.guides.push(Guide { axis, position: 0.0 });
+                app.dragged_guide = Some(app.guides.len() - 1);
+            }
+        }
+        Some(origin) if port_at(app, shape_idx, origin, rect).is_none() && vertex_at(app, shape_idx, origin, rect).is_none() => {
+            app.dragged_guide = guide_at(app, origin, rect);
+        }
+        _ => {}
+    }
+    
+    if let Some(idx) = app.dragged_guide {
+        let pointer = response.interact_pointer_pos();
+        if let (Some(pos), Some(guide)) = (pointer, app.guides.get(idx).copied()) {
+            let point = app.screen_to_shape_coords(pos, rect);
+            let position = match guide.axis {
+                GuideAxis::X => point.x,
+                GuideAxis::Y => point.y,
+            };
+            app.guides[idx].position = position;
+        }
+        if response.drag_released() {
+            if pointer.is_some_and(on_ruler) {
+                app.guides.remove(idx);
+            }
+            app.dragged_guide = None;
+        }
+        return true;
+    }
+    
+    let ruler_click = response.interact_pointer_pos().filter(|&pos| response.clicked() && on_ruler(pos));
+    if let Some(pos) = ruler_click {
+        let point = app.screen_to_shape_coords(pos, rect);
+        match ruler_axis(pos) {
+            Some(GuideAxis::X) => app.guides.push(Guide { axis: GuideAxis::X, position: point.x }),
+            Some(GuideAxis::Y) => app.guides.push(Guide { axis: GuideAxis::Y, position: point.y }),
+            None => {}
+        }
+        return true;
+    }
+    
+    // Drags that start on a ruler don't move the selection either
+    press_origin.is_some_and(on_ruler) && (response.dragged() || response.drag_released())
+}
+
+// Index of the port under the given screen position
+fn port_at(app: &ShapeEditor, shape_idx: usize, pos: Pos2, rect: Rect) -> Option<usize> {
+    let shape = &app.shapes[shape_idx];
+    let n = shape.vertices.len();
+    
+    shape.ports.iter().position(|port| {
+        if port.edge >= n {
+            return false;
+        }
+        let start = app.shape_to_screen_coords(&shape.vertices[port.edge], rect);
+        let end = app.shape_to_screen_coords(&shape.vertices[(port.edge + 1) % n], rect);
+        let port_pos = start + (end - start) * port.position;
+        (pos - port_pos).length() < 10.0 * app.handle_scale
+    })
+}
+
+// Index of the vertex under the given screen position
+fn vertex_at(app: &ShapeEditor, shape_idx: usize, pos: Pos2, rect: Rect) -> Option<usize> {
+    app.shapes[shape_idx].vertices.iter().position(|v| {
+        (pos - app.shape_to_screen_coords(v, rect)).length() < 10.0 * app.handle_scale
+    })
+}
+
+// Edge under the given screen position and the normalized position along it
+fn edge_at(app: &ShapeEditor, shape_idx: usize, pos: Pos2, rect: Rect) -> Option<(usize, f32)> {
+    let vertices = &app.shapes[shape_idx].vertices;
+    let n = vertices.len();
+    
+    for i in 0..n {
+        let start = app.shape_to_screen_coords(&vertices[i], rect);
+        let end = app.shape_to_screen_coords(&vertices[(i + 1) % n], rect);
+        
+        // Check distance from point to line segment
+        let closest = closest_point_on_line_segment(pos, start, end);
+        if (pos - closest).length() < 10.0 {
+            let total_length = (end - start).length();
+            let position = if total_length > 0.0 {
+                (closest - start).length() / total_length
+            } else {
+                0.5
+            };
+            return Some((i, position));
+        }
+    }
+    
+    None
+}
+
+// Helper function to find the closest point on a line segment
+fn closest_point_on_line_segment(p: Pos2, a: Pos2, b: Pos2) -> Pos2 {
+    let ap = Vec2::new(p.x - a.x, p.y - a.y);
+    let ab = Vec2::new(b.x - a.x, b.y - a.y);
+    
+    let ab_squared = ab.x * ab.x + ab.y * ab.y;
+    if ab_squared < 0.0001 {
+        return a; // Points A and B are the same
+    }
+    
+    let t = (ap.x * ab.x + ap.y * ab.y) / ab_squared;
+    if t < 0.0 {
+        return a; // Beyond point A on the line
+    } else if t > 1.0 {
+        return b; // Beyond point B on the line
+    }
+    
+    Pos2::new(
+        a.x + ab.x * t,
+        a.y + ab.y * t
+    )
+}
+
+// Fallback language chain and the list of keys missing for the current language
+fn render_translation_diagnostics(ui: &mut Ui, app: &mut ShapeEditor) {
+    ui.horizontal(|ui| {
+        ui.label(&t("fallback_chain"));
+        let response = ui.add(egui::TextEdit::singleline(&mut app.fallback_chain_text).desired_width(120.0))
+            .on_hover_text(t("fallback_chain_hint"));
+        if response.changed() {
+            let chain = app.fallback_chain_text
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|lang| !lang.is_empty())
+                .map(str::to_string)
+                .collect();
+            crate::translations::set_fallback_chain(chain);
+        }
+    });
+    
+    let mut diagnostics = crate::translations::diagnostics_enabled();
+    if styled_checkbox(ui, &mut diagnostics, &t("translation_diagnostics")).changed() {
+        crate::translations::set_diagnostics(diagnostics);
+    }
+    
+    if !diagnostics {
+        return;
+    }
+    
+    let missing = crate::translations::missing_keys();
+    ui.horizontal(|ui| {
+        ui.label(format!("{}: {}", t("missing_translations"), missing.len()));
+        if !missing.is_empty() {
+            if styled_button(ui, &t("copy")).clicked() {
+                ui.output().copied_text = missing.join("\n");
+            }
+            if styled_button(ui, &t("clear")).clicked() {
+                crate::translations::clear_missing_keys();
+            }
+        }
+    });
+    
+    if missing.is_empty() {
+        ui.label(RichText::new(t("no_missing_translations")).weak());
+    } else {
+        ScrollArea::vertical()
+            .id_source("missing_translations")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for key in &missing {
+                    ui.monospace(key);
+                }
+            });
+    }
 }
 
 // Render settings panel with language selection
-pub fn render_settings_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
+// Arrange tab: a sandbox where copies of the shapes are placed, dragged, turned and
+// snapped port to port, to check that a set of shapes fits together
+pub fn render_arrange_tab(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    let overlaps = app.arrange.overlaps(&app.shapes);
+    let joints = app.arrange.joints(&app.shapes);
+    let mate = app.arrange.picked_mate(&app.shapes);
+    let mut add = None;
+    let mut select = None;
+    let mut remove = None;
+    let mut apply_mate = false;
+    
+    egui::SidePanel::left("arrange_panel")
+        .frame(ui_panel_frame())
+        .resizable(true)
+        .default_width(240.0)
+        .show_inside(ui, |ui| {
+            ui.strong(t("shapes"));
+            egui::ScrollArea::vertical().id_source("arrange_shapes").max_height(200.0).show(ui, |ui| {
+                for (i, shape) in app.shapes.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&shape.name);
+                        ui.with_layout(Layout::right_to_left(), |ui| {
+                            if styled_button(ui, "+").on_hover_text(t("arrange_add")).clicked() {
+                                add = Some(i);
+                            }
+                        });
+                    });
+                }
+            });
+            
+            ui.separator();
+            ui.strong(t("arrange_placed"));
+            for (i, placement) in app.arrange.placements.iter().enumerate() {
+                let name = find_shape(&app.shapes, placement.shape_id)
+                    .map_or_else(|| placement.shape_id.to_string(), |shape| shape.name.clone());
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(app.arrange.selected == Some(i), name).clicked() {
+                        select = Some(i);
+                    }
+                    ui.with_layout(Layout::right_to_left(), |ui| {
+                        if styled_button(ui, "X").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                });
+            }
+            
+            if let Some(placement) = app.arrange.selected.and_then(|i| app.arrange.placements.get_mut(i)) {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("X:");
+                    ui.add(egui::DragValue::new(&mut placement.position.x).speed(0.1).fixed_decimals(1));
+                    ui.label("Y:");
+                    ui.add(egui::DragValue::new(&mut placement.position.y).speed(0.1).fixed_decimals(1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", t("arrange_angle")));
+                    ui.add(egui::DragValue::new(&mut placement.angle).speed(1.0).suffix("°"));
+                    if styled_button(ui, "⟲").clicked() {
+                        placement.angle -= ARRANGE_ROTATION_STEP;
+                    }
+                    if styled_button(ui, "⟳").clicked() {
+                        placement.angle += ARRANGE_ROTATION_STEP;
+                    }
+                });
+            }
+            
+            // Mating two picked ports, with how well they would fit
+            ui.separator();
+            ui.strong(t("mate_ports"));
+            match &mate {
+                Some((_, mate)) => {
+                    let misalignment = mate.port_misalignment.map_or_else(|| "-".to_string(), |d| format!("{:.3}", d));
+                    egui::Grid::new("mate_residuals").num_columns(2).show(ui, |ui| {
+                        ui.label(t("mate_port_gap"));
+                        ui.label(format!("{:.4}", mate.port_gap));
+                        ui.end_row();
+                        ui.label(t("mate_angle_error"));
+                        ui.label(format!("{:.4}°", mate.angle_error));
+                        ui.end_row();
+                        ui.label(t("mate_edge_difference"));
+                        ui.label(format!("{:.3}", mate.edge_length_difference));
+                        ui.end_row();
+                        ui.label(t("mate_port_misalignment"));
+                        ui.label(misalignment);
+                        ui.end_row();
+                    });
+                    if action_button(ui, &t("mate")).clicked() {
+                        apply_mate = true;
+                    }
+                }
+                None => {
+                    ui.label(RichText::new(t("mate_hint")).weak());
+                }
+            }
+            
+            ui.separator();
+            ui.label(format!("{}: {}", t("arrange_joints"), joints.len()));
+            let overlap_color = if overlaps.is_empty() { ui.visuals().text_color() } else { Color32::from_rgb(230, 90, 80) };
+            ui.colored_label(overlap_color, format!("{}: {}", t("arrange_overlaps"), overlaps.len()));
+            if styled_button(ui, &t("arrange_clear")).clicked() {
+                app.arrange = Scene::default();
+            }
+            ui.add_space(6.0);
+            ui.label(RichText::new(t("arrange_help")).weak());
+        });
+    
+    if let Some(i) = add {
+        app.add_to_arrangement(i);
+    }
+    if select.is_some() {
+        app.arrange.selected = select;
+    }
+    if let Some(i) = remove {
+        app.arrange.remove(i);
+    }
+    if let Some((i, mate)) = mate.clone().filter(|_| apply_mate) {
+        app.arrange.placements[i] = mate.placement;
+        app.arrange.picked_ports.clear();
+    }
+    
+    egui::CentralPanel::default()
+        .frame(Frame::none().fill(Color32::from_rgb(0, 0, 0)))
+        .show_inside(ui, |ui| {
+            let response = ui.allocate_response(ui.available_size(), Sense::click_and_drag());
+            let rect = response.rect;
+            
+            // Zoom about the pointer, pan with the middle or right button
+            if let Some(pos) = response.hover_pos() {
+                let scroll = ui.input().scroll_delta.y;
+                if scroll != 0.0 {
+                    let zoom = (app.arrange_zoom * (1.0 + scroll * 0.001)).clamp(0.5, 40.0);
+                    app.arrange_pan += (pos - rect.center()) * (1.0 / zoom - 1.0 / app.arrange_zoom);
+                    app.arrange_zoom = zoom;
+                }
+            }
+            if response.dragged_by(PointerButton::Middle) || response.dragged_by(PointerButton::Secondary) {
+                app.arrange_pan += response.drag_delta() / app.arrange_zoom;
+            }
+            
+            let (zoom, pan, center) = (app.arrange_zoom, app.arrange_pan, rect.center());
+            let to_screen = |v: &Vertex| pos2(center.x + (v.x + pan.x) * zoom, center.y + (v.y + pan.y) * zoom);
+            let to_scene = |p: Pos2| Vertex { x: (p.x - center.x) / zoom - pan.x, y: (p.y - center.y) / zoom - pan.y };
+            
+            // Pick up a placement under the pointer, drag it, and snap it when dropped
+            // A click on a port picks it for mating
+            let port_hit = |pos: Pos2| {
+                app.arrange.placements.iter().enumerate().rev().find_map(|(i, placement)| {
+                    let shape = find_shape(&app.shapes, placement.shape_id)?;
+                    placement.ports(shape).into_iter()
+                        .find(|(_, port)| to_screen(port).distance(pos) <= 8.0 * app.handle_scale)
+                        .map(|(port, _)| (i, port))
+                })
+            };
+            if let Some((placement, port)) = response.interact_pointer_pos().filter(|_| response.clicked()).and_then(port_hit) {
+                app.arrange.pick_port(placement, port);
+            }
+            
+            let picked = response.clicked() || (response.drag_started() && ui.input().pointer.primary_down());
+            if let Some(pos) = response.interact_pointer_pos().filter(|_| picked) {
+                app.arrange.selected = app.arrange.placement_at(&app.shapes, &to_scene(pos));
+            }
+            if let Some(i) = app.arrange.selected {
+                if response.dragged_by(PointerButton::Primary) {
+                    let delta = response.drag_delta() / zoom;
+                    let position = &mut app.arrange.placements[i].position;
+                    position.x += delta.x;
+                    position.y += delta.y;
+                } else if response.drag_released() {
+                    app.arrange.snap(&app.shapes, i, ARRANGE_SNAP_PIXELS / zoom);
+                }
+                
+                if response.hovered() && ui.ctx().memory().focus().is_none() {
+                    let input = ui.input();
+                    if input.key_pressed(Key::Q) {
+                        app.arrange.placements[i].angle -= ARRANGE_ROTATION_STEP;
+                    }
+                    if input.key_pressed(Key::E) {
+                        app.arrange.placements[i].angle += ARRANGE_ROTATION_STEP;
+                    }
+                    let delete = input.key_pressed(Key::Delete);
+                    drop(input);
+                    if delete {
+                        app.arrange.remove(i);
+                    }
+                }
+            }
+            
+            let painter = ui.painter_at(rect);
+            let origin = to_screen(&Vertex { x: 0.0, y: 0.0 });
+            let axis_stroke = Stroke::new(1.0, Color32::from_gray(40));
+            painter.line_segment([pos2(rect.left(), origin.y), pos2(rect.right(), origin.y)], axis_stroke);
+            painter.line_segment([pos2(origin.x, rect.top()), pos2(origin.x, rect.bottom())], axis_stroke);
+            
+            if app.arrange.placements.is_empty() {
+                painter.text(center, Align2::CENTER_CENTER, t("arrange_empty"), FontId::proportional(16.0), Color32::from_gray(120));
+            }
+            
+            let joined: Vec<(usize, usize)> = joints.iter().flat_map(|joint| [joint.a, joint.b]).collect();
+            for (i, placement) in app.arrange.placements.iter().enumerate() {
+                let Some(shape) = find_shape(&app.shapes, placement.shape_id) else { continue };
+                let points: Vec<Pos2> = placement.outline(shape).iter().map(to_screen).collect();
+                if points.len() < 2 {
+                    continue;
+                }
+                
+                let overlapping = overlaps.iter().any(|&(a, b)| a == i || b == i);
+                let selected = app.arrange.selected == Some(i);
+                let outline = match (overlapping, selected) {
+                    (true, _) => Color32::from_rgb(230, 90, 80),
+                    (false, true) => Color32::from_rgb(255, 215, 0),
+                    (false, false) => Color32::from_rgb(200, 220, 255),
+                };
+                if points.len() > 2 {
+                    painter.add(egui::Shape::convex_polygon(points.clone(), outline.linear_multiply(0.12), Stroke::none()));
+                }
+                let n = points.len();
+                for j in 0..n {
+                    painter.line_segment([points[j], points[(j + 1) % n]], Stroke::new(if selected { 2.0 } else { 1.5 }, outline));
+                }
+                
+                for (port_idx, position) in placement.ports(shape) {
+                    let pos = to_screen(&position);
+                    let color = app.port_palette.color(&shape.ports[port_idx].port_type);
+                    painter.circle_filled(pos, 3.0 * app.handle_scale, color);
+                    if joined.contains(&(i, port_idx)) {
+                        painter.circle_stroke(pos, 6.0 * app.handle_scale, Stroke::new(1.5, Color32::from_rgb(80, 220, 120)));
+                    }
+                    if app.arrange.picked_ports.contains(&(i, port_idx)) {
+                        painter.circle_stroke(pos, 8.0 * app.handle_scale, Stroke::new(2.0, Color32::from_rgb(80, 200, 255)));
+                    }
+                }
+            }
+            
+            // Where the mate would move the second picked shape
+            if let Some((mate, shape)) = mate.as_ref().and_then(|(_, mate)| Some((mate, find_shape(&app.shapes, mate.placement.shape_id)?))) {
+                let points: Vec<Pos2> = mate.placement.outline(shape).iter().map(to_screen).collect();
+                let n = points.len();
+                for j in 0..n {
+                    painter.line_segment([points[j], points[(j + 1) % n]], Stroke::new(1.0, Color32::from_rgb(80, 200, 255)));
+                }
+            }
+        });
+}
+
+pub fn render_settings_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
     if app.active_tab != 1 {
         return;
     }
     
     egui::CentralPanel::default()
-        .show(ctx, |ui| {
+        .show_inside(ui, |ui| {
             ui.add_space(20.0);
             
             // Create a frame for settings
@@ -1077,13 +3501,99 @@ pub fn render_settings_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                                 }
                             });
                         
+                        ui.add_space(10.0);
+                        render_translation_diagnostics(ui, app);
+                        
+                        ui.add_space(20.0);
+                        
+                        // Grid appearance
+                        ui.heading(&t("grid_settings"));
+                        ui.add_space(10.0);
+                        
+                        ui.horizontal(|ui| {
+                            ui.label(&t("grid_subdivisions"));
+                            ui.add(egui::DragValue::new(&mut app.grid_style.subdivisions).clamp_range(1..=20));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(&t("grid_minor_color"));
+                            egui::color_picker::color_edit_button_srgba(ui, &mut app.grid_style.minor_color, egui::color_picker::Alpha::OnlyBlend);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(&t("grid_major_color"));
+                            egui::color_picker::color_edit_button_srgba(ui, &mut app.grid_style.major_color, egui::color_picker::Alpha::OnlyBlend);
+                        });
+                        styled_checkbox(ui, &mut app.grid_style.emphasize_axes, &t("grid_emphasize_axes"));
+                        
+                        ui.add_space(20.0);
+                        
+                        // Canvas labels and handles
+                        ui.heading(&t("display_settings"));
+                        ui.add_space(10.0);
+                        
+                        styled_checkbox(ui, &mut app.show_vertex_labels, &t("show_vertex_labels"));
+                        styled_checkbox(ui, &mut app.show_port_labels, &t("show_port_labels"));
+                        ui.horizontal(|ui| {
+                            ui.label(&t("ui_scale"));
+                            ui.add(egui::Slider::new(&mut app.ui_scale, 0.5..=3.0).step_by(0.05).fixed_decimals(2));
+                            if styled_button(ui, "1.0").clicked() {
+                                app.ui_scale = 1.0;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(&t("port_palette"));
+                            egui::ComboBox::from_id_source("port_palette_selector")
+                                .selected_text(match app.port_palette {
+                                    PortPalette::Standard => t("port_palette_standard"),
+                                    PortPalette::ColorBlindSafe => t("port_palette_color_blind"),
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut app.port_palette, PortPalette::Standard, t("port_palette_standard"));
+                                    ui.selectable_value(&mut app.port_palette, PortPalette::ColorBlindSafe, t("port_palette_color_blind"));
+                                });
+                        });
+                        styled_checkbox(ui, &mut app.port_shape_markers, &t("port_shape_markers"));
+                        ui.horizontal(|ui| {
+                            ui.label(&t("handle_scale"));
+                            ui.add(egui::Slider::new(&mut app.handle_scale, 0.5..=3.0).fixed_decimals(1));
+                        });
+                        
+                        ui.add_space(10.0);
+                        ui.label(RichText::new(t("firing_arcs")).strong());
+                        ui.horizontal(|ui| {
+                            ui.label(&t("firing_arc_spread"));
+                            ui.add(egui::Slider::new(&mut app.firing_arc_spread, 0.0..=90.0).suffix("°").fixed_decimals(1));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(&t("firing_arc_range"));
+                            ui.add(egui::DragValue::new(&mut app.firing_arc_range).speed(10.0).clamp_range(0.0..=10000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(&t("firing_arc_range_scale"));
+                            ui.add(egui::Slider::new(&mut app.firing_arc_range_scale, 0.005..=0.5).logarithmic(true).fixed_decimals(3));
+                        });
+                        ui.label(RichText::new(t("firing_arc_blocks_hint")).weak().small());
+                        
+                        ui.add_space(20.0);
+                        
+                        render_keybindings_settings(ui, app);
+                        
+                        ui.add_space(20.0);
+                        
+                        render_id_category_settings(ui, app);
+                        
+                        ui.add_space(20.0);
+                        
+                        if styled_button(ui, &t("tutorial_restart")).clicked() {
+                            app.onboarding = Some(Onboarding::new());
+                            app.active_tab = 0;
+                        }
+                        
                         ui.add_space(20.0);
                         
                         // Add Apply button
                         if action_button(ui, &t("apply")).clicked() {
-                            // Show confirmation message
-                            app.status_message = Some(t("settings_saved"));
-                            app.status_time = 3.0; // Show for 3 seconds
+                            app.flush_settings();
+                            app.toasts.success(t("settings_saved"));
                         }
                     });
                 });
@@ -1091,56 +3601,1488 @@ pub fn render_settings_panel(ctx: &egui::Context, app: &mut ShapeEditor) {
                 ui.add_space(10.0);
             });
             
-            // Show status message if exists
-            if let Some(msg) = &app.status_message {
-                if app.status_time > 0.0 {
-                    // Create a toast-like notification
-                    let job = egui::text::LayoutJob::simple_singleline(
-                        msg.clone(), 
-                        TextStyle::Body.resolve(ui.style()),
-                        Color32::WHITE
-                    );
-                    let galley = ui.painter().layout(
-                        job.text.clone(),
-                        job.sections.first().map(|s| s.format.font_id.clone()).unwrap_or_else(|| TextStyle::Body.resolve(ui.style())),
-                        Color32::WHITE,
-                        f32::INFINITY
-                    );
-                    let padding = 10.0;
-                    let width = galley.rect.width() + padding * 2.0;
-                    let height = galley.rect.height() + padding * 2.0;
-                    
-                    let screen_width = ui.available_width();
-                    let toast_rect = Rect::from_center_size(
-                        Pos2::new(screen_width / 2.0, 60.0),
-                        egui::Vec2::new(width, height)
-                    );
-                    
-                    ui.painter().rect_filled(
-                        toast_rect,
-                        Rounding::same(4.0),
-                        Color32::from_rgba_unmultiplied(40, 40, 40, 230)
-                    );
-                    
-                    ui.painter().rect_stroke(
-                        toast_rect,
-                        Rounding::same(4.0),
-                        Stroke::new(1.0, Color32::from_rgb(100, 200, 100))
-                    );
-                    
-                    ui.painter().text(
-                        toast_rect.center(),
-                        Align2::CENTER_CENTER,
-                        msg,
-                        TextStyle::Body.resolve(ui.style()),
-                        Color32::from_rgb(100, 200, 100)
-                    );
-                    
-                    // Update the timer when drawing the frame
-                    let ctx = ui.ctx();
-                    app.status_time -= ctx.input().predicted_dt;
-                    ctx.request_repaint(); // Ensure we keep rendering
+        });
+} 
+
+// Named starting points for new shape IDs, e.g. armor from 5100 up
+fn render_id_category_settings(ui: &mut Ui, app: &mut ShapeEditor) {
+    ui.heading(t("shape_ids"));
+    ui.add_space(10.0);
+    ui.label(RichText::new(format!(
+        "{} {}–{}",
+        t("shape_id_range"),
+        crate::shape_ids::MIN_SHAPE_ID,
+        crate::shape_ids::MAX_SHAPE_ID
+    )).weak());
+    
+    let mut remove = None;
+    for (i, category) in app.id_categories.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut category.name).desired_width(160.0));
+            ui.add(egui::DragValue::new(&mut category.base)
+                .clamp_range(crate::shape_ids::MIN_SHAPE_ID..=crate::shape_ids::MAX_SHAPE_ID));
+            if styled_button(ui, "X").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        app.id_categories.remove(i);
+        app.id_category = match app.id_category {
+            Some(selected) if selected == i => None,
+            Some(selected) if selected > i => Some(selected - 1),
+            selected => selected,
+        };
+    }
+    
+    if styled_button(ui, &t("add_id_category")).clicked() {
+        let base = app.id_categories.iter().map(|category| category.base + 100).max().unwrap_or(5100);
+        app.id_categories.push(IdCategory {
+            name: t("id_category_new"),
+            base: base.min(crate::shape_ids::MAX_SHAPE_ID),
+        });
+    }
+}
+
+// Keybindings table in the settings tab: click a binding, then press the new key
+fn render_keybindings_settings(ui: &mut Ui, app: &mut ShapeEditor) {
+    ui.heading(&t("keybindings"));
+    ui.add_space(10.0);
+    
+    // Capture the next key press for the binding being edited; Escape cancels
+    if let Some(idx) = app.rebinding {
+        let pressed = ui.input().events.iter().find_map(|event| match event {
+            egui::Event::Key { key, pressed: true, modifiers } => Some((*key, *modifiers)),
+            _ => None,
+        });
+        if let Some((key, modifiers)) = pressed {
+            if key != egui::Key::Escape {
+                if let Some((_, binding)) = app.keybindings.bindings.get_mut(idx) {
+                    *binding = KeyBinding::from_key_event(key, modifiers);
                 }
             }
+            app.rebinding = None;
+        }
+    }
+    
+    egui::Grid::new("keybindings_grid").num_columns(2).striped(true).show(ui, |ui| {
+        for idx in 0..app.keybindings.bindings.len() {
+            let (action, binding) = app.keybindings.bindings[idx];
+            ui.label(action.label());
+            let text = if app.rebinding == Some(idx) { t("press_key") } else { binding.display() };
+            if ui.button(text).clicked() {
+                app.rebinding = Some(idx);
+            }
+            ui.end_row();
+        }
+    });
+    
+    ui.add_space(4.0);
+    if styled_button(ui, &t("reset_keybindings")).clicked() {
+        app.keybindings = Default::default();
+        app.rebinding = None;
+    }
+}
+
+// Progress window for a running import, with a button to cancel it
+pub fn render_import_progress(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(job) = &app.import_job else { return };
+    let mut cancel = false;
+    
+    egui::Window::new(t("importing"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.label(&job.path);
+            ui.add_space(4.0);
+            ui.label(t(job.stage.key()));
+            
+            // Reading and parsing give no intermediate progress
+            let converting = job.stage == ImportStage::Converting;
+            ui.add(egui::ProgressBar::new(if converting { job.progress } else { 0.0 })
+                .desired_width(260.0)
+                .animate(!converting)
+                .show_percentage());
+            ui.label(format!("{}: {}", t("shapes_loaded"), job.shapes.len()));
+            
+            ui.add_space(4.0);
+            if styled_button(ui, &t("cancel")).clicked() {
+                cancel = true;
+            }
         });
-} 
\ No newline at end of file
+    
+    if cancel {
+        app.cancel_import();
+    }
+}
+
+// Spinner in the bottom left corner while an export is written in the background
+pub fn render_export_progress(ctx: &egui::Context, app: &ShapeEditor) {
+    let Some(job) = &app.export_job else { return };
+    
+    egui::Area::new("export_progress")
+        .anchor(Align2::LEFT_BOTTOM, [12.0, -40.0])
+        .order(Order::Foreground)
+        .interactable(false)
+        .show(ctx, |ui| {
+            popup_frame().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(RichText::new(format!("{} {}", t("exporting"), job.path)).small());
+                });
+            });
+        });
+}
+
+// Notification stack in the bottom right corner, newest at the bottom.
+// Hovering a toast keeps it up, clicking it dismisses it.
+pub fn render_toasts(ctx: &egui::Context, app: &mut ShapeEditor) {
+    if app.toasts.is_empty() {
+        return;
+    }
+    
+    let mut hovered = None;
+    let mut dismissed = None;
+    
+    egui::Area::new("toasts")
+        .anchor(Align2::RIGHT_BOTTOM, [-12.0, -40.0])
+        .order(Order::Foreground)
+        .show(ctx, |ui| {
+            for toast in app.toasts.iter() {
+                let (icon, color) = match toast.kind {
+                    ToastKind::Info => ("ℹ", Color32::from_rgb(120, 170, 230)),
+                    ToastKind::Success => ("✔", Color32::from_rgb(100, 200, 100)),
+                    ToastKind::Warning => ("⚠", Color32::from_rgb(230, 180, 60)),
+                    ToastKind::Error => ("❌", Color32::from_rgb(230, 90, 90)),
+                };
+                
+                let frame = Frame::none()
+                    .fill(Color32::from_rgba_unmultiplied(40, 40, 40, 230))
+                    .stroke(Stroke::new(1.0, color))
+                    .inner_margin(10.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(icon).color(color));
+                            ui.label(RichText::new(&toast.text).color(Color32::WHITE));
+                        });
+                    });
+                
+                let response = ui.interact(frame.response.rect, Id::new(("toast", toast.id)), Sense::click());
+                if response.hovered() {
+                    hovered = Some(toast.id);
+                }
+                if response.clicked() {
+                    dismissed = Some(toast.id);
+                }
+                ui.add_space(4.0);
+            }
+        });
+    
+    if let Some(id) = dismissed {
+        app.toasts.dismiss(id);
+    }
+    let dt = ctx.input().unstable_dt.min(0.1);
+    app.toasts.tick(dt, hovered);
+    // Keep the timers running without input
+    ctx.request_repaint();
+}
+
+// Log records collected by the in-app logger, in a resizable panel at the bottom
+pub fn render_log_console(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    if !app.show_log_console {
+        return;
+    }
+    
+    egui::TopBottomPanel::bottom("log_console")
+        .resizable(true)
+        .default_height(160.0)
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+            let entries: Vec<_> = crate::log_console::entries()
+                .into_iter()
+                .filter(|entry| entry.level <= app.log_console_level)
+                .collect();
+            
+            ui.horizontal(|ui| {
+                ui.strong(&t("log_console"));
+                ui.separator();
+                
+                egui::ComboBox::from_id_source("log_console_level")
+                    .selected_text(app.log_console_level.as_str())
+                    .show_ui(ui, |ui| {
+                        for level in [log::LevelFilter::Error, log::LevelFilter::Warn, log::LevelFilter::Info, log::LevelFilter::Debug] {
+                            ui.selectable_value(&mut app.log_console_level, level, level.as_str());
+                        }
+                    });
+                
+                if styled_button(ui, &t("copy")).clicked() {
+                    ui.output().copied_text = entries.iter()
+                        .map(|entry| format!("[{}] {}: {}", entry.level, entry.target, entry.message))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                }
+                if styled_button(ui, &t("clear")).clicked() {
+                    crate::log_console::clear();
+                }
+            });
+            ui.separator();
+            
+            let row_height = ui.text_style_height(&TextStyle::Monospace);
+            egui::ScrollArea::both()
+                .id_source("log_console_entries")
+                .auto_shrink([false, false])
+                .stick_to_bottom()
+                .show_rows(ui, row_height, entries.len(), |ui, range| {
+                    for entry in &entries[range] {
+                        let color = match entry.level {
+                            log::Level::Error => Color32::from_rgb(230, 90, 90),
+                            log::Level::Warn => Color32::from_rgb(230, 180, 60),
+                            log::Level::Info => Color32::LIGHT_GRAY,
+                            log::Level::Debug | log::Level::Trace => Color32::GRAY,
+                        };
+                        let text = format!("[{:<5}] {}: {}", entry.level, entry.target, entry.message);
+                        ui.add(egui::Label::new(RichText::new(text).monospace().color(color)).wrap(false));
+                    }
+                });
+        });
+}
+
+// Results of the validation checks; clicking a problem selects its shape
+pub fn render_problems_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    if !app.show_problems {
+        return;
+    }
+    
+    let mut select = None;
+    let mut apply_fix = None;
+    
+    egui::TopBottomPanel::bottom("problems_panel")
+        .resizable(true)
+        .default_height(140.0)
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+            ui.strong(&t("problems"));
+            ui.separator();
+            
+            if app.problems.is_empty() {
+                ui.label(RichText::new(t("no_problems")).weak());
+                return;
+            }
+            
+            egui::ScrollArea::vertical()
+                .id_source("problems_entries")
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for problem in &app.problems {
+                        let (icon, color) = match problem.severity {
+                            Severity::Error => ("⛔", Color32::from_rgb(230, 90, 90)),
+                            Severity::Warning => ("⚠", Color32::from_rgb(230, 180, 60)),
+                        };
+                        let shape_idx = app.shapes.iter().position(|shape| shape.id == problem.shape_id);
+                        let shape_name = shape_idx.map_or("?", |idx| app.shapes[idx].name.as_str());
+                        
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, icon);
+                            let shape_label = format!("{} (ID: {})", shape_name, problem.shape_id);
+                            if ui.selectable_label(shape_idx == Some(app.current_shape_idx), shape_label).clicked() {
+                                select = shape_idx;
+                            }
+                            if problem.element != Element::Shape {
+                                ui.label(RichText::new(problem.element.to_string()).weak());
+                            }
+                            ui.label(&problem.message);
+                            if let Some(fix) = problem.fix {
+                                if styled_button(ui, &t("fix")).on_hover_text(fix.description()).clicked() {
+                                    apply_fix = Some((problem.shape_id, fix));
+                                }
+                            }
+                        });
+                    }
+                });
+        });
+    
+    if let Some(shape_idx) = select {
+        app.current_shape_idx = shape_idx;
+    }
+    if let Some((shape_id, fix)) = apply_fix {
+        app.apply_fix(shape_id, fix);
+    }
+}
+
+// Area, mass and estimated cost of every block, totalled by group. With blocks.lua
+// loaded the blocks come from there; otherwise every shape counts as one block.
+pub fn render_budget_panel(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    if !app.show_budget {
+        return;
+    }
+    
+    let shapes = app.shapes.iter().map(|shape| &**shape);
+    let budget = crate::budget::budget(shapes, app.blocks_file.as_ref(), &app.budget_heuristics);
+    let mut select = None;
+    
+    egui::TopBottomPanel::bottom("budget_panel")
+        .resizable(true)
+        .default_height(200.0)
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(t("budget"));
+                let source = match &app.blocks_file {
+                    Some(blocks_file) => blocks_file.path.clone(),
+                    None => t("budget_no_blocks"),
+                };
+                ui.label(RichText::new(source).weak());
+            });
+            
+            let heuristics = &mut app.budget_heuristics;
+            ui.horizontal_wrapped(|ui| {
+                ui.label(t("budget_density"));
+                ui.add(egui::DragValue::new(&mut heuristics.density).speed(0.01).clamp_range(0.0..=100.0));
+                ui.label(t("budget_durability"));
+                ui.add(egui::DragValue::new(&mut heuristics.durability).speed(0.01).clamp_range(0.0..=100.0));
+                ui.label(t("budget_points_per_mass"));
+                ui.add(egui::DragValue::new(&mut heuristics.points_per_mass).speed(0.001).clamp_range(0.0..=100.0));
+                ui.label(t("budget_points_per_health"));
+                ui.add(egui::DragValue::new(&mut heuristics.points_per_health).speed(0.001).clamp_range(0.0..=100.0));
+                ui.label(RichText::new("ℹ").weak()).on_hover_text(t("budget_heuristics_hint"));
+            });
+            ui.separator();
+            
+            egui::Grid::new("budget_groups").num_columns(5).striped(true).show(ui, |ui| {
+                for heading in ["budget_group", "budget_blocks", "budget_area", "budget_mass", "budget_points"] {
+                    ui.strong(t(heading));
+                }
+                ui.end_row();
+                for (group, totals) in &budget.groups {
+                    ui.label(group.map_or_else(|| "—".to_string(), |group| group.to_string()));
+                    ui.label(totals.blocks.to_string());
+                    ui.label(format!("{:.1}", totals.area));
+                    ui.label(format!("{:.1}", totals.mass));
+                    ui.label(format!("{:.1}", totals.points));
+                    ui.end_row();
+                }
+            });
+            
+            if !budget.missing_shapes.is_empty() {
+                ui.colored_label(Color32::from_rgb(230, 180, 60), format!("⚠ {}: {}", t("budget_missing_shapes"), budget.missing_shapes.join(", ")));
+            }
+            ui.separator();
+            
+            egui::ScrollArea::vertical()
+                .id_source("budget_blocks")
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    egui::Grid::new("budget_block_rows").num_columns(6).striped(true).show(ui, |ui| {
+                        for heading in ["budget_block", "budget_group", "budget_area", "budget_mass", "budget_health", "budget_points"] {
+                            ui.strong(t(heading));
+                        }
+                        ui.end_row();
+                        for block in &budget.blocks {
+                            let shape_idx = app.shapes.iter().position(|shape| shape.id == block.shape_id);
+                            if ui.selectable_label(shape_idx == Some(app.current_shape_idx), &block.label).clicked() {
+                                select = shape_idx;
+                            }
+                            ui.label(block.group.map_or_else(|| "—".to_string(), |group| group.to_string()));
+                            ui.label(format!("{:.1}", block.area));
+                            ui.label(format!("{:.1}", block.mass));
+                            ui.label(format!("{:.1}", block.health));
+                            if block.points_set {
+                                ui.label(format!("{:.1}", block.points));
+                            } else {
+                                ui.label(RichText::new(format!("~{:.1}", block.points)).weak()).on_hover_text(t("budget_estimate_hint"));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+    
+    if let Some(shape_idx) = select {
+        app.current_shape_idx = shape_idx;
+    }
+}
+
+// Rhai scripts run over all shapes at once, for edits too repetitive to do by hand
+pub fn render_script_console(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    if !app.show_script_console {
+        return;
+    }
+    
+    let mut run = false;
+    
+    egui::TopBottomPanel::bottom("script_console")
+        .resizable(true)
+        .default_height(220.0)
+        .frame(ui_panel_frame())
+        .show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(t("script_console"));
+                ui.with_layout(Layout::right_to_left(), |ui| {
+                    run = action_button(ui, &t("run_script")).on_hover_text("Ctrl+Enter").clicked();
+                    ui.label(RichText::new("ℹ").weak()).on_hover_text(crate::script::HELP);
+                });
+            });
+            ui.separator();
+            
+            ui.columns(2, |columns| {
+                let source_id = columns[0].make_persistent_id("script_source");
+                // Taken before the editor sees it, so Ctrl+Enter doesn't also insert a line
+                if columns[0].memory().has_focus(source_id) {
+                    run |= columns[0].input_mut().consume_key(Modifiers::COMMAND, Key::Enter);
+                }
+                egui::ScrollArea::vertical()
+                    .id_source("script_source_scroll")
+                    .auto_shrink([false, false])
+                    .show(&mut columns[0], |ui| {
+                        ui.add(egui::TextEdit::multiline(&mut app.script_source)
+                            .id(source_id)
+                            .code_editor()
+                            .hint_text(t("script_hint"))
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(8));
+                    });
+                
+                egui::ScrollArea::vertical()
+                    .id_source("script_output_scroll")
+                    .auto_shrink([false, false])
+                    .stick_to_bottom()
+                    .show(&mut columns[1], |ui| {
+                        for line in &app.script_output {
+                            ui.monospace(line);
+                        }
+                        if let Some(error) = &app.script_error {
+                            ui.colored_label(Color32::from_rgb(230, 90, 90), format!("⛔ {}", error));
+                        }
+                    });
+            });
+        });
+    
+    if run {
+        app.run_script();
+    }
+}
+
+// The current shape as it will be written to shapes.lua, updated while editing
+pub fn render_lua_preview(ui: &mut egui::Ui, app: &mut ShapeEditor) {
+    let ctx = &ui.ctx().clone();
+    if !app.show_lua_preview {
+        return;
+    }
+    
+    let panel = egui::SidePanel::right("lua_preview_panel")
+        .frame(ui_panel_frame())
+        .resizable(true)
+        .default_width(app.panel_layout.lua_panel_width)
+        .width_range(200.0..=800.0)
+        .show_inside(ui, |ui| {
+            let Some(preview) = &mut app.lua_preview else { return false };
+            
+            ui.horizontal(|ui| {
+                ui.strong(t("lua_preview"));
+                ui.with_layout(Layout::right_to_left(), |ui| {
+                    if styled_button(ui, &t("copy_all")).clicked() {
+                        ui.output().copied_text = preview.text.clone();
+                    }
+                });
+            });
+            ui.label(RichText::new(t("lua_preview_hint")).weak().small());
+            
+            let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            if let Some(error) = &preview.error {
+                ui.colored_label(Color32::from_rgb(230, 90, 90), format!("⛔ {}", error));
+                // The line the parser stopped at
+                if let Some((line, source)) = crate::lua_highlight::error_snippet(&preview.text, error) {
+                    let mut job = crate::lua_highlight::highlight(&format!("{:>4} | {}", line, source.trim_end()), font_id.clone());
+                    job.wrap.max_width = ui.available_width();
+                    ui.label(job);
+                }
+            }
+            ui.separator();
+            
+            let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                let mut job = crate::lua_highlight::highlight(text, font_id.clone());
+                job.wrap.max_width = wrap_width;
+                ui.fonts().layout_job(job)
+            };
+            
+            egui::ScrollArea::both()
+                .id_source("lua_preview_text")
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut preview.text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .layouter(&mut layouter))
+                        .changed()
+                })
+                .inner
+        });
+    app.panel_layout.lua_panel_width = panel.response.rect.width();
+    
+    if panel.inner {
+        app.lua_text_changed(ctx.input().time);
+    }
+}
+
+// Offer to restore the shapes dumped by a crash in the previous session
+pub fn render_recovery_prompt(ctx: &egui::Context, app: &mut ShapeEditor) {
+    if app.recovered_shapes.is_none() {
+        return;
+    }
+    
+    let mut restore = false;
+    let mut discard = false;
+    
+    egui::Window::new(t("recovery_title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            ui.label(t("recovery_message"));
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = crate::recovery::recovery_path() {
+                ui.label(RichText::new(path.display().to_string()).weak().small());
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                restore = action_button(ui, &t("recovery_restore")).clicked();
+                discard = styled_button(ui, &t("recovery_discard")).clicked();
+            });
+        });
+    
+    if restore {
+        app.restore_recovered_shapes();
+    } else if discard {
+        app.discard_recovered_shapes();
+    }
+}
+
+// Small outline of a shape with its ports, fitted into a square
+fn shape_thumbnail(ui: &mut Ui, shape: &crate::data_structures::Shape, size: f32) -> Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 3.0, Color32::from_rgb(10, 10, 10));
+    
+    let n = shape.vertices.len();
+    if n < 2 {
+        return response;
+    }
+    let (mut min, mut max) = (pos2(f32::MAX, f32::MAX), pos2(f32::MIN, f32::MIN));
+    for v in &shape.vertices {
+        min = min.min(pos2(v.x, v.y));
+        max = max.max(pos2(v.x, v.y));
+    }
+    let extent = (max - min).max_elem().max(1e-3);
+    let scale = (size - 8.0) / extent;
+    let center = min + (max - min) * 0.5;
+    // Shape space has y up
+    let to_thumb = |v: &Vertex| rect.center() + egui::vec2(v.x - center.x, center.y - v.y) * scale;
+    
+    let points: Vec<Pos2> = shape.vertices.iter().map(to_thumb).collect();
+    for i in 0..n {
+        painter.line_segment([points[i], points[(i + 1) % n]], Stroke::new(1.0, Color32::WHITE));
+    }
+    for port_idx in 0..shape.ports.len() {
+        if let Some(pos) = shape.port_position(port_idx) {
+            painter.circle_filled(to_thumb(&pos), 1.5, Color32::from_rgb(120, 200, 255));
+        }
+    }
+    response
+}
+
+// Pick which of the imported shapes to bring into the session
+pub fn render_import_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(dialog) = &mut app.import_dialog else { return };
+    
+    let mut import = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("import_dialog_title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            ui.label(RichText::new(&dialog.path).weak());
+            
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if styled_button(ui, &t("select_all")).clicked() {
+                    dialog.include = (0..dialog.shapes.len()).collect();
+                }
+                if styled_button(ui, &t("select_none")).clicked() {
+                    dialog.include.clear();
+                }
+            });
+            
+            ui.add_space(4.0);
+            let shapes = &dialog.shapes;
+            let include = &mut dialog.include;
+            let conflicts = &mut dialog.conflicts;
+            let replace_session = dialog.replace_session;
+            let session = &app.shapes;
+            virtual_list(ui, "import_dialog_shapes", 300.0, shapes.len(), |ui, i| {
+                ui.horizontal(|ui| {
+                    let mut included = include.contains(&i);
+                    if ui.checkbox(&mut included, "").changed() {
+                        if included {
+                            include.insert(i);
+                        } else {
+                            include.remove(&i);
+                        }
+                    }
+                    shape_thumbnail(ui, &shapes[i], 40.0);
+                    ui.vertical(|ui| {
+                        ui.label(&shapes[i].name);
+                        ui.label(RichText::new(format!(
+                            "ID: {} · {} {} · {} {}",
+                            shapes[i].id,
+                            shapes[i].vertices.len(), t("vertices"),
+                            shapes[i].ports.len(), t("ports"),
+                        )).weak().small());
+                    });
+                    
+                    // The ID is taken in the session: keep, replace or renumber
+                    let existing = session.iter().find(|shape| shape.id == shapes[i].id);
+                    if let (Some(existing), false) = (existing, replace_session) {
+                        ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                            let resolution = conflicts.entry(i).or_default();
+                            let label = |resolution: ImportConflict| match resolution {
+                                ImportConflict::KeepExisting => t("conflict_keep_existing"),
+                                ImportConflict::Replace => t("conflict_replace"),
+                                ImportConflict::NewId => t("conflict_new_id"),
+                            };
+                            egui::ComboBox::from_id_source(("import_conflict", i))
+                                .selected_text(label(*resolution))
+                                .width(130.0)
+                                .show_ui(ui, |ui| {
+                                    for option in [ImportConflict::Replace, ImportConflict::KeepExisting, ImportConflict::NewId] {
+                                        ui.selectable_value(resolution, option, label(option));
+                                    }
+                                });
+                            ui.colored_label(Color32::from_rgb(230, 180, 60), "⚠")
+                                .on_hover_text(format!("{}: {}", t("conflict_id_taken"), existing.name));
+                        });
+                    }
+                });
+            });
+            
+            ui.add_space(4.0);
+            ui.checkbox(&mut dialog.replace_session, t("import_replace_session"));
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                let count = dialog.include.len();
+                import = ui.add_enabled_ui(count > 0, |ui| action_button(ui, &format!("{} ({})", t("import"), count))).inner.clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if import {
+        app.apply_import_dialog();
+    } else if cancel {
+        app.import_dialog = None;
+    }
+}
+
+// Choose the mod folder for "Export to mod"
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_mod_export_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(dialog) = &mut app.mod_export_dialog else { return };
+    
+    let mut export = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("export_to_mod"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            ui.horizontal(|ui| {
+                ui.label(t("mod_folder"));
+                ui.add(egui::TextEdit::singleline(&mut dialog.root).desired_width(240.0));
+                if styled_button(ui, &t("browse")).clicked() {
+                    if let Some(root) = ShapeEditor::pick_folder() {
+                        dialog.root = root;
+                    }
+                }
+            });
+            
+            let root = std::path::Path::new(&dialog.root);
+            if !dialog.root.is_empty() && !root.exists() {
+                ui.label(RichText::new(t("mod_folder_will_be_created")).weak().small());
+            }
+            
+            ui.add_enabled_ui(app.blocks_file.is_some(), |ui| {
+                ui.checkbox(&mut dialog.include_blocks, t("mod_include_blocks"));
+            });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                export = ui.add_enabled_ui(!dialog.root.is_empty(), |ui| action_button(ui, &t("export"))).inner.clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if export {
+        app.request_export(ExportKind::Mod);
+    } else if cancel {
+        app.mod_export_dialog = None;
+    }
+}
+
+// Parameters for a procedural shape, with a preview of what they give
+pub fn render_generator_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let id = app.next_shape_id();
+    let Some(dialog) = &mut app.generator_dialog else { return };
+    
+    let mut added = None;
+    let mut close = false;
+    
+    egui::Window::new(t("generate_shape"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut dialog.kind, GeneratorKind::Asteroid, t("asteroid"));
+                ui.selectable_value(&mut dialog.kind, GeneratorKind::Hull, t("hull"));
+            });
+            ui.separator();
+            
+            let shape = match dialog.kind {
+                GeneratorKind::Asteroid => crate::generators::asteroid(id, &dialog.asteroid),
+                GeneratorKind::Hull => crate::generators::hull(id, &dialog.hull),
+            };
+            
+            ui.horizontal(|ui| {
+                shape_thumbnail(ui, &shape, 160.0);
+                ui.vertical(|ui| {
+                    match dialog.kind {
+                        GeneratorKind::Asteroid => asteroid_params_ui(ui, &mut dialog.asteroid),
+                        GeneratorKind::Hull => hull_params_ui(ui, &mut dialog.hull),
+                    }
+                    ui.label(RichText::new(format!(
+                        "{}: {}, {}: {}", t("vertices"), shape.vertices.len(), t("ports"), shape.ports.len()
+                    )).weak());
+                });
+            });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if action_button(ui, &t("add_shape")).clicked() {
+                    added = Some(shape);
+                }
+                close = styled_button(ui, &t("close")).clicked();
+            });
+        });
+    
+    if let Some(shape) = added {
+        app.add_generated_shape(shape);
+    }
+    if close {
+        app.generator_dialog = None;
+    }
+}
+
+fn asteroid_params_ui(ui: &mut Ui, params: &mut AsteroidParams) {
+    egui::Grid::new("asteroid_params").num_columns(2).show(ui, |ui| {
+        ui.label(t("vertices"));
+        ui.add(egui::Slider::new(&mut params.vertex_count, 3..=24));
+        ui.end_row();
+        ui.label(t("radius"));
+        ui.add(egui::Slider::new(&mut params.radius, 1.0..=50.0));
+        ui.end_row();
+        ui.label(t("irregularity"));
+        ui.add(egui::Slider::new(&mut params.irregularity, 0.0..=0.9));
+        ui.end_row();
+        ui.label(t("angle_jitter"));
+        ui.add(egui::Slider::new(&mut params.angle_jitter, 0.0..=1.0));
+        ui.end_row();
+        ui.label(t("seed"));
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut params.seed));
+            if styled_button(ui, "🎲").on_hover_text(t("random_seed")).clicked() {
+                params.seed = (ui.input().time * 1e6) as u64;
+            }
+        });
+        ui.end_row();
+    });
+}
+
+fn hull_params_ui(ui: &mut Ui, params: &mut HullParams) {
+    egui::Grid::new("hull_params").num_columns(2).show(ui, |ui| {
+        ui.label(t("length"));
+        ui.add(egui::Slider::new(&mut params.length, 5.0..=200.0));
+        ui.end_row();
+        ui.label(t("max_width"));
+        ui.add(egui::Slider::new(&mut params.max_width, 2.0..=100.0));
+        ui.end_row();
+        ui.label(t("nose_taper"));
+        ui.add(egui::Slider::new(&mut params.nose_taper, 0.0..=0.9));
+        ui.end_row();
+        ui.label(t("tail_taper"));
+        ui.add(egui::Slider::new(&mut params.tail_taper, 0.0..=0.9));
+        ui.end_row();
+        ui.label(t("notches"));
+        ui.add(egui::Slider::new(&mut params.notch_count, 0..=6)).on_hover_text(t("notches_hint"));
+        ui.end_row();
+        ui.label(t("port_spacing"));
+        ui.add(egui::Slider::new(&mut params.port_spacing, 1.0..=50.0));
+        ui.end_row();
+    });
+}
+
+// Wizard rescaling one shape onto the game's sizes, with the result shown before applying
+pub fn render_normalize_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(dialog) = &mut app.normalize_dialog else { return };
+    let Some(shape) = app.shapes.get(dialog.shape_idx) else {
+        app.normalize_dialog = None;
+        return;
+    };
+    
+    let mut apply = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("normalize_shape"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(460.0);
+            ui.label(RichText::new(format!("{} (ID: {})", shape.name, shape.id)).strong());
+            ui.add_space(4.0);
+            
+            let params = &mut dialog.params;
+            egui::Grid::new("normalize_params").num_columns(2).show(ui, |ui| {
+                ui.label(t("reference_edge"));
+                egui::ComboBox::from_id_source("normalize_reference")
+                    .selected_text(reference_edge_label(shape, params.reference))
+                    .width(200.0)
+                    .show_ui(ui, |ui| {
+                        let options = [ReferenceEdge::MostCommon, ReferenceEdge::Shortest, ReferenceEdge::Longest]
+                            .into_iter()
+                            .chain((0..shape.vertices.len()).map(ReferenceEdge::Edge));
+                        for reference in options {
+                            ui.selectable_value(&mut params.reference, reference, reference_edge_label(shape, reference));
+                        }
+                    });
+                ui.end_row();
+                
+                ui.label(t("target_length"));
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut params.target_length).speed(0.25).clamp_range(0.25..=200.0));
+                    for &(key, size) in GRID_PRESETS.iter().filter(|(_, size)| *size >= TEST_BLOCK_SIZE / 2.0) {
+                        if ui.selectable_label(params.target_length == size, size.to_string()).on_hover_text(t(key)).clicked() {
+                            params.target_length = size;
+                        }
+                    }
+                });
+                ui.end_row();
+                
+                ui.label(t("center_on"));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut params.anchor, Anchor::Centroid, t("anchor_centroid"));
+                    ui.selectable_value(&mut params.anchor, Anchor::BoundsCenter, t("anchor_bounds_center"));
+                    ui.selectable_value(&mut params.anchor, Anchor::Keep, t("anchor_keep"));
+                });
+                ui.end_row();
+                
+                ui.label(t("round_vertices_to"));
+                ui.add(egui::DragValue::new(&mut params.vertex_step).speed(0.01).clamp_range(0.0..=10.0))
+                    .on_hover_text(t("round_vertices_hint"));
+                ui.end_row();
+                
+                ui.label(t("ports"));
+                ui.checkbox(&mut params.round_ports, t("round_ports"));
+                ui.end_row();
+            });
+            
+            ui.add_space(8.0);
+            match crate::normalize::normalize_shape(shape, params) {
+                Some(normalized) => {
+                    ui.horizontal(|ui| {
+                        shape_thumbnail(ui, &normalized, 120.0);
+                        egui::Grid::new("normalize_preview").num_columns(3).striped(true).show(ui, |ui| {
+                            ui.label("");
+                            ui.strong(t("before"));
+                            ui.strong(t("after"));
+                            ui.end_row();
+                            
+                            let length = |shape: &crate::data_structures::Shape| crate::normalize::reference_length(shape, params.reference).unwrap_or(0.0);
+                            ui.label(t("reference_edge"));
+                            ui.label(format!("{:.2}", length(shape)));
+                            ui.label(format!("{:.2}", length(&normalized)));
+                            ui.end_row();
+                            
+                            ui.label(t("size"));
+                            for outline in [shape, &normalized] {
+                                let (width, height) = outline_size(&outline.vertices);
+                                ui.label(format!("{:.2} × {:.2}", width, height));
+                            }
+                            ui.end_row();
+                            
+                            ui.label(t("centroid"));
+                            for outline in [shape, &normalized] {
+                                let center = outline.centroid().unwrap_or(Vertex { x: 0.0, y: 0.0 });
+                                ui.label(format!("{:.2}, {:.2}", center.x, center.y));
+                            }
+                            ui.end_row();
+                        });
+                    });
+                    let moved = shape.ports.iter().zip(&normalized.ports).filter(|(a, b)| a.position != b.position).count();
+                    if moved > 0 {
+                        ui.label(RichText::new(format!("{}: {}", t("ports_rounded"), moved)).weak());
+                    }
+                }
+                None => {
+                    ui.colored_label(Color32::from_rgb(230, 160, 60), t("normalize_no_edge"));
+                }
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                apply = action_button(ui, &t("apply")).clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if apply {
+        if let Some(dialog) = app.normalize_dialog.take() {
+            app.normalize_shape(dialog.shape_idx, &dialog.params);
+        }
+    } else if cancel {
+        app.normalize_dialog = None;
+    }
+}
+
+// One operation applied to the current shape or all shapes, with what it would change
+pub fn render_batch_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(dialog) = &mut app.batch_dialog else { return };
+    
+    let mut apply = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("batch_operations"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            
+            egui::Grid::new("batch_params").num_columns(2).show(ui, |ui| {
+                ui.label(t("operation"));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut dialog.kind, BatchKind::Scale, t("batch_scale"));
+                    ui.selectable_value(&mut dialog.kind, BatchKind::SnapToGrid, t("batch_snap_to_grid"));
+                    ui.selectable_value(&mut dialog.kind, BatchKind::Round, t("batch_round"));
+                });
+                ui.end_row();
+                
+                match dialog.kind {
+                    BatchKind::Scale => {
+                        ui.label(t("scale_factor"));
+                        ui.add(egui::DragValue::new(&mut dialog.factor).speed(0.05).clamp_range(0.01..=100.0));
+                    }
+                    BatchKind::SnapToGrid => {
+                        ui.label(t("grid_size"));
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut dialog.grid_step).speed(0.25).clamp_range(0.01..=200.0));
+                            for &(key, size) in GRID_PRESETS {
+                                if ui.selectable_label(dialog.grid_step == size, size.to_string()).on_hover_text(t(key)).clicked() {
+                                    dialog.grid_step = size;
+                                }
+                            }
+                        });
+                    }
+                    BatchKind::Round => {
+                        ui.label(t("decimal_places"));
+                        ui.add(egui::DragValue::new(&mut dialog.decimals).clamp_range(0..=4));
+                    }
+                }
+                ui.end_row();
+                
+                ui.label(t("apply_to"));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut dialog.all_shapes, false, t("current_shape"));
+                    ui.selectable_value(&mut dialog.all_shapes, true, format!("{} ({})", t("all_shapes"), app.shapes.len()));
+                });
+                ui.end_row();
+            });
+            
+            ui.add_space(8.0);
+            let shapes: Vec<&crate::data_structures::Shape> = if dialog.all_shapes {
+                app.shapes.iter().map(|shape| &**shape).collect()
+            } else {
+                app.shapes.get(app.current_shape_idx).map(|shape| &**shape).into_iter().collect()
+            };
+            let (_, summary) = crate::batch::apply_all(shapes, dialog.op());
+            ui.label(RichText::new(crate::batch::describe(&summary)).weak());
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                apply = action_button(ui, &t("apply")).clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if apply {
+        if let Some(dialog) = app.batch_dialog.take() {
+            app.apply_batch(dialog.op(), dialog.all_shapes);
+        }
+    } else if cancel {
+        app.batch_dialog = None;
+    }
+}
+
+fn reference_edge_label(shape: &crate::data_structures::Shape, reference: ReferenceEdge) -> String {
+    let length = crate::normalize::reference_length(shape, reference).unwrap_or(0.0);
+    let name = match reference {
+        ReferenceEdge::MostCommon => t("edge_most_common"),
+        ReferenceEdge::Shortest => t("edge_shortest"),
+        ReferenceEdge::Longest => t("edge_longest"),
+        ReferenceEdge::Edge(edge) => format!("{} {}", t("edge"), edge),
+    };
+    format!("{} ({:.2})", name, length)
+}
+
+// Width and height of the box around an outline
+fn outline_size(vertices: &[Vertex]) -> (f32, f32) {
+    vertices.iter()
+        .map(|v| (v.x, v.y, v.x, v.y))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .map_or((0.0, 0.0), |(min_x, min_y, max_x, max_y)| (max_x - min_x, max_y - min_y))
+}
+
+// Diff of a pending export against the file on disk, written only on confirmation
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_export_preview(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(preview) = &app.export_preview else { return };
+    
+    let mut write = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("export_preview"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(640.0);
+            ui.label(RichText::new(&preview.path).strong());
+            
+            if preview.new_file {
+                ui.label(RichText::new(t("export_preview_new_file")).weak());
+            } else if preview.diff.is_empty() {
+                ui.label(RichText::new(t("export_preview_no_changes")).weak());
+            } else {
+                let added = preview.diff.iter().filter(|line| line.kind == LineKind::Added).count();
+                let removed = preview.diff.iter().filter(|line| line.kind == LineKind::Removed).count();
+                ui.label(format!("+{} −{}", added, removed));
+                
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .max_height(400.0)
+                    .auto_shrink([false, true])
+                    .show_rows(ui, row_height, preview.diff.len(), |ui, rows| {
+                        for line in &preview.diff[rows] {
+                            let color = match line.kind {
+                                LineKind::Hunk => Color32::from_rgb(120, 160, 220),
+                                LineKind::Context => ui.visuals().weak_text_color(),
+                                LineKind::Removed => Color32::from_rgb(230, 100, 100),
+                                LineKind::Added => Color32::from_rgb(110, 200, 110),
+                            };
+                            ui.label(RichText::new(&line.text).monospace().color(color));
+                        }
+                    });
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                write = action_button(ui, &t("export_preview_write")).clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if write {
+        app.confirm_export_preview();
+    } else if cancel {
+        app.export_preview = None;
+    }
+}
+
+// Pick which shapes to write to a separate file
+pub fn render_export_dialog(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(dialog) = &mut app.export_dialog else { return };
+    let shapes = &app.shapes;
+    let current_id = shapes.get(app.current_shape_idx).map(|shape| shape.id);
+    
+    let mut export = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("export_selection"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            ui.horizontal(|ui| {
+                ui.label(&t("export_file"));
+                ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(240.0));
+                #[cfg(not(target_arch = "wasm32"))]
+                if styled_button(ui, &t("browse")).clicked() {
+                    if let Some(path) = ShapeEditor::pick_save_path() {
+                        dialog.path = path;
+                    }
+                }
+            });
+            
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if styled_button(ui, &t("select_all")).clicked() {
+                    dialog.include = shapes.iter().map(|shape| shape.id).collect();
+                }
+                if styled_button(ui, &t("select_none")).clicked() {
+                    dialog.include.clear();
+                }
+                if let Some(current_id) = current_id {
+                    if styled_button(ui, &t("current_shape_only")).clicked() {
+                        dialog.include = std::iter::once(current_id).collect();
+                    }
+                }
+            });
+            
+            ui.add_space(4.0);
+            virtual_list(ui, "export_dialog_shapes", 240.0, shapes.len(), |ui, i| {
+                let id = shapes[i].id;
+                let mut included = dialog.include.contains(&id);
+                if ui.checkbox(&mut included, format!("{} (ID: {})", shapes[i].name, id)).changed() {
+                    if included {
+                        dialog.include.insert(id);
+                    } else {
+                        dialog.include.remove(&id);
+                    }
+                }
+            });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                let count = dialog.include.len();
+                export = ui.add_enabled_ui(count > 0, |ui| action_button(ui, &format!("{} ({})", t("export"), count))).inner.clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if export {
+        app.request_export(ExportKind::Selection);
+    } else if cancel {
+        app.export_dialog = None;
+    }
+}
+
+// What the validation checks found in the shapes about to be exported. Errors have to
+// be fixed first; warnings can be exported anyway.
+pub fn render_export_check(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(check) = &app.export_check else { return };
+    let errors = check.problems.iter().filter(|problem| problem.severity == Severity::Error).count();
+    let warnings = check.problems.len() - errors;
+    
+    let mut export = false;
+    let mut close = false;
+    let mut select = None;
+    
+    egui::Window::new(t("export_check"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(520.0);
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::from_rgb(230, 90, 90), format!("⛔ {} {}", errors, t("export_check_errors")));
+                ui.colored_label(Color32::from_rgb(230, 180, 60), format!("⚠ {} {}", warnings, t("export_check_warnings")));
+            });
+            let note = if errors > 0 { t("export_check_blocked") } else { t("export_check_confirm") };
+            ui.label(RichText::new(note).weak());
+            
+            ui.add_space(4.0);
+            egui::ScrollArea::vertical()
+                .id_source("export_check_problems")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    for problem in &check.problems {
+                        let color = match problem.severity {
+                            Severity::Error => Color32::from_rgb(230, 90, 90),
+                            Severity::Warning => Color32::from_rgb(230, 180, 60),
+                        };
+                        let shape_idx = app.shapes.iter().position(|shape| shape.id == problem.shape_id);
+                        let shape_name = shape_idx.map_or("?", |idx| app.shapes[idx].name.as_str());
+                        
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, "●");
+                            if ui.selectable_label(false, format!("{} (ID: {})", shape_name, problem.shape_id)).clicked() {
+                                select = shape_idx;
+                            }
+                            if problem.element != Element::Shape {
+                                ui.label(RichText::new(problem.element.to_string()).weak());
+                            }
+                            ui.label(&problem.message);
+                        });
+                    }
+                });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if errors == 0 {
+                    export = action_button(ui, &t("export_check_export_anyway")).clicked();
+                }
+                close = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if let Some(shape_idx) = select {
+        // Go to the shape to fix it, dropping the export
+        app.current_shape_idx = shape_idx;
+        app.export_check = None;
+    }
+    if export {
+        app.confirm_export_check();
+    } else if close {
+        app.export_check = None;
+    }
+}
+
+// Ask before deleting or renumbering a shape that blocks in the loaded blocks.lua use
+pub fn render_shape_change_confirm(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let change = match app.pending_shape_change {
+        Some(change) => change,
+        None => return,
+    };
+    let shape_idx = match change {
+        ShapeChange::Renumber(idx, _) | ShapeChange::Delete(idx) => idx,
+    };
+    let used_by = app.blocks_using_shape(shape_idx);
+    
+    let mut proceed = false;
+    let mut cancel = false;
+    
+    egui::Window::new(t("shape_in_use_title"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            ui.set_max_width(360.0);
+            ui.label(t("shape_in_use_message"));
+            ui.add_space(4.0);
+            egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                for label in &used_by {
+                    ui.label(format!("• {}", label));
+                }
+            });
+            
+            // Blocks can only be rewritten when the file was read from disk
+            #[cfg(not(target_arch = "wasm32"))]
+            if let ShapeChange::Renumber(..) = change {
+                ui.add_space(4.0);
+                ui.checkbox(&mut app.update_blocks_on_renumber, t("update_blocks_on_renumber"));
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                proceed = action_button(ui, &t("continue")).clicked();
+                cancel = styled_button(ui, &t("cancel")).clicked();
+            });
+        });
+    
+    if proceed {
+        app.apply_shape_change(change);
+    } else if cancel {
+        app.pending_shape_change = None;
+    }
+}
+
+// First-run tutorial. Each step watches a counter on the editor and moves on by
+// itself once the counter has grown enough; steps without one wait for "Next".
+struct TutorialStep {
+    title: &'static str,
+    text: &'static str,
+    progress: Option<(fn(&ShapeEditor) -> usize, usize)>,
+}
+
+fn current_shape_vertices(app: &ShapeEditor) -> usize {
+    app.shapes.get(app.current_shape_idx).map_or(0, |shape| shape.vertices.len())
+}
+
+fn current_shape_ports(app: &ShapeEditor) -> usize {
+    app.shapes.get(app.current_shape_idx).map_or(0, |shape| shape.ports.len())
+}
+
+fn export_count(app: &ShapeEditor) -> usize {
+    app.export_count
+}
+
+const TUTORIAL_STEPS: [TutorialStep; 4] = [
+    TutorialStep { title: "tutorial_welcome_title", text: "tutorial_welcome_text", progress: None },
+    TutorialStep { title: "tutorial_vertices_title", text: "tutorial_vertices_text", progress: Some((current_shape_vertices, 3)) },
+    TutorialStep { title: "tutorial_port_title", text: "tutorial_port_text", progress: Some((current_shape_ports, 1)) },
+    TutorialStep { title: "tutorial_export_title", text: "tutorial_export_text", progress: Some((export_count, 1)) },
+];
+
+// Position in the tutorial and the watched counter's value when the step began
+pub struct Onboarding {
+    step: usize,
+    baseline: usize,
+}
+
+impl Onboarding {
+    pub fn new() -> Self {
+        Self { step: 0, baseline: 0 }
+    }
+    
+    fn advance(&mut self, app: &ShapeEditor) {
+        self.step += 1;
+        self.baseline = TUTORIAL_STEPS.get(self.step)
+            .and_then(|step| step.progress)
+            .map_or(0, |(counter, _)| counter(app));
+    }
+}
+
+// Tutorial card at the top of the canvas; finishing or skipping it marks onboarding done
+pub fn render_onboarding(ctx: &egui::Context, app: &mut ShapeEditor) {
+    let Some(mut onboarding) = app.onboarding.take() else { return };
+    
+    // Move past every step whose task is already done
+    while let Some((counter, required)) = TUTORIAL_STEPS.get(onboarding.step).and_then(|step| step.progress) {
+        if counter(app) < onboarding.baseline + required {
+            break;
+        }
+        onboarding.advance(app);
+    }
+    
+    let Some(step) = TUTORIAL_STEPS.get(onboarding.step) else {
+        app.onboarding_done = true;
+        app.toasts.success(t("tutorial_complete"));
+        return;
+    };
+    
+    let mut next = false;
+    let mut skip = false;
+    let width = 340.0;
+    
+    egui::Area::new("onboarding")
+        .fixed_pos(app.canvas_rect.center_top() + vec2(-width / 2.0, 12.0))
+        .order(Order::Foreground)
+        .show(ctx, |ui| {
+            Frame::none()
+                .fill(Color32::from_rgba_unmultiplied(16, 16, 16, 230))
+                .stroke(Stroke::new(1.0, Color32::from_rgb(100, 200, 100)))
+                .inner_margin(10.0)
+                .rounding(4.0)
+                .show(ui, |ui| {
+                    ui.set_width(width);
+                    ui.horizontal(|ui| {
+                        ui.strong(t(step.title));
+                        ui.with_layout(Layout::right_to_left(), |ui| {
+                            ui.label(RichText::new(format!("{}/{}", onboarding.step + 1, TUTORIAL_STEPS.len())).weak());
+                        });
+                    });
+                    ui.add_space(4.0);
+                    ui.label(t(step.text));
+                    
+                    if let Some((counter, required)) = step.progress {
+                        let done = counter(app).saturating_sub(onboarding.baseline).min(required);
+                        ui.add(egui::ProgressBar::new(done as f32 / required as f32)
+                            .text(format!("{}/{}", done, required)));
+                    }
+                    
+                    ui.add_space(6.0);
+                    ui.horizontal(|ui| {
+                        if step.progress.is_none() && action_button(ui, &t("tutorial_next")).clicked() {
+                            next = true;
+                        }
+                        if styled_button(ui, &t("tutorial_skip")).clicked() {
+                            skip = true;
+                        }
+                    });
+                });
+        });
+    
+    if skip {
+        app.onboarding_done = true;
+        return;
+    }
+    if next {
+        onboarding.advance(app);
+    }
+    app.onboarding = Some(onboarding);
+}
+
+// Cheat sheet of all shortcuts grouped by category: the remappable ones from the
+// keybindings, followed by the fixed mouse and navigation ones
+pub fn render_shortcuts_overlay(ctx: &egui::Context, app: &mut ShapeEditor) {
+    if !app.show_shortcuts {
+        return;
+    }
+    
+    // One row per action, with all of its bindings
+    let mut actions: Vec<Action> = Vec::new();
+    for (action, _) in &app.keybindings.bindings {
+        if !actions.contains(action) {
+            actions.push(*action);
+        }
+    }
+    
+    let mut open = true;
+    egui::Window::new(t("shortcuts"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .frame(popup_frame())
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(ctx.available_rect().height() * 0.8).show(ui, |ui| {
+                for category in SHORTCUT_CATEGORIES {
+                    let mut rows: Vec<(String, String)> = actions.iter()
+                        .filter(|action| action.category() == category)
+                        .map(|action| (app.keybindings.describe(*action), action.label()))
+                        .collect();
+                    rows.extend(FIXED_SHORTCUTS.iter()
+                        .filter(|(fixed_category, _, _)| *fixed_category == category)
+                        .map(|(_, keys, description)| (t(keys), t(description))));
+                    if rows.is_empty() {
+                        continue;
+                    }
+                    
+                    ui.add_space(6.0);
+                    ui.strong(t(category));
+                    egui::Grid::new(("shortcuts_grid", category)).num_columns(2).striped(true).show(ui, |ui| {
+                        for (keys, description) in rows {
+                            ui.monospace(keys);
+                            ui.label(description);
+                            ui.end_row();
+                        }
+                    });
+                }
+            });
+        });
+    
+    if !open {
+        app.show_shortcuts = false;
+    }
+}
@@ -0,0 +1,94 @@
+// The user's own template library, shared by all sessions: a JSON file next to the
+// settings natively, localStorage in the browser. Each template keeps its shape as
+// shapes.lua text, so ports, scales and properties come back as they were saved.
+use serde::{Deserialize, Serialize};
+
+use crate::data_structures::Shape as AppShape;
+use crate::serializer::serialize_shapes_file;
+use crate::shape_editor::ShapeEditor;
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "reassembly_shape_editor.templates";
+
+#[derive(Serialize, Deserialize)]
+struct StoredTemplate {
+    name: String,
+    lua: String,
+}
+
+/// Templates saved so far; ones that no longer parse are skipped
+pub fn load() -> Vec<AppShape> {
+    let Some(json) = read_stored() else { return Vec::new() };
+    let stored: Vec<StoredTemplate> = match serde_json::from_str(&json) {
+        Ok(stored) => stored,
+        Err(e) => {
+            log::warn!("Ignoring unreadable template library: {}", e);
+            return Vec::new();
+        }
+    };
+
+    stored.into_iter()
+        .filter_map(|template| {
+            let shapes_file = match crate::parser::parse_shapes_content(&template.lua) {
+                Ok(shapes_file) => shapes_file,
+                Err(e) => {
+                    log::warn!("Skipping template {}: {}", template.name, e);
+                    return None;
+                }
+            };
+            let mut shape = ShapeEditor::convert_from_ast_shape(shapes_file.shapes.first()?);
+            shape.name = template.name;
+            Some(shape)
+        })
+        .collect()
+}
+
+pub fn save(templates: &[AppShape]) -> Result<(), String> {
+    let stored: Vec<StoredTemplate> = templates.iter()
+        .map(|shape| StoredTemplate {
+            name: shape.name.clone(),
+            lua: serialize_shapes_file(&crate::ast::ShapesFile {
+                shapes: vec![ShapeEditor::convert_to_ast_shape(shape)],
+            }),
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+    write_stored(&json)
+}
+
+/// Location of the library, next to the settings
+#[cfg(not(target_arch = "wasm32"))]
+pub fn templates_path() -> Option<std::path::PathBuf> {
+    Some(crate::settings::config_dir()?.join("templates.json"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stored() -> Option<String> {
+    std::fs::read_to_string(templates_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stored(json: &str) -> Result<(), String> {
+    let path = templates_path().ok_or("No config directory found")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| "localStorage is not available".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_stored() -> Option<String> {
+    local_storage().ok()?.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stored(json: &str) -> Result<(), String> {
+    local_storage()?.set_item(STORAGE_KEY, json).map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,392 @@
+// Checks over parsed shapes that go beyond what the parser enforces. The editor
+// shows the results in the Problems panel; other tools get the same rules through
+// `validate_shapes_file`.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::ast::{Port, PortType, Scale, Shape, ShapesFile};
+use crate::geometry::{intersect_segment_segment, spanned_area, Vec2};
+
+// Port positions closer than this count as the same position
+const POSITION_TOLERANCE: f32 = 0.01;
+
+// Vertices closer than this count as the same point
+const SAME_POINT_DISTANCE: f32 = 1e-4;
+
+/// Outlines enclosing less area than this are treated as having none
+pub const MIN_SHAPE_AREA: f32 = 1e-3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// The rule a diagnostic comes from, for tools that filter or count problems
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    DuplicateId,
+    NoScales,
+    TooFewVertices,
+    ZeroArea,
+    CoincidentVertices,
+    CrossingEdges,
+    PortEdgeOutOfRange,
+    PortPositionOutOfRange,
+    ScaleWinding,
+    ScaleNotLarger,
+    ScaleEdgeCount,
+    ScalePorts,
+    MissingPayload,
+}
+
+/// Part of a shape a diagnostic points at. Scale indices start at 0 for scale 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+    Shape,
+    Scale(usize),
+    Vertex { scale: usize, index: usize },
+    Edge { scale: usize, index: usize },
+    Port { scale: usize, index: usize },
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Element::Shape => write!(f, "shape"),
+            Element::Scale(scale) => write!(f, "scale {}", scale + 1),
+            Element::Vertex { scale, index } => write!(f, "scale {} V{}", scale + 1, index),
+            Element::Edge { scale, index } => write!(f, "scale {} E{}", scale + 1, index),
+            Element::Port { scale, index } => write!(f, "scale {} P{}", scale + 1, index),
+        }
+    }
+}
+
+/// A change that resolves a diagnostic, applied with [`Fix::apply`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fix {
+    /// Reverse the vertex order of a scale, keeping each port where it was
+    ReverseWinding { scale: usize },
+    /// Remove a vertex and the ports on the edge starting at it
+    RemoveVertex { scale: usize, index: usize },
+    RemovePort { scale: usize, index: usize },
+    /// Move a port's position back into 0..1
+    ClampPortPosition { scale: usize, index: usize },
+    /// Replace the ports of a scale with those of scale 1
+    CopyPortsFromFirstScale { scale: usize },
+}
+
+impl Fix {
+    pub fn description(&self) -> String {
+        match self {
+            Fix::ReverseWinding { scale } => format!("Reverse the vertex order of scale {}", scale + 1),
+            Fix::RemoveVertex { scale, index } => format!("Remove V{} of scale {}", index, scale + 1),
+            Fix::RemovePort { scale, index } => format!("Remove P{} of scale {}", index, scale + 1),
+            Fix::ClampPortPosition { scale, index } => format!("Move P{} of scale {} onto its edge", index, scale + 1),
+            Fix::CopyPortsFromFirstScale { scale } => format!("Copy the ports of scale 1 to scale {}", scale + 1),
+        }
+    }
+
+    /// Change `shape` as the fix describes; does nothing if the element is gone
+    pub fn apply(&self, shape: &mut Shape) {
+        match *self {
+            Fix::ReverseWinding { scale } => {
+                let Some(scale) = shape.scales.get_mut(scale) else { return };
+                let n = scale.verts.len();
+                if n == 0 {
+                    return;
+                }
+                scale.verts.reverse();
+                // Edge e runs backwards as edge n - 2 - e once the order is reversed
+                for port in &mut scale.ports {
+                    port.edge = (2 * n - 2 - port.edge % n) % n;
+                    port.position = 1.0 - port.position;
+                }
+            }
+            Fix::RemoveVertex { scale, index } => {
+                let Some(scale) = shape.scales.get_mut(scale) else { return };
+                if index >= scale.verts.len() {
+                    return;
+                }
+                scale.verts.remove(index);
+                scale.ports.retain(|port| port.edge != index);
+                for port in &mut scale.ports {
+                    if port.edge > index {
+                        port.edge -= 1;
+                    }
+                }
+            }
+            Fix::RemovePort { scale, index } => {
+                if let Some(scale) = shape.scales.get_mut(scale) {
+                    if index < scale.ports.len() {
+                        scale.ports.remove(index);
+                    }
+                }
+            }
+            Fix::ClampPortPosition { scale, index } => {
+                if let Some(port) = shape.scales.get_mut(scale).and_then(|scale| scale.ports.get_mut(index)) {
+                    port.position = port.position.clamp(0.0, 1.0);
+                }
+            }
+            Fix::CopyPortsFromFirstScale { scale } => {
+                if scale > 0 && scale < shape.scales.len() {
+                    shape.scales[scale].ports = shape.scales[0].ports.clone();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub rule: Rule,
+    pub shape_id: usize,
+    pub element: Element,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    fn error(rule: Rule, shape_id: usize, element: Element, message: String) -> Self {
+        Self { severity: Severity::Error, rule, shape_id, element, message, fix: None }
+    }
+
+    fn warning(rule: Rule, shape_id: usize, element: Element, message: String) -> Self {
+        Self { severity: Severity::Warning, rule, shape_id, element, message, fix: None }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+/// Every problem the editor knows how to find in a shapes file, shape by shape in
+/// file order
+pub fn validate_shapes_file(shapes_file: &ShapesFile) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut seen_ids = BTreeSet::new();
+
+    for shape in &shapes_file.shapes {
+        if !seen_ids.insert(shape.id) {
+            diagnostics.push(Diagnostic::error(Rule::DuplicateId, shape.id, Element::Shape, format!(
+                "Shape ID {} is used more than once", shape.id
+            )));
+        }
+        diagnostics.extend(validate_shape(shape));
+    }
+
+    diagnostics
+}
+
+/// Launchers whose payload, given as (launcher ID, payload ID) pairs, isn't a shape in
+/// the file. Only launchers that are in the file are checked.
+pub fn validate_payloads(shapes_file: &ShapesFile, payloads: &[(usize, usize)]) -> Vec<Diagnostic> {
+    let ids: BTreeSet<usize> = shapes_file.shapes.iter().map(|shape| shape.id).collect();
+    payloads.iter()
+        .filter(|(launcher_id, payload_id)| ids.contains(launcher_id) && !ids.contains(payload_id))
+        .map(|&(launcher_id, payload_id)| Diagnostic::warning(Rule::MissingPayload, launcher_id, Element::Shape, format!(
+            "Launches shape {}, which isn't in the file", payload_id
+        )))
+        .collect()
+}
+
+// First line of the comment an export writes at the top of the file
+const SUMMARY_PREFIX: &str = "-- Validation:";
+// Lines listing the problems under it
+const SUMMARY_ENTRY_PREFIX: &str = "--   ";
+
+/// Lua comment lines summing up `diagnostics`, one line with the counts and one per
+/// problem, for the top of an exported file
+pub fn summary_comment(diagnostics: &[Diagnostic]) -> String {
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+    let mut comment = if diagnostics.is_empty() {
+        format!("{} no problems found\n", SUMMARY_PREFIX)
+    } else {
+        format!("{} {} error(s), {} warning(s)\n", SUMMARY_PREFIX, errors, warnings)
+    };
+    for d in diagnostics {
+        let severity = match d.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let element = if d.element == Element::Shape { String::new() } else { format!(" {}", d.element) };
+        let message = d.message.replace('\n', " ");
+        comment.push_str(&format!("{}{} in shape {}{}: {}\n", SUMMARY_ENTRY_PREFIX, severity, d.shape_id, element, message));
+    }
+    comment
+}
+
+/// `content` without the summary comment a previous export put at its top
+pub fn strip_summary_comment(content: &str) -> &str {
+    if !content.starts_with(SUMMARY_PREFIX) {
+        return content;
+    }
+    let mut rest = content;
+    let mut first = true;
+    while first || rest.starts_with(SUMMARY_ENTRY_PREFIX) {
+        first = false;
+        rest = rest.find('\n').map_or("", |end| &rest[end + 1..]);
+    }
+    rest
+}
+
+/// Problems within one shape: its outlines, its ports and how its scales compare
+pub fn validate_shape(shape: &Shape) -> Vec<Diagnostic> {
+    if shape.scales.is_empty() {
+        return vec![Diagnostic::error(Rule::NoScales, shape.id, Element::Shape, "Shape has no scales".to_string())];
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = shape.scales.iter()
+        .enumerate()
+        .flat_map(|(i, scale)| check_outline(shape.id, i, scale))
+        .collect();
+    diagnostics.extend(check_scales(shape));
+    diagnostics
+}
+
+// Vertex count, area, repeated points, crossing edges, and ports that are off their edges
+fn check_outline(shape_id: usize, scale_index: usize, scale: &Scale) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let n = scale.verts.len();
+    let number = scale_index + 1;
+
+    if n < 3 {
+        diagnostics.push(Diagnostic::error(Rule::TooFewVertices, shape_id, Element::Scale(scale_index), format!(
+            "Scale {} has {} vertices, at least 3 are needed", number, n
+        )));
+    }
+
+    let points: Vec<Vec2> = scale.verts.iter().map(|v| Vec2::new(v.x, v.y)).collect();
+    if n >= 3 && spanned_area(&points) < MIN_SHAPE_AREA {
+        diagnostics.push(Diagnostic::error(Rule::ZeroArea, shape_id, Element::Scale(scale_index), format!(
+            "Scale {} has no area: its vertices are all on one line", number
+        )));
+    }
+    let mut coincident = false;
+    for i in 0..n {
+        let next = (i + 1) % n;
+        if n > 1 && points[i].distance(points[next]) < SAME_POINT_DISTANCE {
+            coincident = true;
+            // Removing the first of the two drops only the empty edge between them
+            diagnostics.push(Diagnostic::error(Rule::CoincidentVertices, shape_id, Element::Vertex { scale: scale_index, index: i }, format!(
+                "Vertices {} and {} of scale {} are at the same point", i, next, number
+            )).with_fix(Fix::RemoveVertex { scale: scale_index, index: i }));
+        }
+    }
+
+    // Edges next to each other share a vertex, so only the others are compared. An
+    // empty edge would make its neighbours touch, which is already reported above.
+    if n > 3 && !coincident {
+        'edges: for a in 0..n {
+            for b in a + 2..n {
+                if a == 0 && b == n - 1 {
+                    continue;
+                }
+                if intersect_segment_segment(points[a], points[(a + 1) % n], points[b], points[(b + 1) % n]) {
+                    diagnostics.push(Diagnostic::error(Rule::CrossingEdges, shape_id, Element::Edge { scale: scale_index, index: a }, format!(
+                        "Edges {} and {} of scale {} cross", a, b, number
+                    )));
+                    break 'edges;
+                }
+            }
+        }
+    }
+
+    for (i, port) in scale.ports.iter().enumerate() {
+        let element = Element::Port { scale: scale_index, index: i };
+        if port.edge >= n {
+            diagnostics.push(Diagnostic::error(Rule::PortEdgeOutOfRange, shape_id, element, format!(
+                "Port {} of scale {} is on edge {}, which doesn't exist", i, number, port.edge
+            )).with_fix(Fix::RemovePort { scale: scale_index, index: i }));
+        } else if !(0.0..=1.0).contains(&port.position) {
+            diagnostics.push(Diagnostic::error(Rule::PortPositionOutOfRange, shape_id, element, format!(
+                "Port {} of scale {} is at {}, outside its edge", i, number, port.position
+            )).with_fix(Fix::ClampPortPosition { scale: scale_index, index: i }));
+        }
+    }
+
+    diagnostics
+}
+
+/// Compare every scale of a shape with scale 1 (and size with the scale before it):
+/// winding, growing size, edge count and port layout. Port layouts of scales with a
+/// different edge count can't be compared edge by edge, so those are skipped.
+fn check_scales(shape: &Shape) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let first = match shape.scales.first() {
+        Some(first) => first,
+        None => return diagnostics,
+    };
+    let first_area = signed_area(first);
+
+    for (i, scale) in shape.scales.iter().enumerate().skip(1) {
+        let number = i + 1;
+        let area = signed_area(scale);
+        let element = Element::Scale(i);
+
+        if area != 0.0 && first_area != 0.0 && area.signum() != first_area.signum() {
+            diagnostics.push(Diagnostic::error(Rule::ScaleWinding, shape.id, element, format!(
+                "Scale {} is wound the other way than scale 1", number
+            )).with_fix(Fix::ReverseWinding { scale: i }));
+        }
+
+        let previous_area = signed_area(&shape.scales[i - 1]).abs();
+        if area.abs() <= previous_area {
+            diagnostics.push(Diagnostic::warning(Rule::ScaleNotLarger, shape.id, element, format!(
+                "Scale {} (area {:.1}) is not larger than scale {} (area {:.1})", number, area.abs(), i, previous_area
+            )));
+        }
+
+        if scale.verts.len() != first.verts.len() {
+            diagnostics.push(Diagnostic::warning(Rule::ScaleEdgeCount, shape.id, element, format!(
+                "Scale {} has {} edges, scale 1 has {}", number, scale.verts.len(), first.verts.len()
+            )));
+            continue;
+        }
+
+        let differing: Vec<String> = (0..first.verts.len())
+            .filter(|&edge| !same_edge_ports(first, scale, edge))
+            .map(|edge| format!("E{}", edge))
+            .collect();
+        if !differing.is_empty() {
+            diagnostics.push(Diagnostic::warning(Rule::ScalePorts, shape.id, element, format!(
+                "Scale {} has different ports than scale 1 on {}", number, differing.join(", ")
+            )).with_fix(Fix::CopyPortsFromFirstScale { scale: i }));
+        }
+    }
+
+    diagnostics
+}
+
+// Same number of ports on the edge, with matching types at matching positions
+fn same_edge_ports(a: &Scale, b: &Scale, edge: usize) -> bool {
+    let (a_ports, b_ports) = (edge_ports(a, edge), edge_ports(b, edge));
+    let port_type = |port: &Port| port.port_type.clone().unwrap_or(PortType::Default);
+
+    a_ports.len() == b_ports.len()
+        && a_ports.iter().zip(&b_ports).all(|(x, y)| {
+            (x.position - y.position).abs() <= POSITION_TOLERANCE && port_type(x) == port_type(y)
+        })
+}
+
+// Ports on one edge, in order along it
+fn edge_ports(scale: &Scale, edge: usize) -> Vec<&Port> {
+    let mut ports: Vec<&Port> = scale.ports.iter().filter(|port| port.edge == edge).collect();
+    ports.sort_by(|x, y| x.position.total_cmp(&y.position));
+    ports
+}
+
+// Positive for counter-clockwise winding
+fn signed_area(scale: &Scale) -> f32 {
+    let n = scale.verts.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&scale.verts[i], &scale.verts[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f32>()
+        / 2.0
+}
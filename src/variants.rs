@@ -0,0 +1,93 @@
+// Shapes declared as a rotated, mirrored or scaled copy of another, so a family of
+// shapes can be redone from its source instead of being kept in step by hand
+use serde::{Deserialize, Serialize};
+
+use crate::data_structures::Shape;
+use crate::translations::t;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VariantTransform {
+    // Degrees, counter-clockwise about the origin
+    Rotate(f32),
+    // Flip across the y axis, so x changes sign
+    MirrorX,
+    // Flip across the x axis, so y changes sign
+    MirrorY,
+    Scale(f32),
+}
+
+impl VariantTransform {
+    pub const KINDS: [VariantTransform; 4] = [
+        VariantTransform::Rotate(90.0),
+        VariantTransform::MirrorX,
+        VariantTransform::MirrorY,
+        VariantTransform::Scale(2.0),
+    ];
+
+    pub fn apply(self, shape: &mut Shape) {
+        match self {
+            VariantTransform::Rotate(degrees) => shape.rotate_about_origin(degrees),
+            VariantTransform::MirrorX => shape.mirror(true),
+            VariantTransform::MirrorY => shape.mirror(false),
+            VariantTransform::Scale(factor) => shape.scale_about_origin(factor, factor),
+        }
+    }
+
+    // Name of the kind of transform, without its amount
+    pub fn kind_label(self) -> String {
+        match self {
+            VariantTransform::Rotate(_) => t("variant_rotate"),
+            VariantTransform::MirrorX => t("variant_mirror_x"),
+            VariantTransform::MirrorY => t("variant_mirror_y"),
+            VariantTransform::Scale(_) => t("variant_scale"),
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            VariantTransform::Rotate(degrees) => format!("{} {}°", self.kind_label(), degrees),
+            VariantTransform::Scale(factor) => format!("{} {}x", self.kind_label(), factor),
+            VariantTransform::MirrorX | VariantTransform::MirrorY => self.kind_label(),
+        }
+    }
+
+    pub fn same_kind(self, other: VariantTransform) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+/// Shape `variant_id` is shape `source_id` with `transform` applied
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VariantLink {
+    pub source_id: usize,
+    pub variant_id: usize,
+    pub transform: VariantTransform,
+    // Redo the variant whenever the source changes
+    pub auto_update: bool,
+}
+
+/// The variant redone from its source: the source's outlines and ports with the
+/// transform applied, under the variant's own ID and name
+pub fn derive(source: &Shape, variant: &Shape, transform: VariantTransform) -> Shape {
+    let mut derived = source.clone();
+    transform.apply(&mut derived);
+    derived.id = variant.id;
+    derived.name = variant.name.clone();
+    derived
+}
+
+/// Whether linking `variant_id` to `source_id` would make a shape derived, directly or
+/// through other variants, from itself
+pub fn creates_cycle(links: &[VariantLink], source_id: usize, variant_id: usize) -> bool {
+    let mut id = source_id;
+    for _ in 0..=links.len() {
+        if id == variant_id {
+            return true;
+        }
+        match links.iter().find(|link| link.variant_id == id) {
+            Some(link) => id = link.source_id,
+            None => return false,
+        }
+    }
+    true
+}
@@ -0,0 +1,117 @@
+// Traversal of a parsed shapes file, for tools that analyse or rewrite shapes
+// without walking shapes, scales and their elements by hand
+use crate::ast::{Port, Scale, Shape, ShapesFile, Vertex};
+
+/// Where a vertex or port sits in a shapes file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// Index of the shape in the file
+    pub shape: usize,
+    /// ID of that shape
+    pub shape_id: usize,
+    /// Index of the scale, 0 for the first
+    pub scale: usize,
+    /// Index of the vertex or port within its scale
+    pub index: usize,
+}
+
+/// Callbacks for [`ShapesFile::visit`]; every method does nothing by default
+///
+/// Shapes are visited in file order. Within a shape each scale is visited in turn,
+/// its vertices first and then its ports, and `leave_shape` follows the last scale.
+///
+/// Example: counting the ports of every shape
+/// ```
+/// use std::collections::BTreeMap;
+/// use reassembly_shape_editor::ast::Port;
+/// use reassembly_shape_editor::visit::{Location, ShapeVisitor};
+///
+/// #[derive(Default)]
+/// struct PortCount(BTreeMap<usize, usize>);
+///
+/// impl ShapeVisitor for PortCount {
+///     fn visit_port(&mut self, at: Location, _port: &Port) {
+///         *self.0.entry(at.shape_id).or_default() += 1;
+///     }
+/// }
+///
+/// let file = reassembly_shape_editor::parse_shapes_content(
+///     "{{200, {{verts={{0,0},{10,0},{0,10}}, ports={{0,0.5},{1,0.5}}}}}}",
+/// ).unwrap();
+/// let mut count = PortCount::default();
+/// file.visit(&mut count);
+/// assert_eq!(count.0[&200], 2);
+/// ```
+pub trait ShapeVisitor {
+    fn visit_shape(&mut self, _index: usize, _shape: &Shape) {}
+    fn visit_scale(&mut self, _shape: &Shape, _scale_index: usize, _scale: &Scale) {}
+    fn visit_vertex(&mut self, _at: Location, _vertex: &Vertex) {}
+    fn visit_port(&mut self, _at: Location, _port: &Port) {}
+    fn leave_shape(&mut self, _index: usize, _shape: &Shape) {}
+}
+
+impl ShapesFile {
+    /// Walk every shape, scale, vertex and port in order
+    pub fn visit(&self, visitor: &mut impl ShapeVisitor) {
+        for (index, shape) in self.shapes.iter().enumerate() {
+            visitor.visit_shape(index, shape);
+            for (scale_index, scale) in shape.scales.iter().enumerate() {
+                visitor.visit_scale(shape, scale_index, scale);
+                let at = Location { shape: index, shape_id: shape.id, scale: scale_index, index: 0 };
+                for (i, vertex) in scale.verts.iter().enumerate() {
+                    visitor.visit_vertex(Location { index: i, ..at }, vertex);
+                }
+                for (i, port) in scale.ports.iter().enumerate() {
+                    visitor.visit_port(Location { index: i, ..at }, port);
+                }
+            }
+            visitor.leave_shape(index, shape);
+        }
+    }
+
+    /// Every vertex of every scale, in file order
+    pub fn iter_vertices(&self) -> impl Iterator<Item = (Location, &Vertex)> {
+        self.iter_scales().flat_map(|(at, scale)| {
+            scale.verts.iter().enumerate().map(move |(index, vertex)| (Location { index, ..at }, vertex))
+        })
+    }
+
+    /// Every vertex of every scale, for transforms
+    pub fn iter_vertices_mut(&mut self) -> impl Iterator<Item = (Location, &mut Vertex)> {
+        self.iter_scales_mut().flat_map(|(at, scale)| {
+            scale.verts.iter_mut().enumerate().map(move |(index, vertex)| (Location { index, ..at }, vertex))
+        })
+    }
+
+    /// Every port of every scale, in file order
+    pub fn iter_ports(&self) -> impl Iterator<Item = (Location, &Port)> {
+        self.iter_scales().flat_map(|(at, scale)| {
+            scale.ports.iter().enumerate().map(move |(index, port)| (Location { index, ..at }, port))
+        })
+    }
+
+    /// Every port of every scale, for transforms
+    pub fn iter_ports_mut(&mut self) -> impl Iterator<Item = (Location, &mut Port)> {
+        self.iter_scales_mut().flat_map(|(at, scale)| {
+            scale.ports.iter_mut().enumerate().map(move |(index, port)| (Location { index, ..at }, port))
+        })
+    }
+
+    // Each scale with its location; `index` is left at 0 for the element iterators to fill in
+    fn iter_scales(&self) -> impl Iterator<Item = (Location, &Scale)> {
+        self.shapes.iter().enumerate().flat_map(|(shape_index, shape)| {
+            shape.scales.iter().enumerate().map(move |(scale, value)| {
+                (Location { shape: shape_index, shape_id: shape.id, scale, index: 0 }, value)
+            })
+        })
+    }
+
+    fn iter_scales_mut(&mut self) -> impl Iterator<Item = (Location, &mut Scale)> {
+        self.shapes.iter_mut().enumerate().flat_map(|(shape_index, shape)| {
+            let shape_id = shape.id;
+            shape.scales.iter_mut().enumerate().map(move |(scale, value)| {
+                (Location { shape: shape_index, shape_id, scale, index: 0 }, value)
+            })
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use eframe::egui;
 use egui::*;
+use serde::{Deserialize, Serialize};
 use crate::data_structures::PortType;
 use crate::translations::t;
 
@@ -208,6 +209,11 @@ pub fn styled_checkbox(ui: &mut Ui, checked: &mut bool, text: &str) -> Response
 
 /// Configures visuals to match the CSS style
 pub fn configure_visuals(ctx: &egui::Context) {
+    ctx.set_style(editor_style(&ctx.style()));
+}
+
+/// `base` with the editor's colors and fonts, for applying to a single `Ui`
+pub fn editor_style(base: &egui::Style) -> egui::Style {
     let mut visuals = Visuals::dark();
     
     // Configure dark theme similar to the CSS
@@ -244,10 +250,10 @@ pub fn configure_visuals(ctx: &egui::Context) {
     // Set window rounding to match CSS
     visuals.window_rounding = Rounding::same(4.0);
     
-    ctx.set_visuals(visuals);
+    let mut style = base.clone();
+    style.visuals = visuals;
     
     // Configure fonts
-    let mut style = (*ctx.style()).clone();
     style.text_styles = [
         (TextStyle::Heading, FontId::new(20.0, FontFamily::Proportional)),
         (TextStyle::Body, FontId::new(14.0, FontFamily::Proportional)),
@@ -255,7 +261,7 @@ pub fn configure_visuals(ctx: &egui::Context) {
         (TextStyle::Button, FontId::new(14.0, FontFamily::Proportional)),
         (TextStyle::Small, FontId::new(10.0, FontFamily::Proportional)),
     ].into();
-    ctx.set_style(style);
+    style
 }
 
 /// Creates a custom frame style for UI elements
@@ -685,15 +691,17 @@ pub fn error_dialog_frame() -> egui::Frame {
 /// # Arguments
 /// * `ctx` - The egui context
 /// * `title` - Dialog title (displayed in the window header)
-/// * `message` - Message content as RichText or convertible to RichText
+/// * `message` - Message content
+/// * `details` - Full error report shown in a collapsible section; empty hides it
 /// * `open` - Mutable reference to a boolean controlling dialog visibility
 /// 
 /// # Returns
 /// `true` if the OK button was clicked, `false` otherwise
-pub fn show_error_dialog<T: Into<egui::RichText>>(
+pub fn show_error_dialog(
     ctx: &egui::Context, 
-    title: impl Into<egui::WidgetText>, 
-    message: T, 
+    title: &str, 
+    message: &str, 
+    details: &str, 
     open: &mut bool
 ) -> bool {
     let mut result = false;
@@ -704,9 +712,6 @@ pub fn show_error_dialog<T: Into<egui::RichText>>(
         let dialog_size = egui::vec2(500.0, 250.0); // Larger dialog for more detailed errors
         let dialog_pos = screen_rect.center() - dialog_size / 2.0;
         
-        // Convert message to RichText
-        let rich_message = message.into();
-        
         // Create a modal background overlay
         let _overlay_frame = egui::Frame::none()
             .fill(Color32::from_rgba_unmultiplied(0, 0, 0, 200));
@@ -724,9 +729,10 @@ pub fn show_error_dialog<T: Into<egui::RichText>>(
             });
         
         // Create the dialog window
+        // Only the width is fixed so the dialog can grow when the details are expanded
         egui::Window::new(title)
             .fixed_pos(dialog_pos)
-            .fixed_size(dialog_size)
+            .default_width(dialog_size.x)
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
@@ -742,9 +748,25 @@ pub fn show_error_dialog<T: Into<egui::RichText>>(
                         .max_height(150.0)
                         .show(ui, |ui| {
                             // Show message text with word wrap
-                            ui.label(rich_message.size(16.0));
+                            ui.label(egui::RichText::new(message).size(16.0));
                         });
                     
+                    if !details.is_empty() {
+                        ui.add_space(10.0);
+                        egui::CollapsingHeader::new(t("error_details"))
+                            .id_source("error_dialog_details")
+                            .show(ui, |ui| {
+                                egui::ScrollArea::vertical()
+                                    .max_height(200.0)
+                                    .show(ui, |ui| {
+                                        // Read-only text edit, so parts of the report can be selected
+                                        ui.add(egui::TextEdit::multiline(&mut &*details)
+                                            .font(egui::TextStyle::Monospace)
+                                            .desired_width(dialog_size.x - 40.0));
+                                    });
+                            });
+                    }
+                    
                     ui.add_space(20.0);
                     
                     // Ok button
@@ -756,6 +778,13 @@ pub fn show_error_dialog<T: Into<egui::RichText>>(
                                     *open = false;
                                     result = true;
                                 }
+                                if ui.button(&t("copy_to_clipboard")).clicked() {
+                                    let mut report = format!("{}\n\n{}", title, message);
+                                    if !details.is_empty() {
+                                        report.push_str(&format!("\n\n{}", details));
+                                    }
+                                    ui.output().copied_text = report;
+                                }
                             });
                         }
                     );
@@ -771,3 +800,137 @@ pub fn show_error_dialog<T: Into<egui::RichText>>(
     
     result
 }
+
+/// Canvas grid appearance: minor lines at every grid step, a heavier major line every
+/// `subdivisions` steps and optionally highlighted coordinate axes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GridStyle {
+    pub subdivisions: u32,
+    pub minor_color: Color32,
+    pub major_color: Color32,
+    pub emphasize_axes: bool,
+}
+
+impl Default for GridStyle {
+    fn default() -> Self {
+        Self {
+            subdivisions: 5,
+            minor_color: Color32::from_rgba_unmultiplied(100, 100, 100, 60),
+            major_color: Color32::from_rgba_unmultiplied(140, 140, 140, 140),
+            emphasize_axes: true,
+        }
+    }
+}
+
+/// Color scheme used for port markers on the canvas
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PortPalette {
+    Standard,
+    /// Okabe-Ito colors, distinguishable with the common forms of color blindness
+    ColorBlindSafe,
+}
+
+impl PortPalette {
+    pub fn color(&self, port_type: &PortType) -> Color32 {
+        match self {
+            PortPalette::Standard => match port_type {
+                PortType::Default => Color32::YELLOW,
+                PortType::ThrusterIn | PortType::ThrusterOut => Color32::BLUE,
+                PortType::Missile | PortType::Launcher => Color32::RED,
+                PortType::WeaponIn | PortType::WeaponOut => Color32::LIGHT_BLUE,
+                PortType::Root => Color32::GREEN,
+                PortType::None => Color32::GRAY,
+            },
+            PortPalette::ColorBlindSafe => match port_type {
+                PortType::Default => Color32::from_rgb(0xf0, 0xe4, 0x42),
+                PortType::ThrusterIn | PortType::ThrusterOut => Color32::from_rgb(0x56, 0xb4, 0xe9),
+                PortType::Missile | PortType::Launcher => Color32::from_rgb(0xd5, 0x5e, 0x00),
+                PortType::WeaponIn | PortType::WeaponOut => Color32::from_rgb(0xcc, 0x79, 0xa7),
+                PortType::Root => Color32::from_rgb(0x00, 0x9e, 0x73),
+                PortType::None => Color32::from_rgb(0x99, 0x99, 0x99),
+            },
+        }
+    }
+}
+
+/// Draws a port marker whose outline depends on the port type, so types can be told
+/// apart without relying on color: circle for plain ports, square for thrusters,
+/// triangle for weapons, inverted triangle for missiles and diamond for the root
+pub fn draw_port_shape_marker(painter: &Painter, pos: Pos2, size: f32, port_type: &PortType, color: Color32) {
+    let points = match port_type {
+        PortType::Default | PortType::None => {
+            painter.circle_filled(pos, size, color);
+            return;
+        }
+        PortType::ThrusterIn | PortType::ThrusterOut => vec![
+            pos + vec2(-size, -size),
+            pos + vec2(size, -size),
+            pos + vec2(size, size),
+            pos + vec2(-size, size),
+        ],
+        PortType::WeaponIn | PortType::WeaponOut => vec![
+            pos + vec2(0.0, -size * 1.2),
+            pos + vec2(size * 1.1, size * 0.8),
+            pos + vec2(-size * 1.1, size * 0.8),
+        ],
+        PortType::Missile | PortType::Launcher => vec![
+            pos + vec2(-size * 1.1, -size * 0.8),
+            pos + vec2(size * 1.1, -size * 0.8),
+            pos + vec2(0.0, size * 1.2),
+        ],
+        PortType::Root => vec![
+            pos + vec2(0.0, -size * 1.3),
+            pos + vec2(size * 1.3, 0.0),
+            pos + vec2(0.0, size * 1.3),
+            pos + vec2(-size * 1.3, 0.0),
+        ],
+    };
+    painter.add(egui::Shape::convex_polygon(points, color, Stroke::new(1.0, Color32::BLACK)));
+}
+
+/// Side panel width and which of its sections are expanded
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub side_panel_width: f32,
+    pub shapes_open: bool,
+    pub properties_open: bool,
+    pub vertices_open: bool,
+    pub ports_open: bool,
+    pub templates_open: bool,
+    pub guides_open: bool,
+    pub lua_panel_width: f32,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            side_panel_width: 220.0,
+            shapes_open: true,
+            properties_open: true,
+            vertices_open: true,
+            ports_open: true,
+            templates_open: false,
+            guides_open: false,
+            lua_panel_width: 320.0,
+        }
+    }
+}
+
+/// Distance in pixels at which points snap onto a guide
+pub const GUIDE_SNAP_PIXELS: f32 = 8.0;
+
+/// Which coordinate a guide line fixes: X for a vertical line, Y for a horizontal one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GuideAxis {
+    X,
+    Y,
+}
+
+/// A line across the canvas at a fixed shape coordinate that points snap to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Guide {
+    pub axis: GuideAxis,
+    pub position: f32,
+}
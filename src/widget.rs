@@ -0,0 +1,69 @@
+// The editor as a widget, for Rust tools that draw it inside their own egui UI
+// instead of running it as an eframe app
+use crate::shape_editor::ShapeEditor;
+use crate::visual::editor_style;
+
+/// The whole editor, its canvas and panels, laid out inside a `Ui`
+///
+/// The panels take their space from the `Ui` it is shown in, and the editor's dark
+/// style applies only there; dialogs and toasts float over the host's window in the
+/// host's style. Keyboard shortcuts read the whole window's input, as in the app.
+///
+/// A widget from `new` starts from the default settings and writes nothing to disk.
+/// One from `with_saved_settings` shares the standalone app's settings, crash
+/// recovery file and template library, saving them while it runs and once more when
+/// it is dropped.
+///
+/// ```no_run
+/// # let ctx = egui::Context::default();
+/// let mut editor = reassembly_shape_editor::ShapeEditorWidget::new();
+/// egui::Window::new("Shape editor").show(&ctx, |ui| {
+///     ui.set_min_size(egui::vec2(800.0, 600.0));
+///     editor.show(ui);
+/// });
+/// ```
+pub struct ShapeEditorWidget {
+    editor: ShapeEditor,
+}
+
+impl ShapeEditorWidget {
+    pub fn new() -> Self {
+        ShapeEditorWidget { editor: ShapeEditor::with_defaults() }
+    }
+
+    /// Editor with the standalone app's saved settings, which it keeps up to date
+    pub fn with_saved_settings() -> Self {
+        ShapeEditorWidget { editor: ShapeEditor::new() }
+    }
+
+    /// Draw one frame of the editor into `ui`, filling the space it has
+    pub fn show(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        ui.scope(|ui| {
+            ui.set_style(editor_style(ui.style()));
+            self.editor.show(ui);
+        })
+        .response
+    }
+
+    pub fn editor(&self) -> &ShapeEditor {
+        &self.editor
+    }
+
+    /// Settings changed through this are saved as if changed in the editor
+    pub fn editor_mut(&mut self) -> &mut ShapeEditor {
+        self.editor.mark_settings_dirty();
+        &mut self.editor
+    }
+}
+
+impl Default for ShapeEditorWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ShapeEditorWidget {
+    fn drop(&mut self) {
+        self.editor.flush_settings();
+    }
+}
@@ -0,0 +1,38 @@
+use reassembly_shape_editor::{parse_shapes_content, parse_shapes_content_with_limits, ParseLimits};
+
+const TWO_SHAPES: &str = "{
+    {300, {{verts={{0,0}, {10,0}, {10,10}, {0,10}}}}},
+    {301, {{verts={{0,0}, {10,0}, {5,8}}}}}
+}";
+
+#[test]
+fn test_within_limits() {
+    let file = parse_shapes_content_with_limits(TWO_SHAPES, &ParseLimits::default()).unwrap();
+    assert_eq!(file.shapes.len(), 2);
+}
+
+#[test]
+fn test_limits_exceeded() {
+    let limits = ParseLimits { max_shapes: 1, ..Default::default() };
+    assert!(parse_shapes_content_with_limits(TWO_SHAPES, &limits).is_err());
+
+    let limits = ParseLimits { max_vertices_per_scale: 3, ..Default::default() };
+    let error = parse_shapes_content_with_limits(TWO_SHAPES, &limits).unwrap_err();
+    assert!(error.contains("300"), "{}", error);
+
+    let limits = ParseLimits { max_file_size: 16, ..Default::default() };
+    assert!(parse_shapes_content_with_limits(TWO_SHAPES, &limits).is_err());
+}
+
+#[test]
+fn test_deep_nesting() {
+    let tables = format!("{}{}", "{".repeat(100_000), "}".repeat(100_000));
+    assert!(parse_shapes_content(&tables).is_err());
+
+    let minus_signs = format!("{{{{300, {{{{verts={{{{{}1, 0}}}}}}}}}}}}", "- ".repeat(100_000));
+    assert!(parse_shapes_content(&minus_signs).is_err());
+
+    // Brackets inside comments and strings don't count
+    let commented = format!("-- {}\n{}", "{".repeat(1000), TWO_SHAPES);
+    assert_eq!(parse_shapes_content(&commented).unwrap().shapes.len(), 2);
+}
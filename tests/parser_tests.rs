@@ -0,0 +1,133 @@
+use reassembly_shape_editor::{ast::PortType, parse_shapes_content, parse_shapes_content_recovering, parse_shapes_file, ParseLimits};
+
+#[test]
+fn test_port_types() {
+    let file = parse_shapes_content("{
+        {300, {
+            {verts={{0,0}, {10,0}, {10,10}, {0,10}}, ports={
+                {0, 0.5, THRUSTER_OUT}, {1, 0.5, \"weapon_in\"}, {2, 0.5, 3}, {3, THRUSTER_IN, 0.5}, {0, 0.25, BOGUS}, {1, 0.25}
+            }}
+        }}
+    }").unwrap();
+    let ports = &file.shapes[0].scales[0].ports;
+    let types: Vec<Option<PortType>> = ports.iter().map(|port| port.port_type.clone()).collect();
+
+    assert_eq!(types, vec![
+        Some(PortType::ThrusterOut),
+        Some(PortType::WeaponIn),
+        Some(PortType::Missile),
+        Some(PortType::ThrusterIn),
+        Some(PortType::Default),
+        None,
+    ]);
+    assert_eq!((ports[3].edge, ports[3].position), (3, 0.5));
+}
+
+#[test]
+fn test_port_types_legacy() {
+    // Not valid Lua, so the line-by-line parser reads it
+    let file = parse_shapes_content("{
+        {300,
+            {
+                {verts={
+                    {0,0},
+                    {10,0},
+                    {0,10}},
+                ports={
+                    {0, 0.5, \"THRUSTER_OUT\"},
+                    {1, 0.5, 6},
+                    {2, ROOT, 0.5}}
+                }
+            }
+        }
+    ]").unwrap();
+    let types: Vec<Option<PortType>> = file.shapes[0].scales[0].ports.iter().map(|port| port.port_type.clone()).collect();
+
+    assert_eq!(types, vec![Some(PortType::ThrusterOut), Some(PortType::WeaponOut), Some(PortType::Root)]);
+}
+
+#[test]
+fn test_number_formats() {
+    let file = parse_shapes_content("{
+        {0x12C, {
+            {verts={{-5e0,-0x5}, {5E-0,-5}, {0.5e1,5}, {-0x5,50e-1}}, ports={{0x0, 5e-1}, {2e0, 0.5}}}
+        }, durability=1e-3, fillColor=0xff336699}
+    }").unwrap();
+    let shape = &file.shapes[0];
+
+    assert_eq!(shape.id, 300);
+    let verts: Vec<(f32, f32)> = shape.scales[0].verts.iter().map(|v| (v.x, v.y)).collect();
+    assert_eq!(verts, vec![(-5.0, -5.0), (5.0, -5.0), (5.0, 5.0), (-5.0, 5.0)]);
+    let ports: Vec<(usize, f32)> = shape.scales[0].ports.iter().map(|port| (port.edge, port.position)).collect();
+    assert_eq!(ports, vec![(0, 0.5), (2, 0.5)]);
+    assert_eq!(shape.durability, Some(0.001));
+    assert_eq!(shape.fill_color, Some(0xff336699));
+}
+
+#[test]
+fn test_recovering() {
+    let content = "-- shapes {with braces} in a comment
+{
+    {300, {{verts={{0,0}, {10,0}, {0,10}}}}},
+    {301, {{verts={{0,0} {10,0}, {0,10}}}}},
+    {foo, {{verts={{0,0}, {10,0}, {0,10}}}}},
+    {302, --name with a } in it
+        {{verts={{0,0}, {10,0}, {0,10}}}}, group=\"}\"},
+    {303, {{verts={{0,0}, {10,0}, {0,10}}},
+    {304, {{verts={{0,0}, {10,0}, {0,10}}}}},
+}";
+    let recovered = parse_shapes_content_recovering(content, &ParseLimits::default()).unwrap();
+    let ids: Vec<usize> = recovered.shapes_file.shapes.iter().map(|shape| shape.id).collect();
+    assert_eq!(ids, vec![300, 302, 304]);
+
+    let skipped: Vec<(usize, usize, Option<usize>)> = recovered.skipped.iter().map(|s| (s.line, s.column, s.shape_id)).collect();
+    // 303 is missing a brace and would swallow 304 without picking up again there
+    assert_eq!(skipped, vec![(4, 5, Some(301)), (5, 5, None), (8, 5, Some(303))]);
+
+    assert!(parse_shapes_content_recovering("no shapes here", &ParseLimits::default()).is_err());
+}
+
+#[test]
+fn test_recovering_long_brackets() {
+    // "]]" only closes a long comment or string with no '=' signs between the brackets
+    let content = "{
+    --[==[ {299,
+        ]] {
+    ]==]
+    {300, {{verts={{0,0}, {10,0}, {0,10}}}}},
+    {301, {{verts={{0,0}, {10,0}, {0,10}}}}, group=[=[ } ]] ]=]},
+}";
+    let recovered = parse_shapes_content_recovering(content, &ParseLimits::default()).unwrap();
+    let ids: Vec<usize> = recovered.shapes_file.shapes.iter().map(|shape| shape.id).collect();
+    assert_eq!(ids, vec![300, 301]);
+    assert!(recovered.skipped.is_empty());
+}
+
+#[test]
+fn test_file_encodings() {
+    let path = std::env::temp_dir().join(format!("parser_tests_encodings_{}.lua", std::process::id()));
+    let read = |bytes: &[u8]| {
+        std::fs::write(&path, bytes).unwrap();
+        parse_shapes_file(&path).unwrap()
+    };
+    let shapes = "{\n    {300, --NAME\n        {{verts={{0,0}, {10,0}, {0,10}}}}\n    }\n}\n";
+
+    // UTF-8 with a byte order mark and Windows line endings
+    let utf8 = format!("\u{FEFF}{}", shapes.replace("NAME", "Клин").replace('\n', "\r\n"));
+    let file = read(utf8.as_bytes());
+    assert_eq!(file.shapes[0].name.as_deref(), Some("Клин"));
+    assert_eq!(file.shapes[0].scales[0].verts.len(), 3);
+
+    // Windows-1251 with old Mac line endings; "Клин" is CA EB E8 ED
+    let mut cp1251 = Vec::new();
+    for part in shapes.replace('\n', "\r").split("NAME") {
+        cp1251.extend_from_slice(part.as_bytes());
+        cp1251.extend_from_slice(&[0xCA, 0xEB, 0xE8, 0xED]);
+    }
+    cp1251.truncate(cp1251.len() - 4);
+    let file = read(&cp1251);
+    assert_eq!(file.shapes[0].name.as_deref(), Some("Клин"));
+    assert_eq!(file.shapes[0].scales[0].verts.len(), 3);
+
+    std::fs::remove_file(&path).ok();
+}
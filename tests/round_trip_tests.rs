@@ -0,0 +1,212 @@
+use reassembly_shape_editor::{
+    ast::{
+        CannonProperties, FragmentProperties, Port, PortType, Scale, Shape, ShapesFile, ShroudComponent,
+        ThrusterProperties, Vertex,
+    },
+    parse_shapes_content, serialize_shapes_file, SerializeOptions,
+};
+
+// Property tests over random files; each case is reproducible from its seed
+const CASES: u64 = 300;
+
+struct Rng(u64);
+
+impl Rng {
+    // SplitMix64
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn maybe<T>(&mut self, value: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        if self.chance() { Some(value(self)) } else { None }
+    }
+
+    // Round numbers, awkward fractions, negative zero and extremes
+    fn float(&mut self) -> f32 {
+        match self.below(6) {
+            0 => self.below(41) as f32 - 20.0,
+            1 => -0.0,
+            2 => f32::from_bits(self.next() as u32 & 0x7F7F_FFFF) * if self.chance() { -1.0 } else { 1.0 },
+            _ => (self.next() as f32 / u64::MAX as f32 - 0.5) * 200.0,
+        }
+    }
+
+    fn text(&mut self) -> String {
+        const CHARS: &[char] = &['a', 'Z', '_', '7', ' ', '|', '"', '\\', '\n', '\t', '[', '-', 'ё', '{', '}'];
+        (0..self.below(8)).map(|_| CHARS[self.below(CHARS.len() as u64) as usize]).collect()
+    }
+}
+
+fn random_port_type(rng: &mut Rng) -> PortType {
+    [
+        PortType::Default,
+        PortType::ThrusterIn,
+        PortType::ThrusterOut,
+        PortType::WeaponIn,
+        PortType::WeaponOut,
+        PortType::Missile,
+        PortType::Launcher,
+        PortType::Root,
+        PortType::None,
+    ][rng.below(9) as usize]
+        .clone()
+}
+
+fn random_shape(rng: &mut Rng) -> Shape {
+    let scales = (0..rng.below(4))
+        .map(|_| Scale {
+            verts: (0..rng.below(7)).map(|_| Vertex { x: rng.float(), y: rng.float() }).collect(),
+            ports: (0..rng.below(6))
+                .map(|_| Port { edge: rng.below(8) as usize, position: rng.float(), port_type: rng.maybe(random_port_type) })
+                .collect(),
+        })
+        .collect();
+
+    Shape {
+        id: rng.below(20_000) as usize,
+        name: rng.maybe(Rng::text),
+        scales,
+        launcher_radial: rng.maybe(Rng::chance),
+        mirror_of: rng.maybe(|rng| rng.below(20_000) as usize),
+        group: rng.maybe(|rng| rng.below(100) as usize),
+        features: rng.maybe(|rng| (0..rng.below(4)).map(|_| rng.text()).collect()),
+        fill_color: rng.maybe(|rng| rng.next() as u32),
+        fill_color1: rng.maybe(|rng| rng.next() as u32),
+        line_color: rng.maybe(|rng| rng.next() as u32),
+        durability: rng.maybe(Rng::float),
+        density: rng.maybe(Rng::float),
+        grow_rate: rng.maybe(Rng::float),
+        shroud: rng.maybe(|rng| {
+            (0..rng.below(3))
+                .map(|_| ShroudComponent {
+                    size: (rng.float(), rng.float()),
+                    offset: (rng.float(), rng.float(), rng.float()),
+                    taper: rng.float(),
+                    count: rng.below(10) as usize,
+                    angle: rng.float(),
+                    tri_color_id: rng.below(4) as usize,
+                    tri_color1_id: rng.below(4) as usize,
+                    line_color_id: rng.below(4) as usize,
+                    shape: if rng.chance() { "SQUARE".to_string() } else { rng.text() },
+                })
+                .collect()
+        }),
+        cannon: rng.maybe(|rng| CannonProperties {
+            damage: rng.float(),
+            power: rng.float(),
+            rounds_per_sec: rng.float(),
+            muzzle_vel: rng.float(),
+            range: rng.float(),
+            spread: rng.float(),
+            rounds_per_burst: rng.maybe(|rng| rng.below(10) as usize),
+            burstyness: rng.maybe(Rng::float),
+            color: rng.maybe(|rng| rng.next() as u32),
+            explosive: rng.maybe(|rng| if rng.chance() { "PROXIMITY".to_string() } else { rng.text() }),
+            fragment: rng.maybe(|rng| FragmentProperties {
+                rounds_per_burst: rng.below(10) as usize,
+                muzzle_vel: rng.float(),
+                spread: rng.float(),
+                pattern: rng.maybe(Rng::text),
+                damage: rng.float(),
+                range: rng.float(),
+                color: rng.maybe(|rng| rng.next() as u32),
+            }),
+        }),
+        thruster: rng.maybe(|rng| ThrusterProperties {
+            force: rng.float(),
+            power: rng.float(),
+            color: rng.maybe(|rng| rng.next() as u32),
+        }),
+    }
+}
+
+fn random_file(seed: u64) -> ShapesFile {
+    let mut rng = Rng(seed);
+    ShapesFile { shapes: (0..1 + rng.below(5)).map(|_| random_shape(&mut rng)).collect() }
+}
+
+#[test]
+fn test_canonical_round_trip() {
+    let empty = ShapesFile { shapes: Vec::new() };
+    assert_eq!(parse_shapes_content(&serialize_shapes_file(&empty)).unwrap(), empty);
+
+    for seed in 0..CASES {
+        let canonical = random_file(seed).normalize();
+        let lua = serialize_shapes_file(&canonical);
+        let parsed = parse_shapes_content(&lua).unwrap_or_else(|e| panic!("seed {}: {}\n{}", seed, e, lua));
+        assert_eq!(parsed, canonical, "seed {}:\n{}", seed, lua);
+    }
+}
+
+#[test]
+fn test_shape_snippet() {
+    for seed in 0..CASES {
+        let canonical = random_file(seed).normalize();
+        let Some(shape) = canonical.shapes.first().and_then(|first| canonical.shape_by_id(first.id)) else { continue };
+
+        // A snippet dropped into an otherwise empty file reads back as the same shape
+        let options = SerializeOptions { indent: 2, port_comments: false, scale_comments: false, ..SerializeOptions::default() };
+        let snippet = shape.to_lua_string(&options);
+        assert!(snippet.starts_with(&format!("{{{},", shape.id)) && snippet.ends_with('}'));
+        assert!(!snippet.lines().any(|line| line.trim_start().starts_with("{") && line.contains("-- Edge")));
+        let parsed = parse_shapes_content(&format!("{{\n{}\n}}\n", snippet)).unwrap_or_else(|e| panic!("seed {}: {}\n{}", seed, e, snippet));
+        assert_eq!(parsed.shapes, vec![shape.clone()], "seed {}:\n{}", seed, snippet);
+    }
+}
+
+#[test]
+fn test_normalize_idempotent() {
+    for seed in 0..CASES {
+        let canonical = random_file(seed).normalize();
+        assert_eq!(canonical.normalize(), canonical, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_normalize_order() {
+    let file = parse_shapes_content(
+        "{
+            {302, {{verts={{0,0},{10,0},{0,10}}, ports={{2,0.5},{0,0.75,THRUSTER_IN},{0,0.25,DEFAULT}}}}, features=\"PALETTE| THRUSTER|PALETTE\"},
+            {301, {{verts={{0,0},{10,0},{0,10}}}}}
+        }",
+    )
+    .unwrap();
+    let canonical = file.normalize();
+
+    let ids: Vec<usize> = canonical.shapes.iter().map(|shape| shape.id).collect();
+    assert_eq!(ids, vec![301, 302]);
+    let ports = &canonical.shapes[1].scales[0].ports;
+    assert_eq!(ports.iter().map(|port| (port.edge, port.position)).collect::<Vec<_>>(), vec![(0, 0.25), (0, 0.75), (2, 0.5)]);
+    assert_eq!(ports[0].port_type, None);
+    assert_eq!(ports[1].port_type, Some(PortType::ThrusterIn));
+    assert_eq!(canonical.shapes[1].features, Some(vec!["PALETTE".to_string(), "THRUSTER".to_string()]));
+}
+
+// The layout the wasm parseShapes/serializeShapes bindings hand to JavaScript
+#[test]
+fn test_json_round_trip() {
+    for seed in 0..CASES {
+        let canonical = random_file(seed).normalize();
+        let json = serde_json::to_string(&canonical).unwrap();
+        assert_eq!(serde_json::from_str::<ShapesFile>(&json).unwrap(), canonical, "seed {}", seed);
+    }
+
+    let file: ShapesFile = serde_json::from_str(
+        r#"{"shapes": [{"id": 300, "scales": [{"verts": [{"x": 0, "y": 0}], "ports": [{"edge": 0, "position": 0.5, "port_type": "THRUSTER_OUT"}]}]}]}"#,
+    )
+    .unwrap();
+    assert_eq!(file.shapes[0].name, None);
+    assert_eq!(file.shapes[0].scales[0].ports[0].port_type, Some(PortType::ThrusterOut));
+}
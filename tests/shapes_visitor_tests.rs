@@ -0,0 +1,63 @@
+use reassembly_shape_editor::{
+    ast::{Port, Shape, Vertex},
+    parse_shapes_content,
+    visit::{Location, ShapeVisitor},
+};
+
+const SHAPES: &str = "{
+    {300, {
+        {verts={{0,0}, {10,0}, {10,10}, {0,10}}, ports={{0,0.5}, {2,0.5}}},
+        {verts={{0,0}, {20,0}, {20,20}, {0,20}}, ports={{0,0.25}, {0,0.75}}}
+    }},
+    {301, {
+        {verts={{0,0}, {10,0}, {5,8}}, ports={{1,0.5}}}
+    }}
+}";
+
+#[test]
+fn test_shape_visitor() {
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl ShapeVisitor for Recorder {
+        fn visit_shape(&mut self, index: usize, _shape: &Shape) {
+            self.events.push(format!("shape {}", index));
+        }
+
+        fn visit_vertex(&mut self, at: Location, _vertex: &Vertex) {
+            self.events.push(format!("vertex {}.{}.{}", at.shape_id, at.scale, at.index));
+        }
+
+        fn visit_port(&mut self, at: Location, _port: &Port) {
+            self.events.push(format!("port {}.{}.{}", at.shape_id, at.scale, at.index));
+        }
+    }
+
+    let file = parse_shapes_content(SHAPES).unwrap();
+    let mut recorder = Recorder::default();
+    file.visit(&mut recorder);
+
+    assert_eq!(recorder.events.len(), 2 + 4 + 2 + 4 + 2 + 3 + 1);
+    assert_eq!(recorder.events[0], "shape 0");
+    assert_eq!(recorder.events[5], "port 300.0.0");
+    assert_eq!(recorder.events[7], "vertex 300.1.0");
+    assert_eq!(recorder.events.last().unwrap(), "port 301.0.0");
+}
+
+#[test]
+fn test_iterators() {
+    let mut file = parse_shapes_content(SHAPES).unwrap();
+
+    for (_, vertex) in file.iter_vertices_mut() {
+        vertex.x += 5.0;
+    }
+    let xs: Vec<f32> = file.iter_vertices().filter(|(at, _)| at.shape_id == 301).map(|(_, v)| v.x).collect();
+    assert_eq!(xs, vec![5.0, 15.0, 10.0]);
+
+    let port_locations: Vec<Location> = file.iter_ports().map(|(at, _)| at).collect();
+    assert_eq!(port_locations.len(), 5);
+    assert_eq!(port_locations[3], Location { shape: 0, shape_id: 300, scale: 1, index: 1 });
+    assert_eq!(port_locations[4], Location { shape: 1, shape_id: 301, scale: 0, index: 0 });
+}
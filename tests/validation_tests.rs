@@ -0,0 +1,99 @@
+use reassembly_shape_editor::{parse_shapes_content, strip_summary_comment, summary_comment, validate_payloads, validate_shapes_file, Element, Fix, Rule, Severity};
+
+#[test]
+fn test_clean_file() {
+    let file = parse_shapes_content("{
+        {400, {
+            {verts={{-5,-5}, {5,-5}, {5,5}, {-5,5}}, ports={{0,0.5}, {2,0.5}}},
+            {verts={{-10,-10}, {10,-10}, {10,10}, {-10,10}}, ports={{0,0.5}, {2,0.5}}}
+        }}
+    }").unwrap();
+
+    assert_eq!(validate_shapes_file(&file), vec![]);
+}
+
+#[test]
+fn test_diagnostics() {
+    let file = parse_shapes_content("{
+        {401, {
+            {verts={{0,0}, {10,0}, {10,0}, {0,10}}, ports={{7,0.5}}}
+        }},
+        {401, {
+            {verts={{0,0}, {10,10}, {10,0}, {0,10}}}
+        }}
+    }").unwrap();
+    let diagnostics = validate_shapes_file(&file);
+    let rules: Vec<Rule> = diagnostics.iter().map(|d| d.rule).collect();
+
+    assert_eq!(rules, vec![Rule::CoincidentVertices, Rule::PortEdgeOutOfRange, Rule::DuplicateId, Rule::CrossingEdges]);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error && d.shape_id == 401));
+    assert_eq!(diagnostics[0].element, Element::Vertex { scale: 0, index: 1 });
+    assert_eq!(diagnostics[1].fix, Some(Fix::RemovePort { scale: 0, index: 0 }));
+}
+
+#[test]
+fn test_fixes() {
+    let mut file = parse_shapes_content("{
+        {402, {
+            {verts={{-5,-5}, {5,-5}, {5,5}, {-5,5}}, ports={{0,0.25}, {1,0.5}}},
+            {verts={{-10,10}, {10,10}, {10,-10}, {-10,-10}}, ports={{2,0.75}, {1,0.5}}}
+        }}
+    }").unwrap();
+
+    let diagnostics = validate_shapes_file(&file);
+    let rules: Vec<Rule> = diagnostics.iter().map(|d| d.rule).collect();
+    assert_eq!(rules, vec![Rule::ScaleWinding, Rule::ScalePorts]);
+    diagnostics[0].fix.unwrap().apply(&mut file.shapes[0]);
+
+    // Reversed, the ports of scale 2 sit on the same edges as those of scale 1
+    assert_eq!(validate_shapes_file(&file), vec![]);
+}
+
+#[test]
+fn test_summary_comment() {
+    let file = parse_shapes_content("{
+        {403, {
+            {verts={{0,0}, {10,10}, {10,0}, {0,10}}}
+        }}
+    }").unwrap();
+    let comment = summary_comment(&validate_shapes_file(&file));
+    assert!(comment.starts_with("-- Validation: 1 error(s), 0 warning(s)\n--   error in shape 403 scale 1"));
+
+    // The comment is replaced on the next export, and the file still parses with it
+    let content = format!("{}{{\n    {{404, {{{{verts={{{{0,0}}, {{10,0}}, {{0,10}}}}}}}}}}\n}}\n", comment);
+    assert!(strip_summary_comment(&content).starts_with("{\n"));
+    assert_eq!(parse_shapes_content(&content).unwrap().shapes[0].id, 404);
+    assert_eq!(summary_comment(&[]), "-- Validation: no problems found\n");
+}
+
+#[test]
+fn test_zero_area() {
+    let file = parse_shapes_content("{
+        {405, {
+            {verts={{0,0}, {5,5}, {10,10}}}
+        }},
+        {406, {
+            {verts={{0,0}, {10,0}}}
+        }}
+    }").unwrap();
+    let rules: Vec<Rule> = validate_shapes_file(&file).iter().map(|d| d.rule).collect();
+
+    assert_eq!(rules, vec![Rule::ZeroArea, Rule::TooFewVertices]);
+}
+
+#[test]
+fn test_missing_payload() {
+    let file = parse_shapes_content("{
+        {407, {
+            {verts={{-5,-5}, {5,-5}, {5,5}, {-5,5}}, ports={{0,0.5,LAUNCHER}}}
+        }},
+        {408, {
+            {verts={{-1,-2}, {1,-2}, {1,2}, {-1,2}}, ports={{2,0.5,MISSILE}}}
+        }}
+    }").unwrap();
+
+    assert_eq!(validate_payloads(&file, &[(407, 408)]), vec![]);
+    let diagnostics = validate_payloads(&file, &[(407, 409), (500, 409)]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!((diagnostics[0].rule, diagnostics[0].severity, diagnostics[0].shape_id), (Rule::MissingPayload, Severity::Warning, 407));
+}